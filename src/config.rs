@@ -1,37 +1,1350 @@
-use crate::types::{PayloadType, Protocol};
-use serde::{Deserialize, Serialize};
+use crate::geometry::WindowGeometry;
+use crate::log::{LogEntry, LogLevel};
+use crate::types::{
+    Handshake, PayloadType, Profile, Protocol, RampMode, RecentReplay, RecentTarget, ReplayOptions,
+    ReplayablePayload, ThemeChoice,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::path::{Path, PathBuf};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
 pub struct Config {
     pub address: String,
-    pub port: String,
+    #[serde(deserialize_with = "port")]
+    pub port: u16,
     pub initial_payload: String,
     pub initial_payload_type: PayloadType,
     pub protocol: Protocol,
+    /// Payload type new TCP session windows start with.
+    pub tcp_payload_type: PayloadType,
+    /// Payload type new UDP session windows start with.
+    pub udp_payload_type: PayloadType,
+    #[serde(deserialize_with = "loops")]
+    pub replay_loops: u32,
+    pub replay_ramp_mode: RampMode,
+    #[serde(deserialize_with = "ramp_value")]
+    pub replay_ramp_value: f64,
+    #[serde(deserialize_with = "unsigned")]
+    pub replay_ramp_floor_ms: u64,
+    pub clear_input_on_send: bool,
+    /// Seconds without traffic before a TCP session disconnects; 0 disables the timeout.
+    #[serde(deserialize_with = "unsigned")]
+    pub idle_timeout_secs: u64,
+    /// Further handshake steps sent after the initial payload.
+    pub handshake_steps: Vec<ReplayablePayload>,
+    pub handshake_disconnect_on_failure: bool,
+    /// Milliseconds a TCP write may block before a warning is logged.
+    #[serde(deserialize_with = "stall_warn_ms")]
+    pub send_stall_warn_ms: u64,
+    /// Write a session's replay into `auto_export_dir` when its window is closed.
+    pub auto_export_on_close: bool,
+    pub auto_export_dir: String,
+    /// Directory "Quick export" writes replays and logs to without a dialog.
+    pub export_dir: String,
+    /// Write the log of each new session and replay window to a file in `export_dir`.
+    pub log_to_file: bool,
+    /// Size in MB at which a log file continues in a new file; 0 disables it.
+    #[serde(deserialize_with = "unsigned")]
+    pub log_rotate_mb: u64,
+    /// Continue log files in a new file when the date changes.
+    pub log_rotate_daily: bool,
+    /// Number of earlier log files kept when rotating.
+    #[serde(deserialize_with = "unsigned")]
+    pub log_keep_files: u64,
+    /// Compress earlier log files with gzip.
+    pub log_compress_rotated: bool,
+    /// Also show session and replay log entries in the main log, prefixed with the window.
+    pub forward_session_logs: bool,
+    /// Log socket-level detail as Debug entries.
+    pub debug_log: bool,
+    /// `host:port` of a syslog collector every log line is also sent to over UDP; empty
+    /// disables it.
+    #[serde(deserialize_with = "syslog_target")]
+    pub syslog_target: String,
+    /// Syslog facility of forwarded lines, e.g. `user` or `local0`.
+    #[serde(deserialize_with = "syslog_facility")]
+    pub syslog_facility: String,
+    /// APP-NAME of forwarded lines.
+    pub syslog_app_name: String,
+    /// Targets connected to, most recent first, at most `MAX_RECENT_TARGETS`.
+    pub recent_targets: Vec<RecentTarget>,
+    /// Replay files run recently, most recent first, at most `MAX_RECENT_REPLAYS`.
+    pub recent_replays: Vec<RecentReplay>,
+    /// Named targets, as `[[profiles]]` tables with `name`, `protocol`, `address`, and
+    /// `port`. Only edited in the config file.
+    pub profiles: Vec<Profile>,
+    /// Ask before exiting while sessions are connected or replays are running.
+    pub confirm_exit: bool,
+    /// strftime pattern of log timestamps.
+    pub log_timestamp_format: String,
+    /// Log timestamps in UTC instead of local time.
+    pub log_timestamp_utc: bool,
+    /// Shortest run of identical log lines shown as one line with a repeat count; 0 or 1
+    /// disables it.
+    #[serde(deserialize_with = "unsigned")]
+    pub log_collapse_threshold: u64,
+    /// Collapse runs of numbered packet lines too, compared without their number.
+    pub log_collapse_packets: bool,
+    /// Entries a window log keeps before the oldest are dropped; 0 keeps every entry.
+    #[serde(deserialize_with = "unsigned_size")]
+    pub log_max_entries: usize,
+    /// Messages the log channels hold before the oldest are dropped; read at start.
+    #[serde(deserialize_with = "capacity")]
+    pub log_channel_capacity: usize,
+    /// Session events the connection channel holds before the oldest are dropped; read at
+    /// start.
+    #[serde(deserialize_with = "capacity")]
+    pub event_channel_capacity: usize,
+    /// Replay events the progress channel holds before the oldest are dropped; read at
+    /// start.
+    #[serde(deserialize_with = "capacity")]
+    pub progress_channel_capacity: usize,
+    /// Send replay progress folded into the latest update per window instead of every update.
+    pub coalesce_progress: bool,
+    /// Seconds a banner other than an error shows before it is dismissed on its own; 0
+    /// keeps every banner until dismissed.
+    #[serde(deserialize_with = "unsigned")]
+    pub banner_timeout_secs: u64,
+    /// Largest UDP payload, in bytes, sent without a warning that it may be fragmented.
+    #[serde(deserialize_with = "datagram_size")]
+    pub udp_safe_datagram_size: usize,
+    /// Color theme of the windows.
+    pub theme: ThemeChoice,
+    /// Language of the user interface. Log content stays in English.
+    pub language: crate::i18n::Language,
+    /// Contrast, text size, and motion settings, the `[accessibility]` table.
+    pub accessibility: Accessibility,
+    /// Family name of the monospace font of log panes and payload previews; the bundled
+    /// Fira Mono when empty.
+    pub log_font: String,
+    /// Size of log text at 100% zoom.
+    #[serde(deserialize_with = "font_size")]
+    pub log_font_size: u16,
+    /// Last size and position of the main window.
+    pub main_window: Option<WindowGeometry>,
+    /// Size and position for new session and replay windows, taken from the last one
+    /// resized or moved.
+    pub session_window: Option<WindowGeometry>,
+    /// Offset each new session or replay window from the ones already open.
+    pub cascade_windows: bool,
+    /// Open new session and replay windows as tabs of the main window.
+    pub tabbed_windows: bool,
+    /// Show a tray icon. Only builds with the `tray` feature show one.
+    pub tray_icon: bool,
+    /// Hide the main window to the tray icon when closed, instead of exiting.
+    pub close_to_tray: bool,
+    /// Share of a new session window's height given to the controls above the log.
+    #[serde(deserialize_with = "split_ratio")]
+    pub session_split: f32,
+    /// Settings this version does not know, e.g. added by hand or by a newer version,
+    /// written back unchanged when saving.
+    #[serde(flatten)]
+    pub unknown: toml::Table,
 }
 
+/// Settings for users with low vision or who are distracted by motion.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Accessibility {
+    /// Pure black or white backgrounds, with bordered panes and stronger status colors.
+    pub high_contrast: bool,
+    /// Size of interface text; log text has its own, `log_font_size`. Applies from the
+    /// next start.
+    #[serde(deserialize_with = "font_size")]
+    pub text_size: u16,
+    /// Show connection attempts as static text instead of an animated indicator.
+    pub reduced_motion: bool,
+}
+
+impl Default for Accessibility {
+    fn default() -> Self {
+        Self {
+            high_contrast: false,
+            text_size: 16,
+            reduced_motion: false,
+        }
+    }
+}
+
+/// Number of targets kept in the recent targets history.
+pub const MAX_RECENT_TARGETS: usize = 10;
+/// Number of files kept in the recent replays list.
+pub const MAX_RECENT_REPLAYS: usize = 5;
+/// Family name of the monospace font shipped with the app, loaded at startup.
+pub const BUNDLED_LOG_FONT: &str = "Fira Mono";
+/// The bundled monospace font.
+pub const BUNDLED_LOG_FONT_BYTES: &[u8] = include_bytes!("../assets/fonts/FiraMono-Medium.ttf");
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             address: "127.0.0.1".to_string(),
-            port: "8080".to_string(),
+            port: 8080,
             initial_payload: String::new(),
             initial_payload_type: PayloadType::Hex,
             protocol: Protocol::Tcp,
+            tcp_payload_type: PayloadType::Hex,
+            udp_payload_type: PayloadType::Hex,
+            replay_loops: 1,
+            replay_ramp_mode: RampMode::Off,
+            replay_ramp_value: 0.8,
+            replay_ramp_floor_ms: 0,
+            clear_input_on_send: false,
+            idle_timeout_secs: 0,
+            handshake_steps: Vec::new(),
+            handshake_disconnect_on_failure: false,
+            send_stall_warn_ms: 2000,
+            auto_export_on_close: false,
+            auto_export_dir: "replays".to_string(),
+            export_dir: "exports".to_string(),
+            log_to_file: false,
+            log_rotate_mb: 100,
+            log_rotate_daily: false,
+            log_keep_files: 5,
+            log_compress_rotated: false,
+            forward_session_logs: false,
+            debug_log: false,
+            syslog_target: String::new(),
+            syslog_facility: "user".to_string(),
+            syslog_app_name: "replayr".to_string(),
+            recent_targets: Vec::new(),
+            recent_replays: Vec::new(),
+            profiles: Vec::new(),
+            confirm_exit: true,
+            log_timestamp_format: crate::log::DEFAULT_TIMESTAMP_FORMAT.to_string(),
+            log_timestamp_utc: false,
+            log_collapse_threshold: 3,
+            log_collapse_packets: false,
+            log_max_entries: crate::log::DEFAULT_MAX_ENTRIES,
+            log_channel_capacity: 1000,
+            event_channel_capacity: 1000,
+            progress_channel_capacity: 100,
+            coalesce_progress: true,
+            banner_timeout_secs: 8,
+            theme: ThemeChoice::System,
+            language: Default::default(),
+            accessibility: Accessibility::default(),
+            log_font: BUNDLED_LOG_FONT.to_string(),
+            log_font_size: 16,
+            udp_safe_datagram_size: crate::validate::UDP_SAFE_PAYLOAD,
+            main_window: None,
+            session_window: None,
+            cascade_windows: true,
+            tabbed_windows: false,
+            tray_icon: false,
+            close_to_tray: true,
+            session_split: 0.5,
+            unknown: toml::Table::new(),
         }
     }
 }
 
-pub fn load_config() -> Config {
-    if let Ok(content) = std::fs::read_to_string("config.toml") {
-        toml::from_str(&content).unwrap_or_default()
-    } else {
-        Config::default()
+/// Log font family names handed to iced, which names fonts by `&'static str`. Each name
+/// is leaked once and reused on every redraw.
+static FONT_NAMES: std::sync::Mutex<Vec<&'static str>> = std::sync::Mutex::new(Vec::new());
+
+impl Config {
+    /// Returns the font of log panes and payload previews.
+    pub fn log_font(&self) -> iced::Font {
+        let family = match self.log_font.trim() {
+            "" => BUNDLED_LOG_FONT,
+            family => family,
+        };
+        let mut names = FONT_NAMES.lock().unwrap();
+        let name = match names.iter().find(|name| **name == family) {
+            Some(name) => *name,
+            None => {
+                let name: &'static str = Box::leak(family.to_string().into_boxed_str());
+                names.push(name);
+                name
+            }
+        };
+        iced::Font::with_name(name)
+    }
+
+    /// Returns when log files continue in a new file, from the log rotation settings.
+    pub fn log_rotation(&self) -> crate::logfile::LogRotation {
+        crate::logfile::LogRotation {
+            max_bytes: (self.log_rotate_mb > 0).then(|| self.log_rotate_mb * 1024 * 1024),
+            daily: self.log_rotate_daily,
+            keep: self.log_keep_files as usize,
+            compress: self.log_compress_rotated,
+        }
+    }
+
+    /// Returns the payload type new session windows of `protocol` start with.
+    pub fn default_payload_type(&self, protocol: Protocol) -> PayloadType {
+        match protocol {
+            Protocol::Tcp => self.tcp_payload_type,
+            Protocol::Udp => self.udp_payload_type,
+        }
+    }
+
+    /// Moves `target` to the front of the recent targets history, dropping the oldest
+    /// entry once the history is full. Returns whether the history changed.
+    pub fn remember_target(&mut self, target: RecentTarget) -> bool {
+        if self.recent_targets.first() == Some(&target) {
+            return false;
+        }
+        self.recent_targets.retain(|t| *t != target);
+        self.recent_targets.insert(0, target);
+        self.recent_targets.truncate(MAX_RECENT_TARGETS);
+        true
+    }
+
+    /// Moves `replay` to the front of the recent replays, replacing the entry for the same
+    /// file. Returns whether the list changed.
+    pub fn remember_replay(&mut self, replay: RecentReplay) -> bool {
+        if self.recent_replays.first() == Some(&replay) {
+            return false;
+        }
+        self.recent_replays.retain(|r| r.path != replay.path);
+        self.recent_replays.insert(0, replay);
+        self.recent_replays.truncate(MAX_RECENT_REPLAYS);
+        true
+    }
+
+    /// Removes a replay file that failed to load from the recent replays. Returns whether
+    /// it was listed.
+    pub fn forget_replay(&mut self, path: &Path) -> bool {
+        let len = self.recent_replays.len();
+        self.recent_replays.retain(|r| r.path != path);
+        self.recent_replays.len() != len
+    }
+
+    /// Returns the send stall warning threshold.
+    pub fn stall_warning(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.send_stall_warn_ms)
+    }
+
+    /// Builds the handshake for new sessions: the initial payload, if any, followed by the
+    /// configured steps.
+    pub fn handshake(&self) -> Handshake {
+        let initial = (!self.initial_payload.is_empty()).then(|| ReplayablePayload {
+            payload: self.initial_payload.clone(),
+            payload_type: self.initial_payload_type,
+            delay: 0,
+            recorded_only: false,
+            label: None,
+        });
+        Handshake {
+            steps: initial
+                .into_iter()
+                .chain(self.handshake_steps.iter().cloned())
+                .collect(),
+            disconnect_on_failure: self.handshake_disconnect_on_failure,
+        }
+    }
+
+    /// Collects the replay loop and ramp settings into `ReplayOptions`.
+    pub fn replay_options(&self) -> ReplayOptions {
+        ReplayOptions {
+            loops: self.replay_loops,
+            ramp_mode: self.replay_ramp_mode,
+            ramp_value: match self.replay_ramp_mode {
+                RampMode::Off => 1.0,
+                _ => self.replay_ramp_value,
+            },
+            floor_ms: self.replay_ramp_floor_ms,
+            speed: 1.0,
+        }
+    }
+}
+
+/// Parses a replay loop count.
+pub fn parse_loops(input: &str) -> Result<u32, String> {
+    crate::validate::at_least(input, 1)
+}
+
+/// Parses a ramp factor or step, which must be finite.
+pub fn parse_ramp_value(input: &str) -> Result<f64, String> {
+    crate::validate::at_least(input, 0.0).and_then(|value: f64| {
+        value
+            .is_finite()
+            .then_some(value)
+            .ok_or_else(|| format!("{} is too large", input.trim()))
+    })
+}
+
+/// Parses a ramp value for `mode`. A factor must be at most 1, as the ramp only ever
+/// speeds a replay up.
+pub fn parse_ramp(input: &str, mode: RampMode) -> Result<f64, String> {
+    let value = parse_ramp_value(input)?;
+    match mode {
+        RampMode::Multiply if value > 1.0 => Err(format!("factor {} is more than 1", input.trim())),
+        _ => Ok(value),
+    }
+}
+
+/// Parses the send stall warning threshold in milliseconds.
+pub fn parse_stall_warn_ms(input: &str) -> Result<u64, String> {
+    crate::validate::at_least(input, 1)
+}
+
+/// Reads a number setting. Older versions wrote numbers as strings, e.g. `port = "8080"`,
+/// so both are accepted and checked with `parse`.
+fn number<'de, D, T>(deserializer: D, parse: fn(&str) -> Result<T, String>) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct NumberText;
+
+    impl serde::de::Visitor<'_> for NumberText {
+        type Value = String;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("a number")
+        }
+
+        fn visit_i64<E>(self, v: i64) -> Result<String, E> {
+            Ok(v.to_string())
+        }
+
+        fn visit_u64<E>(self, v: u64) -> Result<String, E> {
+            Ok(v.to_string())
+        }
+
+        fn visit_f64<E>(self, v: f64) -> Result<String, E> {
+            Ok(v.to_string())
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<String, E> {
+            Ok(v.to_string())
+        }
+    }
+
+    let text = deserializer.deserialize_any(NumberText)?;
+    parse(&text).map_err(serde::de::Error::custom)
+}
+
+/// Reads a port, 1 to 65535.
+pub fn port<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u16, D::Error> {
+    number(deserializer, crate::validate::port)
+}
+
+fn loops<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    number(deserializer, parse_loops)
+}
+
+fn ramp_value<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    number(deserializer, parse_ramp_value)
+}
+
+fn stall_warn_ms<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    number(deserializer, parse_stall_warn_ms)
+}
+
+fn unsigned<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    number(deserializer, |input| crate::validate::at_least(input, 0))
+}
+
+fn unsigned_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    number(deserializer, |input| crate::validate::at_least(input, 0))
+}
+
+fn capacity<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    number(deserializer, |input| crate::validate::at_least(input, 1))
+}
+
+fn datagram_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    number(deserializer, |input| {
+        let size = crate::validate::at_least(input, 1)?;
+        if size > crate::validate::UDP_MAX_PAYLOAD {
+            return Err(format!(
+                "{} is more than {}",
+                size,
+                crate::validate::UDP_MAX_PAYLOAD
+            ));
+        }
+        Ok(size)
+    })
+}
+
+fn font_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u16, D::Error> {
+    number(deserializer, |input| {
+        let size = crate::validate::at_least(input, 6)?;
+        if size > 72 {
+            return Err(format!("{} is more than 72", size));
+        }
+        Ok(size)
+    })
+}
+
+fn split_ratio<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    number(deserializer, |input| {
+        let ratio = crate::validate::at_least(input, crate::types::SPLIT_MIN)?;
+        if ratio > crate::types::SPLIT_MAX {
+            return Err(format!(
+                "{} is more than {}",
+                ratio,
+                crate::types::SPLIT_MAX
+            ));
+        }
+        Ok(ratio)
+    })
+}
+
+fn syslog_target<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    crate::syslog::parse_target(&String::deserialize(deserializer)?)
+        .map_err(serde::de::Error::custom)
+}
+
+fn syslog_facility<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    crate::syslog::parse_facility(&String::deserialize(deserializer)?)
+        .map_err(serde::de::Error::custom)
+}
+
+/// A setting in a config or settings file that failed to parse.
+#[derive(Clone, Debug)]
+pub struct SettingError {
+    pub key: String,
+    pub reason: String,
+}
+
+impl std::fmt::Display for SettingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.key, self.reason)
+    }
+}
+
+/// Why the config file could not be used in full.
+#[derive(Clone, Debug)]
+pub enum ConfigError {
+    /// The file exists but could not be read.
+    Read(String),
+    /// The file is not valid TOML, so none of it was used. The location is the line and
+    /// column of the problem, if known.
+    Syntax {
+        location: Option<(usize, usize)>,
+        message: String,
+    },
+    /// A setting has an invalid value and keeps its default.
+    Setting(SettingError),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Read(e) => write!(f, "cannot be read: {}", e),
+            ConfigError::Syntax {
+                location: Some((line, column)),
+                message,
+            } => write!(f, "line {}, column {}: {}", line, column, message),
+            ConfigError::Syntax {
+                location: None,
+                message,
+            } => f.write_str(message),
+            ConfigError::Setting(e) => write!(f, "invalid setting {}", e),
+        }
+    }
+}
+
+impl ConfigError {
+    /// Builds a syntax error, locating it in `content` by line and column.
+    fn syntax(content: &str, e: &toml::de::Error) -> Self {
+        let location = e.span().map(|span| {
+            let before = &content[..span.start.min(content.len())];
+            let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+            (
+                before.matches('\n').count() + 1,
+                before[line_start..].chars().count() + 1,
+            )
+        });
+        ConfigError::Syntax {
+            location,
+            message: e.message().to_string(),
+        }
+    }
+}
+
+/// Parses a config file. Each setting is checked on its own, so one bad value is reported
+/// by name and keeps its default instead of failing the whole file. Fails only if the
+/// file is not valid TOML.
+pub fn parse_config(content: &str) -> Result<(Config, Vec<SettingError>), ConfigError> {
+    let mut table: toml::Table =
+        toml::from_str(content).map_err(|e| ConfigError::syntax(content, &e))?;
+    let mut errors = Vec::new();
+    table.retain(|key, value| {
+        let setting = toml::Table::from_iter([(key.to_string(), value.clone())]);
+        match toml::Value::Table(setting).try_into::<Config>() {
+            Ok(_) => true,
+            Err(e) => {
+                errors.push(SettingError {
+                    key: key.to_string(),
+                    reason: e.message().to_string(),
+                });
+                false
+            }
+        }
+    });
+    let config = toml::Value::Table(table)
+        .try_into()
+        .map_err(|e| ConfigError::Syntax {
+            location: None,
+            message: e.message().to_string(),
+        })?;
+    Ok((config, errors))
+}
+
+/// Environment variable that overrides the config file location.
+pub const CONFIG_ENV: &str = "REPLAYR_CONFIG";
+/// Environment variable setting the least severe level printed to the console.
+pub const CONSOLE_LEVEL_ENV: &str = "REPLAYR_CONSOLE_LEVEL";
+
+/// Config file given with `--config` or picked with "Switch config…", which takes
+/// precedence over `$REPLAYR_CONFIG`.
+static CONFIG_OVERRIDE: std::sync::RwLock<Option<PathBuf>> = std::sync::RwLock::new(None);
+
+/// Uses `path` as the config file from now on, for the next `load_config` and every
+/// save after it.
+pub fn set_config_path(path: PathBuf) {
+    *CONFIG_OVERRIDE.write().unwrap() = Some(path);
+}
+
+/// Returns the config file set with `--config`, "Switch config…", or `$REPLAYR_CONFIG`,
+/// if any.
+fn config_override() -> Option<PathBuf> {
+    CONFIG_OVERRIDE.read().unwrap().clone().or_else(|| {
+        std::env::var_os(CONFIG_ENV)
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+    })
+}
+
+/// Returns the path of the config file: the override if set, otherwise `config.toml`
+/// in the platform config directory (e.g. `~/.config/replayr`). Falls back to the
+/// working directory if the platform has no config directory.
+pub fn config_path() -> PathBuf {
+    if let Some(path) = config_override() {
+        return path;
+    }
+    dirs::config_dir()
+        .map(|dir| dir.join("replayr").join("config.toml"))
+        .unwrap_or_else(|| PathBuf::from("config.toml"))
+}
+
+/// Copies a `config.toml` left in the working directory by older versions to `path`,
+/// unless the override is set or `path` already exists.
+fn migrate_legacy_config(path: &Path, messages: &mut Vec<LogEntry>) {
+    let legacy = Path::new("config.toml");
+    if config_override().is_some() || path.exists() || !legacy.is_file() {
+        return;
+    }
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::copy(legacy, path));
+    messages.push(match result {
+        Ok(_) => LogEntry::new(
+            LogLevel::Info,
+            &format!(
+                "Migrated config.toml from the working directory to {}",
+                path.display()
+            ),
+        ),
+        Err(e) => LogEntry::new(
+            LogLevel::Error,
+            &format!("Failed to migrate config.toml to {}: {}", path.display(), e),
+        ),
+    });
+}
+
+/// Result of loading the config file.
+pub struct ConfigLoad {
+    pub config: Config,
+    pub path: PathBuf,
+    /// Problems with the file. The affected settings, or all of them for a syntax
+    /// error, use their defaults.
+    pub errors: Vec<ConfigError>,
+    /// Where the file was copied before the next save overwrites its broken parts, or
+    /// why that failed. Only set if there are errors.
+    pub backup: Option<Result<PathBuf, String>>,
+    /// Other log entries, e.g. about migration.
+    pub messages: Vec<LogEntry>,
+}
+
+impl ConfigLoad {
+    /// One line summing up the errors for the main window banner, if there are any.
+    pub fn problem(&self) -> Option<String> {
+        let backup = match &self.backup {
+            Some(Ok(backup)) => format!(", the original is backed up to {}", backup.display()),
+            _ => String::new(),
+        };
+        match self.errors.as_slice() {
+            [] => None,
+            [error] => Some(format!(
+                "Problem in config {}: {}{}",
+                self.path.display(),
+                error,
+                backup
+            )),
+            errors => Some(format!(
+                "{} problems in config {}, see the log{}",
+                errors.len(),
+                self.path.display(),
+                backup
+            )),
+        }
+    }
+
+    /// Log entries describing the load for the main log, with any errors spelled out
+    /// first so they are hard to miss.
+    pub fn log_lines(&self) -> Vec<LogEntry> {
+        let mut lines = Vec::new();
+        if !self.errors.is_empty() {
+            lines.push(LogEntry::new(
+                LogLevel::Error,
+                &format!(
+                    "Problems in config {}, affected settings use their defaults:",
+                    self.path.display()
+                ),
+            ));
+            for error in &self.errors {
+                lines.push(LogEntry::new(LogLevel::Error, &format!("  {}", error)));
+            }
+            lines.push(match &self.backup {
+                Some(Ok(backup)) => LogEntry::new(
+                    LogLevel::Warn,
+                    &format!("The original file was backed up to {}", backup.display()),
+                ),
+                Some(Err(e)) => LogEntry::new(
+                    LogLevel::Error,
+                    &format!(
+                        "Failed to back up the original file, the next save overwrites it: {}",
+                        e
+                    ),
+                ),
+                None => LogEntry::new(
+                    LogLevel::Warn,
+                    "The file was not backed up, the next save overwrites it",
+                ),
+            });
+        }
+        lines.extend(self.messages.iter().cloned());
+        lines
+    }
+}
+
+/// Returns where a broken config file is backed up, e.g. `config.toml.bad`.
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bad");
+    path.with_file_name(name)
+}
+
+/// Loads the config. Unreadable settings fall back to their defaults; they are reported
+/// in the result, and the file is backed up first so the next save does not lose them.
+pub fn load_config() -> ConfigLoad {
+    let path = config_path();
+    let mut messages = Vec::new();
+    migrate_legacy_config(&path, &mut messages);
+    // Forget the previous file after a switch, in case this one does not exist yet, and
+    // the environment overrides, which only apply to the file loaded at startup
+    *ON_DISK.lock().unwrap() = None;
+    ENV_OVERRIDES.lock().unwrap().clear();
+    let mut errors = Vec::new();
+    let config: Config = match std::fs::read_to_string(&path) {
+        Ok(content) => match parse_config(&content) {
+            Ok((config, setting_errors)) => {
+                errors.extend(setting_errors.into_iter().map(ConfigError::Setting));
+                *ON_DISK.lock().unwrap() = Some((content, config.clone()));
+                config
+            }
+            Err(e) => {
+                errors.push(e);
+                Config::default()
+            }
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Config::default(),
+        Err(e) => {
+            errors.push(ConfigError::Read(e.to_string()));
+            Config::default()
+        }
+    };
+    let backup = (!errors.is_empty() && path.is_file()).then(|| {
+        let backup = backup_path(&path);
+        std::fs::copy(&path, &backup)
+            .map(|_| backup)
+            .map_err(|e| e.to_string())
+    });
+    if !config.unknown.is_empty() {
+        let keys: Vec<&str> = config.unknown.keys().map(String::as_str).collect();
+        messages.push(LogEntry::new(
+            LogLevel::Warn,
+            &format!(
+                "Unknown settings in {} are kept as they are: {}",
+                path.display(),
+                keys.join(", ")
+            ),
+        ));
+    }
+    if let Err(e) =
+        crate::log::set_timestamp_format(&config.log_timestamp_format, config.log_timestamp_utc)
+    {
+        messages.push(LogEntry::new(
+            LogLevel::Warn,
+            &format!("{}, using {}", e, crate::log::DEFAULT_TIMESTAMP_FORMAT),
+        ));
+    }
+    ConfigLoad {
+        config,
+        path,
+        errors,
+        backup,
+        messages,
+    }
+}
+
+/// Prefix of the environment variables that override settings.
+const ENV_PREFIX: &str = "REPLAYR_";
+
+/// Reads an environment variable's value into the TOML value of a setting.
+type EnvParser = fn(&str) -> Result<toml::Value, String>;
+
+/// Environment variables that override settings, with the setting each one sets.
+const ENV_SETTINGS: &[(&str, &str, EnvParser)] = &[
+    ("REPLAYR_ADDRESS", "address", |v| {
+        crate::validate::address(v).map(|_| toml::Value::String(v.to_string()))
+    }),
+    ("REPLAYR_PORT", "port", |v| {
+        crate::validate::port(v).map(|port| toml::Value::Integer(port.into()))
+    }),
+    ("REPLAYR_PROTOCOL", "protocol", |v| {
+        match v.to_ascii_lowercase().as_str() {
+            "tcp" => Ok(toml::Value::String("Tcp".to_string())),
+            "udp" => Ok(toml::Value::String("Udp".to_string())),
+            _ => Err(format!("{} is not tcp or udp", v)),
+        }
+    }),
+    ("REPLAYR_INITIAL_PAYLOAD", "initial_payload", |v| {
+        Ok(toml::Value::String(v.to_string()))
+    }),
+    ("REPLAYR_IDLE_TIMEOUT_SECS", "idle_timeout_secs", |v| {
+        crate::validate::at_least::<i64>(v, 0).map(toml::Value::Integer)
+    }),
+    ("REPLAYR_SEND_STALL_WARN_MS", "send_stall_warn_ms", |v| {
+        parse_stall_warn_ms(v).map(|ms| toml::Value::Integer(ms.min(i64::MAX as u64) as i64))
+    }),
+    ("REPLAYR_REPLAY_LOOPS", "replay_loops", |v| {
+        parse_loops(v).map(|loops| toml::Value::Integer(loops.into()))
+    }),
+    ("REPLAYR_AUTO_EXPORT_DIR", "auto_export_dir", |v| {
+        Ok(toml::Value::String(v.to_string()))
+    }),
+];
+
+/// A setting overridden by an environment variable, and the value to save in its place
+/// as long as it is not changed in the app.
+struct EnvOverride {
+    key: &'static str,
+    value: toml::Value,
+    saved: Option<toml::Value>,
+}
+
+/// Settings overridden by environment variables at startup.
+static ENV_OVERRIDES: std::sync::Mutex<Vec<EnvOverride>> = std::sync::Mutex::new(Vec::new());
+
+/// Applies the `REPLAYR_*` environment variables on top of the loaded `config` and
+/// returns log lines naming the ones that took effect. Invalid values and unknown
+/// variables are ignored with a warning. Overridden settings are not saved unless they
+/// are changed in the app.
+pub fn apply_env_overrides(config: &mut Config) -> Vec<LogEntry> {
+    let mut lines = Vec::new();
+    let Ok(toml::Value::Table(mut table)) = toml::Value::try_from(&*config) else {
+        return lines;
+    };
+    let mut overrides = Vec::new();
+    let mut applied = Vec::new();
+    for (name, key, parse) in ENV_SETTINGS {
+        let Ok(value) = std::env::var(name) else {
+            continue;
+        };
+        match parse(value.trim()) {
+            Ok(parsed) => {
+                overrides.push(EnvOverride {
+                    key,
+                    saved: table.insert(key.to_string(), parsed.clone()),
+                    value: parsed,
+                });
+                applied.push(format!("{}={}", name, value.trim()));
+            }
+            Err(e) => lines.push(LogEntry::new(
+                LogLevel::Warn,
+                &format!("Ignoring {}: {}", name, e),
+            )),
+        }
+    }
+    for (name, _) in std::env::vars_os() {
+        let name = name.to_string_lossy();
+        if name.starts_with(ENV_PREFIX)
+            && name != CONFIG_ENV
+            && name != CONSOLE_LEVEL_ENV
+            && !ENV_SETTINGS.iter().any(|(known, _, _)| *known == name)
+        {
+            lines.push(LogEntry::new(
+                LogLevel::Warn,
+                &format!("Unknown environment variable {} ignored", name),
+            ));
+        }
     }
+    match toml::Value::Table(table).try_into() {
+        Ok(overridden) => {
+            *config = overridden;
+            *ENV_OVERRIDES.lock().unwrap() = overrides;
+            if !applied.is_empty() {
+                lines.push(LogEntry::new(
+                    LogLevel::Info,
+                    &format!("Settings from environment: {}", applied.join(", ")),
+                ));
+            }
+        }
+        Err(e) => lines.push(LogEntry::new(
+            LogLevel::Warn,
+            &format!("Ignoring environment overrides: {}", e.message()),
+        )),
+    }
+    lines
+}
+
+/// Puts the saved values back for settings still at their environment override, and
+/// forgets overrides that were changed in the app so the new value is saved.
+fn restore_env_overrides(table: &mut toml::Table) {
+    ENV_OVERRIDES.lock().unwrap().retain(|o| {
+        if table.get(o.key) != Some(&o.value) {
+            return false;
+        }
+        match &o.saved {
+            Some(saved) => table.insert(o.key.to_string(), saved.clone()),
+            None => table.remove(o.key),
+        };
+        true
+    });
+}
+
+/// Number of config files kept in the recent configs list.
+pub const MAX_RECENT_CONFIGS: usize = 10;
+
+/// Config files used recently, stored apart from any one config so every project
+/// config shares the list.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct RecentConfigs {
+    paths: Vec<PathBuf>,
+}
+
+/// Returns where the recent configs list is stored, in the platform config directory.
+fn recent_configs_path() -> PathBuf {
+    dirs::config_dir()
+        .map(|dir| dir.join("replayr"))
+        .unwrap_or_default()
+        .join("recent_configs.toml")
+}
+
+/// Returns the config files used recently, most recent first.
+pub fn recent_configs() -> Vec<PathBuf> {
+    std::fs::read_to_string(recent_configs_path())
+        .ok()
+        .and_then(|content| toml::from_str::<RecentConfigs>(&content).ok())
+        .unwrap_or_default()
+        .paths
+}
+
+/// Moves `path` to the front of the recent configs list, dropping the oldest entry once
+/// the list is full. Returns the updated list.
+pub fn remember_config(path: &Path) -> Result<Vec<PathBuf>, String> {
+    let path = std::path::absolute(path).map_err(|e| e.to_string())?;
+    let mut paths = recent_configs();
+    paths.retain(|p| *p != path);
+    paths.insert(0, path);
+    paths.truncate(MAX_RECENT_CONFIGS);
+    let list_path = recent_configs_path();
+    let content = toml::to_string(&RecentConfigs {
+        paths: paths.clone(),
+    })
+    .map_err(|e| e.to_string())?;
+    list_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&list_path, content))
+        .map_err(|e| format!("{}: {}", list_path.display(), e))?;
+    Ok(paths)
+}
+
+/// Serializes the config for saving, with settings still at their environment override
+/// replaced by the values they hide. Returns the text and the config it describes.
+fn saved_form(config: &Config) -> Result<(String, Config), String> {
+    let saved = if ENV_OVERRIDES.lock().unwrap().is_empty() {
+        config.clone()
+    } else {
+        let mut table = toml::Table::try_from(config).map_err(|e| e.to_string())?;
+        restore_env_overrides(&mut table);
+        toml::Value::Table(table)
+            .try_into()
+            .map_err(|e: toml::de::Error| e.to_string())?
+    };
+    let s = toml::to_string(&saved).map_err(|e| e.to_string())?;
+    Ok((s, saved))
 }
 
+/// Time without changes before a pending config is written.
+pub const SAVE_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Config waiting to be written, and when it last changed. Typing in a field thus writes
+/// the file once instead of on every keystroke.
+static PENDING_SAVE: std::sync::Mutex<Option<(Config, std::time::Instant)>> =
+    std::sync::Mutex::new(None);
+
+/// Why the last save failed, until a save succeeds again.
+static SAVE_ERROR: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+
+/// Saves the config once it has not changed for `SAVE_DELAY`, see `flush_config`.
 pub fn save_config(config: &Config) {
-    if let Ok(s) = toml::to_string(config) {
-        let _ = std::fs::write("config.toml", s);
+    *PENDING_SAVE.lock().unwrap() = Some((config.clone(), std::time::Instant::now()));
+}
+
+/// Whether a save is waiting to be written.
+pub fn save_pending() -> bool {
+    PENDING_SAVE.lock().unwrap().is_some()
+}
+
+/// Writes the pending config once it has not changed for `SAVE_DELAY`, or right away
+/// with `now`, e.g. before exiting or switching config files.
+pub fn flush_config(now: bool) {
+    let pending = {
+        let mut pending = PENDING_SAVE.lock().unwrap();
+        match &*pending {
+            Some((_, changed)) if now || changed.elapsed() >= SAVE_DELAY => pending.take(),
+            _ => None,
+        }
+    };
+    if let Some((config, _)) = pending {
+        write_config(&config);
+    }
+}
+
+/// Returns why saving the config fails, until a save succeeds again.
+pub fn save_error() -> Option<String> {
+    SAVE_ERROR.lock().unwrap().clone()
+}
+
+/// Writes the config, creating its directory on first save. A failure is reported in
+/// the main log once, and again only after a save has succeeded in between.
+fn write_config(config: &Config) {
+    let path = config_path();
+    let result = saved_form(config).and_then(|(s, saved)| {
+        path.parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&path, &s))
+            .map_err(|e| e.to_string())?;
+        // Remembered so the watcher does not reload the app's own write
+        *ON_DISK.lock().unwrap() = Some((s, saved));
+        Ok(())
+    });
+    let mut save_error = SAVE_ERROR.lock().unwrap();
+    let (level, message) = match result {
+        Err(e) => {
+            let error = format!("{}: {}", path.display(), e);
+            if save_error.replace(error.clone()).is_some() {
+                return;
+            }
+            (
+                LogLevel::Error,
+                format!(
+                    "Failed to save config {}. Settings changed from now on are lost on exit; \
+                     further failures are not logged until saving works again",
+                    error
+                ),
+            )
+        }
+        Ok(()) if save_error.take().is_some() => (
+            LogLevel::Info,
+            format!("Config saved to {} again", path.display()),
+        ),
+        Ok(()) => return,
+    };
+    drop(save_error);
+    crate::log::main_log_blocking(level, &message);
+}
+
+/// A parsed settings file, ready to be confirmed and applied.
+#[derive(Clone, Debug)]
+pub struct SettingsImport {
+    pub config: Config,
+    /// One line per setting that differs from the current config.
+    pub changes: Vec<String>,
+    /// Unknown and missing settings, which are ignored and kept respectively.
+    pub warnings: Vec<String>,
+}
+
+/// Serializes the config for export, as JSON for `.json` files and TOML otherwise.
+pub fn export_settings(config: &Config, path: &Path) -> Result<String, String> {
+    if is_json(path) {
+        serde_json::to_string_pretty(config).map_err(|e| e.to_string())
+    } else {
+        toml::to_string(config).map_err(|e| e.to_string())
+    }
+}
+
+/// Parses an exported settings file (JSON or TOML, by extension) on top of `current`.
+/// Settings missing from the file keep their current values and unknown ones are
+/// skipped; both are reported as warnings. Invalid settings fail the import, each named
+/// in the error.
+pub fn import_settings(
+    current: &Config,
+    path: &Path,
+    content: &str,
+) -> Result<SettingsImport, String> {
+    let imported: serde_json::Value = if is_json(path) {
+        serde_json::from_str(content).map_err(|e| format!("Invalid JSON: {}", e))?
+    } else {
+        toml::from_str(content).map_err(|e| format!("Invalid TOML: {}", e))?
+    };
+    let serde_json::Value::Object(imported) = imported else {
+        return Err("Settings file does not contain a table of settings".to_string());
+    };
+    let current_value = serde_json::to_value(current).map_err(|e| e.to_string())?;
+    let serde_json::Value::Object(mut merged) = current_value.clone() else {
+        return Err("Current settings are not a table".to_string());
+    };
+    let mut warnings = Vec::new();
+    let mut errors = Vec::new();
+    for (key, value) in imported.iter() {
+        match merged.get_mut(key) {
+            Some(slot) => *slot = value.clone(),
+            None => warnings.push(format!("Unknown setting ignored: {}", key)),
+        }
+        // Checked one by one so every invalid setting is named, not just the first
+        let setting = serde_json::Map::from_iter([(key.clone(), value.clone())]);
+        if let Err(e) = serde_json::from_value::<Config>(serde_json::Value::Object(setting)) {
+            errors.push(SettingError {
+                key: key.clone(),
+                reason: e.to_string(),
+            });
+        }
+    }
+    if !errors.is_empty() {
+        let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        return Err(format!("Invalid settings: {}", errors.join(", ")));
+    }
+    for key in merged.keys().filter(|key| !imported.contains_key(*key)) {
+        warnings.push(format!("Missing setting kept: {}", key));
+    }
+    let config: Config = serde_json::from_value(serde_json::Value::Object(merged.clone()))
+        .map_err(|e| format!("Invalid settings: {}", e))?;
+    let changes = merged
+        .iter()
+        .filter(|(key, value)| current_value.get(key.as_str()) != Some(*value))
+        .map(|(key, value)| describe_change(key, &current_value[key.as_str()], value))
+        .collect();
+    Ok(SettingsImport {
+        config,
+        changes,
+        warnings,
+    })
+}
+
+/// Describes a changed setting, e.g. `port: "8080" -> "9000"`. Lists and tables are
+/// only named.
+fn describe_change(key: &str, old: &serde_json::Value, new: &serde_json::Value) -> String {
+    match new {
+        serde_json::Value::Array(_) | serde_json::Value::Object(_) => format!("{}: changed", key),
+        _ => format!("{}: {} -> {}", key, old, new),
+    }
+}
+
+/// The config file as last read or written by the app, and its parsed form. Tells the
+/// app's own writes apart from outside edits, and settings edited in the app from
+/// settings edited in the file.
+static ON_DISK: std::sync::Mutex<Option<(String, Config)>> = std::sync::Mutex::new(None);
+
+/// Settings merged from a config file edited outside the app.
+#[derive(Clone, Debug)]
+pub struct ConfigReload {
+    pub config: Config,
+    /// One line per setting taken from the file.
+    pub changes: Vec<String>,
+    /// Settings changed both in the app and in the file, where the app's value is kept.
+    pub warnings: Vec<String>,
+}
+
+/// Re-reads the config file and merges it into `current`. A setting changed in the file
+/// is taken unless it was also changed in the app since the file was last read or
+/// written; then the app's value wins. Returns None if the file is unchanged, e.g. after
+/// the app's own save. Unreadable, empty, or malformed files leave `current` untouched.
+pub fn reload_config(current: &Config) -> Result<Option<ConfigReload>, String> {
+    let path = config_path();
+    let content =
+        std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut on_disk = ON_DISK.lock().unwrap();
+    if on_disk.as_ref().is_some_and(|(text, _)| *text == content) {
+        return Ok(None);
+    }
+    // An editor may truncate the file before writing it; that must not reset everything
+    if content.trim().is_empty() {
+        return Err(format!("{} is empty", path.display()));
+    }
+    let (file, errors) =
+        parse_config(&content).map_err(|e| format!("Invalid config {}: {}", path.display(), e))?;
+    let base = on_disk.as_ref().map_or(current, |(_, config)| config);
+    let to_value = |config: &Config| serde_json::to_value(config).map_err(|e| e.to_string());
+    let (base, ui, file_value) = (to_value(base)?, to_value(current)?, to_value(&file)?);
+    let (serde_json::Value::Object(mut merged), serde_json::Value::Object(file_settings)) =
+        (ui.clone(), file_value)
+    else {
+        return Err("Settings are not a table".to_string());
+    };
+    let mut changes = Vec::new();
+    let mut warnings: Vec<String> = errors
+        .iter()
+        .map(|error| format!("Invalid setting {}, keeping the current value", error))
+        .collect();
+    for (key, value) in file_settings {
+        let (old, edited) = (&base[key.as_str()], &ui[key.as_str()]);
+        if value == *old || value == *edited || errors.iter().any(|error| error.key == key) {
+            continue;
+        }
+        if edited == old {
+            changes.push(describe_change(&key, old, &value));
+            merged.insert(key, value);
+        } else {
+            warnings.push(format!(
+                "{} was changed in the app and in the file, keeping the app's value",
+                key
+            ));
+        }
+    }
+    let config = serde_json::from_value(serde_json::Value::Object(merged))
+        .map_err(|e| format!("Invalid config {}: {}", path.display(), e))?;
+    *on_disk = Some((content, file));
+    Ok(Some(ConfigReload {
+        config,
+        changes,
+        warnings,
+    }))
+}
+
+/// Yields whenever the config file changes on disk. The containing directory is watched,
+/// since editors often replace the file instead of writing to it.
+pub fn watch_config() -> impl iced::futures::Stream<Item = ()> {
+    iced::stream::channel(1, |mut output| async move {
+        use iced::futures::SinkExt;
+        let path = config_path();
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+        let name = path.file_name().map(|name| name.to_os_string());
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let watcher = std::fs::create_dir_all(&dir)
+            .map_err(|e| e.to_string())
+            .and_then(|_| {
+                let mut watcher =
+                    notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                        if let Ok(event) = event
+                            && !matches!(event.kind, notify::EventKind::Access(_))
+                            && event.paths.iter().any(|p| p.file_name() == name.as_deref())
+                        {
+                            let _ = tx.send(());
+                        }
+                    })
+                    .map_err(|e| e.to_string())?;
+                notify::Watcher::watch(&mut watcher, &dir, notify::RecursiveMode::NonRecursive)
+                    .map_err(|e| e.to_string())?;
+                Ok(watcher)
+            });
+        let _watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                crate::log::main_log(
+                    LogLevel::Warn,
+                    &format!("Not watching {} for changes: {}", path.display(), e),
+                )
+                .await;
+                return;
+            }
+        };
+        while rx.recv().await.is_some() {
+            // Editors write in several steps; let them settle before reading
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            while rx.try_recv().is_ok() {}
+            if output.send(()).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Whether a settings file is JSON rather than TOML.
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn old_files_with_numbers_as_strings_load() {
+        let old = r#"
+            address = "10.0.0.7"
+            port = "8080"
+            replay_loops = "3"
+            replay_ramp_value = "0.5"
+        "#;
+        let (config, errors) = parse_config(old).unwrap();
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(config.address, "10.0.0.7");
+        assert_eq!(config.port, 8080);
+        assert_eq!(config.replay_loops, 3);
+        assert_eq!(config.replay_ramp_value, 0.5);
+    }
+
+    #[test]
+    fn invalid_port_is_reported_and_keeps_its_default() {
+        let old = r#"
+            address = "10.0.0.7"
+            port = "banana"
+            replay_loops = "3"
+        "#;
+        let (config, errors) = parse_config(old).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].key, "port");
+        assert!(errors[0].reason.contains("banana"), "{}", errors[0].reason);
+        assert_eq!(config.port, Config::default().port);
+        // The other settings of the file are still used
+        assert_eq!(config.address, "10.0.0.7");
+        assert_eq!(config.replay_loops, 3);
+    }
+
+    #[test]
+    fn profiles_load_from_tables_and_are_written_back() {
+        let file = r#"
+            address = "10.0.0.7"
+
+            [[profiles]]
+            name = "Boiler room"
+            protocol = "Udp"
+            address = "192.168.1.20"
+            port = "9000"
+        "#;
+        let (config, errors) = parse_config(file).unwrap();
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(config.profiles.len(), 1);
+        assert_eq!(config.profiles[0].to_string(), "Boiler room");
+        assert_eq!(
+            config.profiles[0].target(),
+            RecentTarget {
+                protocol: Protocol::Udp,
+                address: "192.168.1.20".to_string(),
+                port: 9000,
+            }
+        );
+        let written = toml::to_string(&config).unwrap();
+        let (reread, _) = parse_config(&written).unwrap();
+        assert_eq!(reread.profiles, config.profiles);
     }
 }
@@ -0,0 +1,83 @@
+// Remembered window sizes and positions, applied when windows open.
+
+use iced::window;
+use iced::{Point, Size};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Offset between cascaded windows, in logical pixels.
+const CASCADE_STEP: f32 = 30.0;
+/// Number of cascade steps before starting over at the remembered position.
+const CASCADE_STEPS: usize = 10;
+
+/// Size and position of a window. The position is unknown until the window was moved,
+/// and some platforms (e.g. Wayland) never report it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct WindowGeometry {
+    pub x: Option<f32>,
+    pub y: Option<f32>,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for WindowGeometry {
+    fn default() -> Self {
+        let size = window::Settings::default().size;
+        Self {
+            x: None,
+            y: None,
+            width: size.width,
+            height: size.height,
+        }
+    }
+}
+
+lazy_static! {
+    /// Positions of windows about to open, in the order they were requested. The
+    /// position callback of `window::Settings` cannot capture, so it takes them from here.
+    static ref PENDING_POSITIONS: Mutex<VecDeque<Point>> = Mutex::new(VecDeque::new());
+}
+
+/// Builds the settings of a window opened with the remembered `geometry`. `cascade` is
+/// the number of windows already open, each moving the new one further down and right.
+pub fn settings(geometry: Option<&WindowGeometry>, cascade: usize) -> window::Settings {
+    let Some(geometry) = geometry else {
+        return window::Settings::default();
+    };
+    let position = match (geometry.x, geometry.y) {
+        (Some(x), Some(y)) => {
+            let offset = (cascade % CASCADE_STEPS) as f32 * CASCADE_STEP;
+            PENDING_POSITIONS
+                .lock()
+                .unwrap()
+                .push_back(Point::new(x + offset, y + offset));
+            window::Position::SpecificWith(clamp_to_monitor)
+        }
+        _ => window::Position::Default,
+    };
+    window::Settings {
+        size: Size::new(geometry.width, geometry.height),
+        position,
+        ..window::Settings::default()
+    }
+}
+
+/// Places the next pending window position inside the monitor, so windows remembered on
+/// a monitor that is no longer connected still open where they can be seen.
+fn clamp_to_monitor(window: Size, monitor: Size) -> Point {
+    let position = PENDING_POSITIONS
+        .lock()
+        .unwrap()
+        .pop_front()
+        .unwrap_or(Point::ORIGIN);
+    Point::new(
+        position
+            .x
+            .clamp(0.0, (monitor.width - window.width).max(0.0)),
+        position
+            .y
+            .clamp(0.0, (monitor.height - window.height).max(0.0)),
+    )
+}
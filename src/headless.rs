@@ -0,0 +1,131 @@
+// Headless replay for scripts and CI: runs a replay file against a target without
+// starting the GUI and reports the outcome through the exit code.
+
+use crate::log::{LogEntry, LogLevel, console, styled};
+use crate::replay::{ConsoleProgress, Reporter, replay_task};
+use crate::types::{Protocol, ReplayOptions, ReplayableSession};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use tokio_util::sync::CancellationToken;
+
+/// Exit code when the replay was interrupted with Ctrl+C.
+const EXIT_INTERRUPTED: i32 = 130;
+
+/// Settings of a headless replay run.
+pub struct HeadlessReplay {
+    pub file: PathBuf,
+    /// Protocol for CSV files, and checked against JSON files if given.
+    pub protocol: Option<Protocol>,
+    /// Target; defaults to the target recorded in the file.
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    pub loops: u32,
+    pub speed: f64,
+}
+
+/// Runs the replay and returns the process exit code: 0 if every iteration completed
+/// without failures, 1 on load, connection, or send failures, 130 if interrupted.
+pub fn run(args: HeadlessReplay) -> i32 {
+    let (replay, addr, port) = match load(&args) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            anstream::eprintln!("{}", styled(&LogEntry::new(LogLevel::Error, &e)));
+            return 1;
+        }
+    };
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            anstream::eprintln!(
+                "{}",
+                styled(&LogEntry::new(LogLevel::Error, &e.to_string()))
+            );
+            return 1;
+        }
+    };
+    let options = ReplayOptions {
+        loops: args.loops,
+        speed: args.speed,
+        ..Default::default()
+    };
+    let total = replay.payloads.len();
+    let loops = args.loops;
+    runtime.block_on(async move {
+        let cancel = CancellationToken::new();
+        let progress = Arc::new(ConsoleProgress::default());
+        let mut task = tokio::spawn(replay_task(
+            replay.protocol,
+            replay.payloads,
+            addr,
+            port,
+            options,
+            replay.tokens,
+            cancel.clone(),
+            Reporter::Console(progress.clone()),
+        ));
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(5));
+        ticker.tick().await;
+        let mut interrupted = false;
+        loop {
+            tokio::select! {
+                result = &mut task => {
+                    return match result {
+                        Ok(_) if interrupted => EXIT_INTERRUPTED,
+                        Ok(true) => 0,
+                        _ => 1,
+                    };
+                }
+                _ = ticker.tick() => {
+                    console(&LogEntry::new(
+                        LogLevel::Info,
+                        &format!(
+                            "Progress: {}/{} payloads, iteration {}/{}",
+                            progress.sent.load(Ordering::Relaxed),
+                            total,
+                            progress.iteration.load(Ordering::Relaxed),
+                            loops
+                        ),
+                    ));
+                }
+                // Stop the replay and let it close the connection before exiting
+                _ = tokio::signal::ctrl_c(), if !interrupted => {
+                    anstream::eprintln!(
+                        "{}",
+                        styled(&LogEntry::new(LogLevel::Warn, "Interrupted, stopping replay"))
+                    );
+                    interrupted = true;
+                    cancel.cancel();
+                }
+            }
+        }
+    })
+}
+
+/// Reads the replay file and resolves the target to send to.
+fn load(args: &HeadlessReplay) -> Result<(ReplayableSession, String, String), String> {
+    let content = std::fs::read_to_string(&args.file)
+        .map_err(|e| format!("Failed to read {}: {}", args.file.display(), e))?;
+    // JSON files name their protocol; CSV files are assumed to be TCP unless told otherwise
+    let protocol = args.protocol.unwrap_or_else(|| {
+        serde_json::from_str::<ReplayableSession>(&content)
+            .map(|replay| replay.protocol)
+            .unwrap_or(Protocol::Tcp)
+    });
+    let replay = crate::ui::parse_replay_file(&args.file, &content, protocol)?;
+    let recorded = replay
+        .target
+        .as_deref()
+        .and_then(|target| target.rsplit_once(':'));
+    let addr = match (&args.address, recorded) {
+        (Some(addr), _) => addr.clone(),
+        (None, Some((addr, _))) => addr.to_string(),
+        (None, None) => return Err("No --address given and the file has no target".to_string()),
+    };
+    let port = match (args.port, recorded) {
+        (Some(port), _) => port.to_string(),
+        (None, Some((_, port))) => port.to_string(),
+        (None, None) => return Err("No --port given and the file has no target".to_string()),
+    };
+    Ok((replay, addr, port))
+}
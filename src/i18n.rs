@@ -0,0 +1,92 @@
+// Translations of the user interface. Strings are looked up by their English text in a
+// catalog embedded in the binary; log content stays in English.
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Language of the user interface.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    /// Catalog of the language: English text to translation. English needs none.
+    fn catalog(self) -> Option<&'static HashMap<String, String>> {
+        match self {
+            Language::English => None,
+            Language::German => Some(&GERMAN),
+        }
+    }
+}
+
+impl std::fmt::Display for Language {
+    /// Each language is named in itself, so it can be found without reading the current one.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+        })
+    }
+}
+
+lazy_static! {
+    static ref GERMAN: HashMap<String, String> = parse(include_str!("../assets/i18n/de.toml"));
+}
+
+/// Language strings are currently looked up in, as its index in `Language::ALL`.
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+/// Reads a catalog of `"English text" = "translation"` lines. A catalog that does not
+/// parse translates nothing, leaving the English text.
+fn parse(catalog: &str) -> HashMap<String, String> {
+    let table: toml::Table = toml::from_str(catalog).unwrap_or_default();
+    table
+        .into_iter()
+        .filter_map(|(english, translation)| Some((english, translation.as_str()?.to_string())))
+        .collect()
+}
+
+/// Switches the language of strings looked up from now on.
+pub fn set_language(language: Language) {
+    let index = Language::ALL
+        .iter()
+        .position(|candidate| *candidate == language)
+        .unwrap_or_default();
+    LANGUAGE.store(index as u8, Ordering::Relaxed);
+}
+
+fn language() -> Language {
+    Language::ALL
+        .get(LANGUAGE.load(Ordering::Relaxed) as usize)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Translates `english` to the current language, or returns it unchanged if the catalog
+/// has no translation for it.
+pub fn tr(english: &'static str) -> &'static str {
+    language()
+        .catalog()
+        .and_then(|catalog| catalog.get(english))
+        .map_or(english, String::as_str)
+}
+
+/// Translates `english` and fills its `{}` placeholders with `args`, in order.
+pub fn tr_args(english: &'static str, args: &[&dyn std::fmt::Display]) -> String {
+    let mut parts = tr(english).split("{}");
+    let mut text = parts.next().unwrap_or_default().to_string();
+    for (index, part) in parts.enumerate() {
+        if let Some(arg) = args.get(index) {
+            text.push_str(&arg.to_string());
+        }
+        text.push_str(part);
+    }
+    text
+}
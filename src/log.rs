@@ -1,13 +1,16 @@
-use crate::types::LogMessage;
+use crate::types::{Direction, LogMessage, PendingProgress, ReplayEvent, SessionEvent};
+use iced::futures::future::BoxFuture;
 use iced::window;
 use lazy_static::lazy_static;
 use tokio::sync::broadcast;
 
 use chrono::Local;
 
-/// Represents the severity level of a log message.
-#[derive(Clone, Copy, Debug)]
+/// Represents the severity level of a log message, from least to most severe.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum LogLevel {
+    /// Socket-level detail, only logged while debug logging is on.
+    Debug,
     Info,
     Warn,
     Error,
@@ -16,6 +19,7 @@ pub enum LogLevel {
 impl std::fmt::Display for LogLevel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            LogLevel::Debug => write!(f, "DEBUG"),
             LogLevel::Info => write!(f, "INFO"),
             LogLevel::Warn => write!(f, "WARN"),
             LogLevel::Error => write!(f, "ERR"),
@@ -23,41 +27,1251 @@ impl std::fmt::Display for LogLevel {
     }
 }
 
+/// Default strftime pattern for log timestamps.
+pub const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+/// Timestamp settings applied by `format_log`.
+struct TimestampFormat {
+    pattern: String,
+    utc: bool,
+}
+
+/// Checks that `pattern` is a valid strftime pattern.
+pub fn check_timestamp_format(pattern: &str) -> Result<(), String> {
+    chrono::format::StrftimeItems::new(pattern)
+        .parse()
+        .map(|_| ())
+        .map_err(|_| format!("Invalid timestamp pattern: {}", pattern))
+}
+
+/// Sets the timestamp pattern and timezone of all following log lines. An invalid
+/// pattern falls back to `DEFAULT_TIMESTAMP_FORMAT` and returns the error.
+pub fn set_timestamp_format(pattern: &str, utc: bool) -> Result<(), String> {
+    let checked = check_timestamp_format(pattern);
+    let mut format = TIMESTAMP_FORMAT.write().unwrap();
+    format.pattern = match checked {
+        Ok(()) => pattern.to_string(),
+        Err(_) => DEFAULT_TIMESTAMP_FORMAT.to_string(),
+    };
+    format.utc = utc;
+    checked
+}
+
+/// A log message with the time it was logged and its level.
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub level: LogLevel,
+    /// The rendered text, as shown and exported.
+    pub message: String,
+    /// The packet sent or received, for packet lines; None for status lines.
+    pub packet: Option<LogPacket>,
+}
+
+/// A packet logged with its entry.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogPacket {
+    /// Number of the packet in its window.
+    pub seq: u64,
+    pub direction: Direction,
+    pub data: Vec<u8>,
+}
+
+impl LogEntry {
+    /// Creates an entry for `msg` logged now.
+    pub fn new(level: LogLevel, msg: &str) -> Self {
+        let format = TIMESTAMP_FORMAT.read().unwrap();
+        let timestamp = if format.utc {
+            chrono::Utc::now().format(&format.pattern).to_string()
+        } else {
+            Local::now().format(&format.pattern).to_string()
+        };
+        Self {
+            timestamp,
+            level,
+            message: msg.to_string(),
+            packet: None,
+        }
+    }
+
+    /// Creates an Info entry for a packet sent or received now, e.g. "#42 RX 96 B: 4865".
+    /// `detail`, if not empty, follows the size, e.g. the sender of a UDP datagram, and
+    /// `text` is the data as shown.
+    pub fn packet(seq: u64, direction: Direction, data: &[u8], detail: &str, text: &str) -> Self {
+        Self {
+            packet: Some(LogPacket {
+                seq,
+                direction,
+                data: data.to_vec(),
+            }),
+            ..Self::new(
+                LogLevel::Info,
+                &packet_line(seq, direction, data.len(), detail, text),
+            )
+        }
+    }
+
+    /// Creates the entry of a sent packet, e.g. "#41 TX 2 B [firmware erase]: 0a0b".
+    pub fn sent(seq: u64, data: &[u8], label: Option<&str>, payload: &str) -> Self {
+        let label = label
+            .map(|label| format!("[{}]", label))
+            .unwrap_or_default();
+        Self::packet(seq, Direction::Sent, data, &label, payload)
+    }
+}
+
+impl std::fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] [{}] {}", self.timestamp, self.level, self.message)
+    }
+}
+
 /// Formats a log message with timestamp and level prefix.
 pub fn format_log(level: LogLevel, msg: &str) -> String {
-    format!(
-        "[{}] [{}] {}",
-        Local::now().format("%Y-%m-%d %H:%M:%S"),
-        level,
-        msg
-    )
+    LogEntry::new(level, msg).to_string()
+}
+
+/// The log of a window. Filters only hide entries from view. Once it holds more than the
+/// limit set with `set_max_entries`, the oldest entries are dropped.
+#[derive(Clone, Default, Debug)]
+pub struct Log {
+    entries: Vec<LogEntry>,
+    /// Entries dropped from the front to stay within the limit.
+    dropped: usize,
+    /// Rows of the last view drawn, extended as entries are added.
+    index: RowCache,
+}
+
+impl Log {
+    /// Appends `msg` logged now.
+    pub fn push(&mut self, level: LogLevel, msg: &str) {
+        self.push_entry(LogEntry::new(level, msg));
+    }
+
+    /// Appends an entry logged elsewhere, e.g. by a session task.
+    pub fn push_entry(&mut self, entry: LogEntry) {
+        self.entries.push(entry);
+        self.trim();
+    }
+
+    /// Entries kept, oldest first.
+    pub fn entries(&self) -> &[LogEntry] {
+        &self.entries
+    }
+
+    /// Number of entries ever added, including those dropped since. Unlike the number
+    /// kept, it grows with every entry, so it tells when entries were added.
+    pub fn added(&self) -> usize {
+        self.dropped + self.entries.len()
+    }
+
+    /// Number of the oldest entries dropped to stay within the limit.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Drops the oldest entries once the log exceeds its limit by more than a sixteenth.
+    /// Dropping in batches keeps appending cheap, as every drop moves the entries kept.
+    fn trim(&mut self) {
+        let max = MAX_ENTRIES.load(std::sync::atomic::Ordering::Relaxed);
+        if max == 0 || self.entries.len() <= max + max / 16 {
+            return;
+        }
+        let excess = self.entries.len() - max;
+        self.entries.drain(..excess);
+        self.dropped += excess;
+    }
+
+    /// Returns the lines of the entries shown by `filter`, each ending in a newline.
+    pub fn text(&self, filter: LogFilter) -> String {
+        self.lines(filter, &LogSearch::default(), Collapse::OFF)
+    }
+
+    /// Returns the lines shown in a log view: those of the entries shown by `filter`
+    /// matching `search`, with runs of repeated entries collapsed as set with
+    /// `set_collapse`.
+    pub fn view(&self, filter: LogFilter, search: &LogSearch) -> String {
+        self.lines(filter, search, *COLLAPSE.read().unwrap())
+    }
+
+    /// Like `text`, but with runs of repeated entries collapsed as in the view.
+    pub fn collapsed(&self, filter: LogFilter) -> String {
+        self.lines(filter, &LogSearch::default(), *COLLAPSE.read().unwrap())
+    }
+
+    /// Whether the view collapses any run of repeated entries shown by `filter`.
+    pub fn has_repeats(&self, filter: LogFilter) -> bool {
+        let collapse = *COLLAPSE.read().unwrap();
+        let shown = self.shown(filter);
+        runs(&shown, collapse).any(|run| run.len() >= collapse.threshold)
+    }
+
+    /// Returns the rows of a log view, like `view`, without formatting the rows not
+    /// searched, so only those on screen need to be.
+    pub fn rows(&self, filter: LogFilter, search: &LogSearch) -> Vec<LogRow<'_>> {
+        self.rows_with(filter, search, *COLLAPSE.read().unwrap())
+    }
+
+    /// Number of rows of a log view, like `rows`. The rows are indexed once per filter,
+    /// search, and collapsing, and the index extended as entries are added, so redrawing
+    /// a long log does not go through every entry.
+    pub fn row_count(&self, filter: LogFilter, search: &LogSearch) -> usize {
+        self.indexed(filter, search, *COLLAPSE.read().unwrap(), |rows| rows.len())
+    }
+
+    /// Rows of a log view in `range`, which is clamped to the rows there are.
+    pub fn rows_in(
+        &self,
+        filter: LogFilter,
+        search: &LogSearch,
+        range: std::ops::Range<usize>,
+    ) -> Vec<LogRow<'_>> {
+        self.indexed(filter, search, *COLLAPSE.read().unwrap(), |rows| {
+            let end = range.end.min(rows.len());
+            rows[range.start.min(end)..end]
+                .iter()
+                .map(|row| row.row(&self.entries))
+                .collect()
+        })
+    }
+
+    /// Calls `f` with the row index for `filter`, `search`, and `collapse`, rebuilding it
+    /// if any of them changed or entries were dropped, and extending it with the entries
+    /// added since.
+    fn indexed<T>(
+        &self,
+        filter: LogFilter,
+        search: &LogSearch,
+        collapse: Collapse,
+        f: impl FnOnce(&[IndexedRow]) -> T,
+    ) -> T {
+        let key = RowKey {
+            filter,
+            search: search.text().to_string(),
+            regex: search.regex.is_some(),
+            collapse,
+            dropped: self.dropped,
+        };
+        let mut index = self.index.0.lock().unwrap();
+        if index.key.as_ref() != Some(&key) {
+            *index = RowIndex {
+                key: Some(key),
+                ..Default::default()
+            };
+        }
+        index.extend(&self.entries, filter, search, collapse);
+        f(&index.rows)
+    }
+
+    fn rows_with(
+        &self,
+        filter: LogFilter,
+        search: &LogSearch,
+        collapse: Collapse,
+    ) -> Vec<LogRow<'_>> {
+        let shown = self.shown(filter);
+        let mut rows = Vec::new();
+        for run in runs(&shown, collapse) {
+            match run {
+                [first, .., last] if run.len() >= collapse.threshold => {
+                    rows.push(LogRow::Repeated {
+                        first,
+                        last,
+                        count: run.len(),
+                    })
+                }
+                _ => rows.extend(run.iter().map(|entry| LogRow::Entry(entry))),
+            }
+        }
+        if !search.is_empty() {
+            rows.retain(|row| search.matches(&row.to_string()));
+        }
+        rows
+    }
+
+    fn lines(&self, filter: LogFilter, search: &LogSearch, collapse: Collapse) -> String {
+        self.rows_with(filter, search, collapse)
+            .iter()
+            .map(|row| format!("{}\n", row))
+            .collect()
+    }
+
+    fn shown(&self, filter: LogFilter) -> Vec<&LogEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| filter.shows(entry))
+            .collect()
+    }
+}
+
+/// Rows of a log view as positions in the entries of its log, for the filter, search,
+/// and collapsing in `key`.
+#[derive(Default, Debug)]
+struct RowIndex {
+    key: Option<RowKey>,
+    rows: Vec<IndexedRow>,
+    /// Entries indexed so far.
+    scanned: usize,
+    /// Rows of the runs before the last one, which later entries cannot change.
+    settled: usize,
+    /// First and last entry of the last run of shown entries, and its length.
+    run: Option<(usize, usize, usize)>,
+}
+
+impl RowIndex {
+    /// Indexes the entries added since the last call. Only the rows of the last run can
+    /// change: its entries are replaced by one row once it grows long enough to collapse.
+    fn extend(
+        &mut self,
+        entries: &[LogEntry],
+        filter: LogFilter,
+        search: &LogSearch,
+        collapse: Collapse,
+    ) {
+        for (i, entry) in entries.iter().enumerate().skip(self.scanned) {
+            if !filter.shows(entry) {
+                continue;
+            }
+            let row = match self.run {
+                Some((first, last, count)) if collapse.repeats(&entries[last], entry) => {
+                    self.run = Some((first, i, count + 1));
+                    if count + 1 >= collapse.threshold {
+                        self.rows.truncate(self.settled);
+                        IndexedRow::Repeated {
+                            first,
+                            last: i,
+                            count: count + 1,
+                        }
+                    } else {
+                        IndexedRow::Entry(i)
+                    }
+                }
+                _ => {
+                    self.settled = self.rows.len();
+                    self.run = Some((i, i, 1));
+                    IndexedRow::Entry(i)
+                }
+            };
+            if search.is_empty() || search.matches(&row.row(entries).to_string()) {
+                self.rows.push(row);
+            }
+        }
+        self.scanned = entries.len();
+    }
+}
+
+/// What a row index was built for; it is rebuilt when any of it changes.
+#[derive(Clone, PartialEq, Debug)]
+struct RowKey {
+    filter: LogFilter,
+    search: String,
+    regex: bool,
+    collapse: Collapse,
+    /// Dropping entries moves those kept, so the positions indexed no longer hold.
+    dropped: usize,
+}
+
+/// A row of a row index, with the positions of its entries.
+#[derive(Clone, Copy, Debug)]
+enum IndexedRow {
+    Entry(usize),
+    Repeated {
+        first: usize,
+        last: usize,
+        count: usize,
+    },
+}
+
+impl IndexedRow {
+    fn row<'a>(&self, entries: &'a [LogEntry]) -> LogRow<'a> {
+        match *self {
+            IndexedRow::Entry(i) => LogRow::Entry(&entries[i]),
+            IndexedRow::Repeated { first, last, count } => LogRow::Repeated {
+                first: &entries[first],
+                last: &entries[last],
+                count,
+            },
+        }
+    }
+}
+
+/// The row index of a log. A copied log indexes its rows anew.
+#[derive(Default, Debug)]
+struct RowCache(std::sync::Mutex<RowIndex>);
+
+impl Clone for RowCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+/// A row of a log view: an entry, or a run of repeated entries collapsed into one line.
+#[derive(Clone, Copy, Debug)]
+pub enum LogRow<'a> {
+    Entry(&'a LogEntry),
+    Repeated {
+        first: &'a LogEntry,
+        last: &'a LogEntry,
+        count: usize,
+    },
+}
+
+impl LogRow<'_> {
+    /// The packet of a packet line; collapsed runs of packet lines all have the same bytes.
+    pub fn packet(&self) -> Option<&LogPacket> {
+        match self {
+            LogRow::Entry(entry) => entry.packet.as_ref(),
+            LogRow::Repeated { first, .. } => first.packet.as_ref(),
+        }
+    }
+}
+
+impl std::fmt::Display for LogRow<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogRow::Entry(entry) => write!(f, "{}", entry),
+            LogRow::Repeated { first, last, count } => write!(
+                f,
+                "{} (repeated {} times, last at {})",
+                first, count, last.timestamp
+            ),
+        }
+    }
+}
+
+/// Groups shown entries into runs of consecutive entries with the same level and
+/// message. Every entry is a run of its own if collapsing is off.
+fn runs<'s, 'a>(
+    shown: &'s [&'a LogEntry],
+    collapse: Collapse,
+) -> impl Iterator<Item = &'s [&'a LogEntry]> {
+    shown.chunk_by(move |previous, entry| collapse.repeats(previous, entry))
+}
+
+impl FromIterator<LogEntry> for Log {
+    fn from_iter<I: IntoIterator<Item = LogEntry>>(entries: I) -> Self {
+        let mut log = Self::default();
+        for entry in entries {
+            log.push_entry(entry);
+        }
+        log
+    }
+}
+
+/// When runs of repeated entries are collapsed into one line in log views.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Collapse {
+    /// Shortest run collapsed; runs are never collapsed below 2.
+    threshold: usize,
+    /// Whether numbered packet lines are collapsed too, compared without their number.
+    packets: bool,
+}
+
+impl Collapse {
+    const OFF: Self = Self {
+        threshold: usize::MAX,
+        packets: false,
+    };
+
+    /// Whether `entry` repeats `previous`.
+    fn repeats(&self, previous: &LogEntry, entry: &LogEntry) -> bool {
+        if previous.level != entry.level {
+            return false;
+        }
+        match (&previous.packet, &entry.packet) {
+            (None, None) => previous.message == entry.message,
+            (Some(previous_packet), Some(packet)) => {
+                self.packets
+                    && previous_packet.direction == packet.direction
+                    && previous_packet.data == packet.data
+                    && unnumbered(previous) == unnumbered(entry)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Replaces tabs with spaces up to the next multiple of `TAB_WIDTH` columns, so tabbed
+/// columns line up in the monospace log font.
+pub fn expand_tabs(line: String) -> String {
+    if !line.contains('\t') {
+        return line;
+    }
+    let mut expanded = String::with_capacity(line.len() + TAB_WIDTH);
+    let mut column = 0;
+    for c in line.chars() {
+        match c {
+            '\t' => {
+                let spaces = TAB_WIDTH - column % TAB_WIDTH;
+                expanded.extend(std::iter::repeat_n(' ', spaces));
+                column += spaces;
+            }
+            '\n' => {
+                expanded.push(c);
+                column = 0;
+            }
+            c => {
+                expanded.push(c);
+                column += 1;
+            }
+        }
+    }
+    expanded
+}
+
+/// Columns between tab stops in log panes.
+const TAB_WIDTH: usize = 8;
+
+/// Returns the message of a packet entry without its number.
+fn unnumbered(entry: &LogEntry) -> String {
+    match &entry.packet {
+        Some(packet) => entry.message.replacen(&format!("#{} ", packet.seq), "", 1),
+        None => entry.message.clone(),
+    }
+}
+
+/// Collapses runs of at least `threshold` repeated entries in log views and copies from
+/// now on; below 2, runs are shown in full. Numbered packet lines are only collapsed with
+/// `packets`.
+pub fn set_collapse(threshold: u64, packets: bool) {
+    *COLLAPSE.write().unwrap() = Collapse {
+        threshold: if threshold < 2 {
+            usize::MAX
+        } else {
+            usize::try_from(threshold).unwrap_or(usize::MAX)
+        },
+        packets,
+    };
+}
+
+/// Keeps at most `max` entries, plus up to a sixteenth more, in every log from now on; 0
+/// keeps every entry.
+pub fn set_max_entries(max: usize) {
+    MAX_ENTRIES.store(max, std::sync::atomic::Ordering::Relaxed);
+}
+
+/// Text the lines of a log view are filtered by, ignoring case: a regex, or the text
+/// itself.
+#[derive(Clone, Debug, Default)]
+pub struct LogSearch {
+    text: String,
+    /// Lowercased `text`, matched when there is no regex.
+    needle: String,
+    regex: Option<regex::Regex>,
+    /// Why `text` is not a valid regex, when meant as one; it is then matched literally.
+    pub error: Option<String>,
+}
+
+impl LogSearch {
+    /// Matches `text` as a regex if `regex` is set and it is a valid one, otherwise
+    /// literally.
+    pub fn new(text: &str, regex: bool) -> Self {
+        let mut search = Self {
+            text: text.to_string(),
+            needle: text.to_lowercase(),
+            regex: None,
+            error: None,
+        };
+        if regex && !text.is_empty() {
+            match regex::RegexBuilder::new(text)
+                .case_insensitive(true)
+                .build()
+            {
+                Ok(regex) => search.regex = Some(regex),
+                // Parse errors end in a line like "error: unclosed group"
+                Err(e) => {
+                    search.error = e
+                        .to_string()
+                        .lines()
+                        .last()
+                        .map(|line| line.trim_start_matches("error: ").to_string())
+                }
+            }
+        }
+        search
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn matches(&self, line: &str) -> bool {
+        match &self.regex {
+            Some(regex) => regex.is_match(line),
+            None => line.to_lowercase().contains(&self.needle),
+        }
+    }
+}
+
+/// Levels shown in a log view.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LogFilter {
+    pub debug: bool,
+    pub info: bool,
+    pub warn: bool,
+    pub error: bool,
+    /// Whether packet lines are shown, besides the status lines of their level.
+    pub packets: bool,
+}
+
+impl Default for LogFilter {
+    /// Shows every level but Debug.
+    fn default() -> Self {
+        Self {
+            debug: false,
+            ..Self::ALL
+        }
+    }
+}
+
+impl LogFilter {
+    /// Shows every level.
+    pub const ALL: Self = Self {
+        debug: true,
+        info: true,
+        warn: true,
+        error: true,
+        packets: true,
+    };
+
+    /// Shows Info, Warn, Error, and packets, and Debug only if this filter shows it.
+    /// Debug entries are only exported when asked for by showing them.
+    pub fn all_levels(self) -> Self {
+        Self {
+            debug: self.debug,
+            ..Self::ALL
+        }
+    }
+
+    pub fn shows(&self, entry: &LogEntry) -> bool {
+        if entry.packet.is_some() && !self.packets {
+            return false;
+        }
+        match entry.level {
+            LogLevel::Debug => self.debug,
+            LogLevel::Info => self.info,
+            LogLevel::Warn => self.warn,
+            LogLevel::Error => self.error,
+        }
+    }
+
+    /// Shows or hides `level`.
+    pub fn set(&mut self, level: LogLevel, shown: bool) {
+        match level {
+            LogLevel::Debug => self.debug = shown,
+            LogLevel::Info => self.info = shown,
+            LogLevel::Warn => self.warn = shown,
+            LogLevel::Error => self.error = shown,
+        }
+    }
+}
+
+/// Prints `entry` to the console if its level is at least the console level, colored
+/// by level when the console supports it.
+pub fn console(entry: &LogEntry) {
+    if entry.level as u8 >= CONSOLE_LEVEL.load(std::sync::atomic::Ordering::Relaxed) {
+        anstream::println!("{}", styled(entry));
+    }
+}
+
+/// Returns the line of `entry` with ANSI colors: errors red, warnings yellow, and Debug
+/// entries dimmed. The console streams strip them when the output is not a terminal.
+pub fn styled(entry: &LogEntry) -> String {
+    let style = match entry.level {
+        LogLevel::Debug => anstyle::Style::new().dimmed(),
+        LogLevel::Info => anstyle::Style::new(),
+        LogLevel::Warn => anstyle::AnsiColor::Yellow.on_default(),
+        LogLevel::Error => anstyle::AnsiColor::Red.on_default().bold(),
+    };
+    format!("{}{}{:#}", style, entry, style)
+}
+
+/// Sets the least severe level printed to the console, Warn unless changed with
+/// `--console-level` or `--verbose`. Debug also turns on Debug entries.
+pub fn set_console_level(level: LogLevel) {
+    CONSOLE_LEVEL.store(level as u8, std::sync::atomic::Ordering::Relaxed);
+}
+
+/// Parses a console level name, e.g. `warn`, ignoring case.
+pub fn parse_level(name: &str) -> Result<LogLevel, String> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "debug" => Ok(LogLevel::Debug),
+        "info" => Ok(LogLevel::Info),
+        "warn" => Ok(LogLevel::Warn),
+        "error" => Ok(LogLevel::Error),
+        _ => Err(format!(
+            "{} is not debug, info, warn, or error",
+            name.trim()
+        )),
+    }
+}
+
+/// Logs Debug entries from now on, or drops them. A console level of Debug turns them on
+/// as well.
+pub fn set_debug(enabled: bool) {
+    DEBUG.store(enabled, std::sync::atomic::Ordering::Relaxed);
+}
+
+/// Whether Debug entries are logged, for debug output that takes extra work to collect.
+pub fn debug_enabled() -> bool {
+    DEBUG.load(std::sync::atomic::Ordering::Relaxed)
+        || CONSOLE_LEVEL.load(std::sync::atomic::Ordering::Relaxed) == LogLevel::Debug as u8
+}
+
+/// Formats a duration as `HH:MM:SS`, e.g. "00:02:13".
+pub fn format_duration(duration: std::time::Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Formats the log line of a packet with its number in the window and its size.
+fn packet_line(seq: u64, direction: Direction, len: usize, detail: &str, data: &str) -> String {
+    let direction = match direction {
+        Direction::Sent => "TX",
+        Direction::Received => "RX",
+    };
+    if detail.is_empty() {
+        format!("#{} {} {} B: {}", seq, direction, len, data)
+    } else {
+        format!("#{} {} {} B {}: {}", seq, direction, len, detail, data)
+    }
+}
+
+/// A destination log entries are delivered to, e.g. the windows, the console, or a
+/// syslog collector. Sinks are registered with `add_sink`, and every entry passed to
+/// `log` or `main_log` is emitted to each of them in the order they were added.
+pub trait LogSink: Send + Sync {
+    /// Delivers `entry`, logged in the session or replay window `window`, or in the main
+    /// log if `None`.
+    fn emit(&self, window: Option<window::Id>, entry: LogEntry) -> BoxFuture<'_, ()>;
+}
+
+/// Identifies a registered sink, to remove it again.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SinkId(u64);
+
+/// Registers `sink`, which receives every entry logged from now on.
+pub fn add_sink(sink: std::sync::Arc<dyn LogSink>) -> SinkId {
+    let id = SinkId(NEXT_SINK_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed));
+    SINKS.write().unwrap().push((id, sink));
+    id
+}
+
+/// Removes a sink registered with `add_sink`. Entries being emitted may still reach it.
+pub fn remove_sink(id: SinkId) {
+    SINKS.write().unwrap().retain(|(sink_id, _)| *sink_id != id);
+}
+
+/// Emits `entry` to every registered sink, one after another, so each sink receives
+/// the entries of one task in the order they were logged.
+async fn emit(window: Option<window::Id>, entry: LogEntry) {
+    let sinks: Vec<_> = SINKS
+        .read()
+        .unwrap()
+        .iter()
+        .map(|(_, sink)| sink.clone())
+        .collect();
+    for sink in sinks {
+        sink.emit(window, entry.clone()).await;
+    }
+}
+
+/// Delivers entries to the session, replay, and main windows.
+struct WindowSink;
+
+impl LogSink for WindowSink {
+    fn emit(&self, window: Option<window::Id>, entry: LogEntry) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            match window {
+                Some(window_id) => {
+                    let _ = LOG_SENDER.send(LogMessage { window_id, entry });
+                }
+                None => {
+                    let _ = MAIN_LOG_SENDER.send(entry);
+                }
+            }
+        })
+    }
+}
+
+/// Prints main log entries to the console, see `console`.
+struct ConsoleSink;
+
+impl LogSink for ConsoleSink {
+    /// Prints main log entries, and the session and replay entries forwarded to the main
+    /// log while "Session logs" is ticked, tagged with their window's name.
+    fn emit(&self, window: Option<window::Id>, entry: LogEntry) -> BoxFuture<'_, ()> {
+        match window {
+            None => console(&entry),
+            Some(window_id)
+                if entry.level != LogLevel::Debug && FORWARD_SINK.lock().unwrap().is_some() =>
+            {
+                console(&LogEntry {
+                    message: format!("[{}] {}", window_name(window_id), entry.message),
+                    ..entry
+                });
+            }
+            Some(_) => {}
+        }
+        Box::pin(std::future::ready(()))
+    }
+}
+
+/// Copies session and replay log entries but Debug ones to the main log, registered
+/// while "Session logs" is ticked.
+struct ForwardSink;
+
+impl LogSink for ForwardSink {
+    fn emit(&self, window: Option<window::Id>, entry: LogEntry) -> BoxFuture<'_, ()> {
+        // Debug entries come in bursts, so they stay in the window
+        if let Some(window_id) = window
+            && entry.level != LogLevel::Debug
+        {
+            forward(window_id, &entry);
+        }
+        Box::pin(std::future::ready(()))
+    }
 }
 
 /// Convenience function to send a log message asynchronously.
 pub async fn log(level: LogLevel, window_id: window::Id, msg: &str) {
-    let _ = LOG_SENDER.lock().await.send(LogMessage {
-        window_id,
-        content: format_log(level, msg),
-    });
+    if level == LogLevel::Debug && !debug_enabled() {
+        return;
+    }
+    log_entry(window_id, LogEntry::new(level, msg)).await;
+}
+
+/// Like `log`, for an entry built elsewhere, e.g. with `LogEntry::packet`.
+pub async fn log_entry(window_id: window::Id, entry: LogEntry) {
+    if entry.level == LogLevel::Debug && !debug_enabled() {
+        return;
+    }
+    emit(Some(window_id), entry).await;
+}
+
+/// Most forwarded entries held until the main window takes them; further ones are only
+/// counted, so a busy session cannot flood the main window.
+const MAX_FORWARDED: usize = 500;
+
+/// Entries forwarded to the main log and not yet taken, and the number dropped since.
+#[derive(Default)]
+struct Forwarded {
+    entries: Vec<LogEntry>,
+    dropped: usize,
+}
+
+/// Queues a copy of a window's log entry for the main log, prefixed with the window's
+/// short name, e.g. "[boiler] #42 RX 2 B: 0a0b".
+fn forward(window_id: window::Id, entry: &LogEntry) {
+    let name = window_name(window_id);
+    let mut forwarded = FORWARDED.lock().unwrap();
+    if forwarded.entries.len() < MAX_FORWARDED {
+        forwarded.entries.push(LogEntry {
+            message: format!("[{}] {}", name, entry.message),
+            ..entry.clone()
+        });
+    } else {
+        forwarded.dropped += 1;
+    }
+}
+
+/// Returns the short name of a session or replay window.
+pub fn window_name(window_id: window::Id) -> String {
+    WINDOW_NAMES
+        .read()
+        .unwrap()
+        .get(&window_id)
+        .cloned()
+        .unwrap_or_else(|| "window".to_string())
+}
+
+/// Forwards session and replay log entries to the main log from now on, or stops.
+/// `names` are the short names the entries are prefixed with, also used to tag lines
+/// sent to a syslog collector.
+pub fn set_forwarding(
+    enabled: bool,
+    names: impl FnOnce() -> std::collections::HashMap<window::Id, String>,
+) {
+    let mut forward_sink = FORWARD_SINK.lock().unwrap();
+    match (enabled, *forward_sink) {
+        (true, None) => *forward_sink = Some(add_sink(std::sync::Arc::new(ForwardSink))),
+        (false, Some(id)) => {
+            remove_sink(id);
+            *forward_sink = None;
+        }
+        _ => {}
+    }
+    if enabled || crate::syslog::enabled() {
+        *WINDOW_NAMES.write().unwrap() = names();
+    }
+}
+
+/// Takes the entries forwarded since the last call, and the number dropped because the
+/// main window did not keep up.
+pub fn take_forwarded() -> (Vec<LogEntry>, usize) {
+    let forwarded = std::mem::take(&mut *FORWARDED.lock().unwrap());
+    (forwarded.entries, forwarded.dropped)
 }
 
 /// Convenience function to send a main log message asynchronously.
-pub async fn main_log(msg: String) {
-    let _ = MAIN_LOG_SENDER.lock().await.send(msg);
+pub async fn main_log(level: LogLevel, msg: &str) {
+    if level == LogLevel::Debug && !debug_enabled() {
+        return;
+    }
+    emit(None, LogEntry::new(level, msg)).await;
+}
+
+/// Sends a main log message from a thread outside the async runtime.
+pub fn main_log_blocking(level: LogLevel, msg: &str) {
+    iced::futures::executor::block_on(emit(None, LogEntry::new(level, msg)));
+}
+
+/// Least severe level printed to the console, set with `--console-level` or `--verbose`.
+static CONSOLE_LEVEL: std::sync::atomic::AtomicU8 =
+    std::sync::atomic::AtomicU8::new(LogLevel::Warn as u8);
+/// Most entries a log keeps, set from the config; 0 keeps every entry.
+static MAX_ENTRIES: std::sync::atomic::AtomicUsize =
+    std::sync::atomic::AtomicUsize::new(DEFAULT_MAX_ENTRIES);
+/// Default of the `log_max_entries` setting.
+pub const DEFAULT_MAX_ENTRIES: usize = 200_000;
+/// Whether Debug entries are logged, set from the config.
+static DEBUG: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+/// Whether `Progress` updates to replay windows are coalesced, set from the config.
+static COALESCE_PROGRESS: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(true);
+/// Id given to the next sink added, after the window and console sinks.
+static NEXT_SINK_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(2);
+
+/// An internal broadcast channel's capacity, and the messages the interface skipped on it
+/// because it fell behind and the oldest ones were dropped.
+pub struct Channel {
+    /// Name used in diagnostics, e.g. "log".
+    pub name: &'static str,
+    /// Config setting holding the capacity.
+    pub setting: &'static str,
+    capacity: std::sync::atomic::AtomicUsize,
+    dropped: std::sync::atomic::AtomicU64,
+    /// Called when the interface skipped messages, to undo what waits on them.
+    on_lag: Option<fn()>,
+}
+
+impl Channel {
+    const fn new(name: &'static str, setting: &'static str, capacity: usize) -> Self {
+        Self {
+            name,
+            setting,
+            capacity: std::sync::atomic::AtomicUsize::new(capacity),
+            dropped: std::sync::atomic::AtomicU64::new(0),
+            on_lag: None,
+        }
+    }
+
+    const fn on_lag(mut self, on_lag: fn()) -> Self {
+        self.on_lag = Some(on_lag);
+        self
+    }
+
+    /// Capacity the channel is created with.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+            .load(std::sync::atomic::Ordering::Relaxed)
+            .max(1)
+    }
+
+    /// Messages skipped on the channel since start.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Counts `skipped` dropped messages and returns the warning for the main log, with
+    /// the drop counters of every channel.
+    pub fn lagged(&self, skipped: u64) -> LogEntry {
+        self.dropped
+            .fetch_add(skipped, std::sync::atomic::Ordering::Relaxed);
+        if let Some(on_lag) = self.on_lag {
+            on_lag();
+        }
+        let counters = CHANNELS
+            .iter()
+            .map(|channel| format!("{} {}", channel.name, channel.dropped()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        LogEntry::new(
+            LogLevel::Warn,
+            &format!(
+                "The interface fell behind, skipped {} {} messages; raise {} (now {}) if this recurs. Dropped since start: {}",
+                skipped,
+                self.name,
+                self.setting,
+                self.capacity(),
+                counters
+            ),
+        )
+    }
+}
+
+/// Channel of `LOG_SENDER`.
+pub static LOG_CHANNEL: Channel = Channel::new("log", "log_channel_capacity", 1000);
+/// Channel of `MAIN_LOG_SENDER`.
+pub static MAIN_LOG_CHANNEL: Channel = Channel::new("main log", "log_channel_capacity", 1000);
+/// Channel of `CONNECTION_SENDER`.
+pub static CONNECTION_CHANNEL: Channel = Channel::new("connection", "event_channel_capacity", 1000);
+/// Channel of `PROGRESS_SENDER`.
+pub static PROGRESS_CHANNEL: Channel =
+    Channel::new("progress", "progress_channel_capacity", 100).on_lag(clear_progress);
+/// Every channel, in the order of the diagnostics line.
+static CHANNELS: [&Channel; 4] = [
+    &LOG_CHANNEL,
+    &MAIN_LOG_CHANNEL,
+    &CONNECTION_CHANNEL,
+    &PROGRESS_CHANNEL,
+];
+
+/// Sets the capacities of the log, connection event, and progress channels. The channels
+/// are created on first use, so this only has an effect before then; later changes need
+/// a restart.
+pub fn set_channel_capacities(log: usize, events: usize, progress: usize) {
+    for (channel, capacity) in [
+        (&LOG_CHANNEL, log),
+        (&MAIN_LOG_CHANNEL, log),
+        (&CONNECTION_CHANNEL, events),
+        (&PROGRESS_CHANNEL, progress),
+    ] {
+        channel
+            .capacity
+            .store(capacity, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// Turns coalescing of replay progress updates on or off.
+pub fn set_coalesce_progress(enabled: bool) {
+    COALESCE_PROGRESS.store(enabled, std::sync::atomic::Ordering::Relaxed);
+}
+
+/// Sends a replay event to its replay window. With coalescing on, progress updates (the
+/// count sent, iterations started, and what became of each payload) only go on the
+/// channel when the window has taken the previous ones; until then later updates are
+/// folded into the pending progress, which the window takes with `take_progress`. Only
+/// the latest of each matters, so a fast replay cannot push other events out of the
+/// channel.
+pub fn send_progress(window_id: window::Id, event: ReplayEvent) {
+    if COALESCE_PROGRESS.load(std::sync::atomic::Ordering::Relaxed) {
+        let mut pending = PENDING_PROGRESS.lock().unwrap();
+        let queued = pending.contains_key(&window_id);
+        let progress = pending.entry(window_id).or_default();
+        if progress.fold(event.clone()).is_ok() {
+            if queued {
+                return;
+            }
+        } else if !queued {
+            pending.remove(&window_id);
+        }
+    }
+    let _ = PROGRESS_SENDER.send((window_id, event));
+}
+
+/// Takes the coalesced progress of a replay window, if any is pending.
+pub fn take_progress(window_id: window::Id) -> Option<PendingProgress> {
+    PENDING_PROGRESS.lock().unwrap().remove(&window_id)
+}
+
+/// Forgets all pending progress once progress messages were skipped. A skipped message
+/// would leave its progress pending, and no later update of the window would be sent.
+fn clear_progress() {
+    PENDING_PROGRESS.lock().unwrap().clear();
 }
 
 // Global broadcast channels for logging and communication between tasks
 lazy_static! {
+    /// Timestamp pattern and timezone of log lines, taken from the config
+    static ref TIMESTAMP_FORMAT: std::sync::RwLock<TimestampFormat> =
+        std::sync::RwLock::new(TimestampFormat {
+            pattern: DEFAULT_TIMESTAMP_FORMAT.to_string(),
+            utc: false,
+        });
+    /// Collapsing of repeated entries in log views, taken from the config
+    static ref COLLAPSE: std::sync::RwLock<Collapse> = std::sync::RwLock::new(Collapse {
+        threshold: 3,
+        packets: false,
+    });
+    /// Short names of session and replay windows, prefixed to forwarded entries
+    static ref WINDOW_NAMES: std::sync::RwLock<std::collections::HashMap<window::Id, String>> =
+        std::sync::RwLock::new(std::collections::HashMap::new());
+    /// Destinations every log entry is emitted to, in order
+    static ref SINKS: std::sync::RwLock<Vec<(SinkId, std::sync::Arc<dyn LogSink>)>> =
+        std::sync::RwLock::new(vec![
+            (SinkId(0), std::sync::Arc::new(WindowSink) as std::sync::Arc<dyn LogSink>),
+            (SinkId(1), std::sync::Arc::new(ConsoleSink)),
+        ]);
+    /// Sink forwarding session and replay entries to the main log, while registered
+    static ref FORWARD_SINK: std::sync::Mutex<Option<SinkId>> = std::sync::Mutex::new(None);
+    /// Entries forwarded to the main log, taken by the main window in batches
+    static ref FORWARDED: std::sync::Mutex<Forwarded> = std::sync::Mutex::new(Forwarded::default());
+    /// Progress of each replay window whose progress message is still queued
+    static ref PENDING_PROGRESS: std::sync::Mutex<std::collections::HashMap<window::Id, PendingProgress>> =
+        std::sync::Mutex::new(std::collections::HashMap::new());
     /// Sends log messages to specific windows
-    pub static ref LOG_SENDER: std::sync::Arc<tokio::sync::Mutex<broadcast::Sender<LogMessage>>> =
-        std::sync::Arc::new(tokio::sync::Mutex::new(broadcast::Sender::new(100)));
+    pub static ref LOG_SENDER: broadcast::Sender<LogMessage> =
+        broadcast::Sender::new(LOG_CHANNEL.capacity());
     /// Sends log messages to the main window
-    pub static ref MAIN_LOG_SENDER: std::sync::Arc<tokio::sync::Mutex<broadcast::Sender<String>>> =
-        std::sync::Arc::new(tokio::sync::Mutex::new(broadcast::Sender::new(100)));
+    pub static ref MAIN_LOG_SENDER: broadcast::Sender<LogEntry> =
+        broadcast::Sender::new(MAIN_LOG_CHANNEL.capacity());
     /// Sends connection status updates
-    pub static ref CONNECTION_SENDER: std::sync::Arc<tokio::sync::Mutex<broadcast::Sender<(window::Id, bool)>>> =
-        std::sync::Arc::new(tokio::sync::Mutex::new(broadcast::Sender::new(100)));
+    pub static ref CONNECTION_SENDER: broadcast::Sender<(window::Id, SessionEvent)> =
+        broadcast::Sender::new(CONNECTION_CHANNEL.capacity());
     /// Sends replay progress updates
-    pub static ref PROGRESS_SENDER: std::sync::Arc<tokio::sync::Mutex<broadcast::Sender<(iced::window::Id, usize)>>> =
-        std::sync::Arc::new(tokio::sync::Mutex::new(broadcast::Sender::new(100)));
+    pub static ref PROGRESS_SENDER: broadcast::Sender<(iced::window::Id, ReplayEvent)> =
+        broadcast::Sender::new(PROGRESS_CHANNEL.capacity());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Records the entries of one window it receives, tagged with its own name.
+    struct MockSink {
+        name: &'static str,
+        window: window::Id,
+        received: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl LogSink for MockSink {
+        fn emit(&self, window: Option<window::Id>, entry: LogEntry) -> BoxFuture<'_, ()> {
+            Box::pin(async move {
+                // Sinks are global, so entries logged by other tests are ignored
+                if window == Some(self.window) {
+                    self.received
+                        .lock()
+                        .unwrap()
+                        .push(format!("{} {}", self.name, entry.message));
+                }
+            })
+        }
+    }
+
+    /// Sets the global entry limit, restoring the default when dropped, also when a test fails.
+    struct MaxEntries;
+
+    impl MaxEntries {
+        fn set(max: usize) -> Self {
+            set_max_entries(max);
+            MaxEntries
+        }
+    }
+
+    impl Drop for MaxEntries {
+        fn drop(&mut self) {
+            set_max_entries(DEFAULT_MAX_ENTRIES);
+        }
+    }
+
+    #[test]
+    fn log_keeps_the_newest_entries_in_order_within_its_limit() {
+        let _max_entries = MaxEntries::set(64);
+        let mut log = Log::default();
+        for n in 0..1000 {
+            log.push(LogLevel::Info, &n.to_string());
+            // The limit is exceeded by at most a sixteenth between batch drops
+            assert!(log.entries().len() <= 68, "{} entries", log.entries().len());
+            assert_eq!(log.added(), n + 1);
+        }
+        assert!(log.entries().len() >= 64);
+        let messages: Vec<usize> = log
+            .entries()
+            .iter()
+            .map(|entry| entry.message.parse().unwrap())
+            .collect();
+        let oldest = 1000 - messages.len();
+        assert_eq!(messages, (oldest..1000).collect::<Vec<_>>());
+        assert_eq!(log.dropped(), oldest);
+    }
+
+    #[test]
+    fn row_index_matches_the_rows_as_entries_are_added() {
+        let collapse = Collapse {
+            threshold: 3,
+            packets: false,
+        };
+        let search = LogSearch::new("repeat|other", true);
+        let messages = [
+            "other", "repeat", "repeat", "other", "repeat", "repeat", "repeat", "repeat", "quiet",
+            "other", "other", "other", "other", "repeat",
+        ];
+        let mut log = Log::default();
+        for (n, message) in messages.iter().enumerate() {
+            let level = if n % 5 == 4 {
+                LogLevel::Debug
+            } else {
+                LogLevel::Info
+            };
+            log.push(level, message);
+            for filter in [LogFilter::ALL, LogFilter::default()] {
+                for search in [&LogSearch::default(), &search] {
+                    let expected: Vec<String> = log
+                        .rows_with(filter, search, collapse)
+                        .iter()
+                        .map(|row| row.to_string())
+                        .collect();
+                    let indexed: Vec<String> = log.indexed(filter, search, collapse, |rows| {
+                        rows.iter()
+                            .map(|row| row.row(log.entries()).to_string())
+                            .collect()
+                    });
+                    assert_eq!(indexed, expected, "after {} entries", n + 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn progress_is_sent_again_after_the_channel_lagged() {
+        let id = window::Id::unique();
+        let mut receiver = PROGRESS_SENDER.subscribe();
+        send_progress(id, ReplayEvent::Progress(1));
+        send_progress(id, ReplayEvent::Progress(2));
+        // The interface skipped the first message, so 2 stays pending without one queued
+        PROGRESS_CHANNEL.lagged(1);
+        send_progress(id, ReplayEvent::Progress(3));
+        let mut counts = Vec::new();
+        while let Ok((window_id, event)) = receiver.try_recv() {
+            if let (true, ReplayEvent::Progress(sent)) = (window_id == id, event) {
+                counts.push(sent);
+            }
+        }
+        assert_eq!(counts, [1, 3]);
+        assert_eq!(
+            take_progress(id).and_then(|progress| progress.sent),
+            Some(3)
+        );
+    }
+
+    #[tokio::test]
+    async fn sinks_receive_entries_in_order() {
+        let window = window::Id::unique();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = |name| {
+            add_sink(Arc::new(MockSink {
+                name,
+                window,
+                received: received.clone(),
+            }))
+        };
+        let first = sink("a");
+        let second = sink("b");
+        for n in 1..=3 {
+            log(LogLevel::Info, window, &n.to_string()).await;
+        }
+        remove_sink(first);
+        log(LogLevel::Info, window, "4").await;
+        remove_sink(second);
+        log(LogLevel::Info, window, "5").await;
+        assert_eq!(
+            *received.lock().unwrap(),
+            ["a 1", "b 1", "a 2", "b 2", "a 3", "b 3", "b 4"]
+        );
+    }
 }
@@ -0,0 +1,240 @@
+// Continuous copy of a session or replay window's log in a file, kept while the window
+// is open.
+
+use crate::log::{Log, LogEntry, LogFilter, LogLevel, format_log};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Interval at which log files are flushed to disk.
+pub const FLUSH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// When a log file is continued in a new file, and how many earlier files are kept.
+#[derive(Clone, Copy, Debug)]
+pub struct LogRotation {
+    /// Continue in a new file before the current one grows beyond this many bytes.
+    pub max_bytes: Option<u64>,
+    /// Continue in a new file when the date changes.
+    pub daily: bool,
+    /// Number of earlier files kept besides the current one.
+    pub keep: usize,
+    /// Compress earlier files with gzip.
+    pub compress: bool,
+}
+
+/// A file every line of a window log is appended to.
+#[derive(Clone)]
+pub struct LogFile {
+    /// File currently written to; changes when the log is rotated.
+    pub path: PathBuf,
+    /// File chosen when logging started, which numbered files are named after.
+    base: PathBuf,
+    rotation: LogRotation,
+    writer: Arc<Mutex<BufWriter<File>>>,
+    /// Number of window log entries already written to the file.
+    mirrored: usize,
+    /// Size of the file, including lines still buffered.
+    pub size: u64,
+    /// A write failed and was reported, so later failures are not reported again.
+    failed: bool,
+    /// Date the current file was started, for daily rotation.
+    started: chrono::NaiveDate,
+    /// Number of times the log continued in a new file.
+    pub rotations: usize,
+    /// Earlier files still kept, oldest first.
+    rotated: VecDeque<PathBuf>,
+    /// Thread compressing the previous file, which the next one waits for so files are
+    /// only deleted once compressed.
+    compressing: Arc<Mutex<Option<std::thread::JoinHandle<()>>>>,
+}
+
+impl LogFile {
+    /// Opens `path` for appending and writes a header naming the window `title`.
+    pub fn open(path: &Path, title: &str, rotation: LogRotation) -> Result<Self, String> {
+        let file = append(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        let mut log_file = Self {
+            path: path.to_path_buf(),
+            base: path.to_path_buf(),
+            rotation,
+            writer: Arc::new(Mutex::new(BufWriter::new(file))),
+            mirrored: 0,
+            size,
+            failed: false,
+            started: chrono::Local::now().date_naive(),
+            rotations: 0,
+            rotated: VecDeque::new(),
+            compressing: Arc::new(Mutex::new(None)),
+        };
+        log_file
+            .write(&format!(
+                "{}\n",
+                format_log(LogLevel::Info, &format!("Logging {} to file", title))
+            ))
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(log_file)
+    }
+
+    /// Appends the part of `log` not written yet, with the levels `filter` shows. Returns
+    /// an error line the first time writing fails; the window keeps running and later
+    /// failures stay quiet.
+    pub fn mirror(&mut self, log: &Log, filter: LogFilter) -> Option<LogEntry> {
+        // Entries dropped from the log before they were written are lost to the file too
+        let start = self.mirrored.saturating_sub(log.dropped());
+        let new = log.entries().get(start..)?;
+        let result = new
+            .iter()
+            .filter(|entry| filter.shows(entry))
+            .try_for_each(|entry| self.write(&format!("{}\n", entry)));
+        self.mirrored = log.added();
+        self.report(result)
+    }
+
+    /// Starts over at the beginning of the window log, after it was cleared.
+    pub fn log_cleared(&mut self) {
+        self.mirrored = 0;
+    }
+
+    /// Writes buffered lines to disk.
+    pub fn flush(&mut self) -> Option<LogEntry> {
+        let result = self.writer.lock().unwrap().flush();
+        self.report(result)
+    }
+
+    /// Appends a footer with the `reason` the log ends, e.g. a disconnect, and flushes
+    /// the file. Lines logged afterwards, e.g. after a reconnect, are still appended.
+    pub fn footer(&mut self, reason: &str) -> Option<LogEntry> {
+        let footer = format!(
+            "{}\n",
+            format_log(LogLevel::Info, &format!("End of log: {}", reason))
+        );
+        let result = self
+            .write(&footer)
+            .and_then(|()| self.writer.lock().unwrap().flush());
+        self.report(result)
+    }
+
+    /// Writes whole lines, so a line is never split between two files.
+    fn write(&mut self, text: &str) -> std::io::Result<()> {
+        if self.rotation_due(text.len() as u64) {
+            self.rotate()?;
+        }
+        self.writer.lock().unwrap().write_all(text.as_bytes())?;
+        self.size += text.len() as u64;
+        Ok(())
+    }
+
+    fn rotation_due(&self, len: u64) -> bool {
+        self.size > 0
+            && (self
+                .rotation
+                .max_bytes
+                .is_some_and(|max| self.size + len > max)
+                || self.rotation.daily && chrono::Local::now().date_naive() != self.started)
+    }
+
+    /// Continues the log in the next numbered file, e.g. `session.1.log`, once every
+    /// buffered line is in the current one. Earlier files beyond the kept number are
+    /// removed.
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let next = numbered(&self.base, self.rotations + 1);
+        let mut writer = self.writer.lock().unwrap();
+        writer.flush()?;
+        let file = append(&next)?;
+        let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        *writer = BufWriter::new(file);
+        let previous = std::mem::replace(&mut self.path, next);
+        let header = format!(
+            "{}\n",
+            format_log(
+                LogLevel::Info,
+                &format!("Log continued from {}", previous.display())
+            )
+        );
+        writer.write_all(header.as_bytes())?;
+        drop(writer);
+        self.size = size + header.len() as u64;
+        self.rotations += 1;
+        self.started = chrono::Local::now().date_naive();
+        if self.rotation.keep == 0 {
+            let _ = std::fs::remove_file(&previous);
+            return Ok(());
+        }
+        if !self.rotation.compress {
+            self.rotated.push_back(previous);
+            for oldest in self.prune() {
+                let _ = std::fs::remove_file(oldest);
+            }
+            return Ok(());
+        }
+        let mut compressed = previous.clone().into_os_string();
+        compressed.push(".gz");
+        self.rotated.push_back(compressed.into());
+        let pruned = self.prune();
+        let mut compressing = self.compressing.lock().unwrap();
+        let before = compressing.take();
+        // Compressing a large file takes a while, so it happens off the UI thread
+        *compressing = Some(std::thread::spawn(move || {
+            if let Some(before) = before {
+                let _ = before.join();
+            }
+            if let Err(e) = compress(&previous) {
+                crate::log::main_log_blocking(
+                    LogLevel::Warn,
+                    &format!("Failed to compress {}: {}", previous.display(), e),
+                );
+            }
+            for oldest in pruned {
+                let _ = std::fs::remove_file(oldest);
+            }
+        }));
+        Ok(())
+    }
+
+    /// Takes the earlier files beyond the kept number off the list, to be deleted.
+    fn prune(&mut self) -> Vec<PathBuf> {
+        let excess = self.rotated.len().saturating_sub(self.rotation.keep);
+        self.rotated.drain(..excess).collect()
+    }
+
+    fn report(&mut self, result: std::io::Result<()>) -> Option<LogEntry> {
+        match result {
+            Ok(()) => None,
+            Err(_) if self.failed => None,
+            Err(e) => {
+                self.failed = true;
+                Some(LogEntry::new(
+                    LogLevel::Error,
+                    &format!("Failed to write log file {}: {}", self.path.display(), e),
+                ))
+            }
+        }
+    }
+}
+
+fn append(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Returns `path` with `n` before the extension, e.g. `session.log` becomes `session.3.log`.
+fn numbered(path: &Path, n: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, n, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, n),
+    };
+    path.with_file_name(name)
+}
+
+/// Compresses `path` into `path.gz` and removes the original.
+fn compress(path: &Path) -> std::io::Result<()> {
+    let mut compressed = path.as_os_str().to_os_string();
+    compressed.push(".gz");
+    let mut encoder =
+        flate2::write::GzEncoder::new(File::create(compressed)?, flate2::Compression::default());
+    std::io::copy(&mut File::open(path)?, &mut encoder)?;
+    encoder.finish()?;
+    std::fs::remove_file(path)
+}
@@ -1,21 +1,209 @@
 mod config;
+mod geometry;
+mod headless;
+mod i18n;
 mod log;
+mod logfile;
+mod menu;
+mod palette;
 mod replay;
+mod services;
 mod session;
+mod syslog;
+mod tokens;
+mod tray;
 mod types;
 mod udp;
 mod ui;
+mod validate;
 
+use clap::{CommandFactory, Parser};
 use config::load_config;
-use iced::Subscription;
 use iced::window::Icon;
-use png;
-use log::{format_log, LogLevel, CONNECTION_SENDER, LOG_SENDER, MAIN_LOG_SENDER, PROGRESS_SENDER};
+use iced::{Subscription, Task};
+use log::{CONNECTION_SENDER, LOG_SENDER, LogEntry, LogLevel, MAIN_LOG_SENDER, PROGRESS_SENDER};
+use types::WindowState;
 use ui::{App, Message};
+/// Command-line options. Target settings given here override the loaded config for this
+/// run and are only written back with `--save`.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Cli {
+    /// Protocol for new sessions
+    #[arg(long, value_enum)]
+    protocol: Option<CliProtocol>,
+    /// Target address for new sessions
+    #[arg(long)]
+    address: Option<String>,
+    /// Target port for new sessions
+    #[arg(long)]
+    port: Option<u16>,
+    /// Open a session to the target once the main window appears
+    #[arg(long, conflicts_with = "replay")]
+    connect: bool,
+    /// Open a replay window for a JSON or CSV replay file
+    #[arg(long, value_name = "FILE")]
+    replay: Option<std::path::PathBuf>,
+    /// Read and save the config at this path instead of the default location
+    #[arg(long, value_name = "PATH")]
+    config: Option<std::path::PathBuf>,
+    /// Write the overridden protocol, address, and port to the config file
+    #[arg(long)]
+    save: bool,
+    /// Print info messages of the main log to the console, not only warnings and errors,
+    /// and log Debug entries; the same as --console-level debug
+    #[arg(long)]
+    verbose: bool,
+    /// Least severe level printed to the console: debug, info, warn, or error; takes
+    /// precedence over --verbose [default: warn, info for replay]
+    #[arg(long, global = true, value_name = "LEVEL", value_parser = log::parse_level)]
+    console_level: Option<LogLevel>,
+    /// Print to the console without colors, which are otherwise used on terminals
+    #[arg(long, global = true)]
+    no_color: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Run a replay file without the GUI; exits non-zero if the replay fails
+    Replay {
+        /// JSON or CSV replay file
+        file: std::path::PathBuf,
+        /// Target address; defaults to the target recorded in the file
+        #[arg(long)]
+        address: Option<String>,
+        /// Target port; defaults to the target recorded in the file
+        #[arg(long)]
+        port: Option<u16>,
+        /// Protocol of a CSV file (JSON files name their own)
+        #[arg(long, value_enum)]
+        protocol: Option<CliProtocol>,
+        /// Number of times to run the replay
+        #[arg(long = "loop", value_name = "N", default_value_t = 1,
+              value_parser = clap::value_parser!(u32).range(1..))]
+        loops: u32,
+        /// Playback speed; 2 halves every delay
+        #[arg(long, value_name = "F", default_value_t = 1.0)]
+        speed: f64,
+    },
+}
+
+impl CliProtocol {
+    fn protocol(self) -> types::Protocol {
+        match self {
+            CliProtocol::Tcp => types::Protocol::Tcp,
+            CliProtocol::Udp => types::Protocol::Udp,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum CliProtocol {
+    Tcp,
+    Udp,
+}
+
+impl Cli {
+    /// Applies the protocol, address, and port overrides to `config`.
+    fn apply(&self, config: &mut config::Config) {
+        if let Some(protocol) = self.protocol {
+            config.protocol = protocol.protocol();
+        }
+        if let Some(address) = &self.address {
+            config.address = address.clone();
+        }
+        if let Some(port) = self.port {
+            config.port = port;
+        }
+    }
+
+    /// Returns the console level given with `--console-level` or `--verbose`, or else in
+    /// `$REPLAYR_CONSOLE_LEVEL`, or `default`, with a warning if the variable is invalid.
+    fn console_level(&self, default: LogLevel) -> (LogLevel, Option<LogEntry>) {
+        if let Some(level) = self.console_level {
+            return (level, None);
+        }
+        if self.verbose {
+            return (LogLevel::Debug, None);
+        }
+        match std::env::var(config::CONSOLE_LEVEL_ENV) {
+            Ok(value) => match log::parse_level(&value) {
+                Ok(level) => (level, None),
+                Err(e) => (
+                    default,
+                    Some(LogEntry::new(
+                        LogLevel::Warn,
+                        &format!("Ignoring {}: {}", config::CONSOLE_LEVEL_ENV, e),
+                    )),
+                ),
+            },
+            Err(_) => (default, None),
+        }
+    }
+
+    /// Whether any target setting is overridden.
+    fn overrides(&self) -> bool {
+        self.protocol.is_some() || self.address.is_some() || self.port.is_some()
+    }
+}
+
+/// Prints a usage error and exits before the GUI starts.
+fn cli_error(kind: clap::error::ErrorKind, msg: String) -> ! {
+    Cli::command().error(kind, msg).exit()
+}
+
+/// Applies the main window's address and port rules to target overrides.
+fn check_target(address: Option<&str>, port: Option<u16>) {
+    let address = address.map(validate::address).and_then(Result::err);
+    let port = port
+        .map(|port| validate::port(&port.to_string()))
+        .and_then(Result::err);
+    if let Some(e) = address.or(port) {
+        cli_error(clap::error::ErrorKind::InvalidValue, e);
+    }
+}
+
+/// Turns a broadcast channel into a stream of messages. If the UI falls behind and the
+/// channel drops messages, they are counted and noted in the main log and receiving goes
+/// on; the stream only ends once the channel is closed.
+fn broadcast_stream<T: Clone + Send + 'static>(
+    sender: &'static tokio::sync::broadcast::Sender<T>,
+    channel: &'static log::Channel,
+    map: fn(T) -> Message,
+) -> impl iced::futures::Stream<Item = Message> {
+    iced::futures::stream::unfold(None, move |receiver| async move {
+        let mut receiver = match receiver {
+            Some(receiver) => receiver,
+            None => sender.subscribe(),
+        };
+        let message = match receiver.recv().await {
+            Ok(value) => map(value),
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                Message::MainLog(channel.lagged(skipped))
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+        };
+        Some((message, Some(receiver)))
+    })
+}
+
 fn get_app_icon() -> Icon {
-    let icon_bytes = include_bytes!("../assets/icon.png");
+    decode_icon(include_bytes!("../assets/icon.png"))
+}
+
+/// Icon of session and replay windows, each marked so they can be told apart from each
+/// other and from the main window in the task switcher.
+pub fn window_icon(kind: types::WindowKind) -> Icon {
+    match kind {
+        types::WindowKind::Session => decode_icon(include_bytes!("../assets/session-icon.png")),
+        types::WindowKind::Replay => decode_icon(include_bytes!("../assets/replay-icon.png")),
+    }
+}
 
-    let decoder = png::Decoder::new(std::io::Cursor::new(&icon_bytes[..]));
+fn decode_icon(icon_bytes: &[u8]) -> Icon {
+    let decoder = png::Decoder::new(std::io::Cursor::new(icon_bytes));
     let mut reader = decoder.read_info().expect("Failed to read PNG");
     let width = reader.info().width;
     let height = reader.info().height;
@@ -26,102 +214,381 @@ fn get_app_icon() -> Icon {
 }
 
 fn main() -> iced::Result {
-    println!(
-        "{}",
-        format_log(LogLevel::Info, "Starting replayr...")
-    );
-    let config = load_config();
-    println!(
-        "{}",
-        format_log(LogLevel::Info, &format!("Config loaded: {:?}", config))
+    let cli = Cli::parse();
+    if cli.no_color {
+        anstream::ColorChoice::Never.write_global();
+    }
+    // Headless replays print their progress, so they show info messages by default
+    let (console_level, console_warning) = cli.console_level(match cli.command {
+        Some(Command::Replay { .. }) => LogLevel::Info,
+        None => LogLevel::Warn,
+    });
+    log::set_console_level(console_level);
+    if let Some(warning) = console_warning {
+        log::console(&warning);
+    }
+    if let Some(path) = &cli.config {
+        config::set_config_path(path.clone());
+    }
+    if let Some(Command::Replay {
+        file,
+        address,
+        port,
+        protocol,
+        loops,
+        speed,
+    }) = cli.command
+    {
+        check_target(address.as_deref(), port);
+        if !(speed.is_finite() && speed > 0.0) {
+            cli_error(
+                clap::error::ErrorKind::InvalidValue,
+                "--speed must be a positive number".to_string(),
+            );
+        }
+        // Loading the config applies its log timestamp settings
+        for line in load_config().log_lines() {
+            anstream::eprintln!("{}", log::styled(&line));
+        }
+        std::process::exit(headless::run(headless::HeadlessReplay {
+            file,
+            protocol: protocol.map(CliProtocol::protocol),
+            address,
+            port,
+            loops,
+            speed,
+        }));
+    }
+    if cli.save && !cli.overrides() {
+        cli_error(
+            clap::error::ErrorKind::MissingRequiredArgument,
+            "--save needs at least one of --protocol, --address, or --port".to_string(),
+        );
+    }
+    check_target(cli.address.as_deref(), cli.port);
+    let load = load_config();
+    let mut config_messages = load.log_lines();
+    let config_problem = load.problem();
+    let mut config = load.config;
+    config_messages.extend(config::apply_env_overrides(&mut config));
+    let recent_configs = config::remember_config(&config::config_path()).unwrap_or_else(|e| {
+        config_messages.push(LogEntry::new(
+            LogLevel::Warn,
+            &format!("Failed to remember config: {}", e),
+        ));
+        config::recent_configs()
+    });
+    log::console(&LogEntry::new(LogLevel::Info, "Starting replayr..."));
+    cli.apply(&mut config);
+    // Before anything sends on the channels, which are created on first use
+    log::set_channel_capacities(
+        config.log_channel_capacity,
+        config.event_channel_capacity,
+        config.progress_channel_capacity,
     );
-    iced::daemon(App::title, ui::update_app, ui::view_app)
-        .subscription(|_state: &App| {
+    if cli.save {
+        config::save_config(&config);
+        config::flush_config(true);
+    }
+    // Load the replay up front so a bad file is reported before any window opens
+    let replay = cli.replay.as_ref().map(|path| {
+        let replay = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+            .and_then(|content| ui::parse_replay_file(path, &content, config.protocol))
+            .map(|replay| (replay, config.replay_options()))
+            .unwrap_or_else(|e| cli_error(clap::error::ErrorKind::ValueValidation, e));
+        (replay.0, path.clone(), replay.1)
+    });
+    for entry in &config_messages {
+        log::console(entry);
+    }
+    log::console(&LogEntry::new(
+        LogLevel::Info,
+        &format!(
+            "Config loaded from {}: {:?}",
+            config::config_path().display(),
+            config
+        ),
+    ));
+    // Interface text is sized once, at startup
+    let text_size = config.accessibility.text_size;
+    iced::daemon(App::title, ui::update, ui::view_app)
+        .settings(iced::Settings {
+            default_text_size: f32::from(text_size).into(),
+            ..Default::default()
+        })
+        .theme(App::theme)
+        .subscription(|state: &App| {
+            // Idle countdowns and connection timers only need a clock while a session is connected
+            let idle_tick = if state.windows.values().any(|w| {
+                matches!(&w.state, WindowState::Session(data) if data.connected_since.is_some())
+            }) {
+                iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::Tick)
+            } else {
+                Subscription::none()
+            };
+            // Scheduled send countdowns are shown with tenths of a second
+            let schedule_tick = if state.windows.values().any(
+                |w| matches!(&w.state, WindowState::Session(data) if data.scheduled_send.is_some()),
+            ) {
+                iced::time::every(std::time::Duration::from_millis(100)).map(|_| Message::Tick)
+            } else {
+                Subscription::none()
+            };
+            // The connecting indicator is animated and counts the seconds; with reduced
+            // motion only the seconds change
+            let connect_tick = if state.windows.values().any(|w| match &w.state {
+                WindowState::Session(data) => data.connecting,
+                WindowState::Replay(data) => {
+                    data.status == types::ReplayStatus::Running && !data.connected
+                }
+            }) {
+                let interval = if state.config.accessibility.reduced_motion {
+                    std::time::Duration::from_secs(1)
+                } else {
+                    std::time::Duration::from_millis(150)
+                };
+                iced::time::every(interval).map(|_| Message::Tick)
+            } else {
+                Subscription::none()
+            };
+            // Config changes are written once they settle
+            let save_tick = if config::save_pending() {
+                iced::time::every(config::SAVE_DELAY).map(|_| Message::FlushConfig)
+            } else {
+                Subscription::none()
+            };
+            // Forwarded session log entries are taken in batches, so busy sessions cannot
+            // flood the main window
+            let forward_tick = if state.config.forward_session_logs {
+                iced::time::every(std::time::Duration::from_millis(250))
+                    .map(|_| Message::ForwardedLogs)
+            } else {
+                Subscription::none()
+            };
+            // Log files are flushed regularly, so they can be followed while windows are open
+            let log_file_tick = if state.windows.values().any(|w| w.log_file.is_some()) {
+                iced::time::every(logfile::FLUSH_INTERVAL).map(|_| Message::FlushLogFiles)
+            } else {
+                Subscription::none()
+            };
+            // The command palette is driven by the arrow keys and Esc
+            let palette_keys = if state.palette.is_some() {
+                iced::event::listen_with(|event, _status, _id| match event {
+                    iced::Event::Keyboard(iced::keyboard::Event::KeyPressed { key, .. }) => {
+                        use iced::keyboard::{Key, key::Named};
+                        match key {
+                            Key::Named(Named::ArrowUp) => Some(Message::PaletteMoved(-1)),
+                            Key::Named(Named::ArrowDown) => Some(Message::PaletteMoved(1)),
+                            Key::Named(Named::PageUp) => Some(Message::PaletteMoved(-10)),
+                            Key::Named(Named::PageDown) => Some(Message::PaletteMoved(10)),
+                            Key::Named(Named::Escape) => Some(Message::ClosePalette),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                })
+            } else {
+                Subscription::none()
+            };
+            // An open menu closes with Esc
+            let menu_keys = if state.menu.is_some() {
+                iced::event::listen_with(|event, _status, _id| match event {
+                    iced::Event::Keyboard(iced::keyboard::Event::KeyPressed {
+                        key: iced::keyboard::Key::Named(iced::keyboard::key::Named::Escape),
+                        ..
+                    }) => Some(Message::CloseMenu),
+                    _ => None,
+                })
+            } else {
+                Subscription::none()
+            };
+            // The zoom level is shown for a moment after it changes
+            let zoom_tick = if state.main_log_zoom.shown()
+                || state.windows.values().any(|w| w.log_zoom.shown())
+            {
+                iced::time::every(std::time::Duration::from_millis(250)).map(|_| Message::Tick)
+            } else {
+                Subscription::none()
+            };
+            // Notices other than errors are dismissed after a while
+            let banner_tick = if state.config.banner_timeout_secs > 0
+                && (state.main_banners.expiring()
+                    || state.windows.values().any(|w| w.banners.expiring()))
+            {
+                iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::ExpireBanners)
+            } else {
+                Subscription::none()
+            };
             Subscription::batch(vec![
+                idle_tick,
+                banner_tick,
+                zoom_tick,
+                connect_tick,
+                palette_keys,
+                menu_keys,
+                schedule_tick,
+                save_tick,
+                log_file_tick,
+                forward_tick,
                 iced::window::close_events().map(Message::Closed),
+                iced::window::close_requests().map(Message::CloseRequested),
+                // Window geometry is remembered for the next start and new windows
+                iced::event::listen_with(|event, _status, id| match event {
+                    iced::Event::Window(iced::window::Event::Moved(position)) => {
+                        Some(Message::WindowMoved(id, position))
+                    }
+                    iced::Event::Window(iced::window::Event::Resized(size)) => {
+                        Some(Message::WindowResized(id, size))
+                    }
+                    // Files dropped on a session load into its payload input
+                    iced::Event::Window(iced::window::Event::FileHovered(_)) => {
+                        Some(Message::FileHovered(id))
+                    }
+                    iced::Event::Window(iced::window::Event::FilesHoveredLeft) => {
+                        Some(Message::FilesHoveredLeft(id))
+                    }
+                    iced::Event::Window(iced::window::Event::FileDropped(path)) => {
+                        Some(Message::FileDropped(id, path))
+                    }
+                    // Log text zooms with Ctrl+scroll and Ctrl+=, Ctrl+-, Ctrl+0; Ctrl+Tab
+                    // switches tabs
+                    iced::Event::Keyboard(iced::keyboard::Event::ModifiersChanged(modifiers)) => {
+                        Some(Message::ModifiersChanged(modifiers))
+                    }
+                    iced::Event::Keyboard(iced::keyboard::Event::KeyPressed {
+                        key,
+                        modifiers,
+                        ..
+                    }) => ui::shortcut(id, &key, modifiers),
+                    iced::Event::Mouse(iced::mouse::Event::WheelScrolled { delta }) => {
+                        Some(Message::WheelScrolled(
+                            id,
+                            match delta {
+                                iced::mouse::ScrollDelta::Lines { y, .. }
+                                | iced::mouse::ScrollDelta::Pixels { y, .. } => y,
+                            },
+                        ))
+                    }
+                    _ => None,
+                }),
+                // Settings edited in the config file are merged while running
+                Subscription::run_with_id(
+                    ("config_watch", config::config_path()),
+                    config::watch_config(),
+                )
+                .map(|_| Message::ConfigFileChanged),
+                // Messages from session and replay tasks
                 Subscription::run_with_id(
                     "log",
-                    iced::futures::stream::unfold(
-                        None::<tokio::sync::broadcast::Receiver<crate::types::LogMessage>>,
-                        |state| async move {
-                            let mut receiver = match state {
-                                Some(r) => r,
-                                None => LOG_SENDER.lock().await.subscribe(),
-                            };
-                            match receiver.recv().await {
-                                Ok(msg) => Some((
-                                    Message::LogReceived(msg.content, msg.window_id),
-                                    Some(receiver),
-                                )),
-                                Err(_) => None,
-                            }
+                    broadcast_stream(
+                        &LOG_SENDER,
+                        &log::LOG_CHANNEL,
+                        |msg: crate::types::LogMessage| {
+                            Message::LogReceived(msg.entry, msg.window_id)
                         },
                     ),
                 ),
                 Subscription::run_with_id(
                     "main_log",
-                    iced::futures::stream::unfold(
-                        None::<tokio::sync::broadcast::Receiver<String>>,
-                        |state| async move {
-                            let mut receiver = match state {
-                                Some(r) => r,
-                                None => MAIN_LOG_SENDER.lock().await.subscribe(),
-                            };
-                            match receiver.recv().await {
-                                Ok(content) => Some((Message::MainLog(content), Some(receiver))),
-                                Err(_) => None,
-                            }
-                        },
-                    ),
+                    broadcast_stream(&MAIN_LOG_SENDER, &log::MAIN_LOG_CHANNEL, Message::MainLog),
                 ),
                 Subscription::run_with_id(
                     "connection",
-                    iced::futures::stream::unfold(
-                        None::<tokio::sync::broadcast::Receiver<(iced::window::Id, bool)>>,
-                        |state| async move {
-                            let mut receiver = match state {
-                                Some(r) => r,
-                                None => CONNECTION_SENDER.lock().await.subscribe(),
-                            };
-                            match receiver.recv().await {
-                                Ok((id, connected)) => {
-                                    Some((Message::ConnectionStatus(id, connected), Some(receiver)))
-                                }
-                                Err(_) => None,
-                            }
-                        },
+                    broadcast_stream(
+                        &CONNECTION_SENDER,
+                        &log::CONNECTION_CHANNEL,
+                        |(id, event)| Message::SessionEvent(id, event),
                     ),
                 ),
+                // Actions chosen in the tray menu, in builds with a tray icon
+                tray::subscription(),
                 Subscription::run_with_id(
                     "progress",
-                    iced::futures::stream::unfold(
-                        None::<tokio::sync::broadcast::Receiver<(iced::window::Id, usize)>>,
-                        |state| async move {
-                            let mut receiver = match state {
-                                Some(r) => r,
-                                None => PROGRESS_SENDER.lock().await.subscribe(),
-                            };
-                            match receiver.recv().await {
-                                Ok((id, current)) => {
-                                    Some((Message::ReplayProgress(id, current), Some(receiver)))
-                                }
-                                Err(_) => None,
-                            }
-                        },
-                    ),
+                    broadcast_stream(&PROGRESS_SENDER, &log::PROGRESS_CHANNEL, |(id, event)| {
+                        Message::ReplayProgress(id, event)
+                    }),
                 ),
             ])
         })
-        .run_with(|| {
+        .run_with(move || {
             let mut app = App {
                 config,
+                recent_configs,
+                config_problem,
+                main_log: config_messages.into_iter().collect(),
                 ..Default::default()
             };
+            app.sync_inputs();
             let (main_window_id, task) = iced::window::open(iced::window::Settings {
                 icon: Some(get_app_icon()),
-                ..Default::default()
+                // Closing the main window may need confirmation, see Message::CloseRequested
+                exit_on_close_request: false,
+                ..geometry::settings(app.config.main_window.as_ref(), 0)
             });
             app.main_window_id = main_window_id;
-            (app, task.map(|_| Message::NoOp))
+            // Windows requested on the command line open once the main window exists
+            let startup = if cli.connect {
+                Task::done(Message::Connect(main_window_id))
+            } else if let Some((replay, path, options)) = replay {
+                Task::done(Message::ReplayWindowOpenedWithFile(
+                    replay, path, options, None,
+                ))
+            } else {
+                Task::none()
+            };
+            let tray = if app.config.tray_icon {
+                Task::done(Message::StartTray)
+            } else {
+                Task::none()
+            };
+            // The bundled log font must be loaded before the first window is drawn
+            let font = iced::font::load(config::BUNDLED_LOG_FONT_BYTES).map(|_| Message::NoOp);
+            (
+                app,
+                Task::batch([font, tray, task.map(|_| Message::NoOp).chain(startup)]),
+            )
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::futures::StreamExt;
+
+    /// Text of a stream message, which is always a main log entry in these tests.
+    fn text(message: Option<Message>) -> String {
+        match message {
+            Some(Message::MainLog(entry)) => entry.message,
+            _ => panic!("expected a main log message"),
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_keeps_delivering_after_the_channel_overflows() {
+        let sender: &'static tokio::sync::broadcast::Sender<u32> =
+            Box::leak(Box::new(tokio::sync::broadcast::Sender::new(16)));
+        let mut stream = Box::pin(broadcast_stream(sender, &log::LOG_CHANNEL, |n| {
+            Message::MainLog(LogEntry::new(LogLevel::Info, &n.to_string()))
+        }));
+        // The stream subscribes when first polled
+        assert!(iced::futures::poll!(stream.next()).is_pending());
+        for n in 0..5000 {
+            sender.send(n).unwrap();
+        }
+        let lagged = text(stream.next().await);
+        assert!(
+            lagged.contains("skipped 4984 log messages"),
+            "unexpected lag entry: {}",
+            lagged
+        );
+        // The newest messages the channel kept follow in order
+        for n in 4984..5000 {
+            assert_eq!(text(stream.next().await), n.to_string());
+        }
+        for n in 5000..5010 {
+            sender.send(n).unwrap();
+            assert_eq!(text(stream.next().await), n.to_string());
+        }
+    }
+}
@@ -0,0 +1,194 @@
+// Menu bar: drop-down menus of the actions of a window, sending the same messages as its
+// buttons.
+
+use crate::i18n::{tr, tr_args};
+use crate::types::{ReplayStatus, WindowState};
+use crate::ui::{App, Message};
+use iced::window;
+
+/// A drop-down menu of the menu bar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Menu {
+    File,
+    Session,
+    Replay,
+    Help,
+}
+
+impl Menu {
+    pub fn title(self) -> &'static str {
+        match self {
+            Menu::File => "File",
+            Menu::Session => "Session",
+            Menu::Replay => "Replay",
+            Menu::Help => "Help",
+        }
+    }
+}
+
+/// The drop-down menu open in a window.
+#[derive(Clone, Copy, Debug)]
+pub struct OpenMenu {
+    pub window: window::Id,
+    pub menu: Menu,
+}
+
+/// An entry of a drop-down menu.
+pub struct MenuItem {
+    pub label: String,
+    pub shortcut: Option<String>,
+    /// None when the action does not apply, e.g. exporting a replay with nothing recorded.
+    pub message: Option<Message>,
+}
+
+impl MenuItem {
+    fn new(label: impl Into<String>, message: Option<Message>) -> Self {
+        Self {
+            label: label.into(),
+            shortcut: None,
+            message,
+        }
+    }
+
+    fn shortcut(mut self, keys: &str) -> Self {
+        self.shortcut = Some(shortcut_label(keys));
+        self
+    }
+}
+
+/// Keyboard shortcuts of every window, as listed by Help > Keyboard shortcuts.
+pub const SHORTCUTS: &[(&str, &str)] = &[
+    ("Ctrl+P", "Command palette"),
+    ("Ctrl+= / Ctrl+-", "Zoom the log text in or out"),
+    ("Ctrl+scroll", "Zoom the log text"),
+    ("Ctrl+0", "Reset the log zoom"),
+    ("Ctrl+Tab / Ctrl+Shift+Tab", "Next or previous tab"),
+    ("Enter", "Send the payload being typed"),
+    ("Esc", "Close the command palette or a menu"),
+];
+
+/// Shortcut as shown on this platform, where Cmd takes the place of Ctrl on macOS.
+pub fn shortcut_label(keys: &str) -> String {
+    if cfg!(target_os = "macos") {
+        keys.replace("Ctrl", "Cmd")
+    } else {
+        keys.to_string()
+    }
+}
+
+/// Menus of window `id`: all of them in the main window, and the menu of its kind and Help
+/// in session and replay windows.
+pub fn menus(state: &App, id: window::Id) -> Vec<Menu> {
+    match state.windows.get(&id).map(|window| &window.state) {
+        None => vec![Menu::File, Menu::Session, Menu::Replay, Menu::Help],
+        Some(WindowState::Session(_)) => vec![Menu::Session, Menu::Help],
+        Some(WindowState::Replay(_)) => vec![Menu::Replay, Menu::Help],
+    }
+}
+
+/// Items of `menu` in window `id`. Session and replay items of the main window act on the
+/// tab shown in it, and are disabled without one of their kind.
+pub fn items(state: &App, id: window::Id, menu: Menu) -> Vec<MenuItem> {
+    let target = state.shown_window(id);
+    let target_state = state.windows.get(&target).map(|window| &window.state);
+    let main = state.main_window_id;
+    match menu {
+        Menu::File => vec![
+            MenuItem::new(tr("Open replay…"), Some(Message::ReplayConnect)),
+            MenuItem::new(tr("Switch config…"), Some(Message::SwitchConfig(main))),
+            MenuItem::new(tr("Import settings…"), Some(Message::ImportSettings(main))),
+            MenuItem::new(tr("Export settings…"), Some(Message::ExportSettings(main))),
+            MenuItem::new(tr("Export all logs…"), Some(Message::ExportAllLogs(main))),
+            MenuItem::new(tr("Quit"), Some(Message::Quit)),
+        ],
+        Menu::Session => {
+            let session = match target_state {
+                Some(WindowState::Session(data)) => Some(data),
+                _ => None,
+            };
+            let when = |applies: fn(&crate::types::SessionData) -> bool, message: Message| {
+                session.filter(|data| applies(data)).map(|_| message)
+            };
+            vec![
+                MenuItem::new(
+                    tr("New session"),
+                    state
+                        .input_errors()
+                        .iter()
+                        .all(Option::is_none)
+                        .then_some(Message::Connect(main)),
+                ),
+                MenuItem::new(
+                    tr("Duplicate session"),
+                    when(|_| true, Message::DuplicateSession(target)),
+                ),
+                MenuItem::new(
+                    tr("Export replay…"),
+                    when(
+                        |data| !data.replay_payloads.is_empty(),
+                        Message::ExportSession(target),
+                    ),
+                ),
+                MenuItem::new(
+                    tr("Export logs…"),
+                    when(|_| true, Message::ExportLogs(target)),
+                ),
+                MenuItem::new(
+                    tr("Disconnect"),
+                    when(
+                        |data| data.connected || data.connecting,
+                        Message::Disconnect(target),
+                    ),
+                ),
+                MenuItem::new(
+                    tr("Reconnect"),
+                    when(
+                        |data| !data.connected && !data.connecting,
+                        Message::Reconnect(target),
+                    ),
+                ),
+            ]
+        }
+        Menu::Replay => {
+            let replay = match target_state {
+                Some(WindowState::Replay(data)) => Some(data),
+                _ => None,
+            };
+            let last = state.config.recent_replays.first();
+            vec![
+                MenuItem::new(tr("Open replay…"), Some(Message::ReplayConnect)),
+                MenuItem::new(
+                    match last {
+                        Some(recent) => tr_args("Replay last: {}", &[&recent.name()]),
+                        None => tr("Replay last").to_string(),
+                    },
+                    last.filter(|recent| recent.path.is_file())
+                        .map(|recent| Message::ReplayRecent(recent.clone())),
+                ),
+                MenuItem::new(
+                    tr("Start replay"),
+                    replay
+                        .filter(|data| data.cancel.is_none())
+                        .map(|_| Message::StartReplay(target)),
+                ),
+                MenuItem::new(
+                    tr("Stop replay"),
+                    replay
+                        .filter(|data| data.status == ReplayStatus::Running)
+                        .map(|_| Message::StopReplay(target)),
+                ),
+                MenuItem::new(
+                    tr("Save replay"),
+                    replay
+                        .filter(|data| data.dirty)
+                        .map(|_| Message::SaveReplay(target)),
+                ),
+            ]
+        }
+        Menu::Help => vec![
+            MenuItem::new(tr("Command palette"), Some(Message::OpenPalette(id))).shortcut("Ctrl+P"),
+            MenuItem::new(tr("Keyboard shortcuts"), Some(Message::ShowShortcuts)),
+            MenuItem::new(tr("About replayr"), Some(Message::ShowAbout)),
+        ],
+    }
+}
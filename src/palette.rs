@@ -0,0 +1,219 @@
+// Command palette: a searchable list of the actions of a window, opened with Ctrl+P.
+
+use crate::i18n::{tr, tr_args};
+use crate::types::{WindowState, ZoomChange};
+use crate::ui::{App, Message};
+use iced::window;
+
+/// Kind of window an action is offered in.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Main,
+    Session,
+    Replay,
+    /// Session and replay windows.
+    Window,
+    /// Every window.
+    Any,
+}
+
+/// Builds the message of an action for the window the palette was opened in.
+type ActionMessage = fn(window::Id) -> Message;
+
+/// Actions of the command palette, by the kind of window they are offered in.
+const ACTIONS: &[(Scope, &str, ActionMessage)] = &[
+    (Scope::Main, "Connect", Message::Connect),
+    (Scope::Main, "Replay file…", |_| Message::ReplayConnect),
+    (Scope::Main, "Switch config…", Message::SwitchConfig),
+    (Scope::Main, "Export settings…", Message::ExportSettings),
+    (Scope::Main, "Import settings…", Message::ImportSettings),
+    (Scope::Main, "Export all logs…", Message::ExportAllLogs),
+    (Scope::Session, "Send", Message::SubmitPayload),
+    (Scope::Session, "Record only", Message::RecordOnly),
+    (Scope::Session, "Send repeatedly", Message::SendRepeatedly),
+    (Scope::Session, "Stop repeat", Message::StopRepeat),
+    (Scope::Session, "Send each line", Message::SendBatch),
+    (
+        Scope::Session,
+        "Undo last recorded",
+        Message::UndoLastRecorded,
+    ),
+    (
+        Scope::Session,
+        "Pause or resume recording",
+        Message::RecordingPauseToggled,
+    ),
+    (
+        Scope::Session,
+        "Show or hide recording",
+        Message::RecordingToggled,
+    ),
+    (Scope::Session, "Show or hide notes", Message::NotesToggled),
+    (Scope::Session, "Show or hide batch", Message::BatchToggled),
+    (Scope::Session, "Export replay…", Message::ExportSession),
+    (Scope::Session, "Export CSV…", Message::ExportCsv),
+    (
+        Scope::Session,
+        "Export transcript…",
+        Message::ExportTranscript,
+    ),
+    (Scope::Session, "Run replay here…", Message::RunReplayHere),
+    (Scope::Session, "Stop replay", Message::StopSessionReplay),
+    (
+        Scope::Session,
+        "Duplicate session",
+        Message::DuplicateSession,
+    ),
+    (Scope::Session, "Disconnect", Message::Disconnect),
+    (Scope::Session, "Reconnect", Message::Reconnect),
+    (Scope::Replay, "Start replay", Message::StartReplay),
+    (Scope::Replay, "Stop replay", Message::StopReplay),
+    (Scope::Replay, "Validate replay", Message::ValidateReplay),
+    (Scope::Replay, "Save replay", Message::SaveReplay),
+    (Scope::Replay, "Save replay as…", Message::SaveReplayAs),
+    (Scope::Replay, "Export responses…", Message::ExportResponses),
+    (Scope::Window, "Rename window", Message::RenameWindow),
+    (Scope::Window, "Close window", Message::CloseRequested),
+    (Scope::Any, "Clear log", Message::ClearLog),
+    (Scope::Any, "Copy log", Message::CopyLog),
+    (Scope::Any, "Export logs…", Message::ExportLogs),
+    (Scope::Any, "Zoom in", |id| {
+        Message::LogZoomed(id, ZoomChange::In)
+    }),
+    (Scope::Any, "Zoom out", |id| {
+        Message::LogZoomed(id, ZoomChange::Out)
+    }),
+    (Scope::Any, "Reset zoom", |id| {
+        Message::LogZoomed(id, ZoomChange::Reset)
+    }),
+];
+
+/// An action offered by the command palette.
+#[derive(Clone, Debug)]
+pub struct Action {
+    pub name: String,
+    pub message: Message,
+}
+
+/// State of the open command palette.
+#[derive(Clone, Debug)]
+pub struct Palette {
+    /// Window the actions apply to.
+    pub window: window::Id,
+    pub query: String,
+    /// Index of the highlighted action among those matching the query.
+    pub selected: usize,
+}
+
+impl Palette {
+    pub fn new(window: window::Id) -> Self {
+        Self {
+            window,
+            query: String::new(),
+            selected: 0,
+        }
+    }
+
+    /// Actions of the window matching the query, best match first.
+    pub fn matches(&self, state: &App) -> Vec<Action> {
+        let mut scored: Vec<(i32, Action)> = actions(state, self.window)
+            .into_iter()
+            .filter_map(|action| Some((score(&self.query, &action.name)?, action)))
+            .collect();
+        // Stable, so equal scores keep the order of the registry
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(_, action)| action).collect()
+    }
+}
+
+/// Actions offered in window `id`: those of its kind from `ACTIONS`, then the ones that
+/// depend on its state or the recent lists.
+fn actions(state: &App, id: window::Id) -> Vec<Action> {
+    let window = state.windows.get(&id);
+    let scope = match window.map(|window| &window.state) {
+        None if id == state.main_window_id => Scope::Main,
+        None => return Vec::new(),
+        Some(WindowState::Session(_)) => Scope::Session,
+        Some(WindowState::Replay(_)) => Scope::Replay,
+    };
+    let offered = |action_scope: Scope| {
+        action_scope == scope
+            || action_scope == Scope::Any
+            || (action_scope == Scope::Window && scope != Scope::Main)
+    };
+    let mut actions: Vec<Action> = ACTIONS
+        .iter()
+        .filter(|(action_scope, _, _)| offered(*action_scope))
+        .map(|(_, name, message)| Action {
+            name: tr(name).to_string(),
+            message: message(id),
+        })
+        .collect();
+    match window.map(|window| &window.state) {
+        Some(WindowState::Session(data)) => actions.push(Action {
+            name: tr(if data.heartbeat_enabled {
+                "Stop heartbeat"
+            } else {
+                "Start heartbeat"
+            })
+            .to_string(),
+            message: Message::HeartbeatToggled(id, !data.heartbeat_enabled),
+        }),
+        Some(WindowState::Replay(_)) => {}
+        None => {
+            actions.extend(state.config.recent_replays.iter().map(|recent| Action {
+                name: tr_args("Replay {}", &[recent]),
+                message: Message::ReplayRecent(recent.clone()),
+            }));
+            actions.extend(state.config.recent_targets.iter().map(|target| Action {
+                name: tr_args("Use target {}", &[target]),
+                message: Message::RecentTargetSelected(id, target.clone()),
+            }));
+        }
+    }
+    actions
+}
+
+/// Scores how well `query` matches `name`: every character of the query must appear in
+/// the name in order, ignoring case. Characters at the start of a word and right after
+/// the previous match score higher, so "exr" ranks "Export replay…" first.
+fn score(query: &str, name: &str) -> Option<i32> {
+    let query: Vec<char> = query
+        .to_lowercase()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    // Jumping ahead to word starts can miss matches taking the first occurrence finds
+    [true, false]
+        .into_iter()
+        .filter_map(|word_starts| score_matches(&query, &name, word_starts))
+        .max()
+}
+
+/// Scores one way of matching the query characters in the name, taking each at the next
+/// word start if `word_starts` and it does not directly follow the previous match.
+fn score_matches(query: &[char], name: &[char], word_starts: bool) -> Option<i32> {
+    let word_start = |i: usize| i == 0 || !name[i - 1].is_alphanumeric();
+    let mut score = 0;
+    let mut next = 0;
+    for c in query {
+        let position = |start: &dyn Fn(usize) -> bool| {
+            (next..name.len()).find(|i| name[*i] == *c && start(*i))
+        };
+        let found = match name.get(next) {
+            Some(n) if n == c && next > 0 => next,
+            _ if word_starts => position(&word_start).or_else(|| position(&|_| true))?,
+            _ => position(&|_| true)?,
+        };
+        score += 1;
+        if word_start(found) {
+            score += 3;
+        }
+        if next > 0 && found == next {
+            score += 2;
+        }
+        next = found + 1;
+    }
+    Some(score)
+}
@@ -1,178 +1,1025 @@
-use crate::log::{log, LogLevel, PROGRESS_SENDER};
-use crate::types::{Protocol, ReplayablePayload};
-use hex;
+use crate::log::{
+    CONNECTION_SENDER, LogEntry, LogLevel, PROGRESS_SENDER, log, log_entry, main_log,
+};
+use crate::tokens::{TokenRule, Tokens};
+use crate::types::{
+    Direction, Protocol, ReceivedChunk, ReplayEvent, ReplayOptions, ReplayablePayload,
+    SessionCommand, SessionEvent, StressLimit, StressStats,
+};
 use iced::window;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::{broadcast, mpsc};
+use tokio_util::sync::CancellationToken;
+
+/// Where a replay sends its log lines and progress.
+#[derive(Clone)]
+pub enum Reporter {
+    /// A replay window, through the global log and progress channels, with the number of
+    /// the last packet sent or received in it.
+    Window(window::Id, Arc<AtomicU64>),
+    /// Standard output, for headless replays. Progress is only counted, so the caller can
+    /// print it periodically.
+    Console(Arc<ConsoleProgress>),
+}
+
+/// Progress of a headless replay.
+#[derive(Default)]
+pub struct ConsoleProgress {
+    /// Payloads sent in the current iteration.
+    pub sent: AtomicUsize,
+    /// Current iteration (1-based).
+    pub iteration: AtomicU32,
+    /// Number of the last packet sent or received.
+    pub packet_seq: AtomicU64,
+}
+
+impl Reporter {
+    /// Logs a message to the replay window or standard output.
+    async fn log(&self, level: LogLevel, msg: &str) {
+        self.log_entry(LogEntry::new(level, msg)).await;
+    }
+
+    /// Like `log`, for an entry built elsewhere, e.g. with `LogEntry::packet`.
+    async fn log_entry(&self, entry: LogEntry) {
+        match self {
+            Reporter::Window(window_id, _) => log_entry(*window_id, entry).await,
+            Reporter::Console(_) => crate::log::console(&entry),
+        }
+    }
+
+    /// Numbers the next packet sent or received.
+    fn next_packet(&self) -> u64 {
+        let packet_seq = match self {
+            Reporter::Window(_, packet_seq) => packet_seq.as_ref(),
+            Reporter::Console(progress) => &progress.packet_seq,
+        };
+        packet_seq.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Logs an error that ends the replay, and raises it as a banner in the replay window.
+    async fn fail(&self, msg: &str) {
+        self.log(LogLevel::Error, msg).await;
+        self.progress(ReplayEvent::Failed(msg.to_string())).await;
+    }
+
+    /// Reports replay progress to the replay window, or records it for headless replays.
+    async fn progress(&self, event: ReplayEvent) {
+        match self {
+            Reporter::Window(window_id, _) => crate::log::send_progress(*window_id, event),
+            Reporter::Console(progress) => match event {
+                ReplayEvent::Progress(sent) => progress.sent.store(sent, Ordering::Relaxed),
+                ReplayEvent::Iteration(iteration, _) => {
+                    progress.sent.store(0, Ordering::Relaxed);
+                    progress.iteration.store(iteration, Ordering::Relaxed);
+                }
+                _ => {}
+            },
+        }
+    }
+}
 
 /// Replays a sequence of payloads over TCP or UDP, with delays between each packet.
 /// Logs progress and handles connection setup based on the protocol.
+/// The sequence is repeated `options.loops` times, ramping delays between iterations.
+/// Returns true if every iteration completed without failures.
+#[allow(clippy::too_many_arguments)]
 pub async fn replay_task(
     protocol: Protocol,
     payloads: Vec<ReplayablePayload>,
     addr: String,
     port: String,
-    window_id: window::Id,
-) {
+    options: ReplayOptions,
+    rules: Vec<TokenRule>,
+    cancel: CancellationToken,
+    reporter: Reporter,
+) -> bool {
+    // Values captured from responses, substituted into later payloads
+    let tokens = Arc::new(Mutex::new(Tokens::new()));
     // Prepare target address
     let addr_full = format!("{}:{}", addr, port);
-    match protocol {
+    let succeeded = match protocol {
         Protocol::Tcp => {
+            if let Some(resolved) = crate::session::resolved(&addr_full).await {
+                reporter.log(LogLevel::Debug, &resolved).await;
+            }
             // Establish TCP connection for replay
-            let stream = match TcpStream::connect(&addr_full).await {
-                Ok(s) => s,
-                Err(e) => {
-                    log(
-                        LogLevel::Error,
-                        window_id,
-                        &format!("Replay connection failed: {}", e),
-                    )
-                    .await;
-                    return;
+            let connect_started = std::time::Instant::now();
+            let stream = match connect_unless_cancelled(&addr_full, &cancel).await {
+                Some(Ok(s)) => s,
+                Some(Err(e)) => {
+                    reporter
+                        .log(
+                            LogLevel::Debug,
+                            &format!(
+                                "Connect attempt failed after {} ms",
+                                connect_started.elapsed().as_millis()
+                            ),
+                        )
+                        .await;
+                    reporter
+                        .fail(&format!("Replay connection failed: {}", e))
+                        .await;
+                    return false;
+                }
+                None => {
+                    reporter
+                        .log(LogLevel::Warn, "Connection attempt cancelled")
+                        .await;
+                    return false;
                 }
             };
-            log(
-                LogLevel::Info,
-                window_id,
-                &format!("Replay connected to {}", addr_full),
-            )
-            .await;
+            reporter
+                .log(
+                    LogLevel::Info,
+                    &format!("Replay connected to {}", addr_full),
+                )
+                .await;
+            reporter.progress(ReplayEvent::Connected).await;
+            reporter
+                .log(
+                    LogLevel::Debug,
+                    &format!("Connected in {} ms", connect_started.elapsed().as_millis()),
+                )
+                .await;
+            reporter
+                .log(LogLevel::Debug, &crate::session::socket_options(&stream))
+                .await;
             let (mut reader, mut writer) = stream.into_split();
             let disconnect_flag = Arc::new(AtomicBool::new(false));
             let send_fut = {
                 let disconnect_flag = Arc::clone(&disconnect_flag);
+                let tokens = Arc::clone(&tokens);
+                let reporter = reporter.clone();
+                let cancel = cancel.clone();
                 async move {
-                    // Replay each payload with delay
-                    for (i, payload) in payloads.iter().enumerate() {
-                        if disconnect_flag.load(Ordering::Relaxed) {
-                            log(LogLevel::Info, window_id, "Replay stopped due to disconnect").await;
-                            break;
-                        }
-                        tokio::time::sleep(tokio::time::Duration::from_millis(payload.delay)).await;
-                        if disconnect_flag.load(Ordering::Relaxed) {
-                            log(LogLevel::Info, window_id, "Replay stopped due to disconnect").await;
-                            break;
-                        }
-                        let data = payload.get_payload();
-                        match data {
-                            Ok(data) => {
-                                if data.is_empty() {
-                                    continue; // Skip empty payloads
-                                }
-                                if let Err(e) = writer.write_all(&data).await {
-                                    log(
-                                        LogLevel::Error,
-                                        window_id,
-                                        &format!("Replay send failed: {}", e),
-                                    )
+                    let mut first_failure = None;
+                    let mut completed = 0;
+                    'iterations: for iteration in 0..options.loops {
+                        start_iteration(&reporter, &options, iteration).await;
+                        // Replay each payload with delay
+                        for (i, payload) in payloads.iter().enumerate() {
+                            if disconnect_flag.load(Ordering::Relaxed) {
+                                reporter
+                                    .log(LogLevel::Info, "Replay stopped due to disconnect")
                                     .await;
-                                    break;
+                                first_failure.get_or_insert(iteration);
+                                break 'iterations;
+                            }
+                            let delay = options.effective_delay(payload.delay, iteration);
+                            if sleep_unless_cancelled(delay, &cancel).await {
+                                reporter.log(LogLevel::Info, "Replay stopped").await;
+                                break 'iterations;
+                            }
+                            if disconnect_flag.load(Ordering::Relaxed) {
+                                reporter
+                                    .log(LogLevel::Info, "Replay stopped due to disconnect")
+                                    .await;
+                                first_failure.get_or_insert(iteration);
+                                break 'iterations;
+                            }
+                            let captured = tokens.lock().unwrap().clone();
+                            let data = match prepare_payload(i, payload, &captured, &reporter).await
+                            {
+                                Prepared::Data(data) => data,
+                                Prepared::Invalid => {
+                                    reporter.progress(ReplayEvent::Skipped(i)).await;
+                                    first_failure.get_or_insert(iteration);
+                                    continue;
                                 }
-                                let _ = PROGRESS_SENDER.lock().await.send((window_id, i + 1));
-                                log(
-                                    LogLevel::Info,
-                                    window_id,
-                                    &format!("Sent: {}", payload.payload),
-                                )
-                                .await;
+                                Prepared::MissingToken => {
+                                    reporter.progress(ReplayEvent::SendFailed(i)).await;
+                                    first_failure.get_or_insert(iteration);
+                                    break 'iterations;
+                                }
+                            };
+                            if data.is_empty() {
+                                reporter.progress(ReplayEvent::Skipped(i)).await;
+                                continue; // Skip empty payloads
                             }
-                            Err(_) => {
-                                log(LogLevel::Warn, window_id, "Replay invalid payload").await;
+                            let write_started = std::time::Instant::now();
+                            if let Err(e) = writer.write_all(&data).await {
+                                reporter
+                                    .log(LogLevel::Error, &format!("Replay send failed: {}", e))
+                                    .await;
+                                reporter.progress(ReplayEvent::SendFailed(i)).await;
+                                first_failure.get_or_insert(iteration);
+                                break 'iterations;
                             }
+                            reporter
+                                .log(
+                                    LogLevel::Debug,
+                                    &format!(
+                                        "Wrote {} bytes in {} ms",
+                                        data.len(),
+                                        write_started.elapsed().as_millis()
+                                    ),
+                                )
+                                .await;
+                            reporter.progress(ReplayEvent::Progress(i + 1)).await;
+                            let seq = reporter.next_packet();
+                            reporter.progress(ReplayEvent::Sent(i, seq)).await;
+                            reporter
+                                .log_entry(LogEntry::sent(
+                                    seq,
+                                    &data,
+                                    payload.label.as_deref(),
+                                    &payload.payload,
+                                ))
+                                .await;
                         }
+                        completed += 1;
                     }
+                    log_summary(&reporter, &options, completed, first_failure).await
                 }
             };
             let read_fut = {
                 let disconnect_flag = Arc::clone(&disconnect_flag);
+                let tokens = Arc::clone(&tokens);
+                let reporter = reporter.clone();
                 async move {
                     let mut buf = [0; 1024];
                     loop {
                         match reader.read(&mut buf).await {
                             Ok(0) => {
                                 disconnect_flag.store(true, Ordering::Relaxed);
-                                log(LogLevel::Info, window_id, "Connection closed by server").await;
+                                reporter
+                                    .log(
+                                        LogLevel::Debug,
+                                        "Read returned EOF, the server closed its side",
+                                    )
+                                    .await;
+                                reporter
+                                    .log(LogLevel::Info, "Connection closed by server")
+                                    .await;
                                 break; // Connection closed
                             }
                             Ok(n) => {
-                                log(
-                                    LogLevel::Info,
-                                    window_id,
-                                    &format!("Received: {}", hex::encode(&buf[..n])),
-                                )
-                                .await;
+                                reporter
+                                    .log(LogLevel::Debug, &format!("Read {} bytes", n))
+                                    .await;
+                                received(&reporter, &rules, &tokens, &buf[..n], "").await;
                             }
                             Err(e) => {
                                 disconnect_flag.store(true, Ordering::Relaxed);
-                                log(
-                                    LogLevel::Error,
-                                    window_id,
-                                    &format!("Replay read error: {}", e),
-                                )
-                                .await;
+                                reporter
+                                    .log(LogLevel::Error, &format!("Replay read error: {}", e))
+                                    .await;
                                 break;
                             }
                         }
                     }
                 }
             };
-            tokio::join!(send_fut, read_fut);
+            let sends_done = CancellationToken::new();
+            let send_fut = async {
+                let succeeded = send_fut.await;
+                sends_done.cancel();
+                succeeded
+            };
+            // Keep reading after the last send until the server closes or the user stops.
+            // Headless replays have nobody to stop them, so they read one more second.
+            let headless = matches!(reporter, Reporter::Console(_));
+            let read_fut = async {
+                tokio::select! {
+                    _ = read_fut => {}
+                    _ = cancel.cancelled() => {}
+                    _ = async {
+                        sends_done.cancelled().await;
+                        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                    }, if headless => {}
+                }
+            };
+            tokio::join!(send_fut, read_fut).0
         }
         Protocol::Udp => {
+            if let Some(resolved) = crate::session::resolved(&addr_full).await {
+                reporter.log(LogLevel::Debug, &resolved).await;
+            }
             // Bind UDP socket for replay (no connection needed)
             let socket = match UdpSocket::bind("0.0.0.0:0").await {
                 Ok(s) => s,
                 Err(e) => {
-                    log(
-                        LogLevel::Error,
-                        window_id,
-                        &format!("Replay UDP bind failed: {}", e),
-                    )
-                    .await;
-                    return;
+                    reporter
+                        .fail(&format!("Replay UDP bind failed: {}", e))
+                        .await;
+                    return false;
                 }
             };
-            log(
-                LogLevel::Info,
-                window_id,
-                &format!("Replay UDP ready to {}", addr_full),
-            )
-            .await;
-            // Replay each payload with delay
-            for (i, payload) in payloads.iter().enumerate() {
-                tokio::time::sleep(tokio::time::Duration::from_millis(payload.delay)).await;
-                let data = payload.get_payload();
-                match data {
-                    Ok(data) => {
+            reporter
+                .log(
+                    LogLevel::Info,
+                    &format!("Replay UDP ready to {}", addr_full),
+                )
+                .await;
+            reporter.progress(ReplayEvent::Connected).await;
+            let send_fut = async {
+                let mut first_failure = None;
+                let mut completed = 0;
+                'iterations: for iteration in 0..options.loops {
+                    start_iteration(&reporter, &options, iteration).await;
+                    // Replay each payload with delay
+                    for (i, payload) in payloads.iter().enumerate() {
+                        let delay = options.effective_delay(payload.delay, iteration);
+                        if sleep_unless_cancelled(delay, &cancel).await {
+                            reporter.log(LogLevel::Info, "Replay stopped").await;
+                            break 'iterations;
+                        }
+                        let captured = tokens.lock().unwrap().clone();
+                        let data = match prepare_payload(i, payload, &captured, &reporter).await {
+                            Prepared::Data(data) => data,
+                            Prepared::Invalid => {
+                                reporter.progress(ReplayEvent::Skipped(i)).await;
+                                first_failure.get_or_insert(iteration);
+                                continue;
+                            }
+                            Prepared::MissingToken => {
+                                reporter.progress(ReplayEvent::SendFailed(i)).await;
+                                first_failure.get_or_insert(iteration);
+                                break 'iterations;
+                            }
+                        };
                         if data.is_empty() {
+                            reporter.progress(ReplayEvent::Skipped(i)).await;
                             continue; // Skip empty payloads
                         }
-                        if let Err(e) = socket.send_to(&data, &addr_full).await {
-                            log(
-                                LogLevel::Error,
-                                window_id,
-                                &format!("Replay send failed: {}", e),
-                            )
+                        match socket.send_to(&data, &addr_full).await {
+                            Ok(n) => {
+                                reporter
+                                    .log(
+                                        LogLevel::Debug,
+                                        &format!(
+                                            "send_to {} wrote {} of {} bytes",
+                                            addr_full,
+                                            n,
+                                            data.len()
+                                        ),
+                                    )
+                                    .await
+                            }
+                            Err(e) => {
+                                reporter
+                                    .log(LogLevel::Error, &format!("Replay send failed: {}", e))
+                                    .await;
+                                reporter.progress(ReplayEvent::SendFailed(i)).await;
+                                first_failure.get_or_insert(iteration);
+                                break 'iterations;
+                            }
+                        }
+                        reporter.progress(ReplayEvent::Progress(i + 1)).await;
+                        let seq = reporter.next_packet();
+                        reporter.progress(ReplayEvent::Sent(i, seq)).await;
+                        reporter
+                            .log_entry(LogEntry::sent(
+                                seq,
+                                &data,
+                                payload.label.as_deref(),
+                                &payload.payload,
+                            ))
                             .await;
+                    }
+                    completed += 1;
+                }
+                log_summary(&reporter, &options, completed, first_failure).await
+            };
+            // Responses are read while sending, so tokens can be captured from them. UDP has
+            // no end of stream to wait for, so reading stops with the last send.
+            let read_fut = async {
+                let mut buf = [0; 65536];
+                loop {
+                    match socket.recv_from(&mut buf).await {
+                        Ok((n, from)) => {
+                            reporter
+                                .log(
+                                    LogLevel::Debug,
+                                    &format!("recv_from {} read {} bytes", from, n),
+                                )
+                                .await;
+                            let note = format!("from {}", from);
+                            received(&reporter, &rules, &tokens, &buf[..n], &note).await;
+                        }
+                        // Errors such as ICMP port unreachable do not end the replay
+                        Err(e) => {
+                            reporter
+                                .log(LogLevel::Warn, &format!("Receive error: {}", e))
+                                .await
+                        }
+                    }
+                }
+            };
+            tokio::select! {
+                succeeded = send_fut => succeeded,
+                _ = read_fut => false,
+            }
+        }
+    };
+    // Log replay completion
+    reporter.log(LogLevel::Info, "Replay finished").await;
+    reporter.progress(ReplayEvent::Finished).await;
+    succeeded
+}
+
+/// Sends the payload set in a loop as fast as the socket allows, ignoring recorded delays,
+/// until the limit is reached or `cancel` is cancelled. Statistics are reported at most every 100 ms.
+pub async fn stress_task(
+    protocol: Protocol,
+    payloads: Vec<ReplayablePayload>,
+    addr: String,
+    port: String,
+    limit: StressLimit,
+    cancel: CancellationToken,
+    window_id: window::Id,
+) {
+    let addr_full = format!("{}:{}", addr, port);
+    // Decode everything up front so the hot loop only sends
+    let packets: Vec<Vec<u8>> = payloads
+        .iter()
+        .filter_map(|payload| payload.get_payload().ok())
+        .filter(|data| !data.is_empty())
+        .collect();
+    if packets.len() < payloads.len() {
+        log(
+            LogLevel::Warn,
+            window_id,
+            &format!(
+                "Stress skips {} empty, invalid, or token payloads",
+                payloads.len() - packets.len()
+            ),
+        )
+        .await;
+    }
+    if packets.is_empty() {
+        log(LogLevel::Error, window_id, "Stress has nothing to send").await;
+        let _ = PROGRESS_SENDER.send((window_id, ReplayEvent::Finished));
+        return;
+    }
+    let mut stats = StressStats::default();
+    let start = std::time::Instant::now();
+    let deadline = limit
+        .duration
+        .map(|max| tokio::time::Instant::from_std(start) + max);
+    let mut last_report = start;
+    let limit_reached = |stats: &StressStats, iteration: u64| {
+        limit.iterations.is_some_and(|max| iteration >= max)
+            || limit.duration.is_some_and(|max| start.elapsed() >= max)
+            || cancel.is_cancelled()
+            || stats.connection_lost
+    };
+    log(
+        LogLevel::Info,
+        window_id,
+        &format!("Stress started against {}", addr_full),
+    )
+    .await;
+    match protocol {
+        Protocol::Tcp => {
+            let stream = match connect_unless_cancelled(&addr_full, &cancel).await {
+                Some(Ok(s)) => s,
+                Some(Err(e)) => {
+                    let msg = format!("Stress connection failed: {}", e);
+                    log(LogLevel::Error, window_id, &msg).await;
+                    crate::log::send_progress(window_id, ReplayEvent::Failed(msg));
+                    stats.connection_lost = true;
+                    report_stress(protocol, window_id, &stats, start, true).await;
+                    return;
+                }
+                None => {
+                    log(LogLevel::Warn, window_id, "Connection attempt cancelled").await;
+                    report_stress(protocol, window_id, &stats, start, true).await;
+                    return;
+                }
+            };
+            crate::log::send_progress(window_id, ReplayEvent::Connected);
+            let (mut reader, mut writer) = stream.into_split();
+            // Drain responses so the peer never blocks on a full window
+            let closed = Arc::new(AtomicBool::new(false));
+            let drain = {
+                let closed = Arc::clone(&closed);
+                tokio::spawn(async move {
+                    let mut buf = [0; 4096];
+                    while let Ok(n) = reader.read(&mut buf).await {
+                        if n == 0 {
                             break;
                         }
-                        let _ = PROGRESS_SENDER.lock().await.send((window_id, i + 1));
-                        log(
-                            LogLevel::Info,
-                            window_id,
-                            &format!("Sent: {}", payload.payload),
-                        )
-                        .await;
                     }
-                    Err(_) => {
-                        log(LogLevel::Warn, window_id, "Replay invalid payload").await;
+                    closed.store(true, Ordering::Relaxed);
+                })
+            };
+            let mut iteration = 0;
+            // A peer that stops reading blocks the write, so Stop and the duration end it
+            'run: while !limit_reached(&stats, iteration) {
+                for data in &packets {
+                    if limit_reached(&stats, iteration) {
+                        break 'run;
+                    }
+                    match stress_send(writer.write_all(data), &cancel, deadline).await {
+                        Some(Ok(())) => {
+                            stats.packets += 1;
+                            stats.bytes += data.len() as u64;
+                        }
+                        Some(Err(_)) => {
+                            stats.errors += 1;
+                            stats.connection_lost = true;
+                        }
+                        None => break 'run,
+                    }
+                    if closed.load(Ordering::Relaxed) {
+                        stats.connection_lost = true;
+                    }
+                    if last_report.elapsed() >= std::time::Duration::from_millis(100) {
+                        last_report = std::time::Instant::now();
+                        report_stress(protocol, window_id, &stats, start, false).await;
+                    }
+                }
+                iteration += 1;
+            }
+            drain.abort();
+        }
+        Protocol::Udp => {
+            let socket = match UdpSocket::bind("0.0.0.0:0").await {
+                Ok(s) => s,
+                Err(e) => {
+                    let msg = format!("Stress UDP bind failed: {}", e);
+                    log(LogLevel::Error, window_id, &msg).await;
+                    crate::log::send_progress(window_id, ReplayEvent::Failed(msg));
+                    report_stress(protocol, window_id, &stats, start, true).await;
+                    return;
+                }
+            };
+            crate::log::send_progress(window_id, ReplayEvent::Connected);
+            let mut iteration = 0;
+            'run: while !limit_reached(&stats, iteration) {
+                for data in &packets {
+                    if limit_reached(&stats, iteration) {
+                        break 'run;
+                    }
+                    match stress_send(socket.send_to(data, &addr_full), &cancel, deadline).await {
+                        Some(Ok(_)) => {
+                            stats.packets += 1;
+                            stats.bytes += data.len() as u64;
+                        }
+                        Some(Err(_)) => stats.errors += 1,
+                        None => break 'run,
+                    }
+                    if last_report.elapsed() >= std::time::Duration::from_millis(100) {
+                        last_report = std::time::Instant::now();
+                        report_stress(protocol, window_id, &stats, start, false).await;
                     }
                 }
+                iteration += 1;
             }
         }
     }
-    // Log replay completion
+    report_stress(protocol, window_id, &stats, start, true).await;
+}
+
+/// Awaits a stress send, or returns None once the run is stopped or its duration is up.
+async fn stress_send<T>(
+    send: impl Future<Output = T>,
+    cancel: &CancellationToken,
+    deadline: Option<tokio::time::Instant>,
+) -> Option<T> {
+    let send = async {
+        match deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline, send).await.ok(),
+            None => Some(send.await),
+        }
+    };
+    tokio::select! {
+        _ = cancel.cancelled() => None,
+        result = send => result,
+    }
+}
+
+/// Publishes stress statistics; the final report also goes to the window and main logs.
+async fn report_stress(
+    protocol: Protocol,
+    window_id: window::Id,
+    stats: &StressStats,
+    start: std::time::Instant,
+    finished: bool,
+) {
+    let mut stats = stats.clone();
+    stats.elapsed_ms = start.elapsed().as_millis() as u64;
+    if finished {
+        let mut summary = format!("Stress finished: {}", stats.describe());
+        if protocol == Protocol::Tcp {
+            summary.push_str(if stats.connection_lost {
+                ", connection lost"
+            } else {
+                ", connection survived"
+            });
+        }
+        log(LogLevel::Info, window_id, &summary).await;
+        main_log(LogLevel::Info, &summary).await;
+    }
+    let _ = PROGRESS_SENDER.send((window_id, ReplayEvent::Stress(stats)));
+    if finished {
+        let _ = PROGRESS_SENDER.send((window_id, ReplayEvent::Finished));
+    }
+}
+
+/// Reports data received from the target to the replay window and logs it as a packet,
+/// then captures the tokens `rules` find in it.
+async fn received(
+    reporter: &Reporter,
+    rules: &[TokenRule],
+    tokens: &Mutex<Tokens>,
+    data: &[u8],
+    note: &str,
+) {
+    let seq = reporter.next_packet();
+    reporter
+        .progress(ReplayEvent::Received(ReceivedChunk {
+            seq,
+            timestamp: chrono::Local::now(),
+            data: data.to_vec(),
+        }))
+        .await;
+    reporter
+        .log_entry(LogEntry::packet(
+            seq,
+            Direction::Received,
+            data,
+            note,
+            &hex::encode(data),
+        ))
+        .await;
+    let captured = crate::tokens::extract(rules, data, &mut tokens.lock().unwrap());
+    for (name, value) in captured {
+        reporter
+            .log(
+                LogLevel::Info,
+                &format!("Captured token {} = {}", name, hex::encode(value)),
+            )
+            .await;
+    }
+}
+
+/// Connects to `addr`, returning None as soon as `cancel` is cancelled.
+async fn connect_unless_cancelled(
+    addr: &str,
+    cancel: &CancellationToken,
+) -> Option<std::io::Result<TcpStream>> {
+    tokio::select! {
+        result = TcpStream::connect(addr) => Some(result),
+        _ = cancel.cancelled() => None,
+    }
+}
+
+/// Sleeps for `ms` milliseconds, returning true early if `cancel` is cancelled.
+pub async fn sleep_unless_cancelled(ms: u64, cancel: &CancellationToken) -> bool {
+    tokio::select! {
+        _ = tokio::time::sleep(tokio::time::Duration::from_millis(ms)) => cancel.is_cancelled(),
+        _ = cancel.cancelled() => true,
+    }
+}
+
+/// Outcome of preparing a payload for sending.
+enum Prepared {
+    /// Decoded bytes ready to send.
+    Data(Vec<u8>),
+    /// The payload does not decode and is skipped.
+    Invalid,
+    /// The payload references a token that has not been captured; the replay stops.
+    MissingToken,
+}
+
+/// Decodes a payload, substituting captured tokens and logging their resolved values.
+async fn prepare_payload(
+    index: usize,
+    payload: &ReplayablePayload,
+    tokens: &Tokens,
+    reporter: &Reporter,
+) -> Prepared {
+    let (prepared, note) = prepare(index, payload, tokens);
+    if let Some(entry) = note {
+        reporter.log_entry(entry).await;
+    }
+    prepared
+}
+
+/// Decodes a payload, substituting captured tokens. Also returns the line to log about
+/// it: the resolved bytes of a payload using tokens, or why it cannot be sent.
+fn prepare(
+    index: usize,
+    payload: &ReplayablePayload,
+    tokens: &Tokens,
+) -> (Prepared, Option<LogEntry>) {
+    let uses_tokens = crate::tokens::uses_tokens(&payload.payload);
+    match crate::tokens::substitute(payload, tokens) {
+        Ok(data) => {
+            let note = uses_tokens.then(|| {
+                LogEntry::new(
+                    LogLevel::Info,
+                    &format!("Payload {} resolved to {}", index + 1, hex::encode(&data)),
+                )
+            });
+            (Prepared::Data(data), note)
+        }
+        Err(e) if uses_tokens => (
+            Prepared::MissingToken,
+            Some(LogEntry::new(
+                LogLevel::Error,
+                &format!("Payload {}: {}", index + 1, e),
+            )),
+        ),
+        Err(_) => (
+            Prepared::Invalid,
+            Some(LogEntry::new(LogLevel::Warn, "Replay invalid payload")),
+        ),
+    }
+}
+
+/// Announces the start of an iteration to the replay window.
+async fn start_iteration(reporter: &Reporter, options: &ReplayOptions, iteration: u32) {
+    let speed = options.describe(iteration);
+    reporter
+        .progress(ReplayEvent::Iteration(iteration + 1, speed.clone()))
+        .await;
+    if options.loops > 1 {
+        reporter
+            .log(
+                LogLevel::Info,
+                &format!("Iteration {}/{} ({})", iteration + 1, options.loops, speed),
+            )
+            .await;
+    }
+}
+
+/// Logs the completion summary, including where failures first appeared. Returns true if
+/// every iteration completed without failures.
+async fn log_summary(
+    reporter: &Reporter,
+    options: &ReplayOptions,
+    completed: u32,
+    first_failure: Option<u32>,
+) -> bool {
+    let summary = match first_failure {
+        Some(iteration) => format!(
+            "Replay summary: {}/{} iterations completed, first failure in iteration {} ({})",
+            completed,
+            options.loops,
+            iteration + 1,
+            options.describe(iteration)
+        ),
+        None => format!(
+            "Replay summary: {}/{} iterations completed without failures",
+            completed, options.loops
+        ),
+    };
+    let level = if first_failure.is_some() {
+        LogLevel::Warn
+    } else {
+        LogLevel::Info
+    };
+    reporter.log(level, &summary).await;
+    first_failure.is_none() && completed == options.loops
+}
+
+/// Replays payloads through the command channel of an existing session instead of opening
+/// a new connection. Each sent payload is reported back so the session can record it, and
+/// the run stops early when `cancel` is cancelled or the session goes away. Tokens are captured
+/// by `rules` from the packets the session receives meanwhile.
+pub async fn session_replay_task(
+    sender: mpsc::Sender<SessionCommand>,
+    payloads: Vec<ReplayablePayload>,
+    options: ReplayOptions,
+    rules: Vec<TokenRule>,
+    cancel: CancellationToken,
+    window_id: window::Id,
+) {
+    let total = payloads.len() * options.loops as usize;
+    log(
+        LogLevel::Info,
+        window_id,
+        &format!("Replay started in session: {} payloads", total),
+    )
+    .await;
+    // Responses arrive through the session task, so they are read from its events
+    let mut packets = (!rules.is_empty()).then(|| CONNECTION_SENDER.subscribe());
+    let mut tokens = Tokens::new();
+    let mut sent = 0;
+    'iterations: for iteration in 0..options.loops {
+        for (i, payload) in payloads.iter().enumerate() {
+            let delay = options.effective_delay(payload.delay, iteration);
+            if sleep_unless_cancelled(delay, &cancel).await {
+                log(LogLevel::Info, window_id, "Replay stopped").await;
+                break 'iterations;
+            }
+            if let Some(packets) = &mut packets {
+                capture_session_tokens(&rules, packets, &mut tokens, window_id).await;
+            }
+            let (prepared, note) = prepare(i, payload, &tokens);
+            if let Some(entry) = note {
+                log_entry(window_id, entry).await;
+            }
+            let data = match prepared {
+                Prepared::Data(data) if !data.is_empty() => data,
+                Prepared::Data(_) | Prepared::Invalid => continue, // Skip empty and invalid payloads
+                Prepared::MissingToken => break 'iterations,
+            };
+            let command =
+                SessionCommand::SendPacket(data, payload.payload_type, payload.label.clone());
+            if sender.send(command).await.is_err() {
+                log(LogLevel::Error, window_id, "Replay stopped: session closed").await;
+                break 'iterations;
+            }
+            sent += 1;
+            let _ = CONNECTION_SENDER
+                .send((window_id, SessionEvent::ReplayPayloadSent(payload.clone())));
+            let _ = CONNECTION_SENDER.send((window_id, SessionEvent::ReplayProgress(sent, total)));
+        }
+    }
+    let _ = CONNECTION_SENDER.send((window_id, SessionEvent::ReplayFinished));
     log(LogLevel::Info, window_id, "Replay finished").await;
 }
+
+/// Applies `rules` to the packets the session received since the last call, logging each
+/// captured token. Packets missed because the receiver lagged are skipped.
+async fn capture_session_tokens(
+    rules: &[TokenRule],
+    packets: &mut broadcast::Receiver<(window::Id, SessionEvent)>,
+    tokens: &mut Tokens,
+    window_id: window::Id,
+) {
+    loop {
+        match packets.try_recv() {
+            Ok((id, SessionEvent::Packet(packet)))
+                if id == window_id && packet.direction == Direction::Received =>
+            {
+                for (name, value) in crate::tokens::extract(rules, &packet.data, tokens) {
+                    log(
+                        LogLevel::Info,
+                        window_id,
+                        &format!("Captured token {} = {}", name, hex::encode(value)),
+                    )
+                    .await;
+                }
+            }
+            Ok(_) | Err(broadcast::error::TryRecvError::Lagged(_)) => {}
+            Err(_) => break,
+        }
+    }
+}
+
+/// Gap between payloads flushed from the offline queue after reconnecting.
+const QUEUE_FLUSH_INTERVAL_MS: u64 = 50;
+
+/// Flushes payloads queued while the session was disconnected, in order, with a short gap
+/// between them. Each flushed payload is reported so it can be recorded for replay.
+pub async fn flush_queue_task(
+    sender: mpsc::Sender<SessionCommand>,
+    payloads: Vec<ReplayablePayload>,
+    window_id: window::Id,
+) {
+    let total = payloads.len();
+    for (i, payload) in payloads.into_iter().enumerate() {
+        if i > 0 {
+            tokio::time::sleep(tokio::time::Duration::from_millis(QUEUE_FLUSH_INTERVAL_MS)).await;
+        }
+        let data = match payload.get_payload() {
+            Ok(data) => data,
+            Err(_) => {
+                log(LogLevel::Warn, window_id, "Invalid queued payload skipped").await;
+                continue;
+            }
+        };
+        let command = SessionCommand::SendPacket(data, payload.payload_type, payload.label.clone());
+        if sender.send(command).await.is_err() {
+            log(
+                LogLevel::Error,
+                window_id,
+                &format!("Queue flush stopped: send failed at {}/{}", i + 1, total),
+            )
+            .await;
+            break;
+        }
+        log(
+            LogLevel::Info,
+            window_id,
+            &format!(
+                "Flushed queued payload {}/{}: {}",
+                i + 1,
+                total,
+                payload.payload
+            ),
+        )
+        .await;
+        let _ = CONNECTION_SENDER.send((window_id, SessionEvent::QueuedPayloadSent(payload)));
+    }
+}
+
+/// Sends one payload `count` times through an existing session, waiting `interval_ms`
+/// between sends. Stops early when `cancel` is cancelled or a send fails.
+pub async fn repeat_send_task(
+    sender: mpsc::Sender<SessionCommand>,
+    payload: ReplayablePayload,
+    count: usize,
+    interval_ms: u64,
+    cancel: CancellationToken,
+    window_id: window::Id,
+) {
+    let mut sent = 0;
+    match payload.get_payload() {
+        Ok(data) => {
+            for i in 0..count {
+                if i > 0 && sleep_unless_cancelled(interval_ms, &cancel).await
+                    || cancel.is_cancelled()
+                {
+                    break;
+                }
+                let command = SessionCommand::SendPacket(
+                    data.clone(),
+                    payload.payload_type,
+                    payload.label.clone(),
+                );
+                if sender.send(command).await.is_err() {
+                    log(
+                        LogLevel::Error,
+                        window_id,
+                        "Repeat send stopped: send failed",
+                    )
+                    .await;
+                    break;
+                }
+                sent += 1;
+                let _ = CONNECTION_SENDER
+                    .send((window_id, SessionEvent::RepeatPayloadSent(payload.clone())));
+                let _ =
+                    CONNECTION_SENDER.send((window_id, SessionEvent::RepeatProgress(sent, count)));
+            }
+        }
+        Err(_) => log(LogLevel::Warn, window_id, "Invalid input").await,
+    }
+    let _ = CONNECTION_SENDER.send((window_id, SessionEvent::RepeatFinished));
+    log(
+        LogLevel::Info,
+        window_id,
+        &format!("Repeat send finished: {}/{} sent", sent, count),
+    )
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PayloadType;
+
+    /// Runs a TCP stress run against a peer that accepts but never reads, so the socket
+    /// buffers fill and the write blocks. Returns once the run has sent its final report.
+    async fn stress_unread_peer(limit: StressLimit, cancel: CancellationToken) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let _peer = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            std::future::pending::<()>().await;
+            drop(stream);
+        });
+        let payloads = vec![ReplayablePayload {
+            payload: "x".repeat(16 << 20),
+            payload_type: PayloadType::Ascii,
+            delay: 0,
+            recorded_only: false,
+            label: None,
+        }];
+        let run = stress_task(
+            Protocol::Tcp,
+            payloads,
+            "127.0.0.1".to_string(),
+            port.to_string(),
+            limit,
+            cancel,
+            window::Id::unique(),
+        );
+        tokio::time::timeout(std::time::Duration::from_secs(10), run)
+            .await
+            .expect("stress run kept writing to a peer that does not read");
+    }
+
+    #[tokio::test]
+    async fn stress_duration_ends_a_blocked_write() {
+        let limit = StressLimit {
+            duration: Some(std::time::Duration::from_millis(300)),
+            iterations: None,
+        };
+        stress_unread_peer(limit, CancellationToken::new()).await;
+    }
+
+    #[tokio::test]
+    async fn stress_stop_ends_a_blocked_write() {
+        let limit = StressLimit {
+            duration: None,
+            iterations: None,
+        };
+        let cancel = CancellationToken::new();
+        let stop = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+            stop.cancel();
+        });
+        stress_unread_peer(limit, cancel).await;
+    }
+}
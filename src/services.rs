@@ -0,0 +1,134 @@
+// Well-known service names for the port field, so "http" or "dns" can be typed instead
+// of remembering the number.
+
+use crate::types::Protocol;
+
+/// A well-known service and the port it listens on.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Service {
+    pub name: &'static str,
+    pub port: u16,
+}
+
+impl std::fmt::Display for Service {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, self.port)
+    }
+}
+
+const fn service(name: &'static str, port: u16) -> Service {
+    Service { name, port }
+}
+
+/// Commonly used TCP services, offered in the main window's port dropdown.
+const TCP_SERVICES: &[Service] = &[
+    service("ftp", 21),
+    service("ssh", 22),
+    service("telnet", 23),
+    service("smtp", 25),
+    service("dns", 53),
+    service("http", 80),
+    service("pop3", 110),
+    service("imap", 143),
+    service("https", 443),
+    service("modbus", 502),
+    service("mqtt", 1883),
+    service("mysql", 3306),
+    service("postgres", 5432),
+    service("amqp", 5672),
+    service("redis", 6379),
+    service("http-alt", 8080),
+    service("mqtts", 8883),
+];
+
+/// Commonly used UDP services, offered in the main window's port dropdown.
+const UDP_SERVICES: &[Service] = &[
+    service("dns", 53),
+    service("dhcp", 67),
+    service("tftp", 69),
+    service("ntp", 123),
+    service("snmp", 161),
+    service("syslog", 514),
+    service("sip", 5060),
+    service("mdns", 5353),
+    service("coap", 5683),
+    service("statsd", 8125),
+];
+
+/// Returns the services offered in the port dropdown for `protocol`.
+pub fn common_ports(protocol: Protocol) -> &'static [Service] {
+    match protocol {
+        Protocol::Tcp => TCP_SERVICES,
+        Protocol::Udp => UDP_SERVICES,
+    }
+}
+
+/// Resolves the port field: a number, a service name from the built-in table, or one
+/// from `/etc/services`. Names are matched case-insensitively.
+pub fn resolve_port(input: &str, protocol: Protocol) -> Result<u16, String> {
+    let input = input.trim();
+    // Names may contain digits, e.g. "pop3", so only all-digit input is a number
+    if input.bytes().all(|b| b.is_ascii_digit()) {
+        return crate::validate::port(input);
+    }
+    let name = input.to_ascii_lowercase();
+    common_ports(protocol)
+        .iter()
+        .find(|service| service.name == name)
+        .map(|service| service.port)
+        .or_else(|| system_service(&name, protocol))
+        .ok_or_else(|| format!("Unknown service: {}", input))
+}
+
+/// Looks `name` up in `/etc/services`, where lines read `name port/proto aliases...`.
+fn system_service(name: &str, protocol: Protocol) -> Option<u16> {
+    let proto = match protocol {
+        Protocol::Tcp => "tcp",
+        Protocol::Udp => "udp",
+    };
+    let services = std::fs::read_to_string("/etc/services").ok()?;
+    services.lines().find_map(|line| {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut fields = line.split_whitespace();
+        let service = fields.next()?;
+        let (port, service_proto) = fields.next()?.split_once('/')?;
+        let matches = service_proto == proto
+            && std::iter::once(service)
+                .chain(fields)
+                .any(|alias| alias.eq_ignore_ascii_case(name));
+        matches.then(|| port.parse().ok()).flatten()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_resolve_to_themselves() {
+        assert_eq!(resolve_port("8080", Protocol::Tcp), Ok(8080));
+        assert_eq!(resolve_port(" 53 ", Protocol::Udp), Ok(53));
+        assert!(resolve_port("", Protocol::Tcp).is_err());
+    }
+
+    #[test]
+    fn table_names_resolve_ignoring_case() {
+        assert_eq!(resolve_port("http", Protocol::Tcp), Ok(80));
+        assert_eq!(resolve_port("HTTPS", Protocol::Tcp), Ok(443));
+        assert_eq!(resolve_port("Ntp", Protocol::Udp), Ok(123));
+    }
+
+    #[test]
+    fn names_containing_digits_are_looked_up() {
+        assert_eq!(resolve_port("pop3", Protocol::Tcp), Ok(110));
+        assert_eq!(resolve_port("POP3", Protocol::Tcp), Ok(110));
+    }
+
+    #[test]
+    fn unknown_names_are_reported() {
+        assert_eq!(
+            resolve_port("no-such-service-4711", Protocol::Tcp),
+            Err("Unknown service: no-such-service-4711".to_string())
+        );
+    }
+}
@@ -1,9 +1,18 @@
-use crate::log::{format_log, log, main_log, LogLevel, CONNECTION_SENDER};
-use crate::types::{PayloadType, SessionCommand};
-use hex::decode;
+use crate::log::{
+    CONNECTION_SENDER, LogEntry, LogLevel, format_duration, log, log_entry, main_log,
+};
+use crate::replay::sleep_unless_cancelled;
+use crate::types::{
+    Direction, PacketRecord, PayloadType, ReplayablePayload, SessionCommand, SessionEvent,
+    TcpSessionOptions,
+};
 use iced::window;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio_util::sync::CancellationToken;
 
 /// Handles the TCP session asynchronously, managing connection, sending/receiving data, and logging.
 /// Runs in a separate task and communicates via channels for commands and logs.
@@ -11,15 +20,55 @@ pub async fn tcp_task(
     mut rx: tokio::sync::mpsc::Receiver<SessionCommand>,
     addr: String,
     port: String,
-    initial_payload: String,
-    initial_payload_type: PayloadType,
+    options: TcpSessionOptions,
     window_id: window::Id,
 ) {
-    // Attempt to connect to the TCP server
+    let TcpSessionOptions {
+        handshake,
+        mut idle_timeout,
+        stall_warning,
+        discard,
+        packet_seq,
+    } = options;
+    // Attempt to connect to the TCP server, listening for a disconnect or the window
+    // closing (which drops the sender) so a hanging attempt can be abandoned
     let addr_full = format!("{}:{}", addr, port);
-    let stream = match TcpStream::connect(&addr_full).await {
-        Ok(s) => s,
-        Err(e) => {
+    if let Some(resolved) = resolved(&addr_full).await {
+        log(LogLevel::Debug, window_id, &resolved).await;
+    }
+    log(
+        LogLevel::Debug,
+        window_id,
+        &format!("Connecting to {}", addr_full),
+    )
+    .await;
+    let connect_started = Instant::now();
+    let connect = TcpStream::connect(&addr_full);
+    tokio::pin!(connect);
+    let connected = loop {
+        tokio::select! {
+            result = &mut connect => break Some(result),
+            command = rx.recv() => match command {
+                Some(SessionCommand::Disconnect) | None => break None,
+                Some(SessionCommand::SetIdleTimeout(timeout)) => idle_timeout = timeout,
+                Some(SessionCommand::SendPacket(data, ..) | SessionCommand::Heartbeat(data, _)) => {
+                    log(LogLevel::Warn, window_id, &format!("Not connected yet, dropped: {}", hex::encode(&data))).await;
+                }
+            },
+        }
+    };
+    let stream = match connected {
+        Some(Ok(s)) => s,
+        Some(Err(e)) => {
+            log(
+                LogLevel::Debug,
+                window_id,
+                &format!(
+                    "Connect attempt failed after {} ms",
+                    connect_started.elapsed().as_millis()
+                ),
+            )
+            .await;
             // Log connection failure and exit
             log(
                 LogLevel::Error,
@@ -27,14 +76,29 @@ pub async fn tcp_task(
                 &format!("Connection failed: {}", e),
             )
             .await;
-            main_log(format_log(
-                LogLevel::Error,
-                &format!("Session failed: {}", addr_full),
-            ))
-            .await;
+            main_log(LogLevel::Error, &format!("Session failed: {}", addr_full)).await;
+            let _ = CONNECTION_SENDER.send((window_id, SessionEvent::ConnectFailed(e.to_string())));
+            let _ = CONNECTION_SENDER.send((window_id, SessionEvent::Connection(false)));
+            return;
+        }
+        None => {
+            log(LogLevel::Warn, window_id, "Connection attempt cancelled").await;
+            main_log(LogLevel::Info, &format!("Session cancelled: {}", addr_full)).await;
+            let _ = CONNECTION_SENDER.send((window_id, SessionEvent::Connection(false)));
             return;
         }
     };
+    let connected_at = Instant::now();
+    log(
+        LogLevel::Debug,
+        window_id,
+        &format!(
+            "Connected in {} ms",
+            connected_at.duration_since(connect_started).as_millis()
+        ),
+    )
+    .await;
+    log(LogLevel::Debug, window_id, &socket_options(&stream)).await;
     // Log successful connection
     log(
         LogLevel::Info,
@@ -42,94 +106,447 @@ pub async fn tcp_task(
         &format!("Connected to {}", addr_full),
     )
     .await;
-    main_log(format_log(
-        LogLevel::Info,
-        &format!("Session started: {}", addr_full),
-    ))
-    .await;
-    let _ = CONNECTION_SENDER.lock().await.send((window_id, true));
+    main_log(LogLevel::Info, &format!("Session started: {}", addr_full)).await;
+    let _ = CONNECTION_SENDER.send((window_id, SessionEvent::Connection(true)));
+
+    let _ = CONNECTION_SENDER.send((
+        window_id,
+        SessionEvent::Addresses {
+            local: stream
+                .local_addr()
+                .map(|a| a.to_string())
+                .unwrap_or_default(),
+            remote: stream
+                .peer_addr()
+                .map(|a| a.to_string())
+                .unwrap_or_default(),
+        },
+    ));
 
     // Split the stream into reader and writer
     let (mut _reader, mut writer) = stream.into_split();
 
-    // Send initial payload if provided
-    if !initial_payload.is_empty() {
-        let data = match initial_payload_type {
-            PayloadType::Hex => decode(&initial_payload.replace(" ", "")),
-            PayloadType::Ascii => Ok(initial_payload.as_bytes().to_vec()),
-        };
-        match data {
-            Ok(data) => {
-                if let Err(e) = writer.write_all(&data).await {
-                    log(
-                        LogLevel::Error,
-                        window_id,
-                        &format!("Failed to send initial payload: {}", e),
-                    )
-                    .await;
-                } else {
-                    log(
-                        LogLevel::Info,
-                        window_id,
-                        &format!("Sent initial payload: {}", match initial_payload_type {
-                            PayloadType::Hex => hex::encode(&data),
-                            PayloadType::Ascii => String::from_utf8_lossy(&data).to_string(),
-                        }),
-                    )
-                    .await;
-                }
-            }
-            Err(_) => {
-                log(LogLevel::Warn, window_id, &format!("Invalid initial payload ({:?})", initial_payload_type)).await;
-            }
-        }
-    }
-    // Main event loop: handle commands and incoming data
+    // Send the handshake steps in order before accepting commands
+    let mut traffic = Traffic::default();
+    let handshake_ok = send_handshake(
+        &mut writer,
+        &handshake.steps,
+        &mut traffic,
+        &packet_seq,
+        window_id,
+    )
+    .await;
     let mut buf = [0; 1024];
-    loop {
-        tokio::select! {
-            // Handle incoming commands from the UI
-            cmd = rx.recv() => {
-                match cmd {
-                    Some(SessionCommand::SendPacket(data, payload_type)) => {
-                        if let Err(e) = writer.write_all(&data).await {
-                            log(LogLevel::Error, window_id, &format!("Send failed: {}", e)).await;
-                            break;
+    let close = if !handshake_ok && handshake.disconnect_on_failure {
+        CloseReason::HandshakeFailed
+    } else {
+        // Main event loop: handle commands and incoming data
+        let mut last_activity = Instant::now();
+        loop {
+            tokio::select! {
+                // Handle incoming commands from the UI
+                cmd = rx.recv() => {
+                    match cmd {
+                        Some(SessionCommand::SendPacket(data, ..) | SessionCommand::Heartbeat(data, _))
+                            if take_discard(&discard) =>
+                        {
+                            log(LogLevel::Warn, window_id, &format!("Cancelled pending send: {}", hex::encode(&data))).await;
+                        }
+                        Some(SessionCommand::SendPacket(data, payload_type, label)) => {
+                            if let Err(e) = write_watched(&mut writer, &data, stall_warning, window_id).await {
+                                log(LogLevel::Error, window_id, &format!("Send failed: {}", e)).await;
+                                break CloseReason::Error;
+                            }
+                            traffic.sent(data.len());
+                            let seq = report_packet(window_id, &packet_seq, Direction::Sent, &data).await;
+                            last_activity = activity(window_id).await;
+                            log_entry(window_id, LogEntry::sent(seq, &data, label.as_deref(),
+                                &match payload_type {
+                                    PayloadType::Hex => hex::encode(&data),
+                                    PayloadType::Ascii => String::from_utf8_lossy(&data).to_string(),
+                                })).await;
+                        }
+                        Some(SessionCommand::Heartbeat(data, quiet)) => {
+                            if let Err(e) = write_watched(&mut writer, &data, stall_warning, window_id).await {
+                                log(LogLevel::Error, window_id, &format!("Heartbeat failed: {}", e)).await;
+                                break CloseReason::Error;
+                            }
+                            traffic.sent(data.len());
+                            let seq = report_packet(window_id, &packet_seq, Direction::Sent, &data).await;
+                            last_activity = activity(window_id).await;
+                            if !quiet {
+                                log_entry(window_id, LogEntry::packet(seq, Direction::Sent, &data, "heartbeat", &hex::encode(&data))).await;
+                            }
                         }
-                        log(LogLevel::Info, window_id, &format!("Sent: {}",
-                            match payload_type {
-                                PayloadType::Hex => hex::encode(&data),
-                                PayloadType::Ascii => String::from_utf8_lossy(&data).to_string(),
-                            })).await;
+                        Some(SessionCommand::SetIdleTimeout(timeout)) => idle_timeout = timeout,
+                        Some(SessionCommand::Disconnect) | None => break CloseReason::User,
                     }
-                    Some(SessionCommand::Disconnect) => {
-                        main_log(format_log(LogLevel::Info, "Disconnect received")).await;
-                        break;
+                }
+                // Handle incoming data from the server
+                n = _reader.read(&mut buf) => {
+                    match n {
+                        Ok(0) => {
+                            log(LogLevel::Debug, window_id, "Read returned EOF, the server closed its side").await;
+                            break CloseReason::Peer;
+                        }
+                        Ok(n) => {
+                            log(LogLevel::Debug, window_id, &format!("Read {} bytes", n)).await;
+                            traffic.received(n);
+                            let data = &buf[..n];
+                            let seq = report_packet(window_id, &packet_seq, Direction::Received, data).await;
+                            last_activity = activity(window_id).await;
+                            log_entry(window_id, LogEntry::packet(seq, Direction::Received, data, "", &hex::encode(data))).await;
+                        }
+                        Err(e) => {
+                            log(LogLevel::Error, window_id, &format!("Read error: {}", e)).await;
+                            break CloseReason::Error;
+                        }
                     }
-                    None => break,
+                }
+                // Disconnect when nothing was sent or received for the idle timeout
+                _ = idle_expired(idle_timeout, last_activity) => {
+                    let secs = idle_timeout.unwrap_or_default().as_secs();
+                    log(LogLevel::Info, window_id, &format!("Idle timeout after {} s", secs)).await;
+                    break CloseReason::IdleTimeout;
                 }
             }
-            // Handle incoming data from the server
-            n = _reader.read(&mut buf) => {
-                match n {
-                    Ok(0) => break, // Connection closed
-                    Ok(n) => {
-                        let data = &buf[..n];
-                        log(LogLevel::Info, window_id, &format!("Received: {}", hex::encode(data))).await;
-                    }
-                    Err(e) => {
-                        log(LogLevel::Error, window_id, &format!("Read error: {}", e)).await;
+        }
+    };
+
+    // Flush sends queued before a user close; after any other close they can no longer go out
+    rx.close();
+    while let Ok(cmd) = rx.try_recv() {
+        let data = match cmd {
+            SessionCommand::SendPacket(data, ..) | SessionCommand::Heartbeat(data, _) => data,
+            _ => continue,
+        };
+        if close == CloseReason::User && writer.write_all(&data).await.is_ok() {
+            traffic.sent(data.len());
+            let seq = report_packet(window_id, &packet_seq, Direction::Sent, &data).await;
+            log_entry(
+                window_id,
+                LogEntry::packet(seq, Direction::Sent, &data, "queued", &hex::encode(&data)),
+            )
+            .await;
+        } else {
+            log(
+                LogLevel::Warn,
+                window_id,
+                &format!("Dropped unsent: {}", hex::encode(&data)),
+            )
+            .await;
+        }
+    }
+
+    // Shut down our side explicitly, then collect the peer's final bytes for a moment
+    if close != CloseReason::Peer && close != CloseReason::Error {
+        let shutdown = writer.shutdown().await;
+        log(
+            LogLevel::Debug,
+            window_id,
+            &match shutdown {
+                Ok(()) => format!(
+                    "Shut down the sending side, reading for up to {} ms",
+                    CLOSE_READ_GRACE.as_millis()
+                ),
+                Err(e) => format!("Shutdown failed: {}", e),
+            },
+        )
+        .await;
+        let deadline = tokio::time::sleep(CLOSE_READ_GRACE);
+        tokio::pin!(deadline);
+        loop {
+            tokio::select! {
+                n = _reader.read(&mut buf) => match n {
+                    Ok(0) => {
+                        log(LogLevel::Debug, window_id, "Read returned EOF after shutdown").await;
                         break;
                     }
+                    Ok(n) if n > 0 => {
+                        log(LogLevel::Debug, window_id, &format!("Read {} bytes after shutdown", n)).await;
+                        traffic.received(n);
+                        let seq = report_packet(window_id, &packet_seq, Direction::Received, &buf[..n]).await;
+                        log_entry(window_id, LogEntry::packet(seq, Direction::Received, &buf[..n], "", &hex::encode(&buf[..n]))).await;
+                    }
+                    _ => break,
+                },
+                _ = &mut deadline => {
+                    log(LogLevel::Debug, window_id, "Stopped reading, no EOF within the grace period").await;
+                    break;
                 }
             }
         }
     }
+
     // Notify disconnection and session end
-    let _ = CONNECTION_SENDER.lock().await.send((window_id, false));
-    main_log(format_log(
+    // Built from the task's own counters, so it holds even if the window log was cleared
+    let summary = format!(
+        "closed {} ({} close): sent {} packets ({} bytes), received {} packets ({} bytes), connected for {}",
+        close.describe(),
+        close.origin(),
+        traffic.packets_sent,
+        traffic.bytes_sent,
+        traffic.packets_received,
+        traffic.bytes_received,
+        format_duration(connected_at.elapsed())
+    );
+    log(LogLevel::Info, window_id, &format!("Session {}", summary)).await;
+    let _ = CONNECTION_SENDER.send((window_id, SessionEvent::Connection(false)));
+    main_log(
         LogLevel::Info,
-        &format!("Session ended: {}", addr_full),
-    ))
+        &format!("Session ended: {} {}", addr_full, summary),
+    )
     .await;
 }
+
+/// How long to keep reading after our side shut down, to collect the peer's final bytes.
+const CLOSE_READ_GRACE: Duration = Duration::from_millis(500);
+
+/// Why a TCP session ended.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CloseReason {
+    /// Disconnect was requested from the window, or the window was closed.
+    User,
+    /// The peer closed the connection.
+    Peer,
+    /// Reading or writing failed.
+    Error,
+    IdleTimeout,
+    HandshakeFailed,
+}
+
+impl CloseReason {
+    fn describe(self) -> &'static str {
+        match self {
+            CloseReason::User => "by user",
+            CloseReason::Peer => "by peer",
+            CloseReason::Error => "after an error",
+            CloseReason::IdleTimeout => "after idle timeout",
+            CloseReason::HandshakeFailed => "after handshake failure",
+        }
+    }
+
+    /// Which side ended the session: "local", "remote", or "error".
+    fn origin(self) -> &'static str {
+        match self {
+            CloseReason::User | CloseReason::IdleTimeout | CloseReason::HandshakeFailed => "local",
+            CloseReason::Peer => "remote",
+            CloseReason::Error => "error",
+        }
+    }
+}
+
+/// Packet and byte counts in each direction of a session.
+#[derive(Default)]
+struct Traffic {
+    packets_sent: u64,
+    bytes_sent: u64,
+    packets_received: u64,
+    bytes_received: u64,
+}
+
+impl Traffic {
+    fn sent(&mut self, bytes: usize) {
+        self.packets_sent += 1;
+        self.bytes_sent += bytes as u64;
+    }
+
+    fn received(&mut self, bytes: usize) {
+        self.packets_received += 1;
+        self.bytes_received += bytes as u64;
+    }
+}
+
+/// Writes each handshake step after its delay, logging it and reporting it for recording.
+/// Returns false at the first step that fails, naming the step in the log.
+async fn send_handshake(
+    writer: &mut OwnedWriteHalf,
+    steps: &[ReplayablePayload],
+    traffic: &mut Traffic,
+    packet_seq: &AtomicU64,
+    window_id: window::Id,
+) -> bool {
+    for (index, step) in steps.iter().enumerate() {
+        tokio::time::sleep(Duration::from_millis(step.delay)).await;
+        let result = match step.get_payload() {
+            Ok(data) => writer
+                .write_all(&data)
+                .await
+                .map(|_| data)
+                .map_err(|e| e.to_string()),
+            Err(_) => Err(format!("invalid payload ({:?})", step.payload_type)),
+        };
+        match result {
+            Ok(data) => {
+                traffic.sent(data.len());
+                let seq = report_packet(window_id, packet_seq, Direction::Sent, &data).await;
+                log_entry(
+                    window_id,
+                    LogEntry::packet(
+                        seq,
+                        Direction::Sent,
+                        &data,
+                        &format!("handshake {}/{}", index + 1, steps.len()),
+                        &match step.payload_type {
+                            PayloadType::Hex => hex::encode(&data),
+                            PayloadType::Ascii => String::from_utf8_lossy(&data).to_string(),
+                        },
+                    ),
+                )
+                .await;
+                // Only payloads that actually reached the server are recorded for replay
+                let _ =
+                    CONNECTION_SENDER.send((window_id, SessionEvent::HandshakeSent(step.clone())));
+            }
+            Err(e) => {
+                log(
+                    LogLevel::Error,
+                    window_id,
+                    &format!("Handshake step {}/{} failed: {}", index + 1, steps.len(), e),
+                )
+                .await;
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Claims one pending discard, returning true if the next queued send should be dropped.
+fn take_discard(discard: &AtomicUsize) -> bool {
+    discard
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+        .is_ok()
+}
+
+/// Writes `data`, reporting the write to the window and warning once if it blocks for longer
+/// than `stall_warning`.
+async fn write_watched(
+    writer: &mut OwnedWriteHalf,
+    data: &[u8],
+    stall_warning: Duration,
+    window_id: window::Id,
+) -> std::io::Result<()> {
+    let connection = CONNECTION_SENDER.clone();
+    let _ = connection.send((window_id, SessionEvent::Writing(true)));
+    let started = Instant::now();
+    let write = writer.write_all(data);
+    tokio::pin!(write);
+    let result = tokio::select! {
+        result = &mut write => result,
+        _ = tokio::time::sleep(stall_warning) => {
+            log(
+                LogLevel::Warn,
+                window_id,
+                &format!("Send blocked for over {} ms", stall_warning.as_millis()),
+            )
+            .await;
+            write.await
+        }
+    };
+    let _ = connection.send((window_id, SessionEvent::Writing(false)));
+    if result.is_ok() {
+        log(
+            LogLevel::Debug,
+            window_id,
+            &format!(
+                "Wrote {} bytes in {} ms",
+                data.len(),
+                started.elapsed().as_millis()
+            ),
+        )
+        .await;
+    }
+    result
+}
+
+/// Describes what `addr` resolves to, for a Debug entry. Resolving takes a lookup of its
+/// own, so it only happens while debug logging is on.
+pub async fn resolved(addr: &str) -> Option<String> {
+    if !crate::log::debug_enabled() {
+        return None;
+    }
+    Some(match tokio::net::lookup_host(addr).await {
+        Ok(addrs) => format!(
+            "Resolved {} to {}",
+            addr,
+            addrs
+                .map(|addr| addr.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Err(e) => format!("Resolving {} failed: {}", addr, e),
+    })
+}
+
+/// Describes the options of a connected socket, for a Debug entry.
+pub fn socket_options(stream: &TcpStream) -> String {
+    let show = |value: std::io::Result<String>| value.unwrap_or_else(|e| e.to_string());
+    format!(
+        "Socket {} -> {}: TCP_NODELAY {}, TTL {}",
+        show(stream.local_addr().map(|addr| addr.to_string())),
+        show(stream.peer_addr().map(|addr| addr.to_string())),
+        show(stream.nodelay().map(|on| on.to_string())),
+        show(stream.ttl().map(|ttl| ttl.to_string())),
+    )
+}
+
+/// Numbers a packet and reports it to the window for its transcript, returning its
+/// number for the log line.
+pub async fn report_packet(
+    window_id: window::Id,
+    packet_seq: &AtomicU64,
+    direction: Direction,
+    data: &[u8],
+) -> u64 {
+    let seq = packet_seq.fetch_add(1, Ordering::Relaxed) + 1;
+    let _ = CONNECTION_SENDER.send((
+        window_id,
+        SessionEvent::Packet(PacketRecord {
+            seq,
+            direction,
+            timestamp: chrono::Local::now(),
+            data: data.to_vec(),
+        }),
+    ));
+    seq
+}
+
+/// Reports traffic to the window so it can reset its idle countdown, returning the time of it.
+async fn activity(window_id: window::Id) -> Instant {
+    let _ = CONNECTION_SENDER.send((window_id, SessionEvent::Activity));
+    Instant::now()
+}
+
+/// Completes once `timeout` has passed since `last_activity`; never completes without a timeout.
+async fn idle_expired(timeout: Option<Duration>, last_activity: Instant) {
+    match timeout {
+        Some(timeout) => tokio::time::sleep_until((last_activity + timeout).into()).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Sends `payload` through the session every `interval_ms` until `cancel` is cancelled or the
+/// session goes away.
+pub async fn heartbeat_task(
+    sender: tokio::sync::mpsc::Sender<SessionCommand>,
+    payload: ReplayablePayload,
+    interval_ms: u64,
+    quiet: bool,
+    cancel: CancellationToken,
+    window_id: window::Id,
+) {
+    let Ok(data) = payload.get_payload() else {
+        return;
+    };
+    while !sleep_unless_cancelled(interval_ms, &cancel).await {
+        if sender
+            .send(SessionCommand::Heartbeat(data.clone(), quiet))
+            .await
+            .is_err()
+        {
+            break;
+        }
+        let _ = CONNECTION_SENDER.send((window_id, SessionEvent::HeartbeatSent(payload.clone())));
+    }
+}
@@ -0,0 +1,261 @@
+// Copy of every session, replay, and main log line sent to a remote syslog collector.
+
+use crate::log::{LogEntry, LogLevel, LogSink, SinkId};
+use iced::futures::future::BoxFuture;
+use iced::window;
+use lazy_static::lazy_static;
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Most lines waiting to be sent; further ones are dropped, so a slow or unreachable
+/// collector never holds up logging.
+const QUEUE_SIZE: usize = 1000;
+/// Interval at which dropped lines are reported in the main log.
+const WARN_INTERVAL: Duration = Duration::from_secs(10);
+/// Interval between attempts to resolve an unreachable target.
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+/// SD-ID of the structured data element carrying the window name. 32473 is the private
+/// enterprise number reserved for examples and documentation.
+const SD_ID: &str = "replayr@32473";
+
+/// Syslog facilities by name, with their codes.
+const FACILITIES: [(&str, u8); 24] = [
+    ("kern", 0),
+    ("user", 1),
+    ("mail", 2),
+    ("daemon", 3),
+    ("auth", 4),
+    ("syslog", 5),
+    ("lpr", 6),
+    ("news", 7),
+    ("uucp", 8),
+    ("cron", 9),
+    ("authpriv", 10),
+    ("ftp", 11),
+    ("ntp", 12),
+    ("security", 13),
+    ("console", 14),
+    ("solaris-cron", 15),
+    ("local0", 16),
+    ("local1", 17),
+    ("local2", 18),
+    ("local3", 19),
+    ("local4", 20),
+    ("local5", 21),
+    ("local6", 22),
+    ("local7", 23),
+];
+
+/// Where log lines are forwarded to, taken from the config.
+#[derive(Clone, Debug, PartialEq)]
+struct Settings {
+    target: String,
+    facility: u8,
+    app_name: String,
+}
+
+/// Queue of the background thread sending to the collector.
+struct Sink {
+    settings: Settings,
+    queue: SyncSender<String>,
+    /// Lines dropped because the queue was full or sending failed.
+    dropped: Arc<AtomicU64>,
+}
+
+impl LogSink for Sink {
+    /// Queues `entry`, tagged with the name of the window it was logged in. Never
+    /// blocks: if the queue is full, the line is dropped and counted.
+    fn emit(&self, window: Option<window::Id>, entry: LogEntry) -> BoxFuture<'_, ()> {
+        let window = window.map_or_else(|| "main".to_string(), crate::log::window_name);
+        let line = format_line(&self.settings, &entry, &window);
+        if let Err(TrySendError::Full(_)) = self.queue.try_send(line) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        Box::pin(std::future::ready(()))
+    }
+}
+
+lazy_static! {
+    /// Settings of the registered sink, if a syslog target is configured
+    static ref SINK: Mutex<Option<(Settings, SinkId)>> = Mutex::new(None);
+}
+
+/// Checks a syslog target, `host:port` or empty to turn forwarding off.
+pub fn parse_target(input: &str) -> Result<String, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(String::new());
+    }
+    let (host, port) = input
+        .rsplit_once(':')
+        .ok_or_else(|| format!("{} is not host:port", input))?;
+    crate::validate::address(host)?;
+    crate::validate::port(port)?;
+    Ok(input.to_string())
+}
+
+/// Checks a syslog facility name, e.g. `user` or `local0`.
+pub fn parse_facility(input: &str) -> Result<String, String> {
+    let input = input.trim();
+    facility_code(input)
+        .map(|_| input.to_string())
+        .ok_or_else(|| format!("{} is not a syslog facility, e.g. user or local0", input))
+}
+
+fn facility_code(name: &str) -> Option<u8> {
+    FACILITIES
+        .iter()
+        .find(|(facility, _)| facility.eq_ignore_ascii_case(name))
+        .map(|(_, code)| *code)
+}
+
+/// Starts, restarts, or stops forwarding to match the config. Does nothing if the
+/// settings did not change.
+pub fn configure(config: &crate::config::Config) {
+    let settings = Settings {
+        target: config.syslog_target.trim().to_string(),
+        facility: facility_code(&config.syslog_facility).unwrap_or(1),
+        app_name: config.syslog_app_name.clone(),
+    };
+    let mut sink = SINK.lock().unwrap();
+    if sink.as_ref().map(|(current, _)| current) == Some(&settings)
+        || sink.is_none() && settings.target.is_empty()
+    {
+        return;
+    }
+    // Dropping the previous queue ends its thread once the queued lines are sent
+    if let Some((_, id)) = sink.take() {
+        crate::log::remove_sink(id);
+    }
+    if settings.target.is_empty() {
+        return;
+    }
+    let (queue, lines) = std::sync::mpsc::sync_channel(QUEUE_SIZE);
+    let dropped = Arc::new(AtomicU64::new(0));
+    let target = settings.target.clone();
+    let thread_dropped = dropped.clone();
+    std::thread::spawn(move || send_lines(&target, lines, &thread_dropped));
+    let id = crate::log::add_sink(Arc::new(Sink {
+        settings: settings.clone(),
+        queue,
+        dropped,
+    }));
+    *sink = Some((settings, id));
+}
+
+/// Whether log lines are forwarded to a collector.
+pub fn enabled() -> bool {
+    SINK.lock().unwrap().is_some()
+}
+
+/// Formats an RFC 5424 message, e.g.
+/// `<14>1 2026-01-05T14:03:11.482+01:00 - replayr 4242 - [replayr@32473 window="boiler"] #42 RX 2 B: 0a0b`.
+fn format_line(settings: &Settings, entry: &LogEntry, window: &str) -> String {
+    let severity = match entry.level {
+        LogLevel::Error => 3,
+        LogLevel::Warn => 4,
+        LogLevel::Info => 6,
+        LogLevel::Debug => 7,
+    };
+    // APP-NAME is at most 48 printable characters without spaces
+    let app_name: String = settings
+        .app_name
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(48)
+        .collect();
+    let window: String = window
+        .chars()
+        .flat_map(|c| match c {
+            '"' | '\\' | ']' => vec!['\\', c],
+            c => vec![c],
+        })
+        .collect();
+    format!(
+        "<{}>1 {} - {} {} - [{} window=\"{}\"] {}",
+        u16::from(settings.facility) * 8 + severity,
+        chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+        if app_name.is_empty() { "-" } else { &app_name },
+        std::process::id(),
+        SD_ID,
+        window,
+        entry.message
+    )
+}
+
+/// Sends queued lines to `target` until the queue is dropped. Lines that cannot be sent
+/// are counted, and the count is reported in the main log now and then.
+fn send_lines(target: &str, lines: Receiver<String>, dropped: &AtomicU64) {
+    let mut socket: Option<UdpSocket> = None;
+    let mut last_error = None;
+    let mut reported = 0;
+    let mut last_report = Instant::now();
+    let mut last_attempt: Option<Instant> = None;
+    loop {
+        match lines.recv_timeout(WARN_INTERVAL) {
+            Ok(line) => {
+                if socket.is_none()
+                    && last_attempt.is_none_or(|attempt| attempt.elapsed() >= RETRY_INTERVAL)
+                {
+                    last_attempt = Some(Instant::now());
+                    match connect(target) {
+                        Ok(connected) => socket = Some(connected),
+                        Err(e) => last_error = Some(e),
+                    }
+                }
+                let sent = socket.as_ref().map(|socket| socket.send(line.as_bytes()));
+                match sent {
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => {
+                        // Resolve the target again for the next line, it may have moved
+                        socket = None;
+                        last_error = Some(e.to_string());
+                        dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                    None => {
+                        dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        let total = dropped.load(Ordering::Relaxed);
+        if total > reported && last_report.elapsed() >= WARN_INTERVAL {
+            let reason = last_error
+                .take()
+                .unwrap_or_else(|| "the queue was full".to_string());
+            crate::log::main_log_blocking(
+                LogLevel::Warn,
+                &format!(
+                    "Syslog forwarding to {} dropped {} log lines: {}",
+                    target,
+                    total - reported,
+                    reason
+                ),
+            );
+            reported = total;
+            last_report = Instant::now();
+        }
+    }
+}
+
+/// Binds a socket and connects it to the first address `target` resolves to.
+fn connect(target: &str) -> Result<UdpSocket, String> {
+    let addr = target
+        .to_socket_addrs()
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or_else(|| format!("{} did not resolve to an address", target))?;
+    let local = if addr.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = UdpSocket::bind(local).map_err(|e| e.to_string())?;
+    socket.connect(addr).map_err(|e| e.to_string())?;
+    Ok(socket)
+}
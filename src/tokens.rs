@@ -0,0 +1,176 @@
+use crate::types::{PayloadType, ReplayablePayload};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How a token value is extracted from received data.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum TokenSource {
+    /// Take `length` bytes starting at `offset` of a response.
+    Range { offset: usize, length: usize },
+    /// Take the first capture group (or the whole match) of a regex over the response bytes.
+    Regex { pattern: String },
+}
+
+/// A named value captured from responses and substituted into later payloads as `{{name}}`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TokenRule {
+    pub name: String,
+    pub source: TokenSource,
+}
+
+/// Captured token values by name.
+pub type Tokens = HashMap<String, Vec<u8>>;
+
+/// Applies every rule to a received chunk, storing and returning the values that matched.
+pub fn extract(rules: &[TokenRule], data: &[u8], tokens: &mut Tokens) -> Vec<(String, Vec<u8>)> {
+    let mut captured = Vec::new();
+    for rule in rules {
+        let value = match &rule.source {
+            TokenSource::Range { offset, length } => data
+                .get(*offset..offset.saturating_add(*length))
+                .map(|bytes| bytes.to_vec()),
+            TokenSource::Regex { pattern } => regex::bytes::Regex::new(pattern)
+                .ok()
+                .and_then(|re| re.captures(data))
+                .and_then(|caps| caps.get(1).or_else(|| caps.get(0)))
+                .map(|m| m.as_bytes().to_vec()),
+        };
+        if let Some(value) = value {
+            tokens.insert(rule.name.clone(), value.clone());
+            captured.push((rule.name.clone(), value));
+        }
+    }
+    captured
+}
+
+lazy_static! {
+    /// A token marker: a name of letters, digits, and underscores in double braces, e.g.
+    /// `{{session_id}}`. Other text in braces, such as JSON, is sent as typed.
+    static ref MARKER: regex::Regex =
+        regex::Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_]*)\s*\}\}").unwrap();
+}
+
+/// Returns true if the payload text contains a token marker.
+pub fn uses_tokens(payload: &str) -> bool {
+    MARKER.is_match(payload)
+}
+
+/// Decodes a payload after replacing `{{name}}` markers with captured token values.
+/// Hex payloads receive the value as hex digits, ASCII payloads as escaped text, so
+/// either way the payload decodes to the raw bytes of the value.
+pub fn substitute(payload: &ReplayablePayload, tokens: &Tokens) -> Result<Vec<u8>, String> {
+    if !uses_tokens(&payload.payload) {
+        return payload.get_payload();
+    }
+    let mut text = String::new();
+    let mut rest = 0;
+    for marker in MARKER.captures_iter(&payload.payload) {
+        let (whole, name) = (marker.get(0).unwrap(), &marker[1]);
+        let value = tokens
+            .get(name)
+            .ok_or_else(|| format!("token '{}' has not been captured", name))?;
+        text.push_str(&payload.payload[rest..whole.start()]);
+        match payload.payload_type {
+            PayloadType::Hex => text.push_str(&hex::encode(value)),
+            PayloadType::Ascii => text.push_str(&value.escape_ascii().to_string()),
+        }
+        rest = whole.end();
+    }
+    text.push_str(&payload.payload[rest..]);
+    crate::validate::payload_bytes(&text, payload.payload_type)
+}
+
+/// Kind of token source, as selected in the rule editor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    Range,
+    Regex,
+}
+
+/// A single edit made to a token rule in the replay window.
+#[derive(Clone, Debug)]
+pub enum TokenRuleEdit {
+    Name(String),
+    Kind(TokenKind),
+    Offset(String),
+    Length(String),
+    Pattern(String),
+}
+
+impl TokenRule {
+    /// Returns the kind of source this rule uses.
+    pub fn kind(&self) -> TokenKind {
+        match self.source {
+            TokenSource::Range { .. } => TokenKind::Range,
+            TokenSource::Regex { .. } => TokenKind::Regex,
+        }
+    }
+
+    /// Applies an edit from the rule editor. Numeric fields ignore input that is not a number.
+    pub fn apply(&mut self, edit: TokenRuleEdit) {
+        let parse = |value: &str| match value.trim() {
+            "" => Some(0),
+            value => value.parse().ok(),
+        };
+        match (edit, &mut self.source) {
+            (TokenRuleEdit::Name(name), _) => self.name = name,
+            (TokenRuleEdit::Kind(TokenKind::Range), TokenSource::Regex { .. }) => {
+                self.source = TokenSource::Range {
+                    offset: 0,
+                    length: 0,
+                }
+            }
+            (TokenRuleEdit::Kind(TokenKind::Regex), TokenSource::Range { .. }) => {
+                self.source = TokenSource::Regex {
+                    pattern: String::new(),
+                }
+            }
+            (TokenRuleEdit::Offset(value), TokenSource::Range { offset, .. }) => {
+                if let Some(value) = parse(&value) {
+                    *offset = value;
+                }
+            }
+            (TokenRuleEdit::Length(value), TokenSource::Range { length, .. }) => {
+                if let Some(value) = parse(&value) {
+                    *length = value;
+                }
+            }
+            (TokenRuleEdit::Pattern(value), TokenSource::Regex { pattern }) => *pattern = value,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ascii(payload: &str) -> ReplayablePayload {
+        ReplayablePayload {
+            payload: payload.to_string(),
+            payload_type: PayloadType::Ascii,
+            delay: 0,
+            recorded_only: false,
+            label: None,
+        }
+    }
+
+    #[test]
+    fn only_identifiers_in_braces_are_markers() {
+        let tokens = Tokens::from([("sid".to_string(), b"42".to_vec())]);
+        let json = r#"{"a":{{"b":1}}, "id":"{{ sid }}"}"#;
+        assert_eq!(
+            substitute(&ascii(json), &tokens).unwrap(),
+            br#"{"a":{{"b":1}}, "id":"42"}"#.to_vec()
+        );
+        assert!(!uses_tokens("{{not a token}}"));
+        assert!(substitute(&ascii("{{missing}}"), &tokens).is_err());
+        let binary = Tokens::from([("raw".to_string(), b"\\\x00\xff".to_vec())]);
+        assert_eq!(
+            substitute(&ascii("a\\t{{raw}}"), &binary).unwrap(),
+            b"a\t\\\x00\xff".to_vec()
+        );
+    }
+}
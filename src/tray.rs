@@ -0,0 +1,286 @@
+// System tray icon with quick actions, built with the `tray` feature. Without it, or
+// where no tray can be created, the app runs as before and closing the main window exits.
+
+use crate::config::Config;
+use crate::types::{Profile, RecentTarget};
+use crate::ui::Message;
+use iced::Subscription;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether this build can show a tray icon.
+pub const SUPPORTED: bool = cfg!(feature = "tray");
+
+/// Set once the tray icon exists, so closing the main window can hide it to the tray.
+static AVAILABLE: AtomicBool = AtomicBool::new(false);
+/// Set once the tray icon is being created, so it is only created once.
+static STARTED: AtomicBool = AtomicBool::new(false);
+/// What the tray menu was last built or updated with.
+static CONTENTS: Mutex<Option<MenuContents>> = Mutex::new(None);
+
+/// What a tray menu item asks of the app. Only the tray menu creates them.
+#[derive(Clone, Debug)]
+#[cfg_attr(not(feature = "tray"), expect(dead_code))]
+pub enum TrayAction {
+    /// Shows the main window if hidden, otherwise hides it.
+    ToggleWindow,
+    /// Connects the main window to the target of a profile or a recent target.
+    Connect(RecentTarget),
+    /// Runs the most recent replay again.
+    ReplayLast,
+    /// Exits as the main window's Quit does, asking about running sessions first.
+    Quit,
+}
+
+/// The parts of the tray menu taken from the config.
+#[derive(Clone, PartialEq, Debug)]
+struct MenuContents {
+    profiles: Vec<Profile>,
+    recent_targets: Vec<RecentTarget>,
+    replay_last: bool,
+}
+
+impl MenuContents {
+    fn of(config: &Config) -> Self {
+        MenuContents {
+            profiles: config.profiles.clone(),
+            recent_targets: config.recent_targets.clone(),
+            replay_last: !config.recent_replays.is_empty(),
+        }
+    }
+}
+
+/// Whether the tray icon exists.
+pub fn available() -> bool {
+    AVAILABLE.load(Ordering::Relaxed)
+}
+
+/// Shows the tray icon, with the profiles and recent targets of `config` in its menu.
+/// Must be called on the thread running the event loop. On Linux the icon lives on a
+/// thread of its own running GTK. Failures are logged, and the app goes on without the
+/// icon.
+pub fn start(config: &Config) {
+    if !SUPPORTED || STARTED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    let contents = MenuContents::of(config);
+    *CONTENTS.lock().unwrap() = Some(contents.clone());
+    #[cfg(all(feature = "tray", target_os = "linux"))]
+    std::thread::spawn(move || {
+        if let Err(e) = gtk::init() {
+            unavailable(&e.to_string());
+            return;
+        }
+        // The icon is removed once dropped, so it is kept while GTK runs
+        let _tray = match platform::build(&contents) {
+            Ok(tray) => tray,
+            Err(e) => return unavailable(&e),
+        };
+        AVAILABLE.store(true, Ordering::Relaxed);
+        gtk::main();
+    });
+    #[cfg(all(feature = "tray", not(target_os = "linux")))]
+    match platform::build(&contents) {
+        // The icon lives as long as the app
+        Ok(tray) => {
+            std::mem::forget(tray);
+            AVAILABLE.store(true, Ordering::Relaxed);
+        }
+        Err(e) => unavailable(&e),
+    }
+    #[cfg(not(feature = "tray"))]
+    let _ = contents;
+}
+
+/// Updates the tray menu to the profiles, recent targets, and recent replays of `config`.
+/// Does nothing before the icon is started or if none of them changed. Must be called on
+/// the thread running the event loop.
+pub fn configure(config: &Config) {
+    if !STARTED.load(Ordering::Relaxed) {
+        return;
+    }
+    let contents = MenuContents::of(config);
+    let mut current = CONTENTS.lock().unwrap();
+    if current.as_ref() == Some(&contents) {
+        return;
+    }
+    *current = Some(contents.clone());
+    #[cfg(feature = "tray")]
+    platform::update(contents);
+}
+
+/// Logs why the tray icon could not be shown.
+#[cfg(feature = "tray")]
+fn unavailable(reason: &str) {
+    crate::log::main_log_blocking(
+        crate::log::LogLevel::Warn,
+        &format!("No tray icon, the desktop has no tray: {}", reason),
+    );
+}
+
+/// Actions chosen in the tray menu. A thread waits for menu events and feeds them to the
+/// subscription, as they arrive outside the event loop.
+pub fn subscription() -> Subscription<Message> {
+    #[cfg(feature = "tray")]
+    {
+        Subscription::run(platform::actions)
+    }
+    #[cfg(not(feature = "tray"))]
+    Subscription::none()
+}
+
+#[cfg(feature = "tray")]
+mod platform {
+    use super::{MenuContents, TrayAction};
+    use crate::i18n::{tr, tr_args};
+    use crate::ui::Message;
+    use iced::futures::SinkExt;
+    use lazy_static::lazy_static;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use tray_icon::menu::{Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem, Submenu};
+    use tray_icon::{TrayIcon, TrayIconBuilder};
+
+    lazy_static! {
+        /// Action of each tray menu item, by the id of the item
+        static ref ACTIONS: Mutex<HashMap<MenuId, TrayAction>> = Mutex::new(HashMap::new());
+    }
+
+    thread_local! {
+        /// Items of the menu that follow the config, on the thread the menu was built on
+        static MENUS: RefCell<Option<Menus>> = const { RefCell::new(None) };
+    }
+
+    /// Items of the tray menu that follow the config.
+    struct Menus {
+        profiles: Submenu,
+        connect: Submenu,
+        replay_last: MenuItem,
+    }
+
+    impl Menus {
+        /// Refills the submenus, and enables each item only if it has something to do.
+        fn fill(&self, contents: &MenuContents) -> Result<(), String> {
+            fill(
+                &self.profiles,
+                contents
+                    .profiles
+                    .iter()
+                    .map(|profile| (profile.name.clone(), TrayAction::Connect(profile.target()))),
+            )?;
+            fill(
+                &self.connect,
+                contents
+                    .recent_targets
+                    .iter()
+                    .map(|target| (target.to_string(), TrayAction::Connect(target.clone()))),
+            )?;
+            self.replay_last.set_enabled(contents.replay_last);
+            Ok(())
+        }
+    }
+
+    /// Replaces the items of `submenu`, disabling it while empty.
+    fn fill(
+        submenu: &Submenu,
+        items: impl Iterator<Item = (String, TrayAction)>,
+    ) -> Result<(), String> {
+        while let Some(old) = submenu.remove_at(0) {
+            ACTIONS.lock().unwrap().remove(old.id());
+        }
+        for (label, action) in items {
+            submenu
+                .append(&item(&label, action))
+                .map_err(|e| e.to_string())?;
+        }
+        submenu.set_enabled(!submenu.items().is_empty());
+        Ok(())
+    }
+
+    /// A menu item carrying out `action` when chosen.
+    fn item(label: &str, action: TrayAction) -> MenuItem {
+        let item = MenuItem::new(label, true, None);
+        ACTIONS.lock().unwrap().insert(item.id().clone(), action);
+        item
+    }
+
+    /// Builds the tray icon and its menu. Later updates must come from this thread.
+    pub fn build(contents: &MenuContents) -> Result<TrayIcon, String> {
+        let toggle = item(tr("Show or hide replayr"), TrayAction::ToggleWindow);
+        let menus = Menus {
+            profiles: Submenu::new(tr("Connect with profile"), false),
+            connect: Submenu::new(tr("Connect to"), false),
+            replay_last: item(tr("Replay last file"), TrayAction::ReplayLast),
+        };
+        menus.fill(contents)?;
+        let quit = item(tr("Quit"), TrayAction::Quit);
+        let menu = Menu::with_items(&[
+            &toggle,
+            &menus.profiles,
+            &menus.connect,
+            &menus.replay_last,
+            &PredefinedMenuItem::separator(),
+            &quit,
+        ])
+        .map_err(|e| e.to_string())?;
+        let tray = TrayIconBuilder::new()
+            .with_menu(Box::new(menu))
+            .with_icon(icon()?)
+            .with_tooltip(tr_args("replayr {}", &[&env!("CARGO_PKG_VERSION")]))
+            .build()
+            .map_err(|e| e.to_string())?;
+        MENUS.set(Some(menus));
+        Ok(tray)
+    }
+
+    /// Updates the menu on the thread it was built on: the GTK thread on Linux, which
+    /// runs it once idle, otherwise the calling one.
+    pub fn update(contents: MenuContents) {
+        let apply = move || {
+            MENUS.with_borrow(|menus| {
+                if let Some(Err(e)) = menus.as_ref().map(|menus| menus.fill(&contents)) {
+                    crate::log::main_log_blocking(
+                        crate::log::LogLevel::Warn,
+                        &format!("Could not update the tray menu: {}", e),
+                    );
+                }
+            })
+        };
+        #[cfg(target_os = "linux")]
+        gtk::glib::MainContext::default().invoke(apply);
+        #[cfg(not(target_os = "linux"))]
+        apply();
+    }
+
+    /// The app icon, in the form the tray takes.
+    fn icon() -> Result<tray_icon::Icon, String> {
+        let decoder = png::Decoder::new(std::io::Cursor::new(include_bytes!("../assets/icon.png")));
+        let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+        let width = reader.info().width;
+        let height = reader.info().height;
+        let mut buf = vec![0; (width * height * 4) as usize];
+        reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
+        tray_icon::Icon::from_rgba(buf, width, height).map_err(|e| e.to_string())
+    }
+
+    /// Streams the actions of the menu items chosen.
+    pub fn actions() -> impl iced::futures::Stream<Item = Message> {
+        iced::stream::channel(16, |mut output| async move {
+            let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+            std::thread::spawn(move || {
+                while let Ok(event) = MenuEvent::receiver().recv() {
+                    let action = ACTIONS.lock().unwrap().get(event.id()).cloned();
+                    if let Some(action) = action
+                        && sender.send(action).is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+            while let Some(action) = receiver.recv().await {
+                let _ = output.send(Message::Tray(action)).await;
+            }
+        })
+    }
+}
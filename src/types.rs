@@ -5,28 +5,475 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone)]
 pub struct LogMessage {
     pub window_id: window::Id,
-    pub content: String,
+    pub entry: crate::log::LogEntry,
 }
 
 /// Commands that can be sent to a session task to control its behavior.
 #[derive(Clone)]
 pub enum SessionCommand {
-    SendPacket(Vec<u8>, PayloadType),
+    /// Sends a packet; the label, if any, is shown in its log line.
+    SendPacket(Vec<u8>, PayloadType, Option<String>),
+    /// Sends a heartbeat packet; the flag suppresses its log line.
+    Heartbeat(Vec<u8>, bool),
+    /// Changes the idle timeout; `None` disables it.
+    SetIdleTimeout(Option<std::time::Duration>),
     Disconnect,
 }
 
+/// Events emitted by a session task for its window.
+#[derive(Clone, Debug)]
+pub enum SessionEvent {
+    /// The connection was established (true) or closed (false).
+    Connection(bool),
+    /// A handshake step was written to the connection.
+    HandshakeSent(ReplayablePayload),
+    /// A replay running inside the session sent a payload.
+    ReplayPayloadSent(ReplayablePayload),
+    /// Number of payloads sent so far by a replay running inside the session, and the total.
+    ReplayProgress(usize, usize),
+    /// A replay running inside the session finished or was stopped.
+    ReplayFinished,
+    /// A repeat-send run sent the payload once more.
+    RepeatPayloadSent(ReplayablePayload),
+    /// Number of repetitions sent so far by a repeat-send run, and the total.
+    RepeatProgress(usize, usize),
+    /// A repeat-send run finished or was stopped.
+    RepeatFinished,
+    /// A payload queued while disconnected was flushed to the session task.
+    QueuedPayloadSent(ReplayablePayload),
+    /// The heartbeat task sent its payload.
+    HeartbeatSent(ReplayablePayload),
+    /// Data was sent or received, resetting the idle timer.
+    Activity,
+    /// The session task started (true) or finished (false) writing a packet.
+    Writing(bool),
+    /// Local and remote socket addresses once connected (TCP) or bound (UDP).
+    Addresses { local: String, remote: String },
+    /// A packet went over the wire, kept for the transcript export.
+    Packet(PacketRecord),
+    /// The connection attempt failed, with the reason; followed by `Connection(false)`.
+    ConnectFailed(String),
+}
+
+/// Direction of a packet as seen from this application.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+/// A packet sent or received by a session.
+#[derive(Clone, Debug)]
+pub struct PacketRecord {
+    /// Number of the packet in its window, as shown in the log.
+    pub seq: u64,
+    pub direction: Direction,
+    pub timestamp: chrono::DateTime<chrono::Local>,
+    pub data: Vec<u8>,
+}
+
+/// Maximum number of packets a session keeps for its transcript.
+pub const MAX_TRANSCRIPT_PACKETS: usize = 100_000;
+
+/// First line of a transcript export, describing the lines that follow.
+#[derive(Serialize)]
+struct TranscriptHeader<'a> {
+    version: u32,
+    protocol: Protocol,
+    target: String,
+    local: Option<&'a str>,
+    remote: Option<&'a str>,
+    packets: usize,
+}
+
+/// One packet of a transcript export.
+#[derive(Serialize)]
+struct TranscriptLine {
+    seq: u64,
+    direction: Direction,
+    /// ISO-8601 with milliseconds and UTC offset.
+    timestamp: String,
+    length: usize,
+    hex: String,
+    /// Printable ASCII kept as is, every other byte shown as '.'.
+    ascii: String,
+}
+
+/// Progress of an "Export all logs" bundle being written.
+#[derive(Clone, Debug)]
+pub enum LogBundleEvent {
+    /// Bytes written so far and in total.
+    Progress(u64, u64),
+    /// A file could not be written; the rest of the bundle is still written.
+    Failed(std::path::PathBuf, String),
+    /// Every file was attempted; the bundle directory and the number of files written.
+    Finished(std::path::PathBuf, usize),
+}
+
 /// Represents the state of a window, either a live session or a replay session.
 #[derive(Clone)]
 pub enum WindowState {
-    Session(SessionData),
-    Replay(ReplayData),
+    Session(Box<SessionData>),
+    Replay(Box<ReplayData>),
 }
 
 /// Represents a window with a title and its current state.
 #[derive(Clone)]
 pub struct Window {
     pub title: String,
+    /// User-chosen name shown before the generated title.
+    pub label: Option<String>,
+    /// Label text while the rename field is open.
+    pub label_edit: Option<String>,
     pub state: WindowState,
+    /// File the log is continuously written to, if enabled.
+    pub log_file: Option<crate::logfile::LogFile>,
+    /// Levels shown in the log.
+    pub log_filter: crate::log::LogFilter,
+    pub log_scroll: LogScroll,
+    pub log_zoom: LogZoom,
+    /// Notices about failures shown at the top of the window.
+    pub banners: Banners,
+}
+
+/// Number of banners a window shows at once; older ones are summed up in one line.
+pub const SHOWN_BANNERS: usize = 3;
+/// Number of banners a window keeps; the oldest are dropped beyond it.
+const MAX_BANNERS: usize = 50;
+
+/// A notice shown at the top of a window until dismissed. Its text is logged as well.
+#[derive(Clone, Debug)]
+pub struct Banner {
+    pub id: u64,
+    pub level: crate::log::LogLevel,
+    pub text: String,
+    /// How often the same notice was raised while shown.
+    pub count: usize,
+    pub raised_at: std::time::Instant,
+}
+
+/// Banners of a window, oldest first.
+#[derive(Clone, Debug, Default)]
+pub struct Banners {
+    next_id: u64,
+    pub shown: Vec<Banner>,
+}
+
+impl Banners {
+    /// Shows a notice and returns its id. The same notice raised again while shown is
+    /// counted and moved to the end instead of shown twice.
+    pub fn raise(&mut self, level: crate::log::LogLevel, text: &str) -> u64 {
+        let now = std::time::Instant::now();
+        let banner = match self
+            .shown
+            .iter()
+            .position(|banner| banner.level == level && banner.text == text)
+        {
+            Some(index) => {
+                let mut banner = self.shown.remove(index);
+                banner.count += 1;
+                banner.raised_at = now;
+                banner
+            }
+            None => {
+                self.next_id += 1;
+                Banner {
+                    id: self.next_id,
+                    level,
+                    text: text.to_string(),
+                    count: 1,
+                    raised_at: now,
+                }
+            }
+        };
+        let id = banner.id;
+        self.shown.push(banner);
+        if self.shown.len() > MAX_BANNERS {
+            self.shown.remove(0);
+        }
+        id
+    }
+
+    pub fn dismiss(&mut self, id: u64) {
+        self.shown.retain(|banner| banner.id != id);
+    }
+
+    pub fn dismiss_all(&mut self) {
+        self.shown.clear();
+    }
+
+    /// Whether any banner is dismissed on its own after a while; errors never are.
+    pub fn expiring(&self) -> bool {
+        self.shown
+            .iter()
+            .any(|banner| banner.level != crate::log::LogLevel::Error)
+    }
+
+    /// Dismisses the banners other than errors raised more than `timeout` ago.
+    pub fn expire(&mut self, timeout: std::time::Duration) {
+        self.shown.retain(|banner| {
+            banner.level == crate::log::LogLevel::Error || banner.raised_at.elapsed() < timeout
+        });
+    }
+}
+
+/// Height of a row in a log view at the default text size of 16. Rows do not wrap, so the
+/// rows in view follow from the scroll offset.
+pub const LOG_ROW_HEIGHT: f32 = 20.0;
+
+/// Zoom levels of log text, in percent of the configured size.
+const ZOOM_STEPS: [u16; 12] = [50, 67, 80, 90, 100, 110, 125, 150, 175, 200, 250, 300];
+
+/// How long the zoom level is shown after it changes.
+pub const ZOOM_SHOWN: std::time::Duration = std::time::Duration::from_millis(1500);
+
+/// How the zoom of a window's log text changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ZoomChange {
+    In,
+    Out,
+    Reset,
+}
+
+/// Zoom of the log text of a window, kept while the window is open.
+#[derive(Clone, Copy, Debug)]
+pub struct LogZoom {
+    pub percent: u16,
+    /// When the zoom last changed, to show the level for a moment.
+    pub changed_at: Option<std::time::Instant>,
+}
+
+impl Default for LogZoom {
+    fn default() -> Self {
+        Self {
+            percent: 100,
+            changed_at: None,
+        }
+    }
+}
+
+impl LogZoom {
+    /// Moves to the next zoom level. Returns the factor the text size changed by.
+    pub fn change(&mut self, change: ZoomChange) -> f32 {
+        let previous = self.percent;
+        self.percent = match change {
+            ZoomChange::In => ZOOM_STEPS
+                .into_iter()
+                .find(|step| *step > self.percent)
+                .unwrap_or(self.percent),
+            ZoomChange::Out => ZOOM_STEPS
+                .into_iter()
+                .rev()
+                .find(|step| *step < self.percent)
+                .unwrap_or(self.percent),
+            ZoomChange::Reset => 100,
+        };
+        self.changed_at = Some(std::time::Instant::now());
+        self.percent as f32 / previous as f32
+    }
+
+    /// Text size for the configured base size.
+    pub fn text_size(&self, base: u16) -> f32 {
+        (base as u32 * self.percent as u32) as f32 / 100.0
+    }
+
+    /// Height of a log row for the configured base size.
+    pub fn row_height(&self, base: u16) -> f32 {
+        (LOG_ROW_HEIGHT * self.text_size(base) / 16.0).ceil()
+    }
+
+    /// Whether the zoom level is still shown after a change.
+    pub fn shown(&self) -> bool {
+        self.changed_at
+            .is_some_and(|changed| changed.elapsed() < ZOOM_SHOWN)
+    }
+}
+
+/// Panes of a session window, one above the other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SessionPane {
+    /// Status, input and the panels above the log.
+    Controls,
+    Log,
+}
+
+/// Smallest and largest share of a session window's height the controls pane takes, so
+/// neither pane can be dragged out of sight.
+pub const SPLIT_MIN: f32 = 0.1;
+pub const SPLIT_MAX: f32 = 0.9;
+
+/// Two presses of the split bar this close together reset the split.
+const SPLIT_DOUBLE_CLICK: std::time::Duration = std::time::Duration::from_millis(400);
+
+/// Split of a session window between its controls and its log, kept while the window is
+/// open.
+#[derive(Clone, Debug)]
+pub struct SessionSplit {
+    pub panes: iced::widget::pane_grid::State<SessionPane>,
+    /// When the split bar was last pressed without being dragged, to tell a double-click.
+    pressed_at: Option<std::time::Instant>,
+}
+
+impl SessionSplit {
+    /// Split giving `ratio` of the height to the controls.
+    pub fn new(ratio: f32) -> Self {
+        use iced::widget::pane_grid::{Axis, Configuration};
+        Self {
+            panes: iced::widget::pane_grid::State::with_configuration(Configuration::Split {
+                axis: Axis::Horizontal,
+                ratio: ratio.clamp(SPLIT_MIN, SPLIT_MAX),
+                a: Box::new(Configuration::Pane(SessionPane::Controls)),
+                b: Box::new(Configuration::Pane(SessionPane::Log)),
+            }),
+            pressed_at: None,
+        }
+    }
+
+    /// Moves the split bar as it is dragged.
+    pub fn resize(&mut self, event: iced::widget::pane_grid::ResizeEvent) {
+        self.panes
+            .resize(event.split, event.ratio.clamp(SPLIT_MIN, SPLIT_MAX));
+        self.pressed_at = None;
+    }
+
+    /// Notes a press of the split bar. Returns whether it completes a double-click.
+    pub fn pressed(&mut self) -> bool {
+        let now = std::time::Instant::now();
+        let double = self
+            .pressed_at
+            .is_some_and(|at| now.duration_since(at) < SPLIT_DOUBLE_CLICK);
+        self.pressed_at = (!double).then_some(now);
+        double
+    }
+}
+
+/// Scroll position of a log view, which only lays out the rows near it.
+#[derive(Clone, Copy, Debug)]
+pub struct LogScroll {
+    pub offset: iced::widget::scrollable::AbsoluteOffset,
+    /// Height of the viewport; 0 until the view is first drawn.
+    pub height: f32,
+    /// Keep the newest entry in view as entries are added. Scrolling up turns it off and
+    /// scrolling back to the bottom on again.
+    pub follow: bool,
+    /// Number of log entries when new entries were last looked for.
+    pub seen: usize,
+    /// Row under the cursor, which shows its copy actions. The view holds still while
+    /// it is set, so the row does not move away.
+    pub hovered: Option<usize>,
+}
+
+impl Default for LogScroll {
+    fn default() -> Self {
+        Self {
+            offset: Default::default(),
+            height: 0.0,
+            follow: true,
+            seen: 0,
+            hovered: None,
+        }
+    }
+}
+
+impl LogScroll {
+    /// Takes the position of the view after it was scrolled or resized.
+    pub fn scrolled(&mut self, viewport: iced::widget::scrollable::Viewport) {
+        self.offset = viewport.absolute_offset();
+        self.height = viewport.bounds().height;
+        self.follow =
+            self.offset.y + self.height >= viewport.content_bounds().height - LOG_ROW_HEIGHT;
+    }
+}
+
+impl Window {
+    /// Returns the log of the session or replay.
+    pub fn log(&self) -> &crate::log::Log {
+        match &self.state {
+            WindowState::Session(data) => &data.log,
+            WindowState::Replay(data) => &data.log,
+        }
+    }
+
+    /// Appends an entry to the log of the session or replay.
+    pub fn push_log(&mut self, entry: crate::log::LogEntry) {
+        match &mut self.state {
+            WindowState::Session(data) => data.log.push_entry(entry),
+            WindowState::Replay(data) => data.log.push_entry(entry),
+        }
+    }
+
+    /// Returns the whole log, every level but Debug unless shown, after a header
+    /// describing the session or replay.
+    pub fn log_export(&self) -> String {
+        self.log_header() + &self.log().text(self.log_filter.all_levels())
+    }
+
+    /// Header written at the top of exported logs so captures can be matched to pcaps:
+    /// `# Key: value` lines ending in a blank line, with the session notes, if any,
+    /// indented under `# Notes:`. Keys are only ever added, so scripts can rely on them.
+    pub fn log_header(&self) -> String {
+        let mut header = format!(
+            "# replayr {}\n# Window: {}\n# Label: {}\n",
+            env!("CARGO_PKG_VERSION"),
+            self.title,
+            self.label.as_deref().unwrap_or("-"),
+        );
+        match &self.state {
+            WindowState::Session(data) => header.push_str(&data.log_header()),
+            WindowState::Replay(data) => header.push_str(&data.log_header()),
+        }
+        header.push('\n');
+        header
+    }
+
+    /// Returns the name entries forwarded to the main log are prefixed with: the label if
+    /// set, otherwise the replay file name or the session target.
+    pub fn short_name(&self) -> String {
+        match (&self.label, &self.state) {
+            (Some(label), _) => label.clone(),
+            (None, WindowState::Replay(data)) => data.file_name.clone(),
+            (None, WindowState::Session(data)) => format!("{}:{}", data.address, data.port),
+        }
+    }
+
+    /// Returns the base name of files exported from this window: the label if set,
+    /// otherwise the replay file name or the session's target, e.g. "Tcp_10.0.0.7_8080".
+    /// Quick and auto-export write it without a dialog, so it is always a plain file name.
+    pub fn export_stem(&self) -> String {
+        let stem = match (&self.label, &self.state) {
+            (Some(label), _) => label.clone(),
+            (None, WindowState::Replay(data)) => data
+                .file_name
+                .trim_end_matches(".json")
+                .trim_end_matches(".csv")
+                .to_string(),
+            (None, WindowState::Session(data)) => {
+                format!("{:?}_{}_{}", data.protocol, data.address, data.port)
+            }
+        };
+        file_stem(&stem)
+    }
+}
+
+/// Makes `name` safe as a file name on every platform: characters other than ASCII
+/// letters, digits, '.', '_', and '-' become '_', and leading or trailing dots are dropped
+/// so the name cannot point at a parent directory.
+fn file_stem(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match stem.trim_matches('.') {
+        "" => "export".to_string(),
+        stem => stem.to_string(),
+    }
 }
 
 /// Specifies the type of payload data: hexadecimal or ASCII text.
@@ -43,11 +490,113 @@ pub enum Protocol {
     Udp,
 }
 
+/// An address, port, and protocol a session connected to, kept in the main window's
+/// recent targets history.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct RecentTarget {
+    pub protocol: Protocol,
+    pub address: String,
+    #[serde(deserialize_with = "crate::config::port")]
+    pub port: u16,
+}
+
+impl std::fmt::Display for RecentTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} {}:{}", self.protocol, self.address, self.port)
+    }
+}
+
+/// A named target defined in the config file, offered in the main window and the tray
+/// menu.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct Profile {
+    pub name: String,
+    pub protocol: Protocol,
+    pub address: String,
+    #[serde(deserialize_with = "crate::config::port")]
+    pub port: u16,
+}
+
+impl Profile {
+    /// The target the profile connects to.
+    pub fn target(&self) -> RecentTarget {
+        RecentTarget {
+            protocol: self.protocol,
+            address: self.address.clone(),
+            port: self.port,
+        }
+    }
+}
+
+impl std::fmt::Display for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// A replay file loaded recently and the target it was last run against, kept for the
+/// main window's "Replay last" button.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct RecentReplay {
+    pub path: std::path::PathBuf,
+    pub protocol: Protocol,
+    pub address: String,
+    #[serde(deserialize_with = "crate::config::port")]
+    pub port: u16,
+}
+
+impl RecentReplay {
+    /// Returns the file name, for labels.
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .unwrap_or(self.path.as_os_str())
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+impl std::fmt::Display for RecentReplay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} to {}:{}", self.name(), self.address, self.port)
+    }
+}
+
 /// Represents a complete session that can be replayed, including protocol and list of payloads.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ReplayableSession {
     pub protocol: Protocol,
     pub payloads: Vec<ReplayablePayload>,
+    /// Rules capturing values from responses for substitution into later payloads.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tokens: Vec<crate::tokens::TokenRule>,
+    /// Address and port (`host:port`) the session was recorded against.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Free-text notes describing what the session was for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// Payloads a TCP session sends in order right after connecting, each after its delay.
+#[derive(Clone, Debug, Default)]
+pub struct Handshake {
+    pub steps: Vec<ReplayablePayload>,
+    /// Close the connection when a step fails instead of continuing with the session.
+    pub disconnect_on_failure: bool,
+}
+
+/// Settings a TCP session task is started with.
+#[derive(Clone, Debug)]
+pub struct TcpSessionOptions {
+    pub handshake: Handshake,
+    pub idle_timeout: Option<std::time::Duration>,
+    /// Writes blocked for longer than this are reported in the session log.
+    pub stall_warning: std::time::Duration,
+    /// Number of queued sends the task should drop instead of writing.
+    pub discard: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    /// Number of the last packet sent or received in the window.
+    pub packet_seq: std::sync::Arc<std::sync::atomic::AtomicU64>,
 }
 
 /// Represents a single payload in a replay session, with its data, type, and delay from previous payload.
@@ -56,39 +605,1323 @@ pub struct ReplayablePayload {
     pub payload: String,
     pub payload_type: PayloadType,
     pub delay: u64,
+    /// Added with "Record only" in a session window and never sent there.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub recorded_only: bool,
+    /// Short description shown in logs and payload lists, e.g. "firmware erase".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl ReplayableSession {
+    /// Parses a CSV file with `payload,payload_type,delay_ms` columns into a session.
+    /// The header row is optional and an empty delay is treated as 0.
+    pub fn from_csv(content: &str, protocol: Protocol) -> Result<Self, String> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(content.trim_start_matches('\u{feff}').as_bytes());
+        let mut payloads = Vec::new();
+        for (index, record) in reader.records().enumerate() {
+            let record = record.map_err(|e| format!("Failed to parse CSV: {}", e))?;
+            let row = record
+                .position()
+                .map(|p| p.line())
+                .unwrap_or(index as u64 + 1);
+            if index == 0
+                && record
+                    .get(0)
+                    .is_some_and(|f| f.eq_ignore_ascii_case("payload"))
+            {
+                continue; // Skip header row
+            }
+            if record.iter().all(|field| field.is_empty()) {
+                continue; // Skip blank lines
+            }
+            let payload = record.get(0).unwrap_or_default().to_string();
+            let payload_type = match record
+                .get(1)
+                .unwrap_or_default()
+                .to_ascii_lowercase()
+                .as_str()
+            {
+                "hex" => PayloadType::Hex,
+                "ascii" => PayloadType::Ascii,
+                other => {
+                    return Err(format!("Row {}: invalid payload type '{}'", row, other));
+                }
+            };
+            let delay = match record.get(2).unwrap_or_default() {
+                "" => 0,
+                delay => delay
+                    .parse()
+                    .map_err(|_| format!("Row {}: invalid delay '{}'", row, delay))?,
+            };
+            let replay_payload = ReplayablePayload {
+                payload,
+                payload_type,
+                delay,
+                recorded_only: false,
+                label: None,
+            };
+            if replay_payload.get_payload().is_err() {
+                return Err(format!("Row {}: invalid hex payload", row));
+            }
+            payloads.push(replay_payload);
+        }
+        Ok(Self {
+            protocol,
+            payloads,
+            tokens: Vec::new(),
+            target: None,
+            notes: None,
+        })
+    }
+
+    /// Serializes the payloads as CSV with a `payload,payload_type,delay_ms` header.
+    pub fn to_csv(&self) -> Result<String, String> {
+        let to_err = |e: csv::Error| format!("Failed to write CSV: {}", e);
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer
+            .write_record(["payload", "payload_type", "delay_ms"])
+            .map_err(to_err)?;
+        for payload in &self.payloads {
+            let payload_type = match payload.payload_type {
+                PayloadType::Hex => "hex",
+                PayloadType::Ascii => "ascii",
+            };
+            writer
+                .write_record([
+                    payload.payload.as_str(),
+                    payload_type,
+                    &payload.delay.to_string(),
+                ])
+                .map_err(to_err)?;
+        }
+        let bytes = writer
+            .into_inner()
+            .map_err(|e| format!("Failed to write CSV: {}", e))?;
+        String::from_utf8(bytes).map_err(|e| format!("Failed to write CSV: {}", e))
+    }
 }
 
 impl ReplayablePayload {
-    pub fn get_payload(&self) -> Result<Vec<u8>, hex::FromHexError> {
-        match self.payload_type {
-                PayloadType::Hex => hex::decode(&self.payload.replace(" ", "")),
-                PayloadType::Ascii => Ok(self.payload.as_bytes().to_vec())
+    pub fn get_payload(&self) -> Result<Vec<u8>, String> {
+        crate::validate::payload_bytes(&self.payload, self.payload_type)
+    }
+}
+
+/// Color theme of the windows.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum ThemeChoice {
+    /// Light or dark as the system is set when the app starts.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl ThemeChoice {
+    /// The theme of the choice, or its high-contrast variant.
+    pub fn theme(self, high_contrast: bool) -> iced::Theme {
+        let theme = match self {
+            ThemeChoice::System => iced::Theme::default(),
+            ThemeChoice::Light => iced::Theme::Light,
+            ThemeChoice::Dark => iced::Theme::Dark,
+        };
+        match high_contrast {
+            false => theme,
+            true if theme.extended_palette().is_dark => HIGH_CONTRAST_DARK.clone(),
+            true => HIGH_CONTRAST_LIGHT.clone(),
         }
     }
 }
 
+lazy_static::lazy_static! {
+    /// White text on pure black, with status colors well above WCAG AA contrast on it.
+    static ref HIGH_CONTRAST_DARK: iced::Theme = iced::Theme::custom(
+        "High contrast dark".to_string(),
+        iced::theme::Palette {
+            background: iced::Color::BLACK,
+            text: iced::Color::WHITE,
+            primary: iced::Color::from_rgb(0.55, 0.8, 1.0),
+            success: iced::Color::from_rgb(0.4, 1.0, 0.4),
+            danger: iced::Color::from_rgb(1.0, 0.5, 0.5),
+        },
+    );
+    /// Black text on pure white, with status colors well above WCAG AA contrast on it.
+    static ref HIGH_CONTRAST_LIGHT: iced::Theme = iced::Theme::custom(
+        "High contrast light".to_string(),
+        iced::theme::Palette {
+            background: iced::Color::WHITE,
+            text: iced::Color::BLACK,
+            primary: iced::Color::from_rgb(0.0, 0.2, 0.6),
+            success: iced::Color::from_rgb(0.0, 0.35, 0.0),
+            danger: iced::Color::from_rgb(0.6, 0.0, 0.0),
+        },
+    );
+}
+
+/// How replay delays change from one loop iteration to the next.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum RampMode {
+    /// Every iteration uses the recorded delays.
+    Off,
+    /// Delays are multiplied by the ramp value once per completed iteration.
+    Multiply,
+    /// Delays are reduced by the ramp value (in ms) once per completed iteration.
+    Subtract,
+}
+
+/// Options controlling how many times a replay runs and how its timing evolves.
+///
+/// The effective delay of a payload in iteration `i` (zero-based) is computed by
+/// first applying the ramp to the recorded delay (`delay * value^i` with a factor of at
+/// most 1, or `delay - value * i`) and then clamping the result to `floor_ms`. The floor
+/// never raises a delay that was recorded below it. Finally the delay is divided
+/// by `speed`.
+#[derive(Clone, Debug)]
+pub struct ReplayOptions {
+    pub loops: u32,
+    pub ramp_mode: RampMode,
+    pub ramp_value: f64,
+    pub floor_ms: u64,
+    /// Playback speed; 2.0 halves every delay.
+    pub speed: f64,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            loops: 1,
+            ramp_mode: RampMode::Off,
+            ramp_value: 1.0,
+            floor_ms: 0,
+            speed: 1.0,
+        }
+    }
+}
+
+impl ReplayOptions {
+    /// Returns the delay to wait before a payload with the given recorded delay in `iteration`.
+    pub fn effective_delay(&self, delay: u64, iteration: u32) -> u64 {
+        let ramped = match self.ramp_mode {
+            RampMode::Off => delay,
+            RampMode::Multiply => (delay as f64 * self.factor().powi(iteration as i32)) as u64,
+            RampMode::Subtract => delay.saturating_sub((self.ramp_value * iteration as f64) as u64),
+        };
+        let floored = ramped.max(self.floor_ms.min(delay));
+        if self.speed == 1.0 {
+            floored
+        } else {
+            (floored as f64 / self.speed) as u64
+        }
+    }
+
+    /// Multiply ramp factor, capped at 1 so a ramp never stretches delays. Configs edited
+    /// by hand may hold a larger value.
+    fn factor(&self) -> f64 {
+        self.ramp_value.min(1.0)
+    }
+
+    /// Describes the delay scaling in effect during `iteration`, e.g. "delays x0.64".
+    pub fn describe(&self, iteration: u32) -> String {
+        let ramp = match self.ramp_mode {
+            RampMode::Off => "recorded delays".to_string(),
+            RampMode::Multiply => format!("delays x{:.2}", self.factor().powi(iteration as i32)),
+            RampMode::Subtract => {
+                format!("delays -{} ms", (self.ramp_value * iteration as f64) as u64)
+            }
+        };
+        if self.speed == 1.0 {
+            ramp
+        } else {
+            format!("{} at {}x speed", ramp, self.speed)
+        }
+    }
+}
+
+/// Events emitted by a running replay task for its window.
+#[derive(Clone, Debug)]
+pub enum ReplayEvent {
+    /// Number of payloads sent so far in the current iteration.
+    Progress(usize),
+    /// A new iteration started (1-based) with the given delay scaling description.
+    Iteration(u32, String),
+    /// Data was received from the target.
+    Received(ReceivedChunk),
+    /// Statistics of a running stress run.
+    Stress(StressStats),
+    /// The replay connected, or bound its UDP socket.
+    Connected,
+    /// The replay could not connect; already logged, shown as a banner.
+    Failed(String),
+    /// The payload at the index was sent as the packet with this number in the log.
+    Sent(usize, u64),
+    /// The payload at the index was empty or did not decode, and was not sent.
+    Skipped(usize),
+    /// The payload at the index could not be sent, or used a token not captured yet.
+    SendFailed(usize),
+    /// The replay task ended.
+    Finished,
+}
+
+/// What became of a payload in the current iteration of a replay, shown in its row of the
+/// payload table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PayloadStatus {
+    #[default]
+    Pending,
+    /// Sent, as the packet with this number in the log if the event naming it arrived.
+    Sent(Option<u64>),
+    Failed,
+    Skipped,
+}
+
+/// Progress of a replay not taken by its window yet: the latest of each kind of update.
+#[derive(Clone, Debug, Default)]
+pub struct PendingProgress {
+    /// The iteration started, which resets the rest, with its delay scaling description.
+    pub iteration: Option<(u32, String)>,
+    /// Number of payloads sent so far in the iteration.
+    pub sent: Option<usize>,
+    /// What became of each payload reported, by index.
+    pub statuses: std::collections::BTreeMap<usize, PayloadStatus>,
+}
+
+impl PendingProgress {
+    /// Folds a progress update into the pending progress; other events are returned.
+    pub fn fold(&mut self, event: ReplayEvent) -> Result<(), ReplayEvent> {
+        match event {
+            ReplayEvent::Progress(sent) => self.sent = Some(sent),
+            ReplayEvent::Iteration(iteration, speed) => {
+                *self = Self {
+                    iteration: Some((iteration, speed)),
+                    ..Default::default()
+                }
+            }
+            ReplayEvent::Sent(index, seq) => {
+                self.statuses.insert(index, PayloadStatus::Sent(Some(seq)));
+            }
+            ReplayEvent::Skipped(index) => {
+                self.statuses.insert(index, PayloadStatus::Skipped);
+            }
+            ReplayEvent::SendFailed(index) => {
+                self.statuses.insert(index, PayloadStatus::Failed);
+            }
+            event => return Err(event),
+        }
+        Ok(())
+    }
+}
+
+impl PayloadStatus {
+    /// Glyph shown in front of the row.
+    pub fn glyph(self) -> &'static str {
+        match self {
+            PayloadStatus::Pending => "○",
+            PayloadStatus::Sent(_) => "✓",
+            PayloadStatus::Failed => "✗",
+            PayloadStatus::Skipped => "–",
+        }
+    }
+}
+
+/// Limits for a stress run; at least one must be set so the run cannot go on forever.
+#[derive(Clone, Debug)]
+pub struct StressLimit {
+    pub duration: Option<std::time::Duration>,
+    pub iterations: Option<u64>,
+}
+
+/// Counters collected during a stress run.
+#[derive(Clone, Debug, Default)]
+pub struct StressStats {
+    pub packets: u64,
+    pub bytes: u64,
+    pub errors: u64,
+    pub elapsed_ms: u64,
+    pub connection_lost: bool,
+}
+
+impl StressStats {
+    /// Formats the counters with rates, e.g. "1200 packets (400/s), 9600 B/s, 0 errors in 3.0 s".
+    pub fn describe(&self) -> String {
+        let secs = (self.elapsed_ms as f64 / 1000.0).max(0.001);
+        format!(
+            "{} packets ({:.0}/s), {:.0} B/s, {} errors in {:.1} s",
+            self.packets,
+            self.packets as f64 / secs,
+            self.bytes as f64 / secs,
+            self.errors,
+            secs
+        )
+    }
+}
+
+/// A chunk of data received from the target during a replay.
+#[derive(Clone, Debug)]
+pub struct ReceivedChunk {
+    /// Number of the read in its window, as shown in the log.
+    pub seq: u64,
+    pub timestamp: chrono::DateTime<chrono::Local>,
+    pub data: Vec<u8>,
+}
+
+/// File format used when exporting the data received during a replay.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ResponseFormat {
+    /// All received bytes concatenated into a single binary file.
+    Binary,
+    /// A JSON array with one record (timestamp, hex bytes) per read.
+    Json,
+    /// A replayable session sending the received data back, with the observed delays.
+    Replay,
+}
+
+/// Kind of window opened beside the main window.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WindowKind {
+    Session,
+    Replay,
+}
+
+/// What a session window exports.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExportKind {
+    Replay,
+    Logs,
+}
+
+/// Maximum number of received bytes retained per replay window for export.
+pub const MAX_RECEIVED_BYTES: usize = 16 * 1024 * 1024;
+
 /// Holds the state of an active session window, including user input, logs, connection status, and replay data.
 #[derive(Clone)]
 pub struct SessionData {
     pub payload_input: String,
-    pub log: String,
+    pub log: crate::log::Log,
     pub sender: Option<tokio::sync::mpsc::Sender<SessionCommand>>,
     pub connected: bool,
     pub payload_type: PayloadType,
     pub protocol: Protocol,
     pub replay_payloads: Vec<ReplayablePayload>,
     pub last_packet_time: Option<std::time::Instant>,
-    pub input_placeholder: String,
-    pub initial_payload: String,
-    pub initial_payload_type: PayloadType,
+    pub replay_progress: Option<(usize, usize)>,
+    pub replay_cancel: Option<tokio_util::sync::CancellationToken>,
+    pub address: String,
+    pub port: String,
+    pub handshake: Handshake,
+    pub repeat_count: String,
+    pub repeat_interval_ms: String,
+    pub repeat_progress: Option<(usize, usize)>,
+    pub repeat_cancel: Option<tokio_util::sync::CancellationToken>,
+    pub heartbeat_enabled: bool,
+    pub heartbeat_payload: String,
+    pub heartbeat_type: PayloadType,
+    pub heartbeat_interval_ms: String,
+    pub heartbeat_record: bool,
+    pub heartbeat_quiet: bool,
+    pub heartbeat_cancel: Option<tokio_util::sync::CancellationToken>,
+    pub idle_timeout_secs: String,
+    pub last_activity: Option<std::time::Instant>,
+    pub local_addr: Option<String>,
+    pub remote_addr: Option<String>,
+    pub writing: bool,
+    pub discard: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    /// Number of the last packet sent or received, kept across reconnects so packet
+    /// numbers in the log and transcript never repeat within the window.
+    pub packet_seq: std::sync::Arc<std::sync::atomic::AtomicU64>,
+    /// Hold payloads sent while disconnected and flush them on reconnect.
+    pub queue_offline: bool,
+    pub offline_queue: Vec<ReplayablePayload>,
+    /// Label attached to the next send, cleared once it is used.
+    pub send_label: String,
+    pub notes: EditorContent,
+    pub show_notes: bool,
+    /// Payloads sent one per line with "Send each line".
+    pub batch: EditorContent,
+    pub show_batch: bool,
+    pub batch_interval_ms: String,
+    /// Delay before a pressed Send goes out; empty or zero sends right away.
+    pub send_after_ms: String,
+    pub scheduled_send: Option<ScheduledSend>,
+    /// When the current connection came up.
+    pub connected_since: Option<std::time::Instant>,
+    /// Length of the last finished connection, shown frozen while disconnected.
+    pub last_connection: Option<std::time::Duration>,
+    /// Time spent connected over all finished connections of this window.
+    pub total_connected: std::time::Duration,
+    /// When the window first connected.
+    pub started_at: Option<chrono::DateTime<chrono::Local>>,
+    /// When the last connection ended; None while connected.
+    pub ended_at: Option<chrono::DateTime<chrono::Local>>,
+    /// Every packet sent and received, including those beyond the transcript limit.
+    pub traffic: TrafficCounters,
+    /// Export the recorded replay automatically when the window is closed.
+    pub auto_export: bool,
+    /// A session task was started and has not reported its connection state yet.
+    pub connecting: bool,
+    /// When the current connection attempt started, for the time shown while connecting.
+    pub connect_started: Option<std::time::Instant>,
+    /// Why the last connection attempt failed, shown with a Retry button until the next.
+    pub connect_error: Option<String>,
+    /// Packets sent and received, up to `MAX_TRANSCRIPT_PACKETS`.
+    pub packets: Vec<PacketRecord>,
+    pub packets_capped: bool,
+    /// Whether the recording inspector is expanded.
+    pub show_recording: bool,
+    /// Sent payloads are not added to the recording while paused.
+    pub recording_paused: bool,
+    /// The recording changed since the replay was last exported; closing asks first.
+    pub unexported: bool,
+    /// Split between the controls and the log, dragged by the user.
+    pub split: SessionSplit,
+    /// Files dragged over the window and not dropped yet, for the drop hint and to refuse
+    /// dropping several files at once.
+    pub files_hovered: usize,
+    /// The files being dropped were refused as a group, and the refusal was logged.
+    pub drop_refused: bool,
+}
+
+/// A payload waiting for its timer before being sent through the session.
+#[derive(Clone, Debug)]
+pub struct ScheduledSend {
+    pub payload: ReplayablePayload,
+    pub data: Vec<u8>,
+    pub fire_at: std::time::Instant,
+    pub cancel: tokio_util::sync::CancellationToken,
+}
+
+/// State of a multi-line text editor in a session window; cloning copies the text.
+#[derive(Default)]
+pub struct EditorContent(pub iced::widget::text_editor::Content);
+
+impl Clone for EditorContent {
+    fn clone(&self) -> Self {
+        Self(iced::widget::text_editor::Content::with_text(
+            &self.0.text(),
+        ))
+    }
+}
+
+impl EditorContent {
+    /// The text without trailing whitespace, or None if it is empty.
+    pub fn text(&self) -> Option<String> {
+        let text = self.0.text();
+        let text = text.trim_end();
+        (!text.is_empty()).then(|| text.to_string())
+    }
+}
+
+/// Size above which loading a dropped file into the payload input asks first.
+pub const DROP_CONFIRM_BYTES: u64 = 1024 * 1024;
+
+/// Maximum number of payloads held while a session is disconnected.
+pub const OFFLINE_QUEUE_CAP: usize = 100;
+
+/// Packets and bytes sent and received in a window, over all its connections.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct TrafficCounters {
+    pub sent_packets: u64,
+    pub sent_bytes: u64,
+    pub received_packets: u64,
+    pub received_bytes: u64,
+}
+
+impl TrafficCounters {
+    /// Adds `packet` to the counters of its direction.
+    pub fn count(&mut self, packet: &PacketRecord) {
+        match packet.direction {
+            Direction::Sent => {
+                self.sent_packets += 1;
+                self.sent_bytes += packet.data.len() as u64;
+            }
+            Direction::Received => {
+                self.received_packets += 1;
+                self.received_bytes += packet.data.len() as u64;
+            }
+        }
+    }
+}
+
+/// Formats a start or end time in the log header, or `-` if there is none.
+fn header_time(time: Option<chrono::DateTime<chrono::Local>>) -> String {
+    time.map_or_else(
+        || "-".to_string(),
+        |time| time.to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+    )
+}
+
+impl SessionData {
+    /// State of a new session window to `address`:`port`, connecting, with the defaults
+    /// from `config`. The caller attaches the command sender of the session task.
+    pub fn new(
+        protocol: Protocol,
+        address: String,
+        port: String,
+        config: &crate::config::Config,
+    ) -> Self {
+        Self {
+            payload_input: String::new(),
+            log: Default::default(),
+            sender: None,
+            connected: false,
+            payload_type: config.default_payload_type(protocol),
+            protocol,
+            replay_payloads: Vec::new(),
+            last_packet_time: None,
+            replay_progress: None,
+            replay_cancel: None,
+            address,
+            port,
+            handshake: Handshake::default(),
+            repeat_count: "10".to_string(),
+            repeat_interval_ms: "100".to_string(),
+            repeat_progress: None,
+            repeat_cancel: None,
+            heartbeat_enabled: false,
+            heartbeat_payload: String::new(),
+            heartbeat_type: PayloadType::Hex,
+            heartbeat_interval_ms: "5000".to_string(),
+            heartbeat_record: false,
+            heartbeat_quiet: false,
+            heartbeat_cancel: None,
+            idle_timeout_secs: config.idle_timeout_secs.to_string(),
+            last_activity: None,
+            local_addr: None,
+            remote_addr: None,
+            writing: false,
+            discard: Default::default(),
+            packet_seq: Default::default(),
+            queue_offline: false,
+            offline_queue: Vec::new(),
+            send_label: String::new(),
+            notes: Default::default(),
+            show_notes: false,
+            batch: Default::default(),
+            show_batch: false,
+            batch_interval_ms: "100".to_string(),
+            send_after_ms: String::new(),
+            scheduled_send: None,
+            connected_since: None,
+            last_connection: None,
+            total_connected: std::time::Duration::ZERO,
+            started_at: None,
+            ended_at: None,
+            traffic: Default::default(),
+            auto_export: config.auto_export_on_close,
+            connecting: true,
+            connect_started: Some(std::time::Instant::now()),
+            connect_error: None,
+            packets: Vec::new(),
+            packets_capped: false,
+            show_recording: false,
+            recording_paused: false,
+            unexported: false,
+            split: SessionSplit::new(config.session_split),
+            files_hovered: 0,
+            drop_refused: false,
+        }
+    }
+
+    /// Lines of the log header describing the session, see `Window::log_header`.
+    pub fn log_header(&self) -> String {
+        let mut header = format!(
+            "# Protocol: {:?}\n# Target: {}:{}\n# Local: {}\n# Remote: {}\n\
+             # Started: {}\n# Ended: {}\n# Connected: {} total\n\
+             # Packets: {} sent, {} received, {} recorded\n# Bytes: {} sent, {} received\n",
+            self.protocol,
+            self.address,
+            self.port,
+            self.local_addr.as_deref().unwrap_or("-"),
+            self.remote_addr.as_deref().unwrap_or("-"),
+            header_time(self.started_at),
+            header_time(self.ended_at),
+            crate::log::format_duration(self.cumulative_connected()),
+            self.traffic.sent_packets,
+            self.traffic.received_packets,
+            self.replay_payloads.len(),
+            self.traffic.sent_bytes,
+            self.traffic.received_bytes,
+        );
+        if let Some(notes) = self.notes.text() {
+            header.push_str("# Notes:\n");
+            for line in notes.lines() {
+                header.push_str("#   ");
+                header.push_str(line);
+                header.push('\n');
+            }
+        }
+        header
+    }
+
+    /// Serializes the packets as JSON lines: a header object, then one object per packet.
+    pub fn transcript(&self) -> Result<String, String> {
+        let header = TranscriptHeader {
+            version: 1,
+            protocol: self.protocol,
+            target: format!("{}:{}", self.address, self.port),
+            local: self.local_addr.as_deref(),
+            remote: self.remote_addr.as_deref(),
+            packets: self.packets.len(),
+        };
+        let mut out = serde_json::to_string(&header).map_err(|e| e.to_string())?;
+        out.push('\n');
+        for packet in &self.packets {
+            let line = TranscriptLine {
+                seq: packet.seq,
+                direction: packet.direction,
+                timestamp: packet
+                    .timestamp
+                    .to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+                length: packet.data.len(),
+                hex: hex::encode(&packet.data),
+                ascii: packet
+                    .data
+                    .iter()
+                    .map(|&b| {
+                        if b.is_ascii_graphic() || b == b' ' {
+                            b as char
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect(),
+            };
+            out.push_str(&serde_json::to_string(&line).map_err(|e| e.to_string())?);
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    /// Number of sends queued for the session task plus the one being written, if any,
+    /// not counting queued sends already marked for discarding.
+    pub fn pending_sends(&self) -> usize {
+        let queued = self
+            .sender
+            .as_ref()
+            .map_or(0, |sender| sender.max_capacity() - sender.capacity());
+        let discarded = self.discard.load(std::sync::atomic::Ordering::Relaxed);
+        queued.saturating_sub(discarded) + usize::from(self.writing)
+    }
+
+    /// Window title built from the session's own protocol, target, and connection state,
+    /// e.g. "Tcp session 10.0.0.7:8080 (connected)". A user label is prepended by `App::title`.
+    pub fn title(&self) -> String {
+        format!(
+            "{:?} session {}:{} ({})",
+            self.protocol,
+            self.address,
+            self.port,
+            if self.connected {
+                "connected"
+            } else if self.connecting {
+                "connecting"
+            } else {
+                "disconnected"
+            }
+        )
+    }
+
+    /// Length of the current connection, or of the last one while disconnected.
+    pub fn connection_duration(&self) -> Option<std::time::Duration> {
+        self.connected_since
+            .map(|since| since.elapsed())
+            .or(self.last_connection)
+    }
+
+    /// Time spent connected across all connections, including the current one.
+    pub fn cumulative_connected(&self) -> std::time::Duration {
+        self.total_connected
+            + self
+                .connected_since
+                .map_or_else(Default::default, |s| s.elapsed())
+    }
+
+    /// Time left until the scheduled send fires, if one is pending.
+    pub fn scheduled_remaining(&self) -> Option<std::time::Duration> {
+        self.scheduled_send.as_ref().map(|scheduled| {
+            scheduled
+                .fire_at
+                .saturating_duration_since(std::time::Instant::now())
+        })
+    }
+
+    /// Builds the options for a new TCP session task of this window.
+    pub fn tcp_options(&self, stall_warning: std::time::Duration) -> TcpSessionOptions {
+        self.discard.store(0, std::sync::atomic::Ordering::Relaxed);
+        TcpSessionOptions {
+            handshake: self.handshake.clone(),
+            idle_timeout: self.idle_timeout(),
+            stall_warning,
+            discard: self.discard.clone(),
+            packet_seq: self.packet_seq.clone(),
+        }
+    }
+
+    /// Parses the idle timeout field; empty or zero disables the timeout.
+    pub fn idle_timeout(&self) -> Option<std::time::Duration> {
+        parse_idle_timeout(&self.idle_timeout_secs)
+    }
+
+    /// Seconds left before the idle timeout disconnects a connected TCP session.
+    pub fn idle_remaining(&self) -> Option<u64> {
+        if self.protocol != Protocol::Tcp {
+            return None;
+        }
+        let timeout = self.idle_timeout()?;
+        let last = self.last_activity.filter(|_| self.connected)?;
+        Some(timeout.saturating_sub(last.elapsed()).as_secs())
+    }
+
+    /// Builds the replayable session for export from the recorded payloads.
+    pub fn to_replayable_session(&self) -> ReplayableSession {
+        ReplayableSession {
+            protocol: self.protocol,
+            payloads: self.replay_payloads.clone(),
+            tokens: Vec::new(),
+            target: Some(format!("{}:{}", self.address, self.port)),
+            notes: self.notes.text(),
+        }
+    }
+
+    /// Removes the most recent recorded payload. The clock for the next recorded delay is
+    /// rewound by the removed delay, so the next payload's delay covers the time since the
+    /// previous kept payload.
+    pub fn undo_last_recorded(&mut self) -> Option<ReplayablePayload> {
+        let removed = self.replay_payloads.pop()?;
+        self.unexported = true;
+        self.last_packet_time = if self.replay_payloads.is_empty() {
+            None
+        } else {
+            self.last_packet_time
+                .and_then(|last| last.checked_sub(std::time::Duration::from_millis(removed.delay)))
+        };
+        Some(removed)
+    }
+
+    /// Removes a recorded payload, adding its delay to the following one so later payloads
+    /// keep their timing. Removing the newest payload behaves like `undo_last_recorded`.
+    pub fn remove_recorded(&mut self, index: usize) -> Option<ReplayablePayload> {
+        if index + 1 == self.replay_payloads.len() {
+            return self.undo_last_recorded();
+        }
+        if index >= self.replay_payloads.len() {
+            return None;
+        }
+        let removed = self.replay_payloads.remove(index);
+        self.replay_payloads[index].delay += removed.delay;
+        self.unexported = true;
+        Some(removed)
+    }
+
+    /// Number of recorded payloads and their total decoded size in bytes.
+    pub fn recording_summary(&self) -> (usize, usize) {
+        let bytes = self
+            .replay_payloads
+            .iter()
+            .filter_map(|p| p.get_payload().ok())
+            .map(|data| data.len())
+            .sum();
+        (self.replay_payloads.len(), bytes)
+    }
+
+    /// Records a payload for replay without sending it, using `delay` if given and the time
+    /// since the previous payload otherwise.
+    pub fn record_only(&mut self, payload: ReplayablePayload, delay: Option<u64>) {
+        self.record_payload(payload);
+        if let Some(recorded) = self.replay_payloads.last_mut() {
+            recorded.recorded_only = true;
+            if let Some(delay) = delay {
+                recorded.delay = delay;
+            }
+        }
+    }
+
+    /// Holds a payload until the session reconnects, logging whether it was queued.
+    pub fn queue_offline_payload(&mut self, payload: ReplayablePayload) {
+        let entry = if self.offline_queue.len() >= OFFLINE_QUEUE_CAP {
+            crate::log::LogEntry::new(
+                crate::log::LogLevel::Warn,
+                &format!("Queue full ({}), payload not queued", OFFLINE_QUEUE_CAP),
+            )
+        } else {
+            self.offline_queue.push(payload);
+            crate::log::LogEntry::new(
+                crate::log::LogLevel::Info,
+                &format!(
+                    "Disconnected, queued payload ({} queued)",
+                    self.offline_queue.len()
+                ),
+            )
+        };
+        self.log.push_entry(entry);
+    }
+
+    /// Takes the label typed for the next send, if any.
+    pub fn take_send_label(&mut self) -> Option<String> {
+        let label = std::mem::take(&mut self.send_label);
+        let label = label.trim();
+        (!label.is_empty()).then(|| label.to_string())
+    }
+
+    /// Records a sent payload for replay, measuring the delay since the previous one.
+    /// While recording is paused only the clock advances, so the first payload recorded after
+    /// resuming is delayed by the time since the last packet actually sent.
+    pub fn record_payload(&mut self, payload: ReplayablePayload) {
+        let now = std::time::Instant::now();
+        let delay = self
+            .last_packet_time
+            .map(|last| now.duration_since(last).as_millis() as u64)
+            .unwrap_or(0);
+        if !self.recording_paused || payload.recorded_only {
+            self.replay_payloads
+                .push(ReplayablePayload { delay, ..payload });
+            self.unexported = true;
+        }
+        self.last_packet_time = Some(now);
+    }
+}
+
+/// Parses multi-line input into one payload per non-empty line, each but the first delayed by
+/// `interval_ms`. Fails on the first invalid line, naming its line number.
+pub fn parse_lines(
+    text: &str,
+    payload_type: PayloadType,
+    interval_ms: u64,
+) -> Result<Vec<ReplayablePayload>, String> {
+    let mut payloads = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let payload = ReplayablePayload {
+            payload: line.to_string(),
+            payload_type,
+            delay: if payloads.is_empty() { 0 } else { interval_ms },
+            recorded_only: false,
+            label: None,
+        };
+        if payload.get_payload().is_err() {
+            return Err(format!(
+                "Line {}: invalid {:?} payload",
+                index + 1,
+                payload_type
+            ));
+        }
+        payloads.push(payload);
+    }
+    if payloads.is_empty() {
+        return Err("No lines to send".to_string());
+    }
+    Ok(payloads)
+}
+
+/// Parses an idle timeout in seconds; empty, zero, or invalid input means no timeout.
+pub fn parse_idle_timeout(secs: &str) -> Option<std::time::Duration> {
+    secs.trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|secs| *secs > 0)
+        .map(std::time::Duration::from_secs)
+}
+
+/// Run state of a replay window, shown in its title.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplayStatus {
+    Running,
+    Stopped,
+    Finished,
 }
 
 /// Holds the state of a replay session window, including logs, payloads, and progress tracking.
 #[derive(Clone)]
 pub struct ReplayData {
-    pub log: String,
+    pub log: crate::log::Log,
     pub payloads: Vec<ReplayablePayload>,
+    pub tokens: Vec<crate::tokens::TokenRule>,
+    /// Target stored in the loaded file, kept when the replay is saved again.
+    pub recorded_target: Option<String>,
+    /// Notes stored in the loaded file, shown in the window and kept when saved again.
+    pub notes: Option<String>,
     pub connected: bool,
     pub file_name: String,
+    pub path: Option<std::path::PathBuf>,
+    pub dirty: bool,
     pub current_index: usize,
+    pub loops: u32,
+    pub iteration: u32,
+    pub speed: String,
+    pub protocol: Protocol,
+    pub received: Vec<ReceivedChunk>,
+    pub received_bytes: usize,
+    pub received_capped: bool,
+    /// Number of the last packet sent or received, kept across runs of the replay.
+    pub packet_seq: std::sync::Arc<std::sync::atomic::AtomicU64>,
+    pub response_format: ResponseFormat,
+    pub validation: Vec<crate::validate::PayloadReport>,
+    pub show_validation: bool,
+    pub address: String,
+    pub port: String,
+    pub options: ReplayOptions,
+    pub cancel: Option<tokio_util::sync::CancellationToken>,
+    pub stress: bool,
+    pub stress_duration_secs: String,
+    pub stress_iterations: String,
+    pub stress_stats: Option<StressStats>,
+    pub status: ReplayStatus,
+    /// Why the last run could not connect.
+    pub failure: Option<String>,
+    /// When the last run started.
+    pub started_at: Option<chrono::DateTime<chrono::Local>>,
+    /// When the last run ended; None while running.
+    pub ended_at: Option<chrono::DateTime<chrono::Local>>,
+    /// Whether the payload table is expanded.
+    pub show_payloads: bool,
+    /// Status of each payload in the current iteration, by index.
+    pub payload_status: Vec<PayloadStatus>,
+    /// Vertical scroll offset of the payload table, to lay out the rows near it.
+    pub payloads_offset: f32,
+    /// Scroll the payload table along with the payload being sent.
+    pub follow_payload: bool,
+}
+
+impl ReplayData {
+    /// Sets the status of the payload at `index`.
+    pub fn set_payload_status(&mut self, index: usize, status: PayloadStatus) {
+        if self.payload_status.len() <= index {
+            self.payload_status
+                .resize(index + 1, PayloadStatus::Pending);
+        }
+        self.payload_status[index] = status;
+    }
+
+    /// Applies progress taken from the channel, or a single update.
+    pub fn apply_progress(&mut self, progress: PendingProgress) {
+        if let Some((iteration, speed)) = progress.iteration {
+            self.iteration = iteration;
+            self.speed = speed;
+            self.current_index = 0;
+            self.payload_status.clear();
+        }
+        if let Some(sent) = progress.sent {
+            self.current_index = sent;
+        }
+        for (index, status) in progress.statuses {
+            self.set_payload_status(index, status);
+        }
+        self.mark_sent_before_current();
+    }
+
+    /// Marks the payloads before the current one that are still pending as sent. With
+    /// progress coalesced, the count can run ahead of the events naming each payload.
+    pub fn mark_sent_before_current(&mut self) {
+        for index in 0..self.current_index.min(self.payloads.len()) {
+            if self.payload_status.get(index).copied().unwrap_or_default() == PayloadStatus::Pending
+            {
+                self.set_payload_status(index, PayloadStatus::Sent(None));
+            }
+        }
+    }
+
+    /// Builds the window title from the file name, target, and run state,
+    /// e.g. "Replay device-a.json → 10.0.0.7:8080 — 58% (23/40)".
+    pub fn title(&self) -> String {
+        let total = self.payloads.len();
+        let percent = (self.current_index * 100).checked_div(total).unwrap_or(100);
+        let progress = if self.stress {
+            let packets = self.stress_stats.as_ref().map_or(0, |stats| stats.packets);
+            format!("stress, {} packets", packets)
+        } else if self.loops > 1 {
+            format!(
+                "{}% ({}/{}), loop {}/{}",
+                percent, self.current_index, total, self.iteration, self.loops
+            )
+        } else {
+            format!("{}% ({}/{})", percent, self.current_index, total)
+        };
+        let state = match self.status {
+            ReplayStatus::Running => progress,
+            ReplayStatus::Stopped => format!("stopped at {}", progress),
+            ReplayStatus::Finished => format!("finished, {}", progress),
+        };
+        format!(
+            "Replay {} → {:?} {}:{} — {}",
+            self.file_name, self.protocol, self.address, self.port, state
+        )
+    }
+
+    /// Parses the stress duration and iteration fields, requiring at least one limit.
+    pub fn stress_limit(&self) -> Result<StressLimit, String> {
+        let duration = match self.stress_duration_secs.trim() {
+            "" => None,
+            secs => Some(
+                secs.parse::<f64>()
+                    .ok()
+                    .filter(|secs| secs.is_finite() && *secs > 0.0)
+                    .map(std::time::Duration::from_secs_f64)
+                    .ok_or_else(|| format!("Invalid stress duration: {}", secs))?,
+            ),
+        };
+        let iterations = match self.stress_iterations.trim() {
+            "" => None,
+            count => Some(
+                count
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|count| *count > 0)
+                    .ok_or_else(|| format!("Invalid stress iterations: {}", count))?,
+            ),
+        };
+        if duration.is_none() && iterations.is_none() {
+            return Err("Stress mode needs a duration or iteration limit".to_string());
+        }
+        Ok(StressLimit {
+            duration,
+            iterations,
+        })
+    }
+
+    /// Builds the replayable session from the in-memory payloads, including any edits.
+    pub fn to_replayable_session(&self) -> ReplayableSession {
+        ReplayableSession {
+            protocol: self.protocol,
+            payloads: self.payloads.clone(),
+            tokens: self.tokens.clone(),
+            target: self.recorded_target.clone(),
+            notes: self.notes.clone(),
+        }
+    }
+
+    /// Header written at the top of exported logs, with the target and progress.
+    pub fn log_header(&self) -> String {
+        let mut header = format!(
+            "# Protocol: {:?}\n# Target: {}:{}\n# File: {}\n# Started: {}\n# Ended: {}\n\
+             # Payloads: {}/{} sent, loop {}/{}\n# Received: {} bytes\n",
+            self.protocol,
+            self.address,
+            self.port,
+            self.file_name,
+            header_time(self.started_at),
+            header_time(self.ended_at),
+            self.current_index,
+            self.payloads.len(),
+            self.iteration,
+            self.loops,
+            self.received_bytes,
+        );
+        if let Some(stats) = &self.stress_stats {
+            header.push_str(&format!("# Stress: {}\n", stats.describe()));
+        }
+        header
+    }
+
+    /// Retains a received chunk for export, returning false once the size cap is reached.
+    pub fn push_received(&mut self, chunk: ReceivedChunk) -> bool {
+        if self.received_bytes + chunk.data.len() > MAX_RECEIVED_BYTES {
+            return false;
+        }
+        self.received_bytes += chunk.data.len();
+        self.received.push(chunk);
+        true
+    }
+
+    /// Serializes the received data in the selected response format.
+    pub fn export_responses(&self) -> Result<Vec<u8>, serde_json::Error> {
+        match self.response_format {
+            ResponseFormat::Binary => Ok(self
+                .received
+                .iter()
+                .flat_map(|chunk| chunk.data.iter().copied())
+                .collect()),
+            ResponseFormat::Json => {
+                let records: Vec<serde_json::Value> = self
+                    .received
+                    .iter()
+                    .map(|chunk| {
+                        serde_json::json!({
+                            "seq": chunk.seq,
+                            "timestamp": chunk
+                                .timestamp
+                                .to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+                            "bytes": hex::encode(&chunk.data),
+                        })
+                    })
+                    .collect();
+                serde_json::to_vec_pretty(&records)
+            }
+            ResponseFormat::Replay => {
+                let mut previous: Option<chrono::DateTime<chrono::Local>> = None;
+                let payloads = self
+                    .received
+                    .iter()
+                    .map(|chunk| {
+                        let delay = previous
+                            .map(|prev| (chunk.timestamp - prev).num_milliseconds().max(0) as u64)
+                            .unwrap_or(0);
+                        previous = Some(chunk.timestamp);
+                        ReplayablePayload {
+                            payload: hex::encode(&chunk.data),
+                            payload_type: PayloadType::Hex,
+                            delay,
+                            recorded_only: false,
+                            label: None,
+                        }
+                    })
+                    .collect();
+                serde_json::to_vec_pretty(&ReplayableSession {
+                    protocol: self.protocol,
+                    payloads,
+                    tokens: Vec::new(),
+                    target: Some(format!("{}:{}", self.address, self.port)),
+                    notes: self.notes.clone(),
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    #[test]
+    fn pending_progress_keeps_the_latest_updates_of_the_current_iteration() {
+        let mut progress = PendingProgress::default();
+        for event in [
+            ReplayEvent::Progress(1),
+            ReplayEvent::Sent(0, 7),
+            ReplayEvent::Iteration(2, "x1".to_string()),
+            ReplayEvent::Progress(1),
+            ReplayEvent::Sent(0, 8),
+            ReplayEvent::Skipped(1),
+            ReplayEvent::Progress(3),
+            ReplayEvent::SendFailed(2),
+        ] {
+            assert!(progress.fold(event).is_ok());
+        }
+        assert!(progress.fold(ReplayEvent::Connected).is_err());
+        assert_eq!(progress.iteration, Some((2, "x1".to_string())));
+        assert_eq!(progress.sent, Some(3));
+        assert_eq!(
+            progress.statuses.into_iter().collect::<Vec<_>>(),
+            [
+                (0, PayloadStatus::Sent(Some(8))),
+                (1, PayloadStatus::Skipped),
+                (2, PayloadStatus::Failed)
+            ]
+        );
+    }
+
+    #[test]
+    fn transcript_has_header_then_one_line_per_packet() {
+        let mut session = SessionData::new(
+            Protocol::Tcp,
+            "10.0.0.7".to_string(),
+            "8080".to_string(),
+            &crate::config::Config::default(),
+        );
+        session.local_addr = Some("10.0.0.2:51512".to_string());
+        session.remote_addr = Some("10.0.0.7:8080".to_string());
+        let at = |time: &str| {
+            chrono::DateTime::parse_from_rfc3339(time)
+                .unwrap()
+                .with_timezone(&chrono::Local)
+        };
+        session.packets = vec![
+            PacketRecord {
+                seq: 1,
+                direction: Direction::Sent,
+                timestamp: at("2026-01-05T14:03:11.482+01:00"),
+                data: b"hello".to_vec(),
+            },
+            PacketRecord {
+                seq: 2,
+                direction: Direction::Received,
+                timestamp: at("2026-01-05T14:03:11.490+01:00"),
+                data: vec![b'o', b'k', 0x00, 0x7f],
+            },
+        ];
+        let transcript = session.transcript().unwrap();
+        let lines: Vec<Value> = transcript
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            json!({
+                "version": 1,
+                "protocol": "Tcp",
+                "target": "10.0.0.7:8080",
+                "local": "10.0.0.2:51512",
+                "remote": "10.0.0.7:8080",
+                "packets": 2,
+            })
+        );
+        let timestamp = |line: &Value| {
+            chrono::DateTime::parse_from_rfc3339(line["timestamp"].as_str().unwrap()).unwrap()
+        };
+        let tx = &lines[1];
+        assert_eq!(tx["seq"], 1);
+        assert_eq!(tx["direction"], "sent");
+        assert_eq!(timestamp(tx), at("2026-01-05T14:03:11.482+01:00"));
+        assert_eq!(tx["length"], 5);
+        assert_eq!(tx["hex"], "68656c6c6f");
+        assert_eq!(tx["ascii"], "hello");
+        let rx = &lines[2];
+        assert_eq!(rx["seq"], 2);
+        assert_eq!(rx["direction"], "received");
+        assert_eq!(timestamp(rx), at("2026-01-05T14:03:11.490+01:00"));
+        assert_eq!(rx["length"], 4);
+        assert_eq!(rx["hex"], "6f6b007f");
+        assert_eq!(rx["ascii"], "ok..");
+    }
+
+    #[test]
+    fn delay_applies_ramp_then_floor_then_speed() {
+        let options = ReplayOptions {
+            loops: 3,
+            ramp_mode: RampMode::Multiply,
+            ramp_value: 0.5,
+            floor_ms: 300,
+            speed: 2.0,
+        };
+        assert_eq!(options.effective_delay(1000, 0), 500);
+        assert_eq!(options.effective_delay(1000, 1), 250);
+        // 1000 * 0.25 = 250 is raised to the floor before the speed halves it; dividing
+        // first would give 125 and floor it to 300
+        assert_eq!(options.effective_delay(1000, 2), 150);
+        // The floor never raises a delay recorded below it
+        assert_eq!(options.effective_delay(100, 2), 50);
+    }
+
+    #[test]
+    fn subtract_ramp_stops_at_floor() {
+        let options = ReplayOptions {
+            loops: 5,
+            ramp_mode: RampMode::Subtract,
+            ramp_value: 400.0,
+            floor_ms: 100,
+            speed: 1.0,
+        };
+        assert_eq!(options.effective_delay(1000, 1), 600);
+        assert_eq!(options.effective_delay(1000, 3), 100);
+    }
+
+    #[test]
+    fn multiply_factor_above_one_keeps_recorded_delays() {
+        let options = ReplayOptions {
+            loops: 40,
+            ramp_mode: RampMode::Multiply,
+            ramp_value: 50.0,
+            ..Default::default()
+        };
+        assert_eq!(options.effective_delay(1000, 30), 1000);
+        assert_eq!(options.describe(30), "delays x1.00");
+    }
 }
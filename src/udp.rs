@@ -1,51 +1,126 @@
-use crate::log::{log, LogLevel};
-use crate::types::PayloadType;
+use crate::log::{
+    CONNECTION_SENDER, LogEntry, LogLevel, format_duration, log, log_entry, main_log,
+};
+use crate::session::report_packet;
+use crate::types::{Direction, PayloadType, SessionCommand, SessionEvent};
 use iced::window;
 use tokio::net::UdpSocket;
 
-/// Sends a UDP packet to the specified address and port, logging the result.
-/// Since UDP is connectionless, this function binds to a local socket and sends the data.
-pub async fn send_udp_packet(
-    data: Vec<u8>,
+/// Handles a UDP session asynchronously: binds one local socket for the lifetime of the
+/// window, sends packets received over the command channel to the target, and logs every
+/// datagram that arrives on the socket. The socket is released when the task ends.
+pub async fn udp_task(
+    mut rx: tokio::sync::mpsc::Receiver<SessionCommand>,
     addr: String,
     port: String,
+    packet_seq: std::sync::Arc<std::sync::atomic::AtomicU64>,
     window_id: window::Id,
-    payload_type: PayloadType,
 ) {
-    // Prepare the target address and bind a local UDP socket
     let addr_full = format!("{}:{}", addr, port);
+    if let Some(resolved) = crate::session::resolved(&addr_full).await {
+        log(LogLevel::Debug, window_id, &resolved).await;
+    }
     let socket = match UdpSocket::bind("0.0.0.0:0").await {
         Ok(s) => s,
         Err(e) => {
             log(
-                crate::log::LogLevel::Error,
+                LogLevel::Error,
                 window_id,
                 &format!("UDP bind failed: {}", e),
             )
             .await;
+            let _ = CONNECTION_SENDER.send((
+                window_id,
+                SessionEvent::ConnectFailed(format!("bind failed: {}", e)),
+            ));
+            let _ = CONNECTION_SENDER.send((window_id, SessionEvent::Connection(false)));
             return;
         }
     };
-    // Attempt to send the packet and log the result
-    if let Err(e) = socket.send_to(&data, &addr_full).await {
-        log(
-            LogLevel::Error,
-            window_id,
-            &format!("UDP send failed: {}", e),
-        )
-        .await;
-    } else {
-        log(
-            LogLevel::Info,
-            window_id,
-            &format!(
-                "Sent: {}",
-                match payload_type {
-                    PayloadType::Hex => hex::encode(&data),
-                    PayloadType::Ascii => String::from_utf8_lossy(&data).to_string(),
+    let local = socket
+        .local_addr()
+        .map(|a| a.to_string())
+        .unwrap_or_default();
+    log(
+        LogLevel::Info,
+        window_id,
+        &format!("Bound to {}, sending to {}", local, addr_full),
+    )
+    .await;
+    let bound_at = std::time::Instant::now();
+    main_log(
+        LogLevel::Info,
+        &format!("UDP session started: {}", addr_full),
+    )
+    .await;
+    let _ = CONNECTION_SENDER.send((window_id, SessionEvent::Connection(true)));
+    let _ = CONNECTION_SENDER.send((
+        window_id,
+        SessionEvent::Addresses {
+            local: local.clone(),
+            remote: addr_full.clone(),
+        },
+    ));
+
+    // Main event loop: handle commands and incoming datagrams
+    let mut buf = [0; 65536];
+    loop {
+        tokio::select! {
+            // Handle incoming commands from the UI
+            cmd = rx.recv() => {
+                match cmd {
+                    Some(SessionCommand::SendPacket(data, payload_type, label)) => {
+                        match socket.send_to(&data, &addr_full).await {
+                            Ok(n) => log(LogLevel::Debug, window_id, &format!("send_to {} wrote {} of {} bytes", addr_full, n, data.len())).await,
+                            Err(e) => {
+                                log(LogLevel::Error, window_id, &format!("UDP send failed: {}", e)).await;
+                                continue;
+                            }
+                        }
+                        let seq = report_packet(window_id, &packet_seq, Direction::Sent, &data).await;
+                        log_entry(window_id, LogEntry::sent(seq, &data, label.as_deref(),
+                            &match payload_type {
+                                PayloadType::Hex => hex::encode(&data),
+                                PayloadType::Ascii => String::from_utf8_lossy(&data).to_string(),
+                            })).await;
+                    }
+                    Some(SessionCommand::Heartbeat(data, quiet)) => {
+                        if let Err(e) = socket.send_to(&data, &addr_full).await {
+                            log(LogLevel::Error, window_id, &format!("Heartbeat failed: {}", e)).await;
+                            continue;
+                        }
+                        let seq = report_packet(window_id, &packet_seq, Direction::Sent, &data).await;
+                        if !quiet {
+                            log_entry(window_id, LogEntry::packet(seq, Direction::Sent, &data, "heartbeat", &hex::encode(&data))).await;
+                        }
+                    }
+                    // UDP sessions have no connection to time out
+                    Some(SessionCommand::SetIdleTimeout(_)) => {}
+                    Some(SessionCommand::Disconnect) | None => break,
                 }
-            ),
-        )
-        .await;
+            }
+            // Handle datagrams from any source
+            received = socket.recv_from(&mut buf) => {
+                match received {
+                    Ok((n, from)) => {
+                        log(LogLevel::Debug, window_id, &format!("recv_from {} read {} bytes", from, n)).await;
+                        let seq = report_packet(window_id, &packet_seq, Direction::Received, &buf[..n]).await;
+                        log_entry(window_id, LogEntry::packet(seq, Direction::Received, &buf[..n], &format!("from {}", from), &hex::encode(&buf[..n]))).await;
+                    }
+                    // Errors such as ICMP port unreachable are reported but do not end the session
+                    Err(e) => log(LogLevel::Warn, window_id, &format!("Receive error: {}", e)).await,
+                }
+            }
+        }
     }
+    let _ = CONNECTION_SENDER.send((window_id, SessionEvent::Connection(false)));
+    main_log(
+        LogLevel::Info,
+        &format!(
+            "UDP session ended: {} (open for {})",
+            addr_full,
+            format_duration(bound_at.elapsed())
+        ),
+    )
+    .await;
 }
@@ -1,23 +1,27 @@
 // UI module for the replayr application using Iced framework.
 // Handles user interface rendering, event handling, and state management.
 
+use iced::Length;
 use iced::widget::{
-    button, column, container, radio, row, scrollable, text, text_input, tooltip, Space,
+    Space, button, checkbox, column, container, pane_grid, pick_list, radio, row, scrollable, text,
+    text_input, tooltip,
 };
-use iced::Length;
-use iced::{exit, Background, Color, Element, Task, Theme};
+use iced::{Background, Color, Element, Task, Theme, exit};
 
-use iced::window;
-use std::collections::HashMap;
 use crate::config::Config;
-use crate::log::LOG_SENDER;
+use crate::i18n::{tr, tr_args};
+use crate::tokens::{TokenKind, TokenRule, TokenRuleEdit, TokenSource};
 use crate::types::{
-    LogMessage, PayloadType, ReplayData, ReplayablePayload, ReplayableSession, SessionCommand,
-    SessionData, Window, WindowState,
+    ExportKind, Handshake, LogBundleEvent, PayloadType, Profile, RampMode, RecentReplay,
+    RecentTarget, ReplayData, ReplayEvent, ReplayOptions, ReplayStatus, ReplayablePayload,
+    ReplayableSession, ResponseFormat, SessionCommand, SessionData, SessionEvent, SessionPane,
+    ThemeChoice, Window, WindowKind, WindowState,
 };
-use tokio::sync::mpsc;
 use WindowState::Replay;
 use WindowState::Session;
+use iced::window;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
 
 /// Messages representing user interactions and system events in the UI.
 /// Each variant corresponds to a specific action or update in the application state.
@@ -27,12 +31,22 @@ pub enum Message {
     AddressChanged(window::Id, String),
     /// User changed the port in the main window.
     PortChanged(window::Id, String),
+    /// User picked a common service from the port dropdown.
+    ServicePicked(window::Id, crate::services::Service),
     /// User changed the initial payload in the main window.
     InitialPayloadChanged(window::Id, String),
     /// User changed the initial payload type in the main window.
     InitialPayloadTypeChanged(window::Id, PayloadType),
     /// User selected a different protocol (TCP/UDP).
     ProtocolChanged(window::Id, crate::types::Protocol),
+    /// User changed the replay loop count in the main window.
+    ReplayLoopsChanged(window::Id, String),
+    /// User changed the replay ramp mode in the main window.
+    RampModeChanged(window::Id, RampMode),
+    /// User changed the replay ramp value in the main window.
+    RampValueChanged(window::Id, String),
+    /// User changed the replay ramp floor in the main window.
+    RampFloorChanged(window::Id, String),
     /// User initiated a connection or session open.
     Connect(window::Id),
     /// A new window has been opened.
@@ -43,34 +57,441 @@ pub enum Message {
     PayloadTypeChanged(window::Id, PayloadType),
     /// User requested to send a packet.
     SendPacket(window::Id),
+    /// User changed how many times "Send repeatedly" sends the payload.
+    RepeatCountChanged(window::Id, String),
+    /// User changed the interval between repeated sends (ms).
+    RepeatIntervalChanged(window::Id, String),
+    /// Send the current payload repeatedly.
+    SendRepeatedly(window::Id),
+    /// Stop a running repeat-send.
+    StopRepeat(window::Id),
+    /// User added a handshake step.
+    HandshakeStepAdded(window::Id),
+    /// User removed the handshake step at the index.
+    HandshakeStepRemoved(window::Id, usize),
+    /// User edited the handshake step at the index.
+    HandshakeStepChanged(window::Id, usize, HandshakeStepEdit),
+    /// User toggled disconnecting when a handshake step fails.
+    HandshakeDisconnectToggled(window::Id, bool),
+    /// Show or hide the notes editor of a session.
+    NotesToggled(window::Id),
+    /// User edited the session notes.
+    NotesEdited(window::Id, iced::widget::text_editor::Action),
+    /// User toggled holding payloads sent while disconnected.
+    QueueOfflineToggled(window::Id, bool),
+    /// Discard the payloads held for reconnect.
+    ClearOfflineQueue(window::Id),
+    /// Drop the sends still queued for a session.
+    CancelPending(window::Id),
+    /// User toggled the default for exporting replays when session windows close.
+    AutoExportDefaultToggled(window::Id, bool),
+    /// User changed the directory replays are exported to on close.
+    AutoExportDirChanged(window::Id, String),
+    /// Pick the directory replays are exported to on close.
+    BrowseAutoExportDir(window::Id),
+    /// User toggled exporting this session's replay when its window closes.
+    AutoExportToggled(window::Id, bool),
+    /// Export a session's replay to the auto-export directory, then close its window.
+    ExportAndClose(window::Id),
+    /// A replay export from the save dialog finished, with the written path, or None if the
+    /// dialog was cancelled.
+    ReplayExported(window::Id, Result<Option<std::path::PathBuf>, String>),
+    /// Export the session's packets as a JSON lines transcript.
+    ExportTranscript(window::Id),
+    /// An automatic replay export finished, with the written path or an error.
+    AutoExported(Result<std::path::PathBuf, String>),
+    /// User changed the directory quick exports are written to.
+    ExportDirChanged(window::Id, String),
+    /// Pick the directory quick exports are written to.
+    BrowseExportDir(window::Id),
+    /// Export a session's replay or logs to the export directory without a dialog.
+    QuickExport(window::Id, ExportKind),
+    /// A quick export finished, with the written path or an error.
+    QuickExported(window::Id, ExportKind, Result<std::path::PathBuf, String>),
+    /// User toggled writing the logs of new windows to files in the export directory.
+    LogToFileDefaultToggled(window::Id, bool),
+    /// User toggled writing a window's log to a file.
+    LogToFileToggled(window::Id, bool),
+    /// The file to write a window's log to was chosen, or the dialog was cancelled.
+    LogFilePicked(window::Id, Option<std::path::PathBuf>),
+    /// Write buffered log file lines to disk.
+    FlushLogFiles,
+    /// User changed the send stall warning threshold (ms).
+    StallWarningChanged(window::Id, String),
+    /// User changed the default idle timeout for new sessions (seconds).
+    IdleTimeoutDefaultChanged(window::Id, String),
+    /// User changed the idle timeout of a session (seconds).
+    IdleTimeoutChanged(window::Id, String),
+    /// Show or hide the recording inspector of a session.
+    RecordingToggled(window::Id),
+    /// Apply an edit to a recorded payload.
+    RecordingChanged(window::Id, usize, RecordingEdit),
+    /// Pause or resume adding sent payloads to the recording.
+    RecordingPauseToggled(window::Id),
+    /// Remove the most recent payload from the recording.
+    UndoLastRecorded(window::Id),
+    /// Show or hide the batch editor of a session.
+    BatchToggled(window::Id),
+    /// User edited the batch lines.
+    BatchEdited(window::Id, iced::widget::text_editor::Action),
+    /// User changed the delay between batch lines (ms).
+    BatchIntervalChanged(window::Id, String),
+    /// Validate every batch line, then send them in order as separate packets.
+    SendBatch(window::Id),
+    /// User changed the label attached to the next send.
+    SendLabelChanged(window::Id, String),
+    /// Show or hide the payload table of a replay window.
+    PayloadsToggled(window::Id),
+    /// Scroll a replay window's payload table to the payload with this label.
+    JumpToLabel(window::Id, String),
+    /// User scrolled the payload table of a replay window.
+    PayloadsScrolled(window::Id, scrollable::Viewport),
+    /// User toggled scrolling the payload table along with the payload being sent.
+    PayloadFollowToggled(window::Id, bool),
+    /// User clicked a row of the payload table; the log scrolls to its packet.
+    PayloadRowPressed(window::Id, usize),
+    /// Validate the input and record it for replay without sending it.
+    RecordOnly(window::Id),
+    /// User changed the delay before a pressed Send goes out (ms).
+    SendAfterChanged(window::Id, String),
+    /// The timer of a scheduled send expired.
+    ScheduledSendFired(window::Id),
+    /// Cancel a scheduled send before it fires.
+    CancelScheduledSend(window::Id),
+    /// Periodic tick while a connection timer or countdown may be visible.
+    Tick,
+    /// User toggled the session heartbeat.
+    HeartbeatToggled(window::Id, bool),
+    /// User edited a heartbeat setting.
+    HeartbeatChanged(window::Id, HeartbeatEdit),
+    /// User asked to clear the log of a window.
+    ClearLog(window::Id),
+    /// Copy the visible lines of a window's log to the clipboard.
+    CopyLog(window::Id),
+    /// Clear the log of a window, leaving a marker line.
+    LogCleared(window::Id),
+    /// User showed or hid a level in the log of a window.
+    LogFilterChanged(window::Id, crate::log::LogLevel, bool),
+    /// A log view was scrolled or resized.
+    LogScrolled(window::Id, scrollable::Viewport),
+    /// User dragged the bar between the controls and the log of a session window.
+    SplitResized(window::Id, pane_grid::ResizeEvent),
+    /// User pressed the bar between the controls and the log of a session window.
+    SplitPressed(window::Id),
+    /// A file is dragged over a window.
+    FileHovered(window::Id),
+    /// Files dragged over a window left it without being dropped.
+    FilesHoveredLeft(window::Id),
+    /// A file was dropped on a window; session windows load it into the payload input.
+    FileDropped(window::Id, std::path::PathBuf),
+    /// A dropped file was read, or declined (None) when asked about its size. The flag
+    /// appends it to the payload input instead of replacing it.
+    DroppedFileRead(
+        window::Id,
+        std::path::PathBuf,
+        bool,
+        Result<Option<Vec<u8>>, String>,
+    ),
+    /// The cursor moved onto a row of a log view.
+    LogRowHovered(window::Id, usize),
+    /// The cursor left a row of a log view.
+    LogRowLeft(window::Id, usize),
+    /// User copied a log row, or the bytes of its packet as hex or ASCII.
+    CopyLogRow(String),
+    /// User zoomed the log text of a window with Ctrl+scroll, Ctrl+=, Ctrl+- or Ctrl+0.
+    LogZoomed(window::Id, crate::types::ZoomChange),
+    /// The keyboard modifiers held changed.
+    ModifiersChanged(iced::keyboard::Modifiers),
+    /// User turned the mouse wheel over a window, by this many lines or pixels up.
+    WheelScrolled(window::Id, f32),
+    /// User showed or hid the packet lines in the log of a window.
+    LogPacketsShown(window::Id, bool),
+    /// User typed in the main log's filter box.
+    MainLogSearchChanged(String),
+    /// User switched the main log filter between regex and literal text.
+    MainLogRegexToggled(bool),
+    /// User toggled showing session and replay log entries in the main log.
+    ForwardSessionLogsToggled(window::Id, bool),
+    /// User toggled logging socket-level detail as Debug entries.
+    DebugLogToggled(window::Id, bool),
+    /// Move session and replay log entries forwarded since the last batch to the main log.
+    ForwardedLogs,
+    /// User asked to rename a session or replay window.
+    RenameWindow(window::Id),
+    /// User edited the window name.
+    RenameInputChanged(window::Id, String),
+    /// User confirmed the new window name.
+    RenameConfirmed(window::Id),
+    /// User cancelled renaming the window.
+    RenameCancelled(window::Id),
+    /// User pressed Enter in the payload input.
+    SubmitPayload(window::Id),
+    /// User toggled clearing the payload input after a successful send.
+    ClearOnSendToggled(window::Id, bool),
+    /// User changed the payload type new session windows of a protocol start with.
+    DefaultPayloadTypeChanged(window::Id, crate::types::Protocol, PayloadType),
+    /// User toggled confirming exit while sessions are open.
+    ConfirmExitToggled(window::Id, bool),
+    /// A window was moved to the position.
+    WindowMoved(window::Id, iced::Point),
+    /// A window was resized.
+    WindowResized(window::Id, iced::Size),
+    /// User toggled cascading new session and replay windows.
+    CascadeWindowsToggled(window::Id, bool),
+    /// User toggled opening new session and replay windows as tabs of the main window.
+    TabbedWindowsToggled(window::Id, bool),
+    /// User toggled the tray icon.
+    TrayIconToggled(window::Id, bool),
+    /// User toggled hiding the main window to the tray when it is closed.
+    CloseToTrayToggled(window::Id, bool),
+    /// Shows the tray icon on start, once the event loop runs.
+    StartTray,
+    /// User chose an item of the tray menu.
+    #[cfg_attr(not(feature = "tray"), expect(dead_code))]
+    Tray(crate::tray::TrayAction),
+    /// User picked a tab of the main window; None is the main window's own view.
+    SelectTab(Option<window::Id>),
+    /// User switched to the next tab, or the previous one if false, with the keyboard.
+    CycleTab(window::Id, bool),
+    /// User picked the color theme.
+    ThemeChanged(window::Id, ThemeChoice),
+    /// User picked the language of the user interface.
+    LanguageChanged(window::Id, crate::i18n::Language),
+    /// User toggled the high-contrast theme.
+    HighContrastToggled(window::Id, bool),
+    /// User toggled reduced motion.
+    ReducedMotionToggled(window::Id, bool),
+    /// Export the whole configuration to a file.
+    ExportSettings(window::Id),
+    /// A settings export finished, with the written path (None if cancelled) or an error.
+    SettingsExported(Result<Option<std::path::PathBuf>, String>),
+    /// Import the configuration from a file, after confirming the changes.
+    ImportSettings(window::Id),
+    /// A settings import was confirmed (Some), cancelled (None), or failed.
+    SettingsImported(Result<Option<Box<crate::config::SettingsImport>>, String>),
+    /// The config file changed on disk.
+    ConfigFileChanged,
+    /// Write the config if it has not changed for a moment.
+    FlushConfig,
+    /// An operation of a window failed; the error is logged there and shown as a banner.
+    Failed(window::Id, String),
+    /// An export of a window finished: the file written, None if the dialog was cancelled,
+    /// or why it failed.
+    ExportFinished(window::Id, Result<Option<std::path::PathBuf>, String>),
+    /// User dismissed a banner of a window.
+    DismissBanner(window::Id, u64),
+    /// User dismissed every banner of a window.
+    DismissBanners(window::Id),
+    /// Timer to dismiss banners other than errors once they have shown long enough.
+    ExpireBanners,
+    /// User dismissed the banner about problems in the config file.
+    DismissConfigProblem,
+    /// User clicked "Switch config…" to load a different config file.
+    SwitchConfig(window::Id),
+    /// User picked a config file to switch to, or cancelled.
+    ConfigFilePicked(Option<std::path::PathBuf>),
+    /// User picked a config file from the recent configs list.
+    RecentConfigSelected(window::Id, String),
+    /// User changed the strftime pattern of log timestamps.
+    TimestampFormatChanged(window::Id, String),
+    /// User toggled logging timestamps in UTC.
+    TimestampUtcToggled(window::Id, bool),
+    /// User asked to exit, with Quit or by closing the main window; may prompt first.
+    Quit,
+    /// User confirmed exiting; true if they asked not to be asked again.
+    ExitConfirmed(bool),
+    /// Session tasks have shut down after a confirmed exit.
+    Exit,
     // CloseSession(window::Id), // Unused variant
+    /// User asked to close a window; may prompt before closing.
+    CloseRequested(window::Id),
     /// A window was closed.
     Closed(window::Id),
+    /// Close a session window during exit, exporting its replay first if true, then go on
+    /// checking the remaining windows.
+    ClosedForExit(window::Id, bool),
     /// Log message received for a specific window.
-    LogReceived(String, window::Id),
+    LogReceived(crate::log::LogEntry, window::Id),
     /// Log message for the main window.
-    MainLog(String),
+    MainLog(crate::log::LogEntry),
     /// Connection status update for a session.
     ConnectionStatus(window::Id, bool),
+    /// Event reported by a running session task.
+    SessionEvent(window::Id, SessionEvent),
     // TitleChanged(window::Id, String), // Unused variant
     /// Export session data for replay.
     ExportSession(window::Id),
+    /// Export session data as CSV.
+    ExportCsv(window::Id),
+    /// User picked a replay file to run through an existing session.
+    RunReplayHere(window::Id),
+    /// Replay file loaded for running inside a session window.
+    SessionReplayLoaded(window::Id, ReplayableSession),
+    /// Stop a replay running inside a session window.
+    StopSessionReplay(window::Id),
     /// Export logs to a file.
     ExportLogs(window::Id),
+    /// User clicked "Export all logs…" to write the main log and every window log.
+    ExportAllLogs(window::Id),
+    /// The directory to write the log bundle into was chosen, or the dialog was cancelled.
+    LogBundleDirPicked(Option<std::path::PathBuf>),
+    /// Progress of the log bundle being written.
+    LogBundle(LogBundleEvent),
+    /// Chose whether exported logs keep runs of repeated lines collapsed.
+    ExportLogsCollapsed(window::Id, bool),
+    /// Export the log entries of the levels in the filter to a file, collapsed or not.
+    ExportLogsFiltered(window::Id, crate::log::LogFilter, bool),
     /// User initiated replay connection by selecting a file.
     ReplayConnect,
     /// Replay window opened with loaded session data.
-    ReplayWindowOpenedWithFile(ReplayableSession, String),
-    /// Replay session has started.
-    ReplayStarted(window::Id),
-    /// Progress update during replay.
-    ReplayProgress(window::Id, usize),
+    /// The last element is the target to send to, if not the main window's.
+    ReplayWindowOpenedWithFile(
+        ReplayableSession,
+        std::path::PathBuf,
+        ReplayOptions,
+        Option<(String, u16)>,
+    ),
+    /// User clicked "Replay last" or picked a recent replay file.
+    ReplayRecent(RecentReplay),
+    /// A recent replay file could not be loaded and is removed from the list.
+    RecentReplayFailed(std::path::PathBuf, String),
+    /// Progress or iteration update during replay.
+    ReplayProgress(window::Id, ReplayEvent),
+    /// Open another session window with the same settings as this one.
+    DuplicateSession(window::Id),
+    /// Close the connection of a session window without closing the window.
+    Disconnect(window::Id),
+    /// Open a new connection for a session window to its original target.
+    Reconnect(window::Id),
+    /// Start a replay or stress run in a replay window.
+    StartReplay(window::Id),
+    /// Stop the running replay or stress run.
+    StopReplay(window::Id),
+    /// User toggled stress mode in a replay window.
+    StressToggled(window::Id, bool),
+    /// User changed the stress duration limit (seconds).
+    StressDurationChanged(window::Id, String),
+    /// User changed the stress iteration limit.
+    StressIterationsChanged(window::Id, String),
     /// Error occurred during replay.
     ReplayError(String),
+    /// User changed the export format for received replay data.
+    ResponseFormatChanged(window::Id, ResponseFormat),
+    /// Export the data received during a replay to a file.
+    ExportResponses(window::Id),
+    /// Validate the replay payloads offline and show the report.
+    ValidateReplay(window::Id),
+    /// Export the replay validation report to a text file.
+    ExportValidation(window::Id),
+    /// Add an empty token rule to a replay.
+    AddTokenRule(window::Id),
+    /// Remove the token rule at the given index from a replay.
+    RemoveTokenRule(window::Id, usize),
+    /// Edit the token rule at the given index of a replay.
+    TokenRuleChanged(window::Id, usize, TokenRuleEdit),
+    /// Save the replay back to the file it was loaded from.
+    SaveReplay(window::Id),
+    /// Save the replay to a newly chosen file.
+    SaveReplayAs(window::Id),
+    /// A replay save finished with the written path or an error.
+    ReplaySaved(window::Id, Result<std::path::PathBuf, String>),
+    /// User picked a target from the recent targets history.
+    RecentTargetSelected(window::Id, RecentTarget),
+    /// User removed the recent target at the index.
+    RecentTargetRemoved(window::Id, usize),
+    /// User cleared the recent targets history.
+    ClearRecentTargets(window::Id),
+    /// Show or hide the recent targets list in the main window.
+    RecentTargetsToggled(window::Id),
+    /// User expanded or collapsed the list of open windows in the main window.
+    DashboardToggled(window::Id),
+    /// User asked to bring a session or replay window to the front.
+    FocusWindow(window::Id),
+    /// User opened the command palette in a window with Ctrl+P.
+    OpenPalette(window::Id),
+    /// User typed in the command palette.
+    PaletteQueryChanged(String),
+    /// User moved the palette highlight down by this many actions, or up if negative.
+    PaletteMoved(isize),
+    /// User pressed Enter in the command palette to run the highlighted action.
+    PaletteSubmitted,
+    /// User clicked the palette action at this index of the matching actions.
+    PaletteRun(usize),
+    /// User dismissed the command palette.
+    ClosePalette,
+    /// User clicked a title of the menu bar of a window.
+    OpenMenu(window::Id, crate::menu::Menu),
+    /// The cursor moved over a title of the menu bar of a window.
+    MenuHovered(window::Id, crate::menu::Menu),
+    /// User chose an item of the open menu.
+    MenuChosen(Box<Message>),
+    /// User dismissed the open menu.
+    CloseMenu,
+    /// User asked for the list of keyboard shortcuts.
+    ShowShortcuts,
+    /// User asked for the version and license of the application.
+    ShowAbout,
     /// No operation (used for async task completion).
     NoOp,
 }
 
+/// Text of the main window's number fields, which may not parse while being typed. The
+/// config keeps the last valid value of each.
+#[derive(Clone, Default)]
+pub struct NumberInputs {
+    pub idle_timeout_secs: String,
+    pub send_stall_warn_ms: String,
+    pub replay_loops: String,
+    pub replay_ramp_value: String,
+    pub replay_ramp_floor_ms: String,
+}
+
+impl NumberInputs {
+    /// Fills the fields from the values in `config`.
+    pub fn new(config: &Config) -> Self {
+        Self {
+            idle_timeout_secs: config.idle_timeout_secs.to_string(),
+            send_stall_warn_ms: config.send_stall_warn_ms.to_string(),
+            replay_loops: config.replay_loops.to_string(),
+            replay_ramp_value: config.replay_ramp_value.to_string(),
+            replay_ramp_floor_ms: config.replay_ramp_floor_ms.to_string(),
+        }
+    }
+
+    /// Problems with the idle timeout and stall warning fields.
+    pub fn session_errors(&self) -> Vec<String> {
+        [
+            crate::validate::at_least::<u64>(&self.idle_timeout_secs, 0)
+                .err()
+                .map(|e| format!("Idle timeout: {}", e)),
+            crate::config::parse_stall_warn_ms(&self.send_stall_warn_ms)
+                .err()
+                .map(|e| format!("Stall warning: {}", e)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// Problems with the replay loop and ramp fields, checking the ramp value for `mode`.
+    pub fn replay_errors(&self, mode: RampMode) -> Vec<String> {
+        [
+            crate::config::parse_loops(&self.replay_loops)
+                .err()
+                .map(|e| format!("Replay loops: {}", e)),
+            crate::config::parse_ramp(&self.replay_ramp_value, mode)
+                .err()
+                .map(|e| format!("Ramp: {}", e)),
+            crate::validate::at_least::<u64>(&self.replay_ramp_floor_ms, 0)
+                .err()
+                .map(|e| format!("Ramp floor: {}", e)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
 /// Main application state holding configuration, logs, and window management.
 /// This struct is the central state for the entire UI application.
 #[derive(Clone)]
@@ -81,12 +502,62 @@ pub struct App {
     pub last_addr: String,
     /// Last used port for new sessions.
     pub last_port: String,
-    /// Last used initial payload for new sessions.
-    pub last_payload: String,
+    /// Last used handshake (initial payload and further steps) for new sessions.
+    pub last_handshake: Handshake,
+    /// Last used protocol for new sessions.
+    pub last_protocol: crate::types::Protocol,
+    /// Label for the next session window, set when duplicating a session.
+    pub last_label: Option<String>,
+    /// Payload input for the next session window, set when duplicating a session.
+    pub last_input: String,
     /// ID of the main configuration window.
     pub main_window_id: window::Id,
     /// Accumulated log messages for the main window.
-    pub main_log: String,
+    pub main_log: crate::log::Log,
+    /// Levels shown in the main window log.
+    pub main_log_filter: crate::log::LogFilter,
+    /// Text the main log is filtered by; empty shows every line.
+    pub main_log_search: crate::log::LogSearch,
+    /// Whether the main log filter text is a regex.
+    pub main_log_regex: bool,
+    pub main_log_scroll: crate::types::LogScroll,
+    pub main_log_zoom: crate::types::LogZoom,
+    /// Keyboard modifiers held, so Ctrl+scroll can zoom log text.
+    pub modifiers: iced::keyboard::Modifiers,
+    /// Session and replay windows shown as tabs of the main window, in the order opened.
+    /// They are kept in `windows` like the others, under ids no OS window has.
+    pub tabs: Vec<window::Id>,
+    /// Tab shown in the main window; None shows the main window's own view.
+    pub active_tab: Option<window::Id>,
+    /// The command palette, while open.
+    pub palette: Option<crate::palette::Palette>,
+    /// The drop-down menu open in a window's menu bar.
+    pub menu: Option<crate::menu::OpenMenu>,
+    /// Notices about failures shown at the top of the main window, also the fallback for
+    /// windows that are gone.
+    pub main_banners: crate::types::Banners,
+    /// Banner raised for the config file failing to save, dismissed once saving works again.
+    pub save_error_banner: Option<u64>,
+    /// Bytes written and in total while "Export all logs…" is running.
+    pub log_bundle_progress: Option<(u64, u64)>,
+    /// Whether the recent targets list is expanded in the main window.
+    pub show_recent_targets: bool,
+    /// Whether the main window is hidden to the tray.
+    pub main_hidden: bool,
+    /// Whether the list of open session and replay windows is expanded in the main window.
+    pub show_dashboard: bool,
+    /// Text of the port field, a number or a service name; `config.port` holds the
+    /// resolved number.
+    pub port_input: String,
+    /// Why `port_input` does not resolve to a port, shown next to the field.
+    pub port_error: Option<String>,
+    /// Text of the number fields in the main window.
+    pub number_inputs: NumberInputs,
+    /// Config files used recently, most recent first, offered by "Switch config…".
+    pub recent_configs: Vec<std::path::PathBuf>,
+    /// Summary of problems loading the config file, shown above the main window until
+    /// dismissed.
+    pub config_problem: Option<String>,
     /// Map of window IDs to their respective Window data.
     pub windows: HashMap<window::Id, Window>,
 }
@@ -98,9 +569,33 @@ impl Default for App {
             config: Config::default(),
             last_addr: String::new(),
             last_port: String::new(),
-            last_payload: String::new(),
+            last_handshake: Handshake::default(),
+            last_protocol: crate::types::Protocol::Tcp,
+            last_label: None,
+            last_input: String::new(),
             main_window_id: window::Id::unique(),
-            main_log: String::new(),
+            main_log: Default::default(),
+            main_log_filter: Default::default(),
+            main_log_search: Default::default(),
+            main_log_regex: false,
+            main_log_scroll: Default::default(),
+            main_log_zoom: Default::default(),
+            modifiers: Default::default(),
+            tabs: Vec::new(),
+            active_tab: None,
+            palette: None,
+            menu: None,
+            main_banners: Default::default(),
+            save_error_banner: None,
+            log_bundle_progress: None,
+            show_recent_targets: false,
+            main_hidden: false,
+            show_dashboard: true,
+            port_input: String::new(),
+            port_error: None,
+            number_inputs: NumberInputs::default(),
+            recent_configs: Vec::new(),
+            config_problem: None,
             windows: HashMap::new(),
         }
     }
@@ -108,6 +603,148 @@ impl Default for App {
 
 /// Implementation for App, providing utility methods.
 impl App {
+    /// Logs a failure or notice in a window and shows it as a banner there. Without such a
+    /// window, e.g. for the main window or one already closed, it goes to the main window.
+    pub fn raise(&mut self, id: window::Id, level: crate::log::LogLevel, text: &str) {
+        let entry = crate::log::LogEntry::new(level, text);
+        match self.windows.get_mut(&id) {
+            Some(window_data) => {
+                window_data.push_log(entry);
+                window_data.banners.raise(level, text);
+            }
+            None => {
+                self.main_log.push_entry(entry);
+                self.main_banners.raise(level, text);
+            }
+        }
+    }
+
+    /// Settings for a new session or replay window, at the remembered geometry. Closing
+    /// it goes through Message::CloseRequested so unsaved work can be exported first.
+    pub fn session_window_settings(&self, kind: WindowKind) -> window::Settings {
+        let cascade = if self.config.cascade_windows {
+            self.windows.len() - self.tabs.len()
+        } else {
+            0
+        };
+        window::Settings {
+            icon: Some(crate::window_icon(kind)),
+            exit_on_close_request: false,
+            ..crate::geometry::settings(self.config.session_window.as_ref(), cascade)
+        }
+    }
+
+    /// Opens a session or replay window, or in tabbed mode a tab of the main window, which
+    /// is shown right away. The task yields the id once the window exists.
+    pub fn open_window(&mut self, kind: WindowKind) -> (window::Id, Task<window::Id>) {
+        if self.config.tabbed_windows {
+            let id = window::Id::unique();
+            self.tabs.push(id);
+            self.active_tab = Some(id);
+            (id, Task::done(id))
+        } else {
+            window::open(self.session_window_settings(kind))
+        }
+    }
+
+    /// Closes an OS window, or removes a tab, showing the tab next to it.
+    fn close_window(&mut self, id: window::Id) -> Task<Message> {
+        let Some(index) = self.tabs.iter().position(|tab| *tab == id) else {
+            return window::close(id);
+        };
+        self.tabs.remove(index);
+        if self.active_tab == Some(id) {
+            self.active_tab = self.tabs.get(index).or_else(|| self.tabs.last()).copied();
+        }
+        Task::none()
+    }
+
+    /// The window whose content is shown in window `id`: the active tab for the main
+    /// window, otherwise `id` itself.
+    pub fn shown_window(&self, id: window::Id) -> window::Id {
+        match self.active_tab {
+            Some(tab) if id == self.main_window_id => tab,
+            _ => id,
+        }
+    }
+
+    /// Remembered geometry of a window: the main window's own, or the one shared by new
+    /// session and replay windows.
+    fn geometry_mut(&mut self, id: window::Id) -> Option<&mut crate::geometry::WindowGeometry> {
+        let geometry = if id == self.main_window_id {
+            &mut self.config.main_window
+        } else if self.windows.contains_key(&id) {
+            &mut self.config.session_window
+        } else {
+            return None;
+        };
+        Some(geometry.get_or_insert_with(Default::default))
+    }
+
+    /// Problems with the main window inputs that prevent connecting: address, port, and
+    /// initial payload, in that order.
+    pub fn input_errors(&self) -> [Option<String>; 3] {
+        let payload = match self.config.protocol {
+            crate::types::Protocol::Tcp => crate::validate::payload(
+                &self.config.initial_payload,
+                self.config.initial_payload_type,
+            )
+            .err(),
+            crate::types::Protocol::Udp => None,
+        };
+        [
+            crate::validate::address(&self.config.address).err(),
+            self.port_error.clone(),
+            payload,
+        ]
+    }
+
+    /// Resolves the port field for the current protocol, storing the number in the config
+    /// or the error for the field. Returns true if the config changed.
+    pub fn resolve_port_input(&mut self) -> bool {
+        match crate::services::resolve_port(&self.port_input, self.config.protocol) {
+            Ok(port) => {
+                self.port_error = None;
+                let changed = self.config.port != port;
+                self.config.port = port;
+                changed
+            }
+            Err(e) => {
+                self.port_error = Some(e);
+                false
+            }
+        }
+    }
+
+    /// Refills the main window inputs after the config was replaced, e.g. by an import,
+    /// and switches to its language.
+    pub fn sync_inputs(&mut self) {
+        crate::i18n::set_language(self.config.language);
+        self.port_input = self.config.port.to_string();
+        self.resolve_port_input();
+        self.number_inputs = NumberInputs::new(&self.config);
+    }
+
+    /// Replay options from the main window, unless one of the replay fields is invalid.
+    pub fn replay_options(&self) -> Result<ReplayOptions, String> {
+        match self
+            .number_inputs
+            .replay_errors(self.config.replay_ramp_mode)
+            .into_iter()
+            .next()
+        {
+            Some(error) => Err(error),
+            None => Ok(self.config.replay_options()),
+        }
+    }
+
+    /// Theme of every window, as set in the config.
+    pub fn theme(&self, _window: window::Id) -> Theme {
+        self.config
+            .theme
+            .theme(self.config.accessibility.high_contrast)
+    }
+
     /// Returns the title for a given window ID.
     /// Main window has a fixed title; session windows use their dynamic title.
     pub fn title(&self, window: window::Id) -> String {
@@ -116,21 +753,305 @@ impl App {
         } else {
             self.windows
                 .get(&window)
-                .map(|window| window.title.clone())
+                .map(|window| {
+                    let title = match &window.label {
+                        Some(label) => format!("{} — {}", label, window.title),
+                        None => window.title.clone(),
+                    };
+                    match &window.state {
+                        WindowState::Replay(data) if data.dirty => format!("*{}", title),
+                        _ => title,
+                    }
+                })
                 .unwrap_or_default()
         }
     }
 }
 
 /// Renders the UI for a specific window based on its ID.
-/// For the main window, shows configuration options; for session windows, delegates to Window::view.
+/// With tabs open, the main window shows a tab bar above the active tab.
 pub fn view_app(state: &App, id: window::Id) -> Element<'_, Message, Theme, iced::Renderer> {
+    let content: Element<'_, Message> = if id == state.main_window_id && !state.tabs.is_empty() {
+        column![
+            menu_bar(state, id),
+            tab_bar(state),
+            window_view(state, state.shown_window(id))
+        ]
+        .into()
+    } else {
+        column![menu_bar(state, id), window_view(state, id)].into()
+    };
+    let content = match state.menu {
+        Some(open) if open.window == id => {
+            iced::widget::stack![content, menu_view(state, open)].into()
+        }
+        _ => content,
+    };
+    match &state.palette {
+        Some(palette) if palette.window == state.shown_window(id) => {
+            iced::widget::stack![content, palette_view(state, palette)].into()
+        }
+        _ => content,
+    }
+}
+
+/// Number of matching actions the command palette lists at once.
+const PALETTE_ROWS: usize = 12;
+
+fn palette_input_id() -> text_input::Id {
+    text_input::Id::new("palette")
+}
+
+/// Renders the command palette over a window. Clicking outside it closes it.
+fn palette_view<'a>(state: &'a App, palette: &'a crate::palette::Palette) -> Element<'a, Message> {
+    let matches = palette.matches(state);
+    // Keep the highlighted action in the rows shown
+    let first = (palette.selected + 1).saturating_sub(PALETTE_ROWS);
+    let rows = matches
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(PALETTE_ROWS)
+        .map(|(index, action)| {
+            button(text(action.name.clone()))
+                .width(Length::Fill)
+                .style(if index == palette.selected {
+                    button::primary
+                } else {
+                    button::text
+                })
+                .on_press(Message::PaletteRun(index))
+                .into()
+        });
+    let panel = container(
+        column![
+            text_input(tr("Type an action…"), &palette.query)
+                .id(palette_input_id())
+                .on_input(Message::PaletteQueryChanged)
+                .on_submit(Message::PaletteSubmitted),
+            if matches.is_empty() {
+                column![text(tr("No matching actions")).style(Tone::Muted.style())]
+            } else {
+                column(rows)
+            },
+            text(tr("↑↓ to choose, Enter to run, Esc to close"))
+                .size(12)
+                .style(Tone::Muted.style()),
+        ]
+        .spacing(8),
+    )
+    .width(Length::Fixed(480f32))
+    .padding(10)
+    .style(container::bordered_box);
+    iced::widget::opaque(
+        iced::widget::mouse_area(
+            container(iced::widget::opaque(panel))
+                .center_x(Length::Fill)
+                .padding(iced::Padding::new(0.0).top(60))
+                .height(Length::Fill)
+                .style(|theme: &Theme| container::Style {
+                    background: Some(Background::Color(Color {
+                        a: 0.4,
+                        ..theme.extended_palette().background.strong.color
+                    })),
+                    ..Default::default()
+                }),
+        )
+        .on_press(Message::ClosePalette),
+    )
+}
+
+/// Width of a title in the menu bar, so each drop-down menu opens under its title.
+const MENU_TITLE_WIDTH: f32 = 80.0;
+
+/// Height of the menu bar; drop-down menus open right below it.
+const MENU_BAR_HEIGHT: f32 = 32.0;
+
+/// Renders the menu bar of a window. While a menu is open, moving over another title opens
+/// that one instead.
+fn menu_bar(state: &App, id: window::Id) -> Element<'_, Message> {
+    let titles = crate::menu::menus(state, id).into_iter().map(|menu| {
+        let open = state
+            .menu
+            .is_some_and(|open| open.window == id && open.menu == menu);
+        iced::widget::mouse_area(
+            button(text(tr(menu.title())))
+                .width(Length::Fixed(MENU_TITLE_WIDTH))
+                .style(if open { button::primary } else { button::text })
+                .on_press(Message::OpenMenu(id, menu)),
+        )
+        .on_enter(Message::MenuHovered(id, menu))
+        .into()
+    });
+    container(iced::widget::Row::with_children(titles).align_y(iced::Alignment::Center))
+        .width(Length::Fill)
+        .height(Length::Fixed(MENU_BAR_HEIGHT))
+        .style(pane_style)
+        .into()
+}
+
+/// Menu items: plain text, highlighted under the cursor.
+fn menu_item_style(theme: &Theme, status: button::Status) -> button::Style {
+    match status {
+        button::Status::Hovered | button::Status::Pressed => button::primary(theme, status),
+        button::Status::Active | button::Status::Disabled => button::text(theme, status),
+    }
+}
+
+/// Renders the open drop-down menu under its title, with the shortcut of each item.
+/// Items that do not apply are disabled. Clicking outside the menu closes it.
+fn menu_view(state: &App, open: crate::menu::OpenMenu) -> Element<'_, Message> {
+    let index = crate::menu::menus(state, open.window)
+        .iter()
+        .position(|menu| *menu == open.menu)
+        .unwrap_or_default();
+    let items = crate::menu::items(state, open.window, open.menu)
+        .into_iter()
+        .map(|item| {
+            button(
+                row![
+                    text(item.label).width(Length::Fill),
+                    text(item.shortcut.unwrap_or_default()).style(Tone::Muted.style()),
+                ]
+                .spacing(20),
+            )
+            .width(Length::Fill)
+            .style(menu_item_style)
+            .on_press_maybe(
+                item.message
+                    .map(|message| Message::MenuChosen(Box::new(message))),
+            )
+            .into()
+        });
+    let dropdown = container(column(items))
+        .width(Length::Fixed(300f32))
+        .padding(5)
+        .style(container::bordered_box);
+    // The menu bar stays uncovered, so its titles can switch or close the menu
+    column![
+        Space::with_height(MENU_BAR_HEIGHT),
+        iced::widget::opaque(
+            iced::widget::mouse_area(
+                container(iced::widget::opaque(dropdown))
+                    .padding(iced::Padding::new(0.0).left(index as f32 * MENU_TITLE_WIDTH))
+                    .width(Length::Fill)
+                    .height(Length::Fill),
+            )
+            .on_press(Message::CloseMenu),
+        ),
+    ]
+    .into()
+}
+
+/// Renders the tabs of the main window: the main window's own view, then a tab with a
+/// close button for each session or replay opened as a tab.
+fn tab_bar(state: &App) -> Element<'_, Message> {
+    let tab = |label: String, tab: Option<window::Id>| {
+        button(text(label))
+            .style(if state.active_tab == tab {
+                button::primary
+            } else {
+                button::secondary
+            })
+            .on_press(Message::SelectTab(tab))
+    };
+    let tabs = state.tabs.iter().map(|id| {
+        row![
+            tab(state.title(*id), Some(*id)),
+            button("×")
+                .style(button::text)
+                .on_press(Message::CloseRequested(*id)),
+        ]
+        .align_y(iced::Alignment::Center)
+        .into()
+    });
+    scrollable(
+        row![tab(tr("Main").to_string(), None)]
+            .extend(tabs)
+            .spacing(5)
+            .padding(
+                iced::Padding::new(0.0)
+                    .top(10)
+                    .left(20)
+                    .right(20)
+                    .bottom(10),
+            ),
+    )
+    .direction(scrollable::Direction::Horizontal(Default::default()))
+    .into()
+}
+
+/// Renders one window: for the main window, shows configuration options; for session
+/// windows and tabs, delegates to Window::view.
+fn window_view(state: &App, id: window::Id) -> Element<'_, Message, Theme, iced::Renderer> {
     // Render main
     if id == state.main_window_id {
+        let input_errors = state.input_errors();
+        let input_valid = input_errors.iter().all(Option::is_none);
         container(
             column![
+                banners_view(id, &state.main_banners),
+                config_banner(state),
+                row![
+                    text(format!(
+                        "Config: {}",
+                        crate::config::config_path().display()
+                    )),
+                    Space::with_width(Length::Fill),
+                    pick_list(
+                        state
+                            .recent_configs
+                            .iter()
+                            .map(|path| path.display().to_string())
+                            .collect::<Vec<_>>(),
+                        None::<String>,
+                        move |path| Message::RecentConfigSelected(id, path)
+                    )
+                    .placeholder(tr("Recent configs")),
+                    button(tr("Switch config…")).on_press(Message::SwitchConfig(id)),
+                    Space::with_width(10),
+                    text(tr("Theme:")),
+                    radio(
+                        tr("System"),
+                        ThemeChoice::System,
+                        Some(state.config.theme),
+                        move |t| Message::ThemeChanged(id, t)
+                    ),
+                    radio(
+                        tr("Light"),
+                        ThemeChoice::Light,
+                        Some(state.config.theme),
+                        move |t| Message::ThemeChanged(id, t)
+                    ),
+                    radio(
+                        tr("Dark"),
+                        ThemeChoice::Dark,
+                        Some(state.config.theme),
+                        move |t| Message::ThemeChanged(id, t)
+                    ),
+                    Space::with_width(10),
+                    text(tr("Language:")),
+                    pick_list(
+                        crate::i18n::Language::ALL,
+                        Some(state.config.language),
+                        move |language| Message::LanguageChanged(id, language)
+                    ),
+                    Space::with_width(10),
+                    checkbox(
+                        tr("High contrast"),
+                        state.config.accessibility.high_contrast
+                    )
+                    .on_toggle(move |on| Message::HighContrastToggled(id, on)),
+                    checkbox(
+                        tr("Reduced motion"),
+                        state.config.accessibility.reduced_motion
+                    )
+                    .on_toggle(move |on| Message::ReducedMotionToggled(id, on)),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
                 row![
-                    text("Protocol:"),
+                    text(tr("Protocol:")),
                     radio(
                         "TCP",
                         crate::types::Protocol::Tcp,
@@ -146,355 +1067,4198 @@ pub fn view_app(state: &App, id: window::Id) -> Element<'_, Message, Theme, iced
                 ]
                 .spacing(10),
                 row![
-                    text("Address:"),
+                    text(tr("Address:")),
                     text_input("", &state.config.address)
-                        .on_input(move |s| Message::AddressChanged(id, s)),
+                        .on_input(move |s| Message::AddressChanged(id, s))
+                        .on_submit(Message::Connect(id)),
                     Space::with_width(10),
-                    text("Port:"),
-                    text_input("", &state.config.port)
+                    text(tr("Port:")),
+                    text_input(tr("port or service"), &state.port_input)
                         .on_input(move |s| Message::PortChanged(id, s))
-                        .width(Length::Fixed(75f32))
+                        .on_submit(Message::Connect(id))
+                        .width(Length::Fixed(110f32)),
+                    if state.port_error.is_none()
+                        && state.port_input.trim() != state.config.port.to_string()
+                    {
+                        text(format!("= {}", state.config.port))
+                    } else {
+                        text("")
+                    },
+                    pick_list(
+                        crate::services::common_ports(state.config.protocol),
+                        None::<crate::services::Service>,
+                        move |service| Message::ServicePicked(id, service)
+                    )
+                    .placeholder(tr("Common ports")),
+                    pick_list(
+                        state.config.recent_targets.as_slice(),
+                        None::<RecentTarget>,
+                        move |target| Message::RecentTargetSelected(id, target)
+                    )
+                    .placeholder(tr("Recent targets")),
+                    profiles_view(id, &state.config),
+                    button(if state.show_recent_targets {
+                        tr("Hide recent")
+                    } else {
+                        tr("Edit recent")
+                    })
+                    .on_press(Message::RecentTargetsToggled(id)),
                 ]
                 .spacing(10),
+                error_hints(input_errors[..2].iter().flatten().cloned()),
+                if state.show_recent_targets {
+                    recent_targets_view(id, &state.config)
+                } else {
+                    row![].into()
+                },
                 if state.config.protocol == crate::types::Protocol::Tcp {
-                           row![
-                    radio(
-                         "Hex",
-                         PayloadType::Hex,
-                         Some(state.config.initial_payload_type),
-                         move |pt| Message::InitialPayloadTypeChanged(id, pt)
-                     ),
-                     radio(
-                         "ASCII",
-                         PayloadType::Ascii,
-                         Some(state.config.initial_payload_type),
-                         move |pt| Message::InitialPayloadTypeChanged(id, pt)
-                     ),
-                     text_input("optional initial payload...", &state.config.initial_payload)
-                         .on_input(move |s| Message::InitialPayloadChanged(id, s)),
-                 ]
-                 .spacing(10)
-                }
-                else
-                {
+                    row![
+                        radio(
+                            "Hex",
+                            PayloadType::Hex,
+                            Some(state.config.initial_payload_type),
+                            move |pt| Message::InitialPayloadTypeChanged(id, pt)
+                        ),
+                        radio(
+                            "ASCII",
+                            PayloadType::Ascii,
+                            Some(state.config.initial_payload_type),
+                            move |pt| Message::InitialPayloadTypeChanged(id, pt)
+                        ),
+                        text_input(tr("optional initial payload..."), &state.config.initial_payload)
+                            .on_input(move |s| Message::InitialPayloadChanged(id, s))
+                            .style(input_style(
+                                crate::validate::payload(
+                                    &state.config.initial_payload,
+                                    state.config.initial_payload_type
+                                )
+                                .is_err()
+                            )),
+                        text(tr("Idle timeout (s):")),
+                        text_input("0", &state.number_inputs.idle_timeout_secs)
+                            .on_input(move |s| Message::IdleTimeoutDefaultChanged(id, s))
+                            .width(Length::Fixed(60f32)),
+                        text(tr("Stall warning (ms):")),
+                        text_input("2000", &state.number_inputs.send_stall_warn_ms)
+                            .on_input(move |s| Message::StallWarningChanged(id, s))
+                            .width(Length::Fixed(60f32)),
+                    ]
+                    .spacing(10)
+                } else {
                     row![]
                 },
-
+                // The readout shows why the initial payload does not decode
+                if state.config.protocol == crate::types::Protocol::Tcp {
+                    payload_size_view(
+                        &state.config.initial_payload,
+                        state.config.initial_payload_type,
+                        state.config.protocol,
+                        state.config.udp_safe_datagram_size,
+                    )
+                } else {
+                    row![].into()
+                },
+                error_hints(if state.config.protocol == crate::types::Protocol::Tcp {
+                    state.number_inputs.session_errors()
+                } else {
+                    Vec::new()
+                }),
+                if state.config.protocol == crate::types::Protocol::Tcp {
+                    handshake_steps_view(id, &state.config)
+                } else {
+                    row![].into()
+                },
+                row![
+                    checkbox(
+                        tr("Auto-export replay on close"),
+                        state.config.auto_export_on_close
+                    )
+                    .on_toggle(move |on| Message::AutoExportDefaultToggled(id, on)),
+                    text(tr("to")),
+                    text_input("replays", &state.config.auto_export_dir)
+                        .on_input(move |s| Message::AutoExportDirChanged(id, s)),
+                    button(tr("Browse…")).on_press(Message::BrowseAutoExportDir(id)),
+                    Space::with_width(10),
+                    text(tr("Quick export to")),
+                    text_input("exports", &state.config.export_dir)
+                        .on_input(move |s| Message::ExportDirChanged(id, s)),
+                    button(tr("Browse…")).on_press(Message::BrowseExportDir(id)),
+                    checkbox(tr("Log new windows there"), state.config.log_to_file)
+                        .on_toggle(move |on| Message::LogToFileDefaultToggled(id, on)),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+                row![
+                    text(tr("New TCP sessions:")),
+                    default_payload_type_radios(id, crate::types::Protocol::Tcp, &state.config),
+                    Space::with_width(10),
+                    text(tr("New UDP sessions:")),
+                    default_payload_type_radios(id, crate::types::Protocol::Udp, &state.config),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+                row![
+                    text(tr("Log timestamps:")),
+                    text_input(
+                        crate::log::DEFAULT_TIMESTAMP_FORMAT,
+                        &state.config.log_timestamp_format
+                    )
+                    .on_input(move |s| Message::TimestampFormatChanged(id, s))
+                    .width(Length::Fixed(200f32)),
+                    checkbox("UTC", state.config.log_timestamp_utc)
+                        .on_toggle(move |on| Message::TimestampUtcToggled(id, on)),
+                    match crate::log::check_timestamp_format(&state.config.log_timestamp_format) {
+                        Ok(()) => text(""),
+                        Err(e) =>
+                            text(format!("{}, using the default", e)).style(Tone::Error.style()),
+                    },
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+                row![
+                    text(tr("Replay loops:")),
+                    text_input("1", &state.number_inputs.replay_loops)
+                        .on_input(move |s| Message::ReplayLoopsChanged(id, s))
+                        .width(Length::Fixed(50f32)),
+                    Space::with_width(10),
+                    text(tr("Ramp:")),
+                    radio(
+                        tr("Off"),
+                        RampMode::Off,
+                        Some(state.config.replay_ramp_mode),
+                        move |m| Message::RampModeChanged(id, m)
+                    ),
+                    radio(
+                        tr("x factor"),
+                        RampMode::Multiply,
+                        Some(state.config.replay_ramp_mode),
+                        move |m| Message::RampModeChanged(id, m)
+                    ),
+                    radio(
+                        tr("- ms"),
+                        RampMode::Subtract,
+                        Some(state.config.replay_ramp_mode),
+                        move |m| Message::RampModeChanged(id, m)
+                    ),
+                    text_input("0.8", &state.number_inputs.replay_ramp_value)
+                        .on_input(move |s| Message::RampValueChanged(id, s))
+                        .width(Length::Fixed(60f32)),
+                    text(tr("Floor (ms):")),
+                    text_input("0", &state.number_inputs.replay_ramp_floor_ms)
+                        .on_input(move |s| Message::RampFloorChanged(id, s))
+                        .width(Length::Fixed(60f32)),
+                ]
+                .spacing(10),
+                error_hints(
+                    state
+                        .number_inputs
+                        .replay_errors(state.config.replay_ramp_mode)
+                ),
+                dashboard_view(id, state),
                 row![
                     tooltip(
-                        button(match state.config.protocol {
+                        button(tr(match state.config.protocol {
                             crate::types::Protocol::Tcp => "Connect",
                             crate::types::Protocol::Udp => "Open Session",
-                        })
-                        .on_press(Message::Connect(id)),
-                        match state.config.protocol {
+                        }))
+                        .on_press_maybe(input_valid.then_some(Message::Connect(id))),
+                        tr(match state.config.protocol {
                             crate::types::Protocol::Tcp => "Connect to the TCP server",
                             crate::types::Protocol::Udp => "Open UDP session",
-                        },
+                        }),
                         tooltip::Position::Top
                     ),
                     Space::with_width(10),
                     tooltip(
-                        button("Replay Connect").on_press(Message::ReplayConnect),
-                        "Use a recorded session to connect",
+                        button(tr("Replay Connect")).on_press(Message::ReplayConnect),
+                        tr("Use a recorded session to connect"),
                         tooltip::Position::Top
                     ),
+                    Space::with_width(10),
+                    replay_last_view(&state.config),
+                    Space::with_width(Length::Fill),
+                    checkbox(tr("Clear input after send"), state.config.clear_input_on_send)
+                        .on_toggle(move |on| Message::ClearOnSendToggled(id, on)),
+                    Space::with_width(10),
+                    checkbox(tr("Confirm exit"), state.config.confirm_exit)
+                        .on_toggle(move |on| Message::ConfirmExitToggled(id, on)),
+                    Space::with_width(10),
+                    checkbox(tr("Cascade windows"), state.config.cascade_windows)
+                        .on_toggle(move |on| Message::CascadeWindowsToggled(id, on)),
+                    Space::with_width(10),
+                    checkbox(tr("Open as tabs"), state.config.tabbed_windows)
+                        .on_toggle(move |on| Message::TabbedWindowsToggled(id, on)),
+                    tray_icon_view(id, &state.config),
+                    Space::with_width(10),
+                    button(tr("Export settings…")).on_press(Message::ExportSettings(id)),
+                    Space::with_width(10),
+                    button(tr("Import settings…")).on_press(Message::ImportSettings(id)),
+                    Space::with_width(10),
+                    button(tr("Clear log")).on_press(Message::ClearLog(id)),
+                    button(tr("Copy log")).on_press(Message::CopyLog(id)),
+                    Space::with_width(10),
+                    log_filter_view(id, state.main_log_filter, state.main_log_zoom),
+                    text_input(tr("Filter log"), state.main_log_search.text())
+                        .on_input(Message::MainLogSearchChanged)
+                        .width(Length::Fixed(150f32)),
+                    checkbox(tr("Regex"), state.main_log_regex).on_toggle(Message::MainLogRegexToggled),
+                    button(tr("Clear filter")).on_press_maybe(
+                        (!state.main_log_search.is_empty())
+                            .then(|| Message::MainLogSearchChanged(String::new()))
+                    ),
+                    checkbox(tr("Session logs"), state.config.forward_session_logs)
+                        .on_toggle(move |on| Message::ForwardSessionLogsToggled(id, on)),
+                    tooltip(
+                        checkbox(tr("Debug logging"), state.config.debug_log)
+                            .on_toggle(move |on| Message::DebugLogToggled(id, on)),
+                        tr("Log socket-level detail, shown with the Debug toggle"),
+                        tooltip::Position::Top,
+                    ),
+                    Space::with_width(10),
+                    match state.log_bundle_progress {
+                        Some((written, total)) => row![
+                            button(tr("Export all logs…")),
+                            text(format!(
+                                "Exporting {} of {}",
+                                format_size(written as usize),
+                                format_size(total as usize)
+                            )),
+                        ]
+                        .spacing(10),
+                        None => row![tooltip(
+                            button(tr("Export all logs…")).on_press(Message::ExportAllLogs(id)),
+                            tr("Save the main log and the log of every open window into one directory"),
+                            tooltip::Position::Top,
+                        )],
+                    },
                 ],
-                container(scrollable(text(&state.main_log)))
-                    .style(|_theme| iced::widget::container::Style {
-                        background: Some(Background::Color(Color::from_rgb(0.3, 0.3, 0.3))),
-                        ..Default::default()
-                    })
-                    .height(Length::Fill)
-                    .width(Length::Fill),
             ]
+            .push_maybe(state.main_log_search.error.as_ref().map(|e| {
+                text(format!(
+                    "Not a valid regex, matching the text literally: {}",
+                    e
+                ))
+                .style(Tone::Error.style())
+            }))
+            .push(log_view(
+                id,
+                &state.main_log,
+                state.main_log_filter,
+                &state.main_log_search,
+                state.main_log_scroll,
+                LogText::new(&state.config, state.main_log_zoom),
+            ))
             .spacing(15)
             .padding(20),
         )
         .center_x(Length::Fill)
         .into()
     } else if let Some(window_data) = state.windows.get(&id) {
-        // Render session or replay window
-        window_data.view(id)
+        // Render session or replay window below its banners
+        column![
+            container(banners_view(id, &window_data.banners))
+                .padding(iced::Padding::new(0.0).top(10).left(20).right(20)),
+            window_data.view(id, &state.config)
+        ]
+        .into()
     } else {
         // Fallback for unknown window
-        text("Unknown window state").into()
+        text(tr("Unknown window state")).into()
     }
 }
 
-/// Updates the application state based on incoming messages.
-/// Handles user interactions, system events, and async task results.
-pub fn update_app(state: &mut App, message: Message) -> Task<Message> {
-    match message {
-        // Update address in config and save
-        Message::AddressChanged(id, addr) => {
-            if id == state.main_window_id {
-                state.config.address = addr;
-                crate::config::save_config(&state.config);
-            }
-            Task::none()
-        }
-        // Update port in config and save
-        Message::PortChanged(id, port) => {
-            if id == state.main_window_id {
-                state.config.port = port;
-                crate::config::save_config(&state.config);
-            }
-            Task::none()
-        }
-        // Update initial payload in config and save
-        Message::InitialPayloadChanged(id, payload) => {
-            if id == state.main_window_id {
-                state.config.initial_payload = payload;
-                crate::config::save_config(&state.config);
-            }
-            Task::none()
-        }
-        // Update initial payload type in config and save
-        Message::InitialPayloadTypeChanged(id, payload_type) => {
-            if id == state.main_window_id {
-                state.config.initial_payload_type = payload_type;
-                crate::config::save_config(&state.config);
+/// Cancels the replays, repeats, and heartbeats running in a window. Returns the sender
+/// of a session window so the caller can disconnect it.
+fn stop_window_tasks(window_state: &WindowState) -> Option<mpsc::Sender<SessionCommand>> {
+    match window_state {
+        Session(data) => {
+            for cancel in [
+                &data.replay_cancel,
+                &data.repeat_cancel,
+                &data.heartbeat_cancel,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                cancel.cancel();
             }
-            Task::none()
+            data.sender.clone()
         }
-        // Update protocol in config and save
-        Message::ProtocolChanged(id, protocol) => {
-            if id == state.main_window_id {
-                state.config.protocol = protocol;
-                crate::config::save_config(&state.config);
+        Replay(data) => {
+            if let Some(cancel) = &data.cancel {
+                cancel.cancel();
             }
-            Task::none()
+            None
         }
-        // Open a new session window and start connection task
-        Message::Connect(id) => {
-            if id == state.main_window_id {
-                state.last_addr = state.config.address.clone();
-                state.last_port = state.config.port.clone();
-                state.last_payload = state.config.initial_payload.clone();
+    }
+}
 
-                let (new_id, task) = window::open(window::Settings {
-                    ..window::Settings::default()
-                });
+/// Hex and ASCII radios for the payload type new session windows of `protocol` start with.
+fn default_payload_type_radios(
+    id: window::Id,
+    protocol: crate::types::Protocol,
+    config: &Config,
+) -> Element<'static, Message> {
+    let selected = Some(config.default_payload_type(protocol));
+    row![
+        radio("Hex", PayloadType::Hex, selected, move |pt| {
+            Message::DefaultPayloadTypeChanged(id, protocol, pt)
+        }),
+        radio("ASCII", PayloadType::Ascii, selected, move |pt| {
+            Message::DefaultPayloadTypeChanged(id, protocol, pt)
+        }),
+    ]
+    .spacing(10)
+    .into()
+}
 
-                Task::batch(vec![
-                    task.map(move |_| Message::WindowOpened(new_id)),
-                    if matches!(state.config.protocol, crate::types::Protocol::Tcp) {
-                        Task::perform(async move { true }, move |_| {
-                            Message::ConnectionStatus(new_id, true)
-                        })
-                    } else {
-                        Task::none()
-                    },
-                ])
-            } else {
-                Task::none()
-            }
-        }
-        // Initialize new session window with data and start TCP task if applicable
-        Message::WindowOpened(id) => {
-            let (tx, rx) = mpsc::channel(100);
-            state.windows.insert(
-                id,
-                Window {
-                    title: match state.config.protocol {
-                        crate::types::Protocol::Tcp => "replayr - Tcp session (disconnected)".to_string(),
-                        crate::types::Protocol::Udp => "replayr - Udp session".to_string(),
-                    },
-                    state: Session(SessionData {
-                        payload_input: String::new(),
-                        log: String::new(),
-                        sender: Some(tx),
-                        connected: matches!(state.config.protocol, crate::types::Protocol::Udp),
-                        payload_type: PayloadType::Hex,
-                        protocol: state.config.protocol,
-                        replay_payloads: Vec::new(),
-                        last_packet_time: None,
-                        input_placeholder: "68656c6c6f20776f726c64".into(),
-                        initial_payload: state.last_payload.clone(),
-                        initial_payload_type: state.config.initial_payload_type,
-                    }),
-                },
+/// Shows input problems as red hint lines.
+fn error_hints(errors: impl IntoIterator<Item = String>) -> Element<'static, Message> {
+    iced::widget::Column::with_children(
+        errors
+            .into_iter()
+            .map(|error| text(error).style(Tone::Error.style()).into()),
+    )
+    .into()
+}
+
+/// Loads the config file at `path` and uses it for every later save. Open sessions keep
+/// the settings they were opened with.
+fn switch_config(state: &mut App, path: std::path::PathBuf) {
+    // Changes still waiting belong to the previous file
+    crate::config::flush_config(true);
+    crate::config::set_config_path(path.clone());
+    let load = crate::config::load_config();
+    let lines = load.log_lines();
+    state.config_problem = load.problem();
+    state.config = load.config;
+    state.sync_inputs();
+    for entry in lines {
+        state.main_log.push_entry(entry);
+    }
+    state.main_log.push(
+        crate::log::LogLevel::Info,
+        &format!("Switched to config {}", path.display()),
+    );
+    match crate::config::remember_config(&path) {
+        Ok(paths) => state.recent_configs = paths,
+        Err(e) => {
+            state.main_log.push(
+                crate::log::LogLevel::Warn,
+                &format!("Failed to remember config: {}", e),
             );
-            let addr = state.last_addr.clone();
-            let port = state.last_port.clone();
-            let payload = state.last_payload.clone();
-            let payload_type = state.config.initial_payload_type;
-            if matches!(state.config.protocol, crate::types::Protocol::Tcp) {
-                Task::batch(vec![Task::perform(
-                    async move {
-                        crate::session::tcp_task(rx, addr, port, payload, payload_type, id).await;
-                    },
-                    |_| Message::NoOp,
-                )])
+        }
+    }
+}
+
+/// Banners of a window, newest last. Only the newest few are shown, below a line
+/// counting the others with a button to dismiss them all.
+fn banners_view(id: window::Id, banners: &crate::types::Banners) -> Element<'_, Message> {
+    let hidden = banners
+        .shown
+        .len()
+        .saturating_sub(crate::types::SHOWN_BANNERS);
+    let more = (hidden > 0).then(|| {
+        row![
+            text(format!("{} earlier notices", hidden)),
+            button(tr("Dismiss all")).on_press(Message::DismissBanners(id)),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center)
+    });
+    banners.shown[hidden..]
+        .iter()
+        .fold(column![].push_maybe(more), |column, banner| {
+            let tone = match banner.level {
+                crate::log::LogLevel::Error => Tone::Error,
+                crate::log::LogLevel::Warn => Tone::Warning,
+                crate::log::LogLevel::Info | crate::log::LogLevel::Debug => Tone::Info,
+            };
+            let label = if banner.count > 1 {
+                format!("{} (×{})", banner.text, banner.count)
+            } else {
+                banner.text.clone()
+            };
+            column.push(
+                row![
+                    text(label).style(tone.style()).width(Length::Fill),
+                    button(tr("Dismiss")).on_press(Message::DismissBanner(id, banner.id)),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            )
+        })
+        .spacing(5)
+        .into()
+}
+
+/// Red banner about problems loading the config file, shown until dismissed. Save
+/// failures are raised as a banner by `update`.
+fn config_banner(state: &App) -> Element<'_, Message> {
+    let load_problem = state.config_problem.as_ref().map(|problem| {
+        row![
+            text(problem).style(Tone::Error.style()),
+            button(tr("Dismiss")).on_press(Message::DismissConfigProblem),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center)
+    });
+    column![].push_maybe(load_problem).into()
+}
+
+/// "Replay last" button for the most recent replay file, disabled if there is none or it
+/// no longer exists, and a list of the recent replay files.
+fn replay_last_view(config: &Config) -> Element<'_, Message> {
+    let last = config.recent_replays.first();
+    row![
+        button(text(match last {
+            Some(recent) => tr_args("Replay last: {}", &[&recent.name()]),
+            None => tr("Replay last").to_string(),
+        }))
+        .on_press_maybe(
+            last.filter(|recent| recent.path.is_file())
+                .map(|recent| Message::ReplayRecent(recent.clone()))
+        ),
+        pick_list(
+            config.recent_replays.as_slice(),
+            None::<RecentReplay>,
+            Message::ReplayRecent
+        )
+        .placeholder(tr("Recent replays")),
+    ]
+    .spacing(10)
+    .into()
+}
+
+/// Picks one of the profiles from the config file as the target, if there are any.
+fn profiles_view(id: window::Id, config: &Config) -> Element<'_, Message> {
+    if config.profiles.is_empty() {
+        return Space::with_width(0).into();
+    }
+    pick_list(
+        config.profiles.as_slice(),
+        None::<Profile>,
+        move |profile| Message::RecentTargetSelected(id, profile.target()),
+    )
+    .placeholder(tr("Profiles"))
+    .into()
+}
+
+/// The tray icon settings, in builds that can show one.
+fn tray_icon_view(id: window::Id, config: &Config) -> Element<'_, Message> {
+    if !crate::tray::SUPPORTED {
+        return Space::with_width(0).into();
+    }
+    row![
+        Space::with_width(10),
+        checkbox(tr("Tray icon"), config.tray_icon)
+            .on_toggle(move |on| Message::TrayIconToggled(id, on)),
+        Space::with_width(10),
+        tooltip(
+            checkbox(tr("Close to tray"), config.close_to_tray).on_toggle_maybe(
+                config
+                    .tray_icon
+                    .then_some(move |on| Message::CloseToTrayToggled(id, on))
+            ),
+            tr("Closing the main window hides it to the tray"),
+            tooltip::Position::Bottom
+        ),
+    ]
+    .into()
+}
+
+/// Lists the recent targets with a button to remove each, and one to clear them all.
+fn recent_targets_view(id: window::Id, config: &Config) -> Element<'_, Message> {
+    let rows = config
+        .recent_targets
+        .iter()
+        .enumerate()
+        .map(|(index, target)| {
+            row![
+                text(target.to_string()).width(Length::Fixed(250f32)),
+                button(tr("Remove")).on_press(Message::RecentTargetRemoved(id, index)),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center)
+            .into()
+        });
+    column![
+        iced::widget::Column::with_children(rows).spacing(5),
+        button(tr("Clear history")).on_press_maybe(
+            (!config.recent_targets.is_empty()).then_some(Message::ClearRecentTargets(id))
+        ),
+    ]
+    .spacing(5)
+    .into()
+}
+
+/// Table of the open session and replay windows in the main window, in the order they
+/// were opened, with their target, state, traffic, and progress. Each can be brought to
+/// the front or closed, which asks first like closing the window itself.
+fn dashboard_view(id: window::Id, state: &App) -> Element<'_, Message> {
+    if state.windows.is_empty() {
+        return row![].into();
+    }
+    let toggle = button(text(if state.show_dashboard {
+        format!("Hide windows ({})", state.windows.len())
+    } else {
+        format!("Show windows ({})", state.windows.len())
+    }))
+    .on_press(Message::DashboardToggled(id));
+    if !state.show_dashboard {
+        return toggle.into();
+    }
+    let (red, green, yellow) = (Tone::Error, Tone::Success, Tone::Warning);
+    let mut ids: Vec<_> = state.windows.keys().copied().collect();
+    ids.sort();
+    let rows = ids.into_iter().map(|window_id| {
+        let window_data = &state.windows[&window_id];
+        let (protocol, target, (status, color), traffic, progress) = match &window_data.state {
+            WindowState::Session(data) => (
+                data.protocol,
+                format!("{}:{}", data.address, data.port),
+                if data.connected {
+                    ("connected", green)
+                } else if data.connecting {
+                    ("connecting", yellow)
+                } else {
+                    ("disconnected", red)
+                },
+                format!(
+                    "{} sent ({}), {} received ({})",
+                    data.traffic.sent_packets,
+                    format_size(data.traffic.sent_bytes as usize),
+                    data.traffic.received_packets,
+                    format_size(data.traffic.received_bytes as usize)
+                ),
+                match (data.replay_progress, data.repeat_progress) {
+                    (Some((sent, total)), _) => format!("replay {}/{}", sent, total),
+                    (None, Some((sent, total))) => format!("repeat {}/{}", sent, total),
+                    (None, None) => String::new(),
+                },
+            ),
+            WindowState::Replay(data) => (
+                data.protocol,
+                format!("{}:{}", data.address, data.port),
+                match data.status {
+                    ReplayStatus::Running => ("running", green),
+                    ReplayStatus::Stopped => ("stopped", red),
+                    ReplayStatus::Finished => ("finished", Tone::Normal),
+                },
+                match &data.stress_stats {
+                    Some(stats) => format!(
+                        "{} sent ({}), {} errors",
+                        stats.packets,
+                        format_size(stats.bytes as usize),
+                        stats.errors
+                    ),
+                    None => format!(
+                        "{}/{} sent, {} received",
+                        data.current_index,
+                        data.payloads.len(),
+                        format_size(data.received_bytes)
+                    ),
+                },
+                if data.loops > 1 {
+                    format!("loop {}/{}", data.iteration, data.loops)
+                } else {
+                    String::new()
+                },
+            ),
+        };
+        let name = match (&window_data.label, &window_data.state) {
+            (Some(label), _) => label.clone(),
+            (None, WindowState::Replay(data)) => data.file_name.clone(),
+            (None, WindowState::Session(_)) => "Session".to_string(),
+        };
+        row![
+            text(name).width(Length::Fixed(160f32)),
+            text(format!("{:?}", protocol)).width(Length::Fixed(40f32)),
+            text(target).width(Length::Fixed(180f32)),
+            text(status)
+                .style(color.style())
+                .width(Length::Fixed(100f32)),
+            text(traffic).width(Length::Fixed(300f32)),
+            text(progress).width(Length::Fixed(100f32)),
+            button(tr("Focus")).on_press(Message::FocusWindow(window_id)),
+            button(tr("Close")).on_press(Message::CloseRequested(window_id)),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center)
+        .into()
+    });
+    column![toggle, iced::widget::Column::with_children(rows).spacing(5)]
+        .spacing(5)
+        .into()
+}
+
+/// A single edit made to a handshake step in the main window.
+#[derive(Debug, Clone)]
+pub enum HandshakeStepEdit {
+    Payload(String),
+    Type(PayloadType),
+    Delay(String),
+}
+
+/// Renders the editable list of handshake steps sent after the initial payload.
+fn handshake_steps_view(id: window::Id, config: &Config) -> Element<'_, Message> {
+    let rows = config
+        .handshake_steps
+        .iter()
+        .enumerate()
+        .map(|(index, step)| {
+            let edit =
+                move |edit: HandshakeStepEdit| Message::HandshakeStepChanged(id, index, edit);
+            row![
+                text(tr_args("Step {}:", &[&(index + 2)])),
+                radio(
+                    "Hex",
+                    PayloadType::Hex,
+                    Some(step.payload_type),
+                    move |pt| { edit(HandshakeStepEdit::Type(pt)) }
+                ),
+                radio(
+                    "ASCII",
+                    PayloadType::Ascii,
+                    Some(step.payload_type),
+                    move |pt| edit(HandshakeStepEdit::Type(pt))
+                ),
+                text_input("payload", &step.payload)
+                    .on_input(move |s| edit(HandshakeStepEdit::Payload(s))),
+                text(tr("after (ms):")),
+                text_input("0", &step.delay.to_string())
+                    .on_input(move |s| edit(HandshakeStepEdit::Delay(s)))
+                    .width(Length::Fixed(70f32)),
+                button(tr("Remove")).on_press(Message::HandshakeStepRemoved(id, index)),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center)
+            .into()
+        });
+    column![
+        iced::widget::Column::with_children(rows).spacing(5),
+        row![
+            button(tr("Add handshake step")).on_press(Message::HandshakeStepAdded(id)),
+            checkbox(
+                tr("Disconnect if the handshake fails"),
+                config.handshake_disconnect_on_failure
+            )
+            .on_toggle(move |on| Message::HandshakeDisconnectToggled(id, on)),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center),
+    ]
+    .spacing(5)
+    .into()
+}
+
+/// A single edit made to the heartbeat settings of a session window.
+#[derive(Debug, Clone)]
+pub enum HeartbeatEdit {
+    Payload(String),
+    Type(PayloadType),
+    Interval(String),
+    Record(bool),
+    Quiet(bool),
+}
+
+/// Stops the session's heartbeat task, then starts a new one if the heartbeat is enabled,
+/// the session is connected, and the payload and interval are valid.
+fn restart_heartbeat(id: window::Id, data: &mut SessionData) -> Task<Message> {
+    if let Some(cancel) = data.heartbeat_cancel.take() {
+        cancel.cancel();
+    }
+    let payload = ReplayablePayload {
+        payload: data.heartbeat_payload.clone(),
+        payload_type: data.heartbeat_type,
+        delay: 0,
+        recorded_only: false,
+        label: None,
+    };
+    let valid = payload.get_payload().is_ok_and(|bytes| !bytes.is_empty());
+    let interval = data
+        .heartbeat_interval_ms
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|interval| *interval > 0);
+    if !data.heartbeat_enabled || !data.connected || !valid {
+        return Task::none();
+    }
+    let (Some(interval), Some(sender)) = (interval, data.sender.clone()) else {
+        return Task::none();
+    };
+    let cancel = tokio_util::sync::CancellationToken::new();
+    data.heartbeat_cancel = Some(cancel.clone());
+    Task::perform(
+        crate::session::heartbeat_task(sender, payload, interval, data.heartbeat_quiet, cancel, id),
+        |_| Message::NoOp,
+    )
+}
+
+/// Renders the heartbeat controls of a session window.
+fn heartbeat_view(id: window::Id, data: &SessionData) -> Element<'_, Message> {
+    let status = match (data.heartbeat_enabled, &data.heartbeat_cancel) {
+        (false, _) => "",
+        (true, Some(_)) => "running",
+        (true, None) if !data.connected => "paused",
+        (true, None) => "invalid payload or interval",
+    };
+    row![
+        checkbox(tr("Heartbeat"), data.heartbeat_enabled)
+            .on_toggle(move |on| Message::HeartbeatToggled(id, on)),
+        radio(
+            "Hex",
+            PayloadType::Hex,
+            Some(data.heartbeat_type),
+            move |pt| { Message::HeartbeatChanged(id, HeartbeatEdit::Type(pt)) }
+        ),
+        radio(
+            "ASCII",
+            PayloadType::Ascii,
+            Some(data.heartbeat_type),
+            move |pt| Message::HeartbeatChanged(id, HeartbeatEdit::Type(pt))
+        ),
+        text_input(tr("heartbeat payload"), &data.heartbeat_payload)
+            .on_input(move |s| Message::HeartbeatChanged(id, HeartbeatEdit::Payload(s))),
+        text(tr("every")),
+        text_input("5000", &data.heartbeat_interval_ms)
+            .on_input(move |s| Message::HeartbeatChanged(id, HeartbeatEdit::Interval(s)))
+            .width(Length::Fixed(70f32)),
+        text(tr("ms")),
+        checkbox(tr("Record"), data.heartbeat_record)
+            .on_toggle(move |on| Message::HeartbeatChanged(id, HeartbeatEdit::Record(on))),
+        checkbox(tr("Quiet"), data.heartbeat_quiet)
+            .on_toggle(move |on| Message::HeartbeatChanged(id, HeartbeatEdit::Quiet(on))),
+        text(status),
+    ]
+    .spacing(10)
+    .align_y(iced::Alignment::Center)
+    .into()
+}
+
+/// Renders the collapsible notes editor of a session window.
+fn notes_view(id: window::Id, data: &SessionData) -> Element<'_, Message> {
+    let toggle = button(if data.show_notes {
+        tr("▾ Notes")
+    } else {
+        tr("▸ Notes")
+    })
+    .on_press(Message::NotesToggled(id));
+    if data.show_notes {
+        column![
+            toggle,
+            iced::widget::text_editor(&data.notes.0)
+                .placeholder(tr("What is this session for?"))
+                .on_action(move |action| Message::NotesEdited(id, action))
+                .height(Length::Fixed(80f32)),
+        ]
+        .spacing(5)
+        .into()
+    } else {
+        toggle.into()
+    }
+}
+
+/// A single edit made to a recorded payload in the session's recording inspector.
+#[derive(Debug, Clone)]
+pub enum RecordingEdit {
+    Delay(String),
+    MoveUp,
+    MoveDown,
+    Remove,
+}
+
+/// Kind of status text, colored to stay readable on the background of the theme.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Tone {
+    /// The theme's text color.
+    Normal,
+    Error,
+    Warning,
+    Success,
+    /// Highlights, such as the payload a replay is at.
+    Info,
+    Muted,
+}
+
+impl Tone {
+    fn color(self, theme: &Theme) -> Option<Color> {
+        let palette = theme.extended_palette();
+        if high_contrast(theme) {
+            return self.high_contrast_color(palette);
+        }
+        let (dark, light) = match self {
+            Tone::Normal => return None,
+            Tone::Muted => {
+                return Some(Color {
+                    a: 0.6,
+                    ..palette.background.base.text
+                });
+            }
+            Tone::Error => (
+                Color::from_rgb(1.0, 0.4, 0.4),
+                Color::from_rgb(0.75, 0.1, 0.1),
+            ),
+            Tone::Warning => (
+                Color::from_rgb(1.0, 0.8, 0.3),
+                Color::from_rgb(0.65, 0.4, 0.0),
+            ),
+            Tone::Success => (
+                Color::from_rgb(0.4, 0.8, 0.4),
+                Color::from_rgb(0.1, 0.5, 0.1),
+            ),
+            Tone::Info => (
+                Color::from_rgb(0.6, 0.8, 1.0),
+                Color::from_rgb(0.1, 0.35, 0.7),
+            ),
+        };
+        Some(if palette.is_dark { dark } else { light })
+    }
+
+    /// Colors on the pure black or white of the high-contrast themes, at least 7:1 against
+    /// it (WCAG AAA).
+    fn high_contrast_color(self, palette: &iced::theme::palette::Extended) -> Option<Color> {
+        let (dark, light) = match self {
+            Tone::Normal => return None,
+            Tone::Muted => {
+                return Some(Color {
+                    a: 0.8,
+                    ..palette.background.base.text
+                });
+            }
+            Tone::Error => (
+                Color::from_rgb(1.0, 0.5, 0.5),
+                Color::from_rgb(0.6, 0.0, 0.0),
+            ),
+            Tone::Warning => (
+                Color::from_rgb(1.0, 0.85, 0.2),
+                Color::from_rgb(0.45, 0.25, 0.0),
+            ),
+            Tone::Success => (
+                Color::from_rgb(0.4, 1.0, 0.4),
+                Color::from_rgb(0.0, 0.35, 0.0),
+            ),
+            Tone::Info => (
+                Color::from_rgb(0.55, 0.8, 1.0),
+                Color::from_rgb(0.0, 0.2, 0.6),
+            ),
+        };
+        Some(if palette.is_dark { dark } else { light })
+    }
+
+    /// Text style for `text(..).style(..)`.
+    fn style(self) -> impl Fn(&Theme) -> text::Style {
+        move |theme| text::Style {
+            color: self.color(theme),
+        }
+    }
+}
+
+/// Connection state of a session, e.g. "Connected to 10.0.0.5:502", in the tone of its
+/// status dot.
+fn session_status(data: &SessionData) -> (Tone, String) {
+    let target = format!("{}:{}", data.address, data.port);
+    if data.connected {
+        (Tone::Success, tr_args("Connected to {}", &[&target]))
+    } else if data.connecting {
+        (Tone::Warning, tr_args("Connecting to {}…", &[&target]))
+    } else if let Some(error) = &data.connect_error {
+        (
+            Tone::Error,
+            tr_args("Could not connect to {}: {}", &[&target, error]),
+        )
+    } else if !data.offline_queue.is_empty() {
+        (
+            Tone::Error,
+            tr_args(
+                "Disconnected from {}, {} payloads queued",
+                &[&target, &data.offline_queue.len()],
+            ),
+        )
+    } else {
+        (Tone::Error, tr_args("Disconnected from {}", &[&target]))
+    }
+}
+
+/// Run state of a replay: connecting, running, stopped, finished, or failed.
+fn replay_status(data: &ReplayData) -> (Tone, String) {
+    let target = format!("{}:{}", data.address, data.port);
+    match data.status {
+        ReplayStatus::Running if !data.connected => {
+            (Tone::Warning, tr_args("Connecting to {}…", &[&target]))
+        }
+        ReplayStatus::Running if data.stress => (
+            Tone::Success,
+            tr_args("Running stress against {}", &[&target]),
+        ),
+        ReplayStatus::Running => (
+            Tone::Success,
+            tr_args(
+                "Running against {}, payload {}/{}",
+                &[&target, &data.current_index, &data.payloads.len()],
+            ),
+        ),
+        ReplayStatus::Stopped => (
+            Tone::Warning,
+            tr_args(
+                "Stopped at payload {}/{}",
+                &[&data.current_index, &data.payloads.len()],
+            ),
+        ),
+        ReplayStatus::Finished => match &data.failure {
+            Some(failure) => (Tone::Error, tr_args("Failed: {}", &[failure])),
+            None => (Tone::Normal, tr_args("Finished against {}", &[&target])),
+        },
+    }
+}
+
+/// "Connecting to host:port…" with an animated indicator (static text when motion is
+/// reduced), the time spent so far, and a button giving up on the attempt.
+fn connecting_view<'a>(
+    target: String,
+    elapsed: std::time::Duration,
+    cancel: Message,
+    reduced_motion: bool,
+) -> Element<'a, Message> {
+    // With reduced motion a still dot stands in for the pulsing ones
+    let frame = (elapsed.as_millis() / 300 % 3) as usize;
+    let spinner = if reduced_motion {
+        row![text("●").style(Tone::Warning.style())]
+    } else {
+        (0..3).fold(row![].spacing(3), |spinner, dot| {
+            spinner.push(text("●").size(10).style(if dot == frame {
+                Tone::Warning.style()
+            } else {
+                Tone::Muted.style()
+            }))
+        })
+    };
+    row![
+        spinner,
+        text(tr_args(
+            "Connecting to {}… {} s",
+            &[&target, &elapsed.as_secs()],
+        )),
+        button(tr("Cancel")).on_press(cancel),
+    ]
+    .spacing(10)
+    .align_y(iced::Alignment::Center)
+    .into()
+}
+
+/// Connection state of a session: the connecting indicator during an attempt, the status
+/// otherwise, with a Retry button after a failed attempt.
+fn session_connection_view<'a>(
+    id: window::Id,
+    data: &SessionData,
+    reduced_motion: bool,
+) -> Element<'a, Message> {
+    if data.connecting {
+        return connecting_view(
+            format!("{}:{}", data.address, data.port),
+            data.connect_started
+                .map_or(std::time::Duration::ZERO, |started| started.elapsed()),
+            Message::Disconnect(id),
+            reduced_motion,
+        );
+    }
+    let retry = (!data.connected && data.connect_error.is_some())
+        .then(|| button(tr("Retry")).on_press(Message::Reconnect(id)));
+    row![status_view(session_status(data))]
+        .push_maybe(retry)
+        .spacing(10)
+        .align_y(iced::Alignment::Center)
+        .into()
+}
+
+/// Run state of a replay: the connecting indicator until it connects, the status
+/// otherwise, with a Retry button after a failed run.
+fn replay_connection_view<'a>(
+    id: window::Id,
+    data: &ReplayData,
+    reduced_motion: bool,
+) -> Element<'a, Message> {
+    if data.status == ReplayStatus::Running && !data.connected {
+        return connecting_view(
+            format!("{}:{}", data.address, data.port),
+            data.started_at
+                .and_then(|started| (chrono::Local::now() - started).to_std().ok())
+                .unwrap_or_default(),
+            Message::StopReplay(id),
+            reduced_motion,
+        );
+    }
+    let retry = (data.status == ReplayStatus::Finished && data.failure.is_some())
+        .then(|| button(tr("Retry")).on_press(Message::StartReplay(id)));
+    row![status_view(replay_status(data))]
+        .push_maybe(retry)
+        .spacing(10)
+        .align_y(iced::Alignment::Center)
+        .into()
+}
+
+/// Byte count under a payload input, e.g. "17 bytes", as a warning when a UDP payload may
+/// be fragmented and as an error when it does not decode or fit a datagram.
+fn payload_size_view<'a>(
+    payload: &str,
+    payload_type: PayloadType,
+    protocol: crate::types::Protocol,
+    udp_safe: usize,
+) -> Element<'a, Message> {
+    let (tone, readout) =
+        match crate::validate::payload_size(payload, payload_type, protocol, udp_safe) {
+            Ok((size, None)) => (Tone::Muted, format!("{} bytes", size)),
+            Ok((size, Some(warning))) => (Tone::Warning, format!("{} bytes, {}", size, warning)),
+            Err(e) => (Tone::Error, e),
+        };
+    text(readout).size(12).style(tone.style()).into()
+}
+
+/// Tooltip of the Send button: the bytes the payload input is sent as, or why it cannot
+/// be sent.
+fn send_preview_view<'a>(data: &SessionData, config: &Config) -> Element<'a, Message> {
+    let preview = match crate::validate::send_preview(&data.payload_input, data.payload_type) {
+        Ok(preview) => text(preview).font(config.log_font()),
+        Err(e) => text(e).style(Tone::Error.style()),
+    };
+    container(preview)
+        .padding(5)
+        .style(container::bordered_box)
+        .into()
+}
+
+/// Text input style with the border in the error tone while the input is invalid.
+fn input_style(invalid: bool) -> impl Fn(&Theme, text_input::Status) -> text_input::Style {
+    move |theme, status| {
+        let mut style = text_input::default(theme, status);
+        if high_contrast(theme) {
+            style.border.color = theme.extended_palette().background.base.text;
+        }
+        if let Some(color) = Tone::Error.color(theme).filter(|_| invalid) {
+            style.border.color = color;
+            style.border.width = 2.0;
+        }
+        style
+    }
+}
+
+/// A status line led by a dot in the status's tone.
+fn status_view<'a>((tone, status): (Tone, String)) -> Element<'a, Message> {
+    row![text("●").style(tone.style()), text(status)]
+        .spacing(5)
+        .align_y(iced::Alignment::Center)
+        .into()
+}
+
+/// Whether `theme` is one of the high-contrast themes, the only custom ones.
+fn high_contrast(theme: &Theme) -> bool {
+    matches!(theme, Theme::Custom(_))
+}
+
+/// Background of log and report panes, set off a little from the window background. In
+/// high contrast the background stays pure black or white and a border sets them off.
+fn pane_style(theme: &Theme) -> container::Style {
+    let palette = theme.extended_palette();
+    if high_contrast(theme) {
+        return container::Style {
+            background: Some(Background::Color(palette.background.base.color)),
+            border: iced::Border {
+                color: palette.background.base.text,
+                width: 1.0,
+                radius: 0.0.into(),
+            },
+            ..Default::default()
+        };
+    }
+    container::Style {
+        background: Some(Background::Color(palette.background.weak.color)),
+        ..Default::default()
+    }
+}
+
+/// Renders a log pane. Only the rows near the viewport are laid out, with empty space
+/// standing in for the rest, so logs with many thousands of entries scroll smoothly.
+fn log_view<'a>(
+    id: window::Id,
+    log: &crate::log::Log,
+    filter: crate::log::LogFilter,
+    search: &crate::log::LogSearch,
+    scroll: crate::types::LogScroll,
+    log_text: LogText,
+) -> Element<'a, Message> {
+    let row_height = log_text.row_height;
+    let count = log.row_count(filter, search);
+    // Until the view is drawn its height is unknown, so a screenful is laid out
+    let height = if scroll.height > 0.0 {
+        scroll.height
+    } else {
+        1000.0
+    };
+    let first = ((scroll.offset.y / row_height) as usize)
+        .saturating_sub(LOG_OVERSCAN)
+        .min(count);
+    let last = (first + (height / row_height).ceil() as usize + 2 * LOG_OVERSCAN).min(count);
+    let rows = log.rows_in(filter, search, first..last);
+    let shown = rows.iter().enumerate().map(|(offset, row)| {
+        let index = first + offset;
+        let line = text(crate::log::expand_tabs(row.to_string()))
+            .font(log_text.font)
+            .size(log_text.size)
+            .line_height(iced::widget::text::LineHeight::Absolute(row_height.into()))
+            .wrapping(iced::widget::text::Wrapping::None);
+        // The actions stay at the left edge of the view when it is scrolled sideways
+        let line: Element<'a, Message> = if scroll.hovered == Some(index) {
+            iced::widget::stack![
+                line,
+                row![
+                    Space::with_width(scroll.offset.x),
+                    log_row_actions(row, log_text)
+                ]
+            ]
+            .into()
+        } else {
+            line.into()
+        };
+        iced::widget::mouse_area(line)
+            .on_enter(Message::LogRowHovered(id, index))
+            .on_exit(Message::LogRowLeft(id, index))
+            .into()
+    });
+    container(
+        scrollable(column![
+            Space::with_height(first as f32 * row_height),
+            column(shown),
+            Space::with_height((count - last) as f32 * row_height),
+        ])
+        .direction(scrollable::Direction::Both {
+            vertical: Default::default(),
+            horizontal: Default::default(),
+        })
+        .id(log_scroll_id(id))
+        .on_scroll(move |viewport| Message::LogScrolled(id, viewport))
+        .height(Length::Fill)
+        .width(Length::Fill),
+    )
+    .style(pane_style)
+    .height(Length::Fill)
+    .width(Length::Fill)
+    .into()
+}
+
+/// Buttons copying a log row: its text, and for packet lines the bytes as spaced hex or
+/// as ASCII with other bytes escaped, e.g. `GET /\r\n\x00`.
+fn log_row_actions<'a>(row: &crate::log::LogRow, log_text: LogText) -> Element<'a, Message> {
+    let action = |label: &'static str, copied: String| {
+        button(text(tr(label)).size(log_text.size * 0.75).line_height(
+            iced::widget::text::LineHeight::Absolute(log_text.row_height.into()),
+        ))
+        .padding([0, 6])
+        .on_press(Message::CopyLogRow(copied))
+    };
+    let mut actions = row![action("Copy", row.to_string())].spacing(2);
+    if let Some(packet) = row.packet() {
+        let hex = packet
+            .data
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(" ");
+        actions = actions
+            .push(action("Hex", hex))
+            .push(action("ASCII", packet.data.escape_ascii().to_string()));
+    }
+    actions.into()
+}
+
+/// Font, size, and row height of log text and payload previews in a window.
+#[derive(Clone, Copy)]
+struct LogText {
+    font: iced::Font,
+    size: f32,
+    row_height: f32,
+}
+
+impl LogText {
+    fn new(config: &Config, zoom: crate::types::LogZoom) -> Self {
+        Self {
+            font: config.log_font(),
+            size: zoom.text_size(config.log_font_size),
+            row_height: zoom.row_height(config.log_font_size),
+        }
+    }
+
+    /// Height of a row in a replay window's payload table.
+    fn payload_row_height(&self) -> f32 {
+        (PAYLOAD_ROW_HEIGHT * self.size / 16.0).ceil()
+    }
+}
+
+/// Changes the zoom of a window's log, keeping the rows in view and the newest entry
+/// in view if the log follows it.
+fn zoom_log(
+    id: window::Id,
+    zoom: &mut crate::types::LogZoom,
+    scroll: &mut crate::types::LogScroll,
+    change: crate::types::ZoomChange,
+) -> Task<Message> {
+    let factor = zoom.change(change);
+    scroll.offset.x *= factor;
+    scroll.offset.y = if scroll.follow {
+        f32::MAX
+    } else {
+        scroll.offset.y * factor
+    };
+    scrollable::scroll_to(log_scroll_id(id), scroll.offset)
+}
+
+/// Keyboard shortcuts pressed in a window: Ctrl+=, Ctrl+- and Ctrl+0 zoom the log text,
+/// Ctrl+Tab and Ctrl+Shift+Tab switch tabs, and Ctrl+P opens the command palette.
+pub fn shortcut(
+    id: window::Id,
+    key: &iced::keyboard::Key,
+    modifiers: iced::keyboard::Modifiers,
+) -> Option<Message> {
+    use crate::types::ZoomChange;
+    use iced::keyboard::Key;
+    if !modifiers.command() {
+        return None;
+    }
+    match key.as_ref() {
+        Key::Character("=" | "+") => Some(Message::LogZoomed(id, ZoomChange::In)),
+        Key::Character("-") => Some(Message::LogZoomed(id, ZoomChange::Out)),
+        Key::Character("0") => Some(Message::LogZoomed(id, ZoomChange::Reset)),
+        Key::Character("p") => Some(Message::OpenPalette(id)),
+        Key::Named(iced::keyboard::key::Named::Tab) => {
+            Some(Message::CycleTab(id, !modifiers.shift()))
+        }
+        _ => None,
+    }
+}
+
+/// Rows laid out above and below the viewport of a log, so scrolling does not show gaps
+/// before the view catches up.
+const LOG_OVERSCAN: usize = 20;
+
+fn log_scroll_id(id: window::Id) -> scrollable::Id {
+    scrollable::Id::new(format!("log-{:?}", id))
+}
+
+/// Scrolls a log view to the newest entry if entries were added while it followed the
+/// log.
+fn follow_log(id: window::Id, scroll: &mut crate::types::LogScroll, len: usize) -> Task<Message> {
+    // A hovered row keeps its place; the view catches up once the cursor leaves
+    if scroll.hovered.is_some() {
+        return Task::none();
+    }
+    let added = len > scroll.seen;
+    scroll.seen = len;
+    if added && scroll.follow {
+        scrollable::scroll_to(
+            log_scroll_id(id),
+            scrollable::AbsoluteOffset {
+                x: scroll.offset.x,
+                y: f32::MAX,
+            },
+        )
+    } else {
+        Task::none()
+    }
+}
+
+/// Gap between the controls and the log of a session window.
+const SPLIT_SPACING: f32 = 6.0;
+
+/// Extra height around the gap where the split can be grabbed, reaching over the grip.
+const SPLIT_LEEWAY: f32 = 16.0;
+
+/// Renders the grip at the top of a session window's log. Dragging it moves the split and
+/// a double-click resets it.
+fn split_grip<'a>(id: window::Id) -> Element<'a, Message> {
+    iced::widget::mouse_area(
+        container(container(iced::widget::horizontal_rule(2)).width(Length::Fixed(40f32)))
+            .center_x(Length::Fill)
+            .padding([3, 0]),
+    )
+    .interaction(iced::mouse::Interaction::ResizingVertically)
+    .on_press(Message::SplitPressed(id))
+    .into()
+}
+
+/// Renders the level and packet toggles of a log. Hidden entries are kept and show up
+/// again when their level is ticked.
+fn log_filter_view<'a>(
+    id: window::Id,
+    filter: crate::log::LogFilter,
+    zoom: crate::types::LogZoom,
+) -> Element<'a, Message> {
+    use crate::log::LogLevel;
+    row![
+        text(tr("Show:")),
+        checkbox("Debug", filter.debug).on_toggle(move |on| Message::LogFilterChanged(
+            id,
+            LogLevel::Debug,
+            on
+        )),
+        checkbox("Info", filter.info).on_toggle(move |on| Message::LogFilterChanged(
+            id,
+            LogLevel::Info,
+            on
+        )),
+        checkbox("Warn", filter.warn).on_toggle(move |on| Message::LogFilterChanged(
+            id,
+            LogLevel::Warn,
+            on
+        )),
+        checkbox("Error", filter.error).on_toggle(move |on| Message::LogFilterChanged(
+            id,
+            LogLevel::Error,
+            on
+        )),
+        checkbox(tr("Packets"), filter.packets)
+            .on_toggle(move |on| Message::LogPacketsShown(id, on)),
+    ]
+    .push_maybe(
+        zoom.shown()
+            .then(|| text(tr_args("Zoom {}%", &[&zoom.percent])).style(Tone::Info.style())),
+    )
+    .push_maybe((zoom.percent != 100).then(|| {
+        button(tr("Reset zoom")).on_press(Message::LogZoomed(id, crate::types::ZoomChange::Reset))
+    }))
+    .spacing(10)
+    .align_y(iced::Alignment::Center)
+    .into()
+}
+
+/// Payload input that sends the bytes under the payload type: spaced hex (`de ad be ef`),
+/// or text with other bytes escaped (`GET /\r\n\x00`).
+fn typed_payload(bytes: &[u8], payload_type: PayloadType) -> String {
+    match payload_type {
+        PayloadType::Hex => bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(" "),
+        PayloadType::Ascii => bytes.escape_ascii().to_string(),
+    }
+}
+
+/// Formats a byte count for display, e.g. "980 B" or "1.4 KB".
+fn format_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+/// Renders the collapsible list of recorded payloads of a session window. The summary stays
+/// visible while the list is collapsed.
+fn recording_view(id: window::Id, data: &SessionData, log_text: LogText) -> Element<'_, Message> {
+    let (count, bytes) = data.recording_summary();
+    let unsent = data
+        .replay_payloads
+        .iter()
+        .filter(|p| p.recorded_only)
+        .count();
+    let header = row![
+        button(if data.show_recording {
+            tr("▾ Recording")
+        } else {
+            tr("▸ Recording")
+        })
+        .on_press(Message::RecordingToggled(id)),
+        if data.recording_paused {
+            text(tr("❚❚ PAUSED")).style(Tone::Muted.style())
+        } else {
+            text(tr("● REC")).style(Tone::Error.style())
+        },
+        text(match unsent {
+            0 => tr_args("{} payloads / {}", &[&count, &format_size(bytes)]),
+            unsent => tr_args(
+                "{} payloads / {} ({} not sent)",
+                &[&count, &format_size(bytes), &unsent],
+            ),
+        }),
+        button(if data.recording_paused {
+            tr("Resume")
+        } else {
+            tr("Pause")
+        })
+        .on_press(Message::RecordingPauseToggled(id)),
+        tooltip(
+            button(tr("Undo last recorded")).on_press_maybe(
+                (!data.replay_payloads.is_empty()).then_some(Message::UndoLastRecorded(id))
+            ),
+            tr("Remove the newest payload from the recording only"),
+            tooltip::Position::FollowCursor,
+        ),
+    ]
+    .spacing(10)
+    .align_y(iced::Alignment::Center);
+    if !data.show_recording {
+        return header.into();
+    }
+    let last = data.replay_payloads.len().saturating_sub(1);
+    let rows = data
+        .replay_payloads
+        .iter()
+        .enumerate()
+        .map(|(index, payload)| {
+            let edit = move |edit: RecordingEdit| Message::RecordingChanged(id, index, edit);
+            let preview: String = payload.payload.chars().take(48).collect();
+            let preview = match &payload.label {
+                Some(label) => format!("[{}] {}", label, preview),
+                None => preview,
+            };
+            row![
+                text(format!("{}.", index + 1))
+                    .size(log_text.size)
+                    .width(Length::Fixed(40f32)),
+                text(format!("{:?}", payload.payload_type))
+                    .size(log_text.size)
+                    .width(Length::Fixed(45f32)),
+                text(if payload.recorded_only {
+                    format!("{} (not sent)", preview)
+                } else {
+                    preview
+                })
+                .font(log_text.font)
+                .size(log_text.size)
+                .width(Length::Fill),
+                text_input("0", &payload.delay.to_string())
+                    .on_input(move |s| edit(RecordingEdit::Delay(s)))
+                    .width(Length::Fixed(70f32)),
+                text(tr("ms")),
+                button("↑").on_press_maybe((index > 0).then(|| edit(RecordingEdit::MoveUp))),
+                button("↓").on_press_maybe((index < last).then(|| edit(RecordingEdit::MoveDown))),
+                button(tr("Remove")).on_press(edit(RecordingEdit::Remove)),
+            ]
+            .spacing(5)
+            .align_y(iced::Alignment::Center)
+            .into()
+        });
+    column![
+        header,
+        scrollable(column(rows).spacing(2)).height(Length::Fixed(160f32)),
+    ]
+    .spacing(5)
+    .into()
+}
+
+/// Renders the collapsible batch editor of a session window.
+fn batch_view(id: window::Id, data: &SessionData) -> Element<'_, Message> {
+    let toggle = button(if data.show_batch {
+        tr("▾ Batch")
+    } else {
+        tr("▸ Batch")
+    })
+    .on_press(Message::BatchToggled(id));
+    if !data.show_batch {
+        return toggle.into();
+    }
+    let can_send = data.connected && data.replay_cancel.is_none() && data.repeat_cancel.is_none();
+    column![
+        toggle,
+        iced::widget::text_editor(&data.batch.0)
+            .placeholder(tr("One payload per line"))
+            .on_action(move |action| Message::BatchEdited(id, action))
+            .height(Length::Fixed(100f32)),
+        row![
+            text(tr("Every")),
+            text_input("100", &data.batch_interval_ms)
+                .on_input(move |s| Message::BatchIntervalChanged(id, s))
+                .width(Length::Fixed(70f32)),
+            text(tr("ms")),
+            button(tr("Send each line")).on_press_maybe(can_send.then_some(Message::SendBatch(id))),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center),
+    ]
+    .spacing(5)
+    .into()
+}
+
+/// Updates the application state, then appends new log lines to the windows' log files.
+pub fn update(state: &mut App, message: Message) -> Task<Message> {
+    let task = update_app(state, message);
+    for window_data in state.windows.values_mut() {
+        let Window {
+            log_file: Some(log_file),
+            state: window_state,
+            log_filter,
+            ..
+        } = window_data
+        else {
+            continue;
+        };
+        let log = match window_state {
+            WindowState::Session(data) => &data.log,
+            WindowState::Replay(data) => &data.log,
+        };
+        if let Some(error) = log_file.mirror(log, log_filter.all_levels()) {
+            window_data.push_log(error);
+        }
+    }
+    let mut tasks = vec![task];
+    for (id, window_data) in &mut state.windows {
+        let len = window_data.log().added();
+        tasks.push(follow_log(*id, &mut window_data.log_scroll, len));
+    }
+    tasks.push(follow_log(
+        state.main_window_id,
+        &mut state.main_log_scroll,
+        state.main_log.added(),
+    ));
+    // A failed config save shows until dismissed or saving works again
+    match (crate::config::save_error(), state.save_error_banner) {
+        (Some(e), None) => {
+            state.save_error_banner = Some(state.main_banners.raise(
+                crate::log::LogLevel::Error,
+                &format!("Settings are not being saved: {}", e),
+            ));
+        }
+        (None, Some(banner)) => {
+            state.main_banners.dismiss(banner);
+            state.save_error_banner = None;
+        }
+        _ => {}
+    }
+    crate::syslog::configure(&state.config);
+    crate::tray::configure(&state.config);
+    crate::log::set_debug(state.config.debug_log);
+    crate::log::set_collapse(
+        state.config.log_collapse_threshold,
+        state.config.log_collapse_packets,
+    );
+    crate::log::set_max_entries(state.config.log_max_entries);
+    crate::log::set_coalesce_progress(state.config.coalesce_progress);
+    crate::log::set_forwarding(state.config.forward_session_logs, || {
+        state
+            .windows
+            .iter()
+            .map(|(id, window_data)| (*id, window_data.short_name()))
+            .collect()
+    });
+    Task::batch(tasks)
+}
+
+/// Updates the application state based on incoming messages.
+/// Handles user interactions, system events, and async task results.
+pub fn update_app(state: &mut App, message: Message) -> Task<Message> {
+    match message {
+        // Update address in config and save
+        Message::AddressChanged(id, addr) => {
+            if id == state.main_window_id {
+                state.config.address = addr;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Update port in config and save
+        Message::PortChanged(id, port) => {
+            if id == state.main_window_id {
+                state.port_input = port;
+                if state.resolve_port_input() {
+                    crate::config::save_config(&state.config);
+                }
+            }
+            Task::none()
+        }
+        // Fill in the port of a commonly used service
+        Message::ServicePicked(id, service) => {
+            if id == state.main_window_id {
+                state.port_input = service.port.to_string();
+                state.port_error = None;
+                state.config.port = service.port;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Update initial payload in config and save
+        Message::InitialPayloadChanged(id, payload) => {
+            if id == state.main_window_id {
+                state.config.initial_payload = payload;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Update initial payload type in config and save
+        Message::InitialPayloadTypeChanged(id, payload_type) => {
+            if id == state.main_window_id {
+                state.config.initial_payload_type = payload_type;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Update protocol in config and save
+        Message::ProtocolChanged(id, protocol) => {
+            if id == state.main_window_id {
+                state.config.protocol = protocol;
+                // Service names can map to different ports per protocol
+                state.resolve_port_input();
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Update replay loop count in config and save once it is valid
+        Message::ReplayLoopsChanged(id, input) => {
+            if id == state.main_window_id {
+                if let Ok(loops) = crate::config::parse_loops(&input) {
+                    state.config.replay_loops = loops;
+                    crate::config::save_config(&state.config);
+                }
+                state.number_inputs.replay_loops = input;
+            }
+            Task::none()
+        }
+        // Update replay ramp mode in config and save
+        Message::RampModeChanged(id, mode) => {
+            if id == state.main_window_id {
+                state.config.replay_ramp_mode = mode;
+                if let Ok(value) =
+                    crate::config::parse_ramp(&state.number_inputs.replay_ramp_value, mode)
+                {
+                    state.config.replay_ramp_value = value;
+                }
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Update replay ramp value in config and save once it is valid
+        Message::RampValueChanged(id, input) => {
+            if id == state.main_window_id {
+                if let Ok(value) = crate::config::parse_ramp(&input, state.config.replay_ramp_mode)
+                {
+                    state.config.replay_ramp_value = value;
+                    crate::config::save_config(&state.config);
+                }
+                state.number_inputs.replay_ramp_value = input;
+            }
+            Task::none()
+        }
+        // Update replay ramp floor in config and save once it is valid
+        Message::RampFloorChanged(id, input) => {
+            if id == state.main_window_id {
+                if let Ok(floor) = crate::validate::at_least(&input, 0) {
+                    state.config.replay_ramp_floor_ms = floor;
+                    crate::config::save_config(&state.config);
+                }
+                state.number_inputs.replay_ramp_floor_ms = input;
+            }
+            Task::none()
+        }
+        // Open a new session window and start connection task
+        Message::Connect(id) => {
+            if id == state.main_window_id && state.input_errors().iter().all(Option::is_none) {
+                state.last_addr = state.config.address.clone();
+                state.last_port = state.config.port.to_string();
+                state.last_handshake = state.config.handshake();
+                state.last_protocol = state.config.protocol;
+                state.last_label = None;
+                state.last_input.clear();
+
+                let (new_id, task) = state.open_window(WindowKind::Session);
+
+                Task::batch(vec![
+                    task.map(move |_| Message::WindowOpened(new_id)),
+                    if matches!(state.config.protocol, crate::types::Protocol::Tcp) {
+                        Task::perform(async move { true }, move |_| {
+                            Message::ConnectionStatus(new_id, true)
+                        })
+                    } else {
+                        Task::none()
+                    },
+                ])
+            } else {
+                Task::none()
+            }
+        }
+        // Open a new session window with the same target and settings as an existing one
+        Message::DuplicateSession(id) => {
+            if let Some(window_data) = state.windows.get(&id)
+                && let WindowState::Session(data) = &window_data.state
+            {
+                let base = window_data.label.clone().unwrap_or_else(|| {
+                    format!("{:?} {}:{}", data.protocol, data.address, data.port)
+                });
+                let base = match base.rsplit_once(" #") {
+                    Some((stem, n)) if n.parse::<u32>().is_ok() => stem.to_string(),
+                    _ => base,
+                };
+                let label = (2..)
+                    .map(|n| format!("{} #{}", base, n))
+                    .find(|label| {
+                        !state
+                            .windows
+                            .values()
+                            .any(|w| w.label.as_deref() == Some(label.as_str()))
+                    })
+                    .unwrap_or(base);
+                state.last_addr = data.address.clone();
+                state.last_port = data.port.clone();
+                state.last_handshake = data.handshake.clone();
+                state.last_protocol = data.protocol;
+                state.last_label = Some(label);
+                state.last_input = data.payload_input.clone();
+                let payload_type = data.payload_type;
+                let (new_id, task) = state.open_window(WindowKind::Session);
+                task.map(move |_| Message::WindowOpened(new_id))
+                    .chain(Task::done(Message::PayloadTypeChanged(
+                        new_id,
+                        payload_type,
+                    )))
+            } else {
+                Task::none()
+            }
+        }
+        // Initialize new session window with data and start TCP task if applicable
+        Message::WindowOpened(id) => {
+            let (tx, rx) = mpsc::channel(100);
+            let data = SessionData {
+                payload_input: std::mem::take(&mut state.last_input),
+                sender: Some(tx),
+                handshake: state.last_handshake.clone(),
+                ..SessionData::new(
+                    state.last_protocol,
+                    state.last_addr.clone(),
+                    state.last_port.clone(),
+                    &state.config,
+                )
+            };
+            let options = data.tcp_options(state.config.stall_warning());
+            let addr = data.address.clone();
+            let port = data.port.clone();
+            state.windows.insert(
+                id,
+                Window {
+                    label: state.last_label.take(),
+                    label_edit: None,
+                    title: data.title(),
+                    state: Session(Box::new(data)),
+                    log_file: None,
+                    log_filter: Default::default(),
+                    log_scroll: Default::default(),
+                    log_zoom: Default::default(),
+                    banners: Default::default(),
+                },
+            );
+            if state.config.log_to_file {
+                auto_log_file(state, id);
+            }
+            match state.last_protocol {
+                crate::types::Protocol::Tcp => Task::perform(
+                    async move {
+                        crate::session::tcp_task(rx, addr, port, options, id).await;
+                    },
+                    |_| Message::NoOp,
+                ),
+                crate::types::Protocol::Udp => Task::perform(
+                    crate::udp::udp_task(rx, addr, port, options.packet_seq, id),
+                    |_| Message::NoOp,
+                ),
+            }
+        }
+        // Ask the session task to close the connection or abandon a pending attempt,
+        // keeping the window and its recordings
+        Message::Disconnect(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+                && let Some(sender) = data.sender.take()
+            {
+                for cancel in [&data.replay_cancel, &data.repeat_cancel]
+                    .into_iter()
+                    .flatten()
+                {
+                    cancel.cancel();
+                }
+                Task::perform(
+                    async move {
+                        let _ = sender.send(SessionCommand::Disconnect).await;
+                    },
+                    |_| Message::NoOp,
+                )
+            } else {
+                Task::none()
+            }
+        }
+        // Start a fresh session task to the target the session was opened with
+        Message::Reconnect(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+                && !data.connected
+                && !data.connecting
+            {
+                let (tx, rx) = mpsc::channel(100);
+                data.sender = Some(tx);
+                data.connecting = true;
+                data.connect_started = Some(std::time::Instant::now());
+                data.connect_error = None;
+                window_data.title = data.title();
+                data.last_packet_time = None;
+                let addr = data.address.clone();
+                let port = data.port.clone();
+                let options = data.tcp_options(state.config.stall_warning());
+                data.writing = false;
+                match data.protocol {
+                    crate::types::Protocol::Tcp => Task::perform(
+                        async move {
+                            crate::session::tcp_task(rx, addr, port, options, id).await;
+                        },
+                        |_| Message::NoOp,
+                    ),
+                    crate::types::Protocol::Udp => Task::perform(
+                        crate::udp::udp_task(rx, addr, port, options.packet_seq, id),
+                        |_| Message::NoOp,
+                    ),
+                }
+            } else {
+                Task::none()
+            }
+        }
+        // Update payload input in session data
+        Message::InputChanged(id, hex) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.payload_input = hex;
+            }
+            Task::none()
+        }
+        // Update payload type
+        Message::PayloadTypeChanged(id, payload_type) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.payload_type = payload_type;
+            }
+            Task::none()
+        }
+        // Send on Enter, explaining in the log why nothing was sent when sending is unavailable
+        Message::SubmitPayload(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                let reason = if data.replay_cancel.is_some() {
+                    Some("Replay running, payload not sent".to_string())
+                } else if let Err(e) =
+                    crate::validate::payload(&data.payload_input, data.payload_type)
+                {
+                    Some(format!("{}, payload not sent", e))
+                } else if !data.connected && !data.queue_offline {
+                    Some(format!("{}, payload not sent", session_status(data).1))
+                } else {
+                    None
+                };
+                if let Some(reason) = reason {
+                    data.log.push(crate::log::LogLevel::Warn, &reason);
+                    return Task::none();
+                }
+            }
+            update_app(state, Message::SendPacket(id))
+        }
+        // Toggle clearing the payload input after a successful send
+        Message::ClearOnSendToggled(id, clear) => {
+            if id == state.main_window_id {
+                state.config.clear_input_on_send = clear;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Remember window positions; saved when a window closes or the app exits
+        Message::WindowMoved(id, position) => {
+            if let Some(geometry) = state.geometry_mut(id) {
+                geometry.x = Some(position.x);
+                geometry.y = Some(position.y);
+            }
+            Task::none()
+        }
+        // Remember window sizes; saved when a window closes or the app exits
+        Message::WindowResized(id, size) => {
+            if let Some(geometry) = state.geometry_mut(id) {
+                geometry.width = size.width;
+                geometry.height = size.height;
+            }
+            Task::none()
+        }
+        // Toggle offsetting new windows from the ones already open
+        Message::CascadeWindowsToggled(id, cascade) => {
+            if id == state.main_window_id {
+                state.config.cascade_windows = cascade;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Toggle the tray icon; once shown, it stays until the app exits
+        Message::TrayIconToggled(id, on) => {
+            if id == state.main_window_id {
+                state.config.tray_icon = on;
+                crate::config::save_config(&state.config);
+                if on {
+                    crate::tray::start(&state.config);
+                }
+            }
+            Task::none()
+        }
+        // Toggle hiding the main window to the tray instead of exiting when it is closed
+        Message::CloseToTrayToggled(id, on) => {
+            if id == state.main_window_id {
+                state.config.close_to_tray = on;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        Message::StartTray => {
+            crate::tray::start(&state.config);
+            Task::none()
+        }
+        // Carry out an action chosen in the tray menu
+        Message::Tray(action) => {
+            let main = state.main_window_id;
+            match action {
+                crate::tray::TrayAction::ToggleWindow if state.main_hidden => {
+                    state.main_hidden = false;
+                    window::change_mode(main, window::Mode::Windowed)
+                        .chain(window::gain_focus(main))
+                }
+                crate::tray::TrayAction::ToggleWindow => {
+                    state.main_hidden = true;
+                    window::change_mode(main, window::Mode::Hidden)
+                }
+                crate::tray::TrayAction::Connect(target) => {
+                    let select = update_app(state, Message::RecentTargetSelected(main, target));
+                    Task::batch([select, update_app(state, Message::Connect(main))])
+                }
+                crate::tray::TrayAction::ReplayLast => match state.config.recent_replays.first() {
+                    Some(recent) if recent.path.is_file() => {
+                        update_app(state, Message::ReplayRecent(recent.clone()))
+                    }
+                    _ => {
+                        state.raise(
+                            main,
+                            crate::log::LogLevel::Warn,
+                            tr("No recent replay to run"),
+                        );
+                        Task::none()
+                    }
+                },
+                crate::tray::TrayAction::Quit => update_app(state, Message::Quit),
+            }
+        }
+        // Toggle tabbed mode; windows already open stay as they are
+        Message::TabbedWindowsToggled(id, tabbed) => {
+            if id == state.main_window_id {
+                state.config.tabbed_windows = tabbed;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Show a tab, or the main window's own view
+        Message::SelectTab(tab) => {
+            state.active_tab = tab.filter(|tab| state.tabs.contains(tab));
+            Task::none()
+        }
+        // Ctrl+Tab goes through the tabs, the main window's own view included
+        Message::CycleTab(id, forward) => {
+            if id != state.main_window_id || state.tabs.is_empty() {
+                return Task::none();
+            }
+            let views: Vec<Option<window::Id>> = std::iter::once(None)
+                .chain(state.tabs.iter().copied().map(Some))
+                .collect();
+            let current = views
+                .iter()
+                .position(|view| *view == state.active_tab)
+                .unwrap_or(0);
+            let next = if forward {
+                (current + 1) % views.len()
+            } else {
+                (current + views.len() - 1) % views.len()
+            };
+            state.active_tab = views[next];
+            Task::none()
+        }
+        // Switch the color theme of every window
+        Message::ThemeChanged(id, theme) => {
+            if id == state.main_window_id {
+                state.config.theme = theme;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Every window shows the new language on its next redraw
+        Message::LanguageChanged(id, language) => {
+            if id == state.main_window_id {
+                state.config.language = language;
+                crate::i18n::set_language(language);
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Every window redraws in the high-contrast variant of its theme, or back
+        Message::HighContrastToggled(id, on) => {
+            if id == state.main_window_id {
+                state.config.accessibility.high_contrast = on;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Connection attempts show static text instead of the animated indicator
+        Message::ReducedMotionToggled(id, on) => {
+            if id == state.main_window_id {
+                state.config.accessibility.reduced_motion = on;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Write the whole configuration to a TOML or JSON file
+        Message::ExportSettings(id) => {
+            if id != state.main_window_id {
+                return Task::none();
+            }
+            let config = state.config.clone();
+            Task::perform(
+                async move {
+                    let Some(path) = dialog(|| {
+                        rfd::FileDialog::new()
+                            .set_title(tr("Export settings"))
+                            .add_filter("TOML", &["toml"])
+                            .add_filter("JSON", &["json"])
+                            .set_file_name("replayr-settings.toml")
+                            .save_file()
+                    })
+                    .await?
+                    else {
+                        return Ok(None);
+                    };
+                    let contents = crate::config::export_settings(&config, &path)?;
+                    tokio::fs::write(&path, contents)
+                        .await
+                        .map_err(|e| format!("{}: {}", path.display(), e))?;
+                    Ok(Some(path))
+                },
+                Message::SettingsExported,
+            )
+        }
+        // Report where the settings went, and flag payloads that may hold credentials
+        Message::SettingsExported(result) => {
+            match result {
+                Ok(Some(path)) => {
+                    state.main_log.push(
+                        crate::log::LogLevel::Info,
+                        &format!("Settings exported to {}", path.display()),
+                    );
+                    let payloads = state.config.handshake().steps.len();
+                    if payloads > 0 {
+                        state.main_log.push(
+                            crate::log::LogLevel::Warn,
+                            &format!(
+                                "The export includes {} handshake payloads, which may contain credentials",
+                                payloads
+                            ),
+                        );
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => state.raise(
+                    state.main_window_id,
+                    crate::log::LogLevel::Error,
+                    &format!("Settings export failed: {}", e),
+                ),
+            }
+            Task::none()
+        }
+        // Read a settings file and confirm the changes before applying them
+        Message::ImportSettings(id) => {
+            if id != state.main_window_id {
+                return Task::none();
+            }
+            let current = state.config.clone();
+            Task::perform(import_settings(current), Message::SettingsImported)
+        }
+        // Apply confirmed settings and refresh the state derived from them
+        Message::SettingsImported(result) => {
+            match result {
+                Ok(Some(import)) => {
+                    state.config = import.config;
+                    state.sync_inputs();
+                    let _ = crate::log::set_timestamp_format(
+                        &state.config.log_timestamp_format,
+                        state.config.log_timestamp_utc,
+                    );
+                    crate::config::save_config(&state.config);
+                    state.main_log.push(
+                        crate::log::LogLevel::Info,
+                        &format!("Settings imported, {} changed", import.changes.len()),
+                    );
+                    for warning in import.warnings {
+                        state.main_log.push(crate::log::LogLevel::Warn, &warning);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    let main_window_id = state.main_window_id;
+                    state.raise(
+                        main_window_id,
+                        crate::log::LogLevel::Error,
+                        &format!("Settings import failed: {}", e),
+                    );
+                }
+            }
+            Task::none()
+        }
+        // Write settings changed a moment ago
+        Message::FlushConfig => {
+            crate::config::flush_config(false);
+            Task::none()
+        }
+        // Hide the banner about problems in the config file
+        Message::DismissConfigProblem => {
+            state.config_problem = None;
+            Task::none()
+        }
+        // Merge settings edited in the config file outside the app
+        Message::ConfigFileChanged => {
+            match crate::config::reload_config(&state.config) {
+                Ok(Some(reload)) => {
+                    state.config = reload.config;
+                    state.sync_inputs();
+                    // A save still waiting must not undo the reloaded settings
+                    if crate::config::save_pending() {
+                        crate::config::save_config(&state.config);
+                    }
+                    let _ = crate::log::set_timestamp_format(
+                        &state.config.log_timestamp_format,
+                        state.config.log_timestamp_utc,
+                    );
+                    for change in reload.changes {
+                        state.main_log.push(
+                            crate::log::LogLevel::Info,
+                            &format!("Config reloaded: {}", change),
+                        );
+                    }
+                    for warning in reload.warnings {
+                        state.main_log.push(crate::log::LogLevel::Warn, &warning);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    state.main_log.push(
+                        crate::log::LogLevel::Error,
+                        &format!("Config not reloaded, keeping current settings: {}", e),
+                    );
+                }
+            }
+            Task::none()
+        }
+        // Ask for a config file to switch to
+        Message::SwitchConfig(id) => {
+            if id != state.main_window_id {
+                return Task::none();
+            }
+            Task::perform(
+                dialog(|| {
+                    rfd::FileDialog::new()
+                        .set_title(tr("Switch config"))
+                        .add_filter(tr("Config"), &["toml"])
+                        .pick_file()
+                }),
+                move |result| match result {
+                    Ok(path) => Message::ConfigFilePicked(path),
+                    Err(e) => Message::Failed(id, e),
+                },
+            )
+        }
+        // Load the picked config file and use it from now on
+        Message::ConfigFilePicked(path) => {
+            if let Some(path) = path {
+                switch_config(state, path);
+            }
+            Task::none()
+        }
+        // Switch back to a recently used config file
+        Message::RecentConfigSelected(id, path) => {
+            if id == state.main_window_id {
+                switch_config(state, std::path::PathBuf::from(path));
+            }
+            Task::none()
+        }
+        // Update the log timestamp pattern; invalid patterns log with the default until fixed
+        Message::TimestampFormatChanged(id, pattern) => {
+            if id == state.main_window_id {
+                let _ = crate::log::set_timestamp_format(&pattern, state.config.log_timestamp_utc);
+                state.config.log_timestamp_format = pattern;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Switch log timestamps between local time and UTC
+        Message::TimestampUtcToggled(id, utc) => {
+            if id == state.main_window_id {
+                let _ = crate::log::set_timestamp_format(&state.config.log_timestamp_format, utc);
+                state.config.log_timestamp_utc = utc;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Update the payload type new session windows start with
+        Message::DefaultPayloadTypeChanged(id, protocol, payload_type) => {
+            if id == state.main_window_id {
+                match protocol {
+                    crate::types::Protocol::Tcp => state.config.tcp_payload_type = payload_type,
+                    crate::types::Protocol::Udp => state.config.udp_payload_type = payload_type,
+                }
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Toggle asking before exiting while sessions are open
+        Message::ConfirmExitToggled(id, confirm) => {
+            if id == state.main_window_id {
+                state.config.confirm_exit = confirm;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Validate and send packet, record for replay if valid
+        Message::SendPacket(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id) {
+                if let WindowState::Session(data) = &mut window_data.state {
+                    // User sends are blocked while a replay runs through this session
+                    if data.replay_cancel.is_some() {
+                        return Task::none();
+                    }
+                    let now = std::time::Instant::now();
+                    let delay = if let Some(last) = data.last_packet_time {
+                        now.duration_since(last).as_millis() as u64
+                    } else {
+                        0
+                    };
+                    let hex = data.payload_input.clone();
+                    let payload_type = data.payload_type;
+                    let window_id = id;
+                    let sender = data.sender.clone();
+                    // Validate and store payload if valid and non-empty
+                    let packet_data = crate::validate::payload_bytes(&hex, payload_type);
+                    match packet_data {
+                        // Held until reconnect; recorded for replay only once flushed
+                        Ok(valid_data) if !data.connected && data.queue_offline => {
+                            if !valid_data.is_empty() {
+                                let label = data.take_send_label();
+                                data.queue_offline_payload(ReplayablePayload {
+                                    payload: hex,
+                                    payload_type,
+                                    delay: 0,
+                                    recorded_only: false,
+                                    label,
+                                });
+                                if state.config.clear_input_on_send {
+                                    data.payload_input.clear();
+                                }
+                            }
+                            Task::none()
+                        }
+                        // Sends while disconnected would go nowhere
+                        Ok(_) if !data.connected && data.send_after_ms.trim().is_empty() => {
+                            let reason = format!("{}, payload not sent", session_status(data).1);
+                            update_app(state, Message::Failed(id, reason))
+                        }
+                        // Held by a timer; recorded with the actual delay once it fires
+                        Ok(valid_data) if !data.send_after_ms.trim().is_empty() => {
+                            let send_after = data.send_after_ms.trim().parse::<u64>();
+                            let reason = match send_after {
+                                Err(_) => Some("Invalid send delay, payload not sent"),
+                                Ok(_) if data.scheduled_send.is_some() => {
+                                    Some("A scheduled send is already pending")
+                                }
+                                Ok(_) if valid_data.is_empty() => {
+                                    Some("Empty payload not scheduled")
+                                }
+                                Ok(_) => None,
+                            };
+                            if let Some(reason) = reason {
+                                data.log.push(crate::log::LogLevel::Warn, reason);
+                                return Task::none();
+                            }
+                            let ms = send_after.unwrap_or_default();
+                            let cancel = tokio_util::sync::CancellationToken::new();
+                            let label = data.take_send_label();
+                            data.scheduled_send = Some(crate::types::ScheduledSend {
+                                payload: ReplayablePayload {
+                                    payload: hex,
+                                    payload_type,
+                                    delay: 0,
+                                    recorded_only: false,
+                                    label,
+                                },
+                                data: valid_data,
+                                fire_at: now + std::time::Duration::from_millis(ms),
+                                cancel: cancel.clone(),
+                            });
+                            data.log.push(
+                                crate::log::LogLevel::Info,
+                                &format!("Send scheduled in {} ms", ms),
+                            );
+                            if state.config.clear_input_on_send {
+                                data.payload_input.clear();
+                            }
+                            Task::perform(
+                                async move { crate::replay::sleep_unless_cancelled(ms, &cancel).await },
+                                move |cancelled| {
+                                    if cancelled {
+                                        Message::NoOp
+                                    } else {
+                                        Message::ScheduledSendFired(id)
+                                    }
+                                },
+                            )
+                        }
+                        Ok(valid_data) => {
+                            let label = data.take_send_label();
+                            if !valid_data.is_empty() && !data.recording_paused {
+                                data.replay_payloads.push(ReplayablePayload {
+                                    payload: hex.clone(),
+                                    payload_type,
+                                    delay,
+                                    recorded_only: false,
+                                    label: label.clone(),
+                                });
+                                data.unexported = true;
+                            }
+                            data.last_packet_time = Some(now);
+                            if state.config.clear_input_on_send {
+                                data.payload_input.clear();
+                            }
+                            Task::perform(
+                                async move {
+                                    if let Some(sender) = sender {
+                                        let _ = sender
+                                            .send(SessionCommand::SendPacket(
+                                                valid_data,
+                                                payload_type,
+                                                label,
+                                            ))
+                                            .await;
+                                    }
+                                },
+                                |_| Message::NoOp,
+                            )
+                        }
+                        Err(e) => Task::perform(
+                            async move {
+                                crate::log::log(
+                                    crate::log::LogLevel::Error,
+                                    window_id,
+                                    &format!("Invalid input: {}", e),
+                                )
+                                .await;
+                            },
+                            |_| Message::NoOp,
+                        ),
+                    }
+                } else {
+                    Task::none()
+                }
+            } else {
+                Task::none()
+            }
+        }
+        // Message::TitleChanged(id, title) => {
+        //     if let Some(window) = state.windows.get_mut(&id) {
+        //         window.title = title;
+        //     }
+        //     Task::none()
+        // }
+        // With the tray icon shown and close to tray set, closing the main window hides it
+        Message::CloseRequested(id)
+            if id == state.main_window_id
+                && state.config.tray_icon
+                && state.config.close_to_tray
+                && crate::tray::available() =>
+        {
+            state.main_hidden = true;
+            window::change_mode(id, window::Mode::Hidden)
+        }
+        Message::CloseRequested(id) if id == state.main_window_id => {
+            update_app(state, Message::Quit)
+        }
+        // Exiting ends every session, so list what would be terminated
+        Message::Quit => {
+            // Sessions with recordings go first, one at a time, as if their windows were closed
+            let recorded = state
+                .windows
+                .iter()
+                .filter_map(|(window_id, window_data)| match &window_data.state {
+                    WindowState::Session(data)
+                        if !data.replay_payloads.is_empty()
+                            && (data.auto_export || data.unexported) =>
+                    {
+                        Some((*window_id, data.auto_export, data.replay_payloads.len()))
+                    }
+                    _ => None,
+                })
+                .min_by_key(|(window_id, _, _)| *window_id);
+            if let Some((window_id, auto_export, count)) = recorded {
+                return if auto_export {
+                    update_app(state, Message::ClosedForExit(window_id, true))
+                } else {
+                    let title = state.windows[&window_id].title.clone();
+                    confirm_unexported(
+                        window_id,
+                        title,
+                        count,
+                        state.config.auto_export_dir.clone(),
+                        true,
+                    )
+                };
+            }
+            let mut open: Vec<String> = state
+                .windows
+                .values()
+                .filter_map(|window_data| match &window_data.state {
+                    WindowState::Session(data) if data.replay_progress.is_some() => {
+                        Some(tr_args("{} (replay running)", &[&window_data.title]))
+                    }
+                    WindowState::Session(data) if data.connected => Some(window_data.title.clone()),
+                    WindowState::Replay(data) if data.status == ReplayStatus::Running => {
+                        Some(tr_args("{} (running)", &[&window_data.title]))
+                    }
+                    _ => None,
+                })
+                .collect();
+            if open.is_empty() || !state.config.confirm_exit {
+                return update_app(state, Message::ExitConfirmed(false));
+            }
+            open.sort();
+            let main_window_id = state.main_window_id;
+            // Custom buttons report their label, so the answer is matched in the same language
+            let (exit, exit_always) = (tr("Exit"), tr("Exit, don't ask again"));
+            Task::perform(
+                dialog(move || {
+                    rfd::MessageDialog::new()
+                        .set_title(tr("Exit replayr"))
+                        .set_description(tr_args(
+                            "Exiting will terminate:\n\n{}",
+                            &[&open.join("\n")],
+                        ))
+                        .set_buttons(rfd::MessageButtons::YesNoCancelCustom(
+                            exit.to_string(),
+                            exit_always.to_string(),
+                            tr("Cancel").to_string(),
+                        ))
+                        .show()
+                }),
+                move |result| match result {
+                    Ok(rfd::MessageDialogResult::Custom(choice)) if choice == exit => {
+                        Message::ExitConfirmed(false)
+                    }
+                    Ok(rfd::MessageDialogResult::Custom(choice)) if choice == exit_always => {
+                        Message::ExitConfirmed(true)
+                    }
+                    Ok(_) => Message::NoOp,
+                    Err(e) => Message::Failed(main_window_id, e),
+                },
+            )
+        }
+        // Stop every session and replay, then exit once the sessions have shut down
+        Message::ExitConfirmed(dont_ask_again) => {
+            if dont_ask_again {
+                state.config.confirm_exit = false;
+            }
+            // Also stores the window geometry
+            crate::config::save_config(&state.config);
+            let senders: Vec<_> = state
+                .windows
+                .values()
+                .filter_map(|window_data| stop_window_tasks(&window_data.state))
+                .collect();
+            Task::perform(
+                async move {
+                    for sender in &senders {
+                        let _ = sender.send(SessionCommand::Disconnect).await;
+                    }
+                    // A session task drops its receiver once the connection is shut down
+                    let closed =
+                        iced::futures::future::join_all(senders.iter().map(|s| s.closed()));
+                    let _ = tokio::time::timeout(std::time::Duration::from_secs(2), closed).await;
+                },
+                |_| Message::Exit,
+            )
+        }
+        Message::Exit => {
+            crate::config::flush_config(true);
+            exit()
+        }
+        Message::CloseRequested(id) => {
+            let dirty = state.windows.get(&id).is_some_and(
+                |window_data| matches!(&window_data.state, WindowState::Replay(data) if data.dirty),
+            );
+            let recorded =
+                state
+                    .windows
+                    .get(&id)
+                    .and_then(|window_data| match &window_data.state {
+                        WindowState::Session(data) if !data.replay_payloads.is_empty() => Some((
+                            window_data.title.clone(),
+                            data.auto_export,
+                            data.unexported,
+                            data.replay_payloads.len(),
+                        )),
+                        _ => None,
+                    });
+            if let Some((title, auto_export, unexported, count)) = recorded
+                && (auto_export || unexported)
+            {
+                if auto_export {
+                    return update_app(state, Message::ExportAndClose(id));
+                }
+                confirm_unexported(
+                    id,
+                    title,
+                    count,
+                    state.config.auto_export_dir.clone(),
+                    false,
+                )
+            } else if dirty {
+                Task::perform(
+                    dialog(|| {
+                        rfd::MessageDialog::new()
+                            .set_title(tr("Unsaved changes"))
+                            .set_description(tr("This replay has unsaved changes. Close anyway?"))
+                            .set_buttons(rfd::MessageButtons::YesNo)
+                            .show()
+                    }),
+                    move |result| match result {
+                        Ok(rfd::MessageDialogResult::Yes) => Message::Closed(id),
+                        Ok(_) => Message::NoOp,
+                        Err(e) => Message::Failed(id, e),
+                    },
+                )
+            } else {
+                update_app(state, Message::Closed(id))
+            }
+        }
+        // Close the window, exporting first, then continue the exit
+        Message::ClosedForExit(id, export) => {
+            let export = match state.windows.get(&id) {
+                Some(window_data) if export => match &window_data.state {
+                    WindowState::Session(data) => Task::perform(
+                        auto_export_replay(
+                            std::path::PathBuf::from(&state.config.auto_export_dir),
+                            window_data.export_stem(),
+                            data.to_replayable_session(),
+                        ),
+                        Message::AutoExported,
+                    ),
+                    WindowState::Replay(_) => Task::none(),
+                },
+                _ => Task::none(),
+            };
+            let close = update_app(state, Message::Closed(id));
+            // The export is written before exiting can end the app
+            Task::batch([close, export.chain(Task::done(Message::Quit))])
+        }
+        // Exit app if main window closed
+        Message::Closed(id) if id == state.main_window_id => {
+            crate::config::flush_config(true);
+            exit()
+        }
+        // Close session window and disconnect if needed
+        Message::Closed(id) => {
+            // Closed windows are forgotten, so bundles and exit prompts only list open ones
+            let Some(mut window_data) = state.windows.remove(&id) else {
+                return state.close_window(id);
+            };
+            let command = match stop_window_tasks(&window_data.state) {
+                Some(sender) => Task::perform(
+                    async move {
+                        let _ = sender.send(SessionCommand::Disconnect).await;
+                    },
+                    |_| Message::NoOp,
+                ),
+                None => Task::none(),
+            };
+            close_log_file(&mut window_data, "window closed");
+            crate::log::take_progress(id);
+            // Store the geometry new session windows open with
+            crate::config::save_config(&state.config);
+            Task::batch(vec![command, state.close_window(id)])
+        }
+        // Append log message to the appropriate window's log
+        Message::LogReceived(entry, id) => {
+            if let Some(window_data) = state.windows.get_mut(&id) {
+                window_data.push_log(entry);
+            }
+            Task::none()
+        }
+        // Clear a window log; the main log cannot be regenerated, so ask first
+        Message::ClearLog(id) if id == state.main_window_id => Task::perform(
+            dialog(|| {
+                rfd::MessageDialog::new()
+                    .set_title(tr("Clear log"))
+                    .set_description(tr("Clear the main window log?"))
+                    .set_buttons(rfd::MessageButtons::YesNo)
+                    .show()
+            }),
+            move |result| match result {
+                Ok(rfd::MessageDialogResult::Yes) => Message::LogCleared(id),
+                Ok(_) => Message::NoOp,
+                Err(e) => Message::Failed(id, e),
+            },
+        ),
+        Message::ClearLog(id) => update_app(state, Message::LogCleared(id)),
+        // Copy the lines shown with the current level filter and search, as exported
+        Message::CopyLog(id) => {
+            let text = if id == state.main_window_id {
+                state
+                    .main_log
+                    .view(state.main_log_filter, &state.main_log_search)
+            } else if let Some(window_data) = state.windows.get(&id) {
+                window_data.log().collapsed(window_data.log_filter)
+            } else {
+                return Task::none();
+            };
+            let message = format!("Copied {} lines to the clipboard", text.lines().count());
+            if id == state.main_window_id {
+                state.main_log.push(crate::log::LogLevel::Info, &message);
+            } else if let Some(window_data) = state.windows.get_mut(&id) {
+                window_data.push_log(crate::log::LogEntry::new(
+                    crate::log::LogLevel::Info,
+                    &message,
+                ));
+            }
+            iced::clipboard::write(text)
+        }
+        // Replace the visible log with a single marker line; recordings and counters are kept
+        Message::LogCleared(id) => {
+            let mut marker = crate::log::Log::default();
+            marker.push(crate::log::LogLevel::Info, "Log cleared");
+            if id == state.main_window_id {
+                state.main_log = marker;
+            } else if let Some(window_data) = state.windows.get_mut(&id) {
+                match &mut window_data.state {
+                    WindowState::Session(data) => data.log = marker,
+                    WindowState::Replay(data) => data.log = marker,
+                }
+                if let Some(log_file) = &mut window_data.log_file {
+                    log_file.log_cleared();
+                }
+            }
+            Task::none()
+        }
+        // Show or hide a level in a log; hidden entries are kept
+        Message::LogFilterChanged(id, level, shown) => {
+            if id == state.main_window_id {
+                state.main_log_filter.set(level, shown);
+            } else if let Some(window_data) = state.windows.get_mut(&id) {
+                window_data.log_filter.set(level, shown);
+            }
+            Task::none()
+        }
+        // Zoom the log text of one window; the zoom stays as entries arrive
+        Message::LogZoomed(id, change) => {
+            let id = state.shown_window(id);
+            if id == state.main_window_id {
+                zoom_log(
+                    id,
+                    &mut state.main_log_zoom,
+                    &mut state.main_log_scroll,
+                    change,
+                )
+            } else if let Some(window_data) = state.windows.get_mut(&id) {
+                zoom_log(
+                    id,
+                    &mut window_data.log_zoom,
+                    &mut window_data.log_scroll,
+                    change,
+                )
+            } else {
+                Task::none()
+            }
+        }
+        // Move the split of a session window; it is kept until the window closes
+        Message::SplitResized(id, event) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.split.resize(event);
+            }
+            Task::none()
+        }
+        // A double-click on the split bar puts it back where new windows have it
+        Message::SplitPressed(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+                && data.split.pressed()
+            {
+                data.split = crate::types::SessionSplit::new(state.config.session_split);
+            }
+            Task::none()
+        }
+        // Count files dragged over a session, for the drop hint
+        Message::FileHovered(id) => {
+            let target = state.shown_window(id);
+            if let Some(window_data) = state.windows.get_mut(&target)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.files_hovered += 1;
+            }
+            Task::none()
+        }
+        Message::FilesHoveredLeft(id) => {
+            let target = state.shown_window(id);
+            if let Some(window_data) = state.windows.get_mut(&target)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.files_hovered = 0;
+                data.drop_refused = false;
+            }
+            Task::none()
+        }
+        // Read a file dropped on a session into its payload input, asking first if it is
+        // large; several files dropped together are refused
+        Message::FileDropped(id, path) => {
+            let target = state.shown_window(id);
+            let append = state.modifiers.shift();
+            let Some(window_data) = state.windows.get_mut(&target) else {
+                return Task::none();
+            };
+            let WindowState::Session(data) = &mut window_data.state else {
+                return Task::none();
+            };
+            // Each file of a drop arrives on its own, after all of them were hovered
+            let together = data.files_hovered;
+            data.files_hovered = data.files_hovered.saturating_sub(1);
+            if together > 1 || data.drop_refused {
+                if !data.drop_refused {
+                    data.drop_refused = true;
+                    window_data.push_log(crate::log::LogEntry::new(
+                        crate::log::LogLevel::Warn,
+                        &format!(
+                            "{} files dropped, nothing loaded; drop one file at a time",
+                            together
+                        ),
+                    ));
+                }
+                if let WindowState::Session(data) = &mut window_data.state
+                    && data.files_hovered == 0
+                {
+                    data.drop_refused = false;
+                }
+                return Task::none();
+            }
+            let read_path = path.clone();
+            Task::perform(
+                async move {
+                    let size = tokio::fs::metadata(&read_path)
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    if size.is_dir() {
+                        return Err("it is a directory".to_string());
+                    }
+                    if size.len() > crate::types::DROP_CONFIRM_BYTES {
+                        let description = tr_args(
+                            "{} is {}. Load it into the payload input anyway?",
+                            &[&read_path.display(), &format_size(size.len() as usize)],
+                        );
+                        let confirmed = dialog(move || {
+                            rfd::MessageDialog::new()
+                                .set_title(tr("Large file"))
+                                .set_description(description)
+                                .set_buttons(rfd::MessageButtons::YesNo)
+                                .show()
+                        })
+                        .await?;
+                        if confirmed != rfd::MessageDialogResult::Yes {
+                            return Ok(None);
+                        }
+                    }
+                    tokio::fs::read(&read_path)
+                        .await
+                        .map(Some)
+                        .map_err(|e| e.to_string())
+                },
+                move |result| Message::DroppedFileRead(target, path.clone(), append, result),
+            )
+        }
+        // Put the bytes of a dropped file in the payload input, spaced hex or text as typed
+        Message::DroppedFileRead(id, path, append, result) => {
+            let Some(window_data) = state.windows.get_mut(&id) else {
+                return Task::none();
+            };
+            let WindowState::Session(data) = &mut window_data.state else {
+                return Task::none();
+            };
+            let name = path.file_name().map_or_else(
+                || path.display().to_string(),
+                |name| name.to_string_lossy().to_string(),
+            );
+            let loaded = match result {
+                Ok(None) => Err(format!("{} not loaded", name)),
+                Err(e) => Err(format!(
+                    "Could not read dropped file {}: {}",
+                    path.display(),
+                    e
+                )),
+                Ok(Some(bytes)) => Ok((typed_payload(&bytes, data.payload_type), bytes.len())),
+            };
+            let entry = match loaded {
+                Ok((payload, size)) => {
+                    let message = if append && !data.payload_input.is_empty() {
+                        if data.payload_type == PayloadType::Hex {
+                            data.payload_input.push(' ');
+                        }
+                        data.payload_input.push_str(&payload);
+                        format!("Appended {} ({}) to the payload", name, format_size(size))
+                    } else {
+                        data.payload_input = payload;
+                        format!("Loaded {} ({}) into the payload", name, format_size(size))
+                    };
+                    crate::log::LogEntry::new(crate::log::LogLevel::Info, &message)
+                }
+                Err(message) => crate::log::LogEntry::new(crate::log::LogLevel::Warn, &message),
+            };
+            window_data.push_log(entry);
+            Task::none()
+        }
+        // Remember the modifiers for Ctrl+scroll
+        Message::ModifiersChanged(modifiers) => {
+            state.modifiers = modifiers;
+            Task::none()
+        }
+        // Ctrl+scroll zooms the log text; plain scrolling is left to the scrollables
+        Message::WheelScrolled(id, delta) => {
+            if !state.modifiers.command() || delta == 0.0 {
+                return Task::none();
+            }
+            let change = if delta > 0.0 {
+                crate::types::ZoomChange::In
+            } else {
+                crate::types::ZoomChange::Out
+            };
+            update_app(state, Message::LogZoomed(id, change))
+        }
+        // Show the copy actions of the row under the cursor
+        Message::LogRowHovered(id, index) => {
+            if id == state.main_window_id {
+                state.main_log_scroll.hovered = Some(index);
+            } else if let Some(window_data) = state.windows.get_mut(&id) {
+                window_data.log_scroll.hovered = Some(index);
+            }
+            Task::none()
+        }
+        // Moving to the next row may enter it before leaving this one
+        Message::LogRowLeft(id, index) => {
+            let scroll = if id == state.main_window_id {
+                &mut state.main_log_scroll
+            } else if let Some(window_data) = state.windows.get_mut(&id) {
+                &mut window_data.log_scroll
+            } else {
+                return Task::none();
+            };
+            if scroll.hovered == Some(index) {
+                scroll.hovered = None;
+            }
+            Task::none()
+        }
+        Message::CopyLogRow(text) => iced::clipboard::write(text),
+        // Remember where a log view is scrolled to, to lay out the rows there
+        Message::LogScrolled(id, viewport) => {
+            if id == state.main_window_id {
+                state.main_log_scroll.scrolled(viewport);
+            } else if let Some(window_data) = state.windows.get_mut(&id) {
+                window_data.log_scroll.scrolled(viewport);
+            }
+            Task::none()
+        }
+        // Show or hide packet lines in a log, leaving status lines
+        Message::LogPacketsShown(id, shown) => {
+            if id == state.main_window_id {
+                state.main_log_filter.packets = shown;
+            } else if let Some(window_data) = state.windows.get_mut(&id) {
+                window_data.log_filter.packets = shown;
+            }
+            Task::none()
+        }
+        // Filter the main log by text
+        Message::MainLogSearchChanged(search) => {
+            state.main_log_search = crate::log::LogSearch::new(&search, state.main_log_regex);
+            Task::none()
+        }
+        // Match the main log filter as a regex or literally
+        Message::MainLogRegexToggled(regex) => {
+            state.main_log_regex = regex;
+            state.main_log_search = crate::log::LogSearch::new(state.main_log_search.text(), regex);
+            Task::none()
+        }
+        // Toggle logging socket-level detail
+        Message::DebugLogToggled(id, enabled) => {
+            if id == state.main_window_id {
+                state.config.debug_log = enabled;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Toggle showing session and replay entries in the main log
+        Message::ForwardSessionLogsToggled(id, enabled) => {
+            if id == state.main_window_id {
+                state.config.forward_session_logs = enabled;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Append a batch of forwarded entries, noting any dropped because there were too many
+        Message::ForwardedLogs => {
+            let (entries, dropped) = crate::log::take_forwarded();
+            for entry in entries {
+                state.main_log.push_entry(entry);
+            }
+            if dropped > 0 {
+                state.main_log.push(
+                    crate::log::LogLevel::Warn,
+                    &format!(
+                        "{} session log entries not shown here, see the session windows",
+                        dropped
+                    ),
+                );
+            }
+            Task::none()
+        }
+        // Open the rename field prefilled with the current label
+        Message::RenameWindow(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id) {
+                window_data.label_edit = Some(window_data.label.clone().unwrap_or_default());
+            }
+            Task::none()
+        }
+        // Update the label being edited
+        Message::RenameInputChanged(id, label) => {
+            if let Some(window_data) = state.windows.get_mut(&id) {
+                window_data.label_edit = Some(label);
+            }
+            Task::none()
+        }
+        // Apply the edited label; an empty label removes it
+        Message::RenameConfirmed(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let Some(label) = window_data.label_edit.take()
+            {
+                let label = label.trim();
+                window_data.label = (!label.is_empty()).then(|| label.to_string());
+            }
+            Task::none()
+        }
+        // Close the rename field without changing the label
+        Message::RenameCancelled(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id) {
+                window_data.label_edit = None;
+            }
+            Task::none()
+        }
+        // Append log message to main window log
+        Message::MainLog(entry) => {
+            state.main_log.push_entry(entry);
+            Task::none()
+        }
+        // Update connection status and the connection part of the session title
+        Message::ConnectionStatus(id, connected) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                let was_connected = data.connected;
+                data.connected = connected;
+                data.connecting = false;
+                data.last_activity = Some(std::time::Instant::now());
+                if connected {
+                    data.connect_error = None;
+                    data.connected_since = Some(std::time::Instant::now());
+                    data.started_at.get_or_insert_with(chrono::Local::now);
+                    data.ended_at = None;
+                    if let Ok(port) = data.port.parse() {
+                        let target = RecentTarget {
+                            protocol: data.protocol,
+                            address: data.address.clone(),
+                            port,
+                        };
+                        if state.config.remember_target(target) {
+                            crate::config::save_config(&state.config);
+                        }
+                    }
+                } else if let Some(since) = data.connected_since.take() {
+                    data.ended_at = Some(chrono::Local::now());
+                    let duration = since.elapsed();
+                    data.last_connection = Some(duration);
+                    data.total_connected += duration;
+                }
+                if !connected {
+                    data.local_addr = None;
+                    data.remote_addr = None;
+                    if let Some(scheduled) = data.scheduled_send.take() {
+                        scheduled.cancel.cancel();
+                        data.log.push(
+                            crate::log::LogLevel::Warn,
+                            "Disconnected, scheduled send abandoned",
+                        );
+                    }
+                    // Mark the end of the connection in the log file, which stays open
+                    // for a reconnect
+                    if was_connected
+                        && let Some(log_file) = &mut window_data.log_file
+                        && let Some(error) = log_file
+                            .mirror(&data.log, window_data.log_filter.all_levels())
+                            .or_else(|| log_file.footer("disconnected"))
+                    {
+                        data.log.push_entry(error);
+                    }
+                }
+                window_data.title = data.title();
+                // Heartbeats pause while disconnected and resume on reconnect
+                let heartbeat = restart_heartbeat(id, data);
+                let flush = match &data.sender {
+                    Some(sender) if connected && !data.offline_queue.is_empty() => {
+                        let payloads = std::mem::take(&mut data.offline_queue);
+                        Task::perform(
+                            crate::replay::flush_queue_task(sender.clone(), payloads, id),
+                            |_| Message::NoOp,
+                        )
+                    }
+                    _ => Task::none(),
+                };
+                Task::batch([heartbeat, flush])
+            } else {
+                Task::none()
+            }
+        }
+        // Append an empty handshake step
+        Message::HandshakeStepAdded(id) => {
+            if id == state.main_window_id {
+                state.config.handshake_steps.push(ReplayablePayload {
+                    payload: String::new(),
+                    payload_type: PayloadType::Hex,
+                    delay: 0,
+                    recorded_only: false,
+                    label: None,
+                });
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Remove a handshake step
+        Message::HandshakeStepRemoved(id, index) => {
+            if id == state.main_window_id && index < state.config.handshake_steps.len() {
+                state.config.handshake_steps.remove(index);
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Apply an edit to a handshake step
+        Message::HandshakeStepChanged(id, index, edit) => {
+            if id == state.main_window_id
+                && let Some(step) = state.config.handshake_steps.get_mut(index)
+            {
+                match edit {
+                    HandshakeStepEdit::Payload(payload) => step.payload = payload,
+                    HandshakeStepEdit::Type(payload_type) => step.payload_type = payload_type,
+                    HandshakeStepEdit::Delay(delay) => match delay.trim() {
+                        "" => step.delay = 0,
+                        delay => {
+                            if let Ok(delay) = delay.parse() {
+                                step.delay = delay;
+                            }
+                        }
+                    },
+                }
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Choose whether a failed handshake closes the connection
+        Message::HandshakeDisconnectToggled(id, disconnect) => {
+            if id == state.main_window_id {
+                state.config.handshake_disconnect_on_failure = disconnect;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Show or hide the notes editor of a session
+        Message::NotesToggled(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.show_notes = !data.show_notes;
+            }
+            Task::none()
+        }
+        // Apply an edit to the session notes
+        Message::NotesEdited(id, action) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.notes.0.perform(action);
+            }
+            Task::none()
+        }
+        // Toggle holding payloads sent while disconnected
+        Message::QueueOfflineToggled(id, enabled) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.queue_offline = enabled;
+            }
+            Task::none()
+        }
+        // Discard the payloads held for reconnect
+        Message::ClearOfflineQueue(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+                && !data.offline_queue.is_empty()
+            {
+                data.log.push(
+                    crate::log::LogLevel::Info,
+                    &format!("Cleared {} queued payloads", data.offline_queue.len()),
+                );
+                data.offline_queue.clear();
+            }
+            Task::none()
+        }
+        // Drop the sends still queued for the session task; the task logs each one it drops
+        Message::CancelPending(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+                && let Some(sender) = &data.sender
+            {
+                let queued = sender.max_capacity() - sender.capacity();
+                data.discard
+                    .store(queued, std::sync::atomic::Ordering::Relaxed);
+                data.log.push(
+                    crate::log::LogLevel::Warn,
+                    &format!("Cancelling {} pending sends", queued),
+                );
+            }
+            Task::none()
+        }
+        // Toggle the auto-export default for new sessions
+        Message::AutoExportDefaultToggled(id, enabled) => {
+            if id == state.main_window_id {
+                state.config.auto_export_on_close = enabled;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Update the auto-export directory
+        Message::AutoExportDirChanged(id, dir) => {
+            if id == state.main_window_id {
+                state.config.auto_export_dir = dir;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Choose the auto-export directory with a folder dialog
+        Message::BrowseAutoExportDir(id) => Task::perform(
+            async move {
+                dialog(|| {
+                    rfd::FileDialog::new()
+                        .set_title(tr("Auto-export directory"))
+                        .pick_folder()
+                })
+                .await
+            },
+            move |dir| match dir {
+                Err(e) => Message::Failed(id, e),
+                Ok(Some(dir)) => Message::AutoExportDirChanged(id, dir.display().to_string()),
+                Ok(None) => Message::NoOp,
+            },
+        ),
+        // Toggle auto-export for one session
+        Message::AutoExportToggled(id, enabled) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.auto_export = enabled;
+            }
+            Task::none()
+        }
+        // Write the session's replay to the auto-export directory and close the window
+        Message::ExportAndClose(id) => {
+            let export = match state.windows.get(&id) {
+                Some(window_data) => match &window_data.state {
+                    WindowState::Session(data) => Task::perform(
+                        auto_export_replay(
+                            std::path::PathBuf::from(&state.config.auto_export_dir),
+                            window_data.export_stem(),
+                            data.to_replayable_session(),
+                        ),
+                        Message::AutoExported,
+                    ),
+                    WindowState::Replay(_) => Task::none(),
+                },
+                None => Task::none(),
+            };
+            Task::batch([export, update_app(state, Message::Closed(id))])
+        }
+        // Report where an automatic export went, or why it failed
+        Message::AutoExported(result) => {
+            let entry = match result {
+                Ok(path) => crate::log::LogEntry::new(
+                    crate::log::LogLevel::Info,
+                    &format!("Replay exported to {}", path.display()),
+                ),
+                Err(err) => crate::log::LogEntry::new(
+                    crate::log::LogLevel::Error,
+                    &format!("Replay auto-export failed: {}", err),
+                ),
+            };
+            update_app(state, Message::MainLog(entry))
+        }
+        // Update the quick export directory
+        Message::ExportDirChanged(id, dir) => {
+            if id == state.main_window_id {
+                state.config.export_dir = dir;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Choose the quick export directory with a folder dialog
+        Message::BrowseExportDir(id) => Task::perform(
+            async move {
+                dialog(|| {
+                    rfd::FileDialog::new()
+                        .set_title(tr("Quick export directory"))
+                        .pick_folder()
+                })
+                .await
+            },
+            move |dir| match dir {
+                Err(e) => Message::Failed(id, e),
+                Ok(Some(dir)) => Message::ExportDirChanged(id, dir.display().to_string()),
+                Ok(None) => Message::NoOp,
+            },
+        ),
+        // Write the replay or logs straight to the export directory under a timestamped name
+        Message::QuickExport(id, kind) => {
+            let Some(window_data) = state.windows.get(&id) else {
+                return Task::none();
+            };
+            let WindowState::Session(data) = &window_data.state else {
+                return Task::none();
+            };
+            let stem = format!(
+                "{}_{}",
+                window_data.export_stem(),
+                chrono::Local::now().format("%Y%m%d_%H%M%S")
+            );
+            let (file_name, contents) = match kind {
+                ExportKind::Replay => (
+                    format!("{}.json", stem),
+                    serde_json::to_string_pretty(&data.to_replayable_session())
+                        .map_err(|e| e.to_string()),
+                ),
+                ExportKind::Logs => (format!("{}_logs.txt", stem), Ok(window_data.log_export())),
+            };
+            let dir = std::path::PathBuf::from(&state.config.export_dir);
+            Task::perform(
+                async move { quick_export(dir, file_name, contents?).await },
+                move |result| Message::QuickExported(id, kind, result),
+            )
+        }
+        // Log where a quick export went, or fall back to the save dialog
+        Message::QuickExported(id, kind, result) => {
+            let what = match kind {
+                ExportKind::Replay => "Replay",
+                ExportKind::Logs => "Logs",
+            };
+            let (entry, fallback) = match result {
+                Ok(path) => (
+                    crate::log::LogEntry::new(
+                        crate::log::LogLevel::Info,
+                        &format!("{} exported to {}", what, path.display()),
+                    ),
+                    None,
+                ),
+                Err(e) => (
+                    crate::log::LogEntry::new(
+                        crate::log::LogLevel::Warn,
+                        &format!("Quick export failed, choose a file instead: {}", e),
+                    ),
+                    Some(match kind {
+                        ExportKind::Replay => Message::ExportSession(id),
+                        ExportKind::Logs => Message::ExportLogs(id),
+                    }),
+                ),
+            };
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.unexported &= !(kind == ExportKind::Replay && fallback.is_none());
+                data.log.push_entry(entry.clone());
+            }
+            state.main_log.push_entry(entry);
+            match fallback {
+                Some(message) => update_app(state, message),
+                None => Task::none(),
+            }
+        }
+        // Toggle writing the logs of new windows to files
+        Message::LogToFileDefaultToggled(id, enabled) => {
+            if id == state.main_window_id {
+                state.config.log_to_file = enabled;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Ask for the file to write a window's log to, or stop writing it
+        Message::LogToFileToggled(id, enabled) => {
+            let Some(window_data) = state.windows.get_mut(&id) else {
+                return Task::none();
+            };
+            if !enabled {
+                close_log_file(window_data, "logging stopped");
+                return Task::none();
+            }
+            let file_name = log_file_name(&window_data.export_stem());
+            Task::perform(
+                async move {
+                    dialog(move || {
+                        rfd::FileDialog::new()
+                            .set_title(tr("Log to file"))
+                            .add_filter(tr("Log Files"), &["log", "txt"])
+                            .set_file_name(file_name)
+                            .save_file()
+                    })
+                    .await
+                },
+                move |result| match result {
+                    Ok(path) => Message::LogFilePicked(id, path),
+                    Err(e) => Message::Failed(id, e),
+                },
+            )
+        }
+        // Start writing a window's log, including the lines logged so far, to the file
+        Message::LogFilePicked(id, path) => {
+            let rotation = state.config.log_rotation();
+            if let Some(path) = path
+                && let Some(window_data) = state.windows.get_mut(&id)
+            {
+                close_log_file(window_data, "logging moved to another file");
+                let log_file = crate::logfile::LogFile::open(&path, &window_data.title, rotation);
+                set_log_file(window_data, log_file);
+            }
+            Task::none()
+        }
+        // Write buffered log file lines to disk
+        Message::FlushLogFiles => {
+            for window_data in state.windows.values_mut() {
+                if let Some(error) = window_data
+                    .log_file
+                    .as_mut()
+                    .and_then(|log_file| log_file.flush())
+                {
+                    window_data.push_log(error);
+                }
+            }
+            Task::none()
+        }
+        // Update the send stall warning threshold once it is valid
+        Message::StallWarningChanged(id, input) => {
+            if id == state.main_window_id {
+                if let Ok(ms) = crate::config::parse_stall_warn_ms(&input) {
+                    state.config.send_stall_warn_ms = ms;
+                    crate::config::save_config(&state.config);
+                }
+                state.number_inputs.send_stall_warn_ms = input;
+            }
+            Task::none()
+        }
+        // Fill in the main window target from the recent targets history
+        Message::RecentTargetSelected(id, target) => {
+            if id == state.main_window_id {
+                state.config.protocol = target.protocol;
+                state.config.address = target.address;
+                state.port_input = target.port.to_string();
+                state.port_error = None;
+                state.config.port = target.port;
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Remove one entry from the recent targets history
+        Message::RecentTargetRemoved(id, index) => {
+            if id == state.main_window_id && index < state.config.recent_targets.len() {
+                state.config.recent_targets.remove(index);
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Forget all recent targets
+        Message::ClearRecentTargets(id) => {
+            if id == state.main_window_id {
+                state.config.recent_targets.clear();
+                crate::config::save_config(&state.config);
+            }
+            Task::none()
+        }
+        // Expand or collapse the recent targets list
+        Message::RecentTargetsToggled(id) => {
+            if id == state.main_window_id {
+                state.show_recent_targets = !state.show_recent_targets;
+            }
+            Task::none()
+        }
+        // Expand or collapse the list of open windows
+        Message::DashboardToggled(id) => {
+            if id == state.main_window_id {
+                state.show_dashboard = !state.show_dashboard;
+            }
+            Task::none()
+        }
+        // Open the command palette for the window shown, a tab in tabbed mode
+        Message::OpenPalette(id) => {
+            state.palette = Some(crate::palette::Palette::new(state.shown_window(id)));
+            text_input::focus(palette_input_id())
+        }
+        // Narrow the palette to the actions matching the text
+        Message::PaletteQueryChanged(query) => {
+            if let Some(palette) = &mut state.palette {
+                palette.query = query;
+                palette.selected = 0;
+            }
+            Task::none()
+        }
+        // Move the highlight, stopping at the first and last action
+        Message::PaletteMoved(by) => {
+            if let Some(palette) = &state.palette {
+                let count = palette.matches(state).len();
+                let selected = palette
+                    .selected
+                    .saturating_add_signed(by)
+                    .min(count.saturating_sub(1));
+                if let Some(palette) = &mut state.palette {
+                    palette.selected = selected;
+                }
+            }
+            Task::none()
+        }
+        Message::PaletteSubmitted => match &state.palette {
+            Some(palette) => update_app(state, Message::PaletteRun(palette.selected)),
+            None => Task::none(),
+        },
+        // Close the palette and run the action as if its button was pressed
+        Message::PaletteRun(index) => {
+            let action = state
+                .palette
+                .take()
+                .and_then(|palette| palette.matches(state).into_iter().nth(index));
+            match action {
+                Some(action) => update_app(state, action.message),
+                None => Task::none(),
+            }
+        }
+        Message::ClosePalette => {
+            state.palette = None;
+            Task::none()
+        }
+        // Open a menu, or close it when its title is clicked again
+        Message::OpenMenu(id, menu) => {
+            state.menu = match state.menu {
+                Some(open) if open.window == id && open.menu == menu => None,
+                _ => Some(crate::menu::OpenMenu { window: id, menu }),
+            };
+            Task::none()
+        }
+        Message::MenuHovered(id, menu) => {
+            if let Some(open) = &mut state.menu
+                && open.window == id
+            {
+                open.menu = menu;
+            }
+            Task::none()
+        }
+        // Close the menu and run the item as if its button was pressed
+        Message::MenuChosen(message) => {
+            state.menu = None;
+            update_app(state, *message)
+        }
+        Message::CloseMenu => {
+            state.menu = None;
+            Task::none()
+        }
+        Message::ShowShortcuts => {
+            let shortcuts = crate::menu::SHORTCUTS
+                .iter()
+                .map(|(keys, action)| {
+                    format!("{}: {}", crate::menu::shortcut_label(keys), tr(action))
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            info_dialog(tr("Keyboard shortcuts"), shortcuts)
+        }
+        Message::ShowAbout => info_dialog(
+            tr("About replayr"),
+            format!(
+                "replayr {}\n\n{}\n\n{}\n\n{}",
+                env!("CARGO_PKG_VERSION"),
+                tr(env!("CARGO_PKG_DESCRIPTION")),
+                env!("CARGO_PKG_REPOSITORY"),
+                tr_args(
+                    "Licensed under the {} license. The log font, Fira Mono, is licensed under \
+                     the SIL Open Font License 1.1.",
+                    &[&env!("CARGO_PKG_LICENSE")],
+                ),
+            ),
+        ),
+        // Bring a window listed in the main window to the front
+        Message::FocusWindow(id) => {
+            if state.tabs.contains(&id) {
+                state.active_tab = Some(id);
+                window::gain_focus(state.main_window_id)
             } else {
-                Task::none()
+                window::gain_focus(id)
             }
         }
-        // Update payload input in session data
-        Message::InputChanged(id, hex) => {
-            if let Some(window_data) = state.windows.get_mut(&id) {
-                if let WindowState::Session(data) = &mut window_data.state {
-                    data.payload_input = hex;
+        // Update the default idle timeout for new sessions once it is valid
+        Message::IdleTimeoutDefaultChanged(id, input) => {
+            if id == state.main_window_id {
+                if let Ok(secs) = crate::validate::at_least(&input, 0) {
+                    state.config.idle_timeout_secs = secs;
+                    crate::config::save_config(&state.config);
                 }
+                state.number_inputs.idle_timeout_secs = input;
             }
             Task::none()
         }
-        // Update payload type and adjust placeholder text
-        Message::PayloadTypeChanged(id, payload_type) => {
-            if let Some(window_data) = state.windows.get_mut(&id) {
-                if let WindowState::Session(data) = &mut window_data.state {
-                    data.payload_type = payload_type;
-                    data.input_placeholder = match payload_type {
-                        PayloadType::Ascii => "Hello World".into(),
-                        _ => "68656c6c6f20776f726c64".into(),
+        // Override the idle timeout of one session and pass it to its running task
+        Message::IdleTimeoutChanged(id, secs) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.idle_timeout_secs = secs;
+                data.last_activity = Some(std::time::Instant::now());
+                if let Some(sender) = data.sender.clone() {
+                    let timeout = data.idle_timeout();
+                    return Task::perform(
+                        async move {
+                            let _ = sender.send(SessionCommand::SetIdleTimeout(timeout)).await;
+                        },
+                        |_| Message::NoOp,
+                    );
+                }
+            }
+            Task::none()
+        }
+        // Redraw so countdowns and connection timers stay current
+        Message::Tick => Task::none(),
+        // Show or hide the recording inspector
+        Message::RecordingToggled(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.show_recording = !data.show_recording;
+            }
+            Task::none()
+        }
+        // Edit the recording only; traffic that was already sent is unaffected
+        Message::RecordingChanged(id, index, edit) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+                && index < data.replay_payloads.len()
+            {
+                match edit {
+                    RecordingEdit::Delay(delay) => {
+                        if let Ok(delay) = delay.trim().parse() {
+                            data.replay_payloads[index].delay = delay;
+                        } else if delay.trim().is_empty() {
+                            data.replay_payloads[index].delay = 0;
+                        }
+                    }
+                    RecordingEdit::MoveUp if index > 0 => {
+                        data.replay_payloads.swap(index - 1, index)
+                    }
+                    RecordingEdit::MoveDown if index + 1 < data.replay_payloads.len() => {
+                        data.replay_payloads.swap(index, index + 1)
                     }
+                    RecordingEdit::Remove => {
+                        if let Some(removed) = data.remove_recorded(index) {
+                            data.log.push(
+                                crate::log::LogLevel::Info,
+                                &format!(
+                                    "Removed from recording: #{} {} ({:?})",
+                                    index + 1,
+                                    removed.payload,
+                                    removed.payload_type
+                                ),
+                            );
+                        }
+                    }
+                    RecordingEdit::MoveUp | RecordingEdit::MoveDown => return Task::none(),
                 }
+                data.unexported = true;
             }
             Task::none()
         }
-        // Validate and send packet, record for replay if valid
-        Message::SendPacket(id) => {
-            if let Some(window_data) = state.windows.get_mut(&id) {
-                if let WindowState::Session(data) = &mut window_data.state {
-                    let now = std::time::Instant::now();
-                    let delay = if let Some(last) = data.last_packet_time {
-                        now.duration_since(last).as_millis() as u64
+        // Pause or resume recording; sends keep going out either way
+        Message::RecordingPauseToggled(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.recording_paused = !data.recording_paused;
+                data.log.push(
+                    crate::log::LogLevel::Info,
+                    if data.recording_paused {
+                        "Recording paused, sends are not recorded"
                     } else {
-                        0
-                    };
-                    let hex = data.payload_input.clone();
-                    let payload_type = data.payload_type;
-                    let protocol = data.protocol;
-                    let window_id = id;
-                    let addr = state.config.address.clone();
-                    let port = state.config.port.clone();
-                    let sender = data.sender.clone();
-                    // Validate and store payload if valid and non-empty
-                    let packet_data = match payload_type {
-                        PayloadType::Hex => hex::decode(hex.replace(" ", "")),
-                        PayloadType::Ascii => Ok(hex.as_bytes().to_vec()),
-                    };
-                    match packet_data {
-                        Ok(valid_data) => {
-                            if !valid_data.is_empty() {
-                                data.replay_payloads.push(ReplayablePayload {
-                                    payload: hex.clone(),
-                                    payload_type,
-                                    delay,
-                                });
-                            }
-                            data.last_packet_time = Some(now);
-                            Task::perform(
-                                async move {
-                                    match protocol {
-                                        crate::types::Protocol::Tcp => {
-                                            if let Some(sender) = sender {
-                                                let _ = sender
-                                                    .send(SessionCommand::SendPacket(
-                                                        valid_data,
-                                                        payload_type,
-                                                    ))
-                                                    .await;
-                                            }
-                                        }
-                                        crate::types::Protocol::Udp => {
-                                            crate::udp::send_udp_packet(
-                                                valid_data,
-                                                addr,
-                                                port,
-                                                window_id,
-                                                payload_type,
-                                            )
-                                            .await;
-                                        }
-                                    }
-                                },
-                                |_| Message::NoOp,
-                            )
+                        "Recording resumed"
+                    },
+                );
+            }
+            Task::none()
+        }
+        // Drop the newest recorded payload; what was already sent is unaffected
+        Message::UndoLastRecorded(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+                && let Some(removed) = data.undo_last_recorded()
+            {
+                data.log.push(
+                    crate::log::LogLevel::Info,
+                    &format!(
+                        "Removed from recording: {} ({:?}, delay {} ms)",
+                        removed.payload, removed.payload_type, removed.delay
+                    ),
+                );
+            }
+            Task::none()
+        }
+        // Record the input for replay without any network I/O; works while disconnected
+        Message::RecordOnly(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                let input = data.payload_input.clone();
+                let valid = crate::validate::payload_bytes(&input, data.payload_type);
+                // The "after" field doubles as the recorded delay when filled in
+                let delay = match data.send_after_ms.trim() {
+                    "" => Ok(None),
+                    ms => ms.parse::<u64>().map(Some),
+                };
+                let (level, message) = match (valid, delay) {
+                    (Err(e), _) => (crate::log::LogLevel::Warn, format!("Invalid input: {}", e)),
+                    (Ok(bytes), _) if bytes.is_empty() => (
+                        crate::log::LogLevel::Warn,
+                        "Empty payload not recorded".to_string(),
+                    ),
+                    (_, Err(_)) => (
+                        crate::log::LogLevel::Warn,
+                        "Invalid delay, payload not recorded".to_string(),
+                    ),
+                    (Ok(_), Ok(delay)) => {
+                        let label = data.take_send_label();
+                        let message = match &label {
+                            Some(label) => format!("Recorded (not sent) [{}]: {}", label, input),
+                            None => format!("Recorded (not sent): {}", input),
+                        };
+                        let payload = ReplayablePayload {
+                            payload: input,
+                            payload_type: data.payload_type,
+                            delay: 0,
+                            recorded_only: true,
+                            label,
+                        };
+                        data.record_only(payload, delay);
+                        if state.config.clear_input_on_send {
+                            data.payload_input.clear();
                         }
-                        Err(_) => Task::perform(
-                            async move {
-                                let _ = LOG_SENDER.lock().await.send(LogMessage {
-                                    window_id,
-                                    content: "Invalid input".to_string(),
-                                });
-                            },
-                            |_| Message::NoOp,
-                        ),
+                        (crate::log::LogLevel::Info, message)
                     }
-                } else {
-                    Task::none()
-                }
+                };
+                data.log.push(level, &message);
+            }
+            Task::none()
+        }
+        // Show or hide the batch editor
+        Message::BatchToggled(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.show_batch = !data.show_batch;
+            }
+            Task::none()
+        }
+        // Apply an edit to the batch lines
+        Message::BatchEdited(id, action) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.batch.0.perform(action);
+            }
+            Task::none()
+        }
+        // Update the delay between batch lines
+        Message::BatchIntervalChanged(id, interval) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.batch_interval_ms = interval;
+            }
+            Task::none()
+        }
+        // Send the batch lines through the session like a replay, so progress and recording
+        // with real delays come for free; nothing is sent unless every line is valid
+        Message::SendBatch(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+                && data.replay_cancel.is_none()
+                && data.repeat_cancel.is_none()
+                && let Some(sender) = data.sender.clone()
+            {
+                let payloads = data
+                    .batch_interval_ms
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|_| format!("Invalid interval: {}", data.batch_interval_ms))
+                    .and_then(|interval| {
+                        crate::types::parse_lines(&data.batch.0.text(), data.payload_type, interval)
+                    });
+                let payloads = match payloads {
+                    Ok(payloads) => payloads,
+                    Err(err) => {
+                        data.log.push(
+                            crate::log::LogLevel::Warn,
+                            &format!("Batch not sent: {}", err),
+                        );
+                        return Task::none();
+                    }
+                };
+                let cancel = tokio_util::sync::CancellationToken::new();
+                data.replay_cancel = Some(cancel.clone());
+                data.replay_progress = Some((0, payloads.len()));
+                Task::perform(
+                    crate::replay::session_replay_task(
+                        sender,
+                        payloads,
+                        ReplayOptions::default(),
+                        Vec::new(),
+                        cancel,
+                        id,
+                    ),
+                    |_| Message::NoOp,
+                )
             } else {
                 Task::none()
             }
         }
-        // Message::TitleChanged(id, title) => {
-        //     if let Some(window) = state.windows.get_mut(&id) {
-        //         window.title = title;
-        //     }
-        //     Task::none()
-        // }
-        // Exit app if main window closed
-        Message::Closed(id) if id == state.main_window_id => exit(),
-        // Close session window and disconnect if needed
-        Message::Closed(id) => {
-            let command = if let Some(window_data) = state.windows.get(&id) {
-                match &window_data.state {
-                    Session(data) => {
-                        if let Some(sender) = &data.sender {
-                            let sender = sender.clone();
-                            Task::perform(
-                                async move {
-                                    let _ = sender.send(SessionCommand::Disconnect).await;
-                                },
-                                |_| Message::NoOp,
-                            )
-                        } else {
-                            Task::none()
+        // Update the label attached to the next send
+        Message::SendLabelChanged(id, label) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.send_label = label;
+            }
+            Task::none()
+        }
+        // Show or hide the payload table of a replay window
+        Message::PayloadsToggled(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Replay(data) = &mut window_data.state
+            {
+                data.show_payloads = !data.show_payloads;
+            }
+            Task::none()
+        }
+        // Expand the payload table and scroll to the labelled payload
+        Message::JumpToLabel(id, label) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Replay(data) = &mut window_data.state
+                && let Some(index) = data
+                    .payloads
+                    .iter()
+                    .position(|p| p.label.as_deref() == Some(label.as_str()))
+            {
+                data.show_payloads = true;
+                let row_height =
+                    LogText::new(&state.config, window_data.log_zoom).payload_row_height();
+                scrollable::scroll_to(
+                    payloads_scroll_id(id),
+                    scrollable::AbsoluteOffset {
+                        x: 0.0,
+                        y: index as f32 * row_height,
+                    },
+                )
+            } else {
+                Task::none()
+            }
+        }
+        // Remember where the payload table is scrolled to, to lay out the rows there
+        Message::PayloadsScrolled(id, viewport) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Replay(data) = &mut window_data.state
+            {
+                data.payloads_offset = viewport.absolute_offset().y;
+            }
+            Task::none()
+        }
+        // Following brings the payload being sent into view right away
+        Message::PayloadFollowToggled(id, follow) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Replay(data) = &mut window_data.state
+            {
+                data.follow_payload = follow;
+                let row_height =
+                    LogText::new(&state.config, window_data.log_zoom).payload_row_height();
+                return follow_payload(id, data, row_height);
+            }
+            Task::none()
+        }
+        // Scroll the log to the packet a payload was sent as in this run
+        Message::PayloadRowPressed(id, index) => {
+            let Some(window_data) = state.windows.get(&id) else {
+                return Task::none();
+            };
+            let WindowState::Replay(data) = &window_data.state else {
+                return Task::none();
+            };
+            let Some(crate::types::PayloadStatus::Sent(Some(seq))) =
+                data.payload_status.get(index).copied()
+            else {
+                return Task::none();
+            };
+            // Collapsed runs of the same packet list the numbers from their first to last
+            let rows = data.log.rows(window_data.log_filter, &Default::default());
+            let Some(position) = rows.iter().position(|row| match row {
+                crate::log::LogRow::Entry(entry) => entry
+                    .packet
+                    .as_ref()
+                    .is_some_and(|packet| packet.seq == seq),
+                crate::log::LogRow::Repeated { first, last, .. } => {
+                    match (&first.packet, &last.packet) {
+                        (Some(first), Some(last)) => (first.seq..=last.seq).contains(&seq),
+                        _ => false,
+                    }
+                }
+            }) else {
+                return Task::none();
+            };
+            let row_height = LogText::new(&state.config, window_data.log_zoom).row_height;
+            scrollable::scroll_to(
+                log_scroll_id(id),
+                scrollable::AbsoluteOffset {
+                    x: 0.0,
+                    y: (position as f32 * row_height - window_data.log_scroll.height / 2.0)
+                        .max(0.0),
+                },
+            )
+        }
+        // Update the delay applied to the next Send
+        Message::SendAfterChanged(id, ms) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.send_after_ms = ms;
+            }
+            Task::none()
+        }
+        // Send the scheduled payload, recording the delay that actually elapsed
+        Message::ScheduledSendFired(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+                && let Some(scheduled) = data.scheduled_send.take()
+            {
+                if !data.connected {
+                    let reason = format!("{}, scheduled payload not sent", session_status(data).1);
+                    return update_app(state, Message::Failed(id, reason));
+                }
+                let command = SessionCommand::SendPacket(
+                    scheduled.data,
+                    scheduled.payload.payload_type,
+                    scheduled.payload.label.clone(),
+                );
+                data.record_payload(scheduled.payload);
+                let sender = data.sender.clone();
+                Task::perform(
+                    async move {
+                        if let Some(sender) = sender {
+                            let _ = sender.send(command).await;
                         }
+                    },
+                    |_| Message::NoOp,
+                )
+            } else {
+                Task::none()
+            }
+        }
+        // Drop a scheduled send before its timer fires
+        Message::CancelScheduledSend(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+                && let Some(scheduled) = data.scheduled_send.take()
+            {
+                scheduled.cancel.cancel();
+                data.log
+                    .push(crate::log::LogLevel::Info, "Scheduled send cancelled");
+            }
+            Task::none()
+        }
+        // Enable or disable the session heartbeat
+        Message::HeartbeatToggled(id, enabled) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.heartbeat_enabled = enabled;
+                restart_heartbeat(id, data)
+            } else {
+                Task::none()
+            }
+        }
+        // Update a heartbeat setting, restarting a running heartbeat with the new value
+        Message::HeartbeatChanged(id, edit) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                match edit {
+                    HeartbeatEdit::Payload(payload) => data.heartbeat_payload = payload,
+                    HeartbeatEdit::Type(payload_type) => data.heartbeat_type = payload_type,
+                    HeartbeatEdit::Interval(interval) => data.heartbeat_interval_ms = interval,
+                    HeartbeatEdit::Record(record) => data.heartbeat_record = record,
+                    HeartbeatEdit::Quiet(quiet) => data.heartbeat_quiet = quiet,
+                }
+                restart_heartbeat(id, data)
+            } else {
+                Task::none()
+            }
+        }
+        // Apply events reported by the session task
+        Message::SessionEvent(id, event) => match event {
+            SessionEvent::Connection(connected) => {
+                update_app(state, Message::ConnectionStatus(id, connected))
+            }
+            SessionEvent::HandshakeSent(payload)
+            | SessionEvent::QueuedPayloadSent(payload)
+            | SessionEvent::ReplayPayloadSent(payload)
+            | SessionEvent::RepeatPayloadSent(payload) => {
+                if let Some(window_data) = state.windows.get_mut(&id)
+                    && let WindowState::Session(data) = &mut window_data.state
+                {
+                    data.record_payload(payload);
+                }
+                Task::none()
+            }
+            SessionEvent::ReplayProgress(sent, total) => {
+                if let Some(window_data) = state.windows.get_mut(&id)
+                    && let WindowState::Session(data) = &mut window_data.state
+                {
+                    data.replay_progress = Some((sent, total));
+                }
+                Task::none()
+            }
+            SessionEvent::ReplayFinished => {
+                if let Some(window_data) = state.windows.get_mut(&id)
+                    && let WindowState::Session(data) = &mut window_data.state
+                {
+                    data.replay_progress = None;
+                    data.replay_cancel = None;
+                }
+                Task::none()
+            }
+            SessionEvent::Writing(writing) => {
+                if let Some(window_data) = state.windows.get_mut(&id)
+                    && let WindowState::Session(data) = &mut window_data.state
+                {
+                    data.writing = writing;
+                }
+                Task::none()
+            }
+            SessionEvent::Addresses { local, remote } => {
+                if let Some(window_data) = state.windows.get_mut(&id)
+                    && let WindowState::Session(data) = &mut window_data.state
+                {
+                    data.local_addr = Some(local);
+                    data.remote_addr = Some(remote);
+                }
+                Task::none()
+            }
+            SessionEvent::Packet(packet) => {
+                if let Some(window_data) = state.windows.get_mut(&id)
+                    && let WindowState::Session(data) = &mut window_data.state
+                {
+                    data.traffic.count(&packet);
+                    if data.packets.len() < crate::types::MAX_TRANSCRIPT_PACKETS {
+                        data.packets.push(packet);
+                    } else if !data.packets_capped {
+                        data.packets_capped = true;
+                        data.log.push(
+                            crate::log::LogLevel::Warn,
+                            "Transcript full, further packets are not kept",
+                        );
                     }
-                    _ => Task::none(),
                 }
+                Task::none()
+            }
+            SessionEvent::Activity => {
+                if let Some(window_data) = state.windows.get_mut(&id)
+                    && let WindowState::Session(data) = &mut window_data.state
+                {
+                    data.last_activity = Some(std::time::Instant::now());
+                }
+                Task::none()
+            }
+            SessionEvent::HeartbeatSent(payload) => {
+                if let Some(window_data) = state.windows.get_mut(&id)
+                    && let WindowState::Session(data) = &mut window_data.state
+                    && data.heartbeat_record
+                {
+                    data.record_payload(payload);
+                }
+                Task::none()
+            }
+            SessionEvent::RepeatProgress(sent, total) => {
+                if let Some(window_data) = state.windows.get_mut(&id)
+                    && let WindowState::Session(data) = &mut window_data.state
+                {
+                    data.repeat_progress = Some((sent, total));
+                }
+                Task::none()
+            }
+            SessionEvent::RepeatFinished => {
+                if let Some(window_data) = state.windows.get_mut(&id)
+                    && let WindowState::Session(data) = &mut window_data.state
+                {
+                    data.repeat_progress = None;
+                    data.repeat_cancel = None;
+                }
+                Task::none()
+            }
+            SessionEvent::ConnectFailed(error) => {
+                if let Some(window_data) = state.windows.get_mut(&id)
+                    && let WindowState::Session(data) = &mut window_data.state
+                {
+                    data.connect_error = Some(error);
+                }
+                Task::none()
+            }
+        },
+        // Pick a replay file to run through an existing session
+        Message::RunReplayHere(id) => {
+            if let Some(window_data) = state.windows.get(&id)
+                && let WindowState::Session(data) = &window_data.state
+                && data.replay_cancel.is_none()
+                && data.repeat_cancel.is_none()
+            {
+                Task::perform(
+                    pick_replay_file(data.protocol),
+                    move |result| match result {
+                        Ok(Some((replay, _))) => Message::SessionReplayLoaded(id, replay),
+                        Ok(None) => Message::NoOp,
+                        Err(err) => Message::Failed(id, err),
+                    },
+                )
             } else {
                 Task::none()
+            }
+        }
+        // Start feeding the loaded payloads through the session
+        Message::SessionReplayLoaded(id, replay) => {
+            let options = match state.replay_options() {
+                Ok(options) => options,
+                Err(err) => return update_app(state, Message::ReplayError(err)),
             };
-            Task::batch(vec![command, window::close(id)])
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+                && data.replay_cancel.is_none()
+                && data.repeat_cancel.is_none()
+                && let Some(sender) = data.sender.clone()
+            {
+                let cancel = tokio_util::sync::CancellationToken::new();
+                data.replay_cancel = Some(cancel.clone());
+                data.replay_progress = Some((0, replay.payloads.len() * options.loops as usize));
+                Task::perform(
+                    crate::replay::session_replay_task(
+                        sender,
+                        replay.payloads,
+                        options,
+                        replay.tokens,
+                        cancel,
+                        id,
+                    ),
+                    |_| Message::NoOp,
+                )
+            } else {
+                Task::none()
+            }
         }
-        // Append log message to the appropriate window's log
-        Message::LogReceived(content, id) => {
-            if let Some(window_data) = state.windows.get_mut(&id) {
-                match &mut window_data.state {
-                    WindowState::Session(data) => {
-                        data.log.push_str(&content);
-                        data.log.push('\n');
-                    }
-                    WindowState::Replay(data) => {
-                        data.log.push_str(&content);
-                        data.log.push('\n');
-                    }
-                }
+        // Ask a replay running inside the session to stop
+        Message::StopSessionReplay(id) => {
+            if let Some(window_data) = state.windows.get(&id)
+                && let WindowState::Session(data) = &window_data.state
+                && let Some(cancel) = &data.replay_cancel
+            {
+                cancel.cancel();
             }
             Task::none()
         }
-        // Append log message to main window log
-        Message::MainLog(content) => {
-            state.main_log.push_str(&content);
-            state.main_log.push('\n');
+        // Update the repeat-send count
+        Message::RepeatCountChanged(id, count) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.repeat_count = count;
+            }
             Task::none()
         }
-        // Update connection status and window title
-        Message::ConnectionStatus(id, connected) => {
-            if let Some(window_data) = state.windows.get_mut(&id) {
-                if let WindowState::Session(data) = &mut window_data.state {
-                    data.connected = connected;
-                    window_data.title = if connected {
-                        format!("Tcp session (connected to {})", state.config.address)
-                    } else {
-                        "Tcp session (disconnected)".to_string()
-                    };
-                }
+        // Update the repeat-send interval
+        Message::RepeatIntervalChanged(id, interval) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+            {
+                data.repeat_interval_ms = interval;
+            }
+            Task::none()
+        }
+        // Send the current input repeatedly unless a repeat or replay is already running
+        Message::SendRepeatedly(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Session(data) = &mut window_data.state
+                && data.replay_cancel.is_none()
+                && data.repeat_cancel.is_none()
+                && let Some(sender) = data.sender.clone()
+            {
+                let count = data.repeat_count.trim().parse::<usize>().ok();
+                let interval = data.repeat_interval_ms.trim().parse::<u64>().ok();
+                let (count, interval) = match (count.filter(|count| *count > 0), interval) {
+                    (Some(count), Some(interval)) => (count, interval),
+                    _ => {
+                        data.log.push(
+                            crate::log::LogLevel::Error,
+                            "Invalid repeat count or interval",
+                        );
+                        return Task::none();
+                    }
+                };
+                let payload = ReplayablePayload {
+                    payload: data.payload_input.clone(),
+                    payload_type: data.payload_type,
+                    delay: 0,
+                    recorded_only: false,
+                    label: None,
+                };
+                let cancel = tokio_util::sync::CancellationToken::new();
+                data.repeat_cancel = Some(cancel.clone());
+                data.repeat_progress = Some((0, count));
+                Task::perform(
+                    crate::replay::repeat_send_task(sender, payload, count, interval, cancel, id),
+                    |_| Message::NoOp,
+                )
+            } else {
+                Task::none()
+            }
+        }
+        // Ask a running repeat-send to stop
+        Message::StopRepeat(id) => {
+            if let Some(window_data) = state.windows.get(&id)
+                && let WindowState::Session(data) = &window_data.state
+                && let Some(cancel) = &data.repeat_cancel
+            {
+                cancel.cancel();
             }
             Task::none()
         }
@@ -502,45 +5266,29 @@ pub fn update_app(state: &mut App, message: Message) -> Task<Message> {
         Message::ExportSession(id) => {
             if let Some(window_data) = state.windows.get(&id) {
                 if let WindowState::Session(data) = &window_data.state {
-                    let mut payloads = data.replay_payloads.clone();
-                    if !data.initial_payload.is_empty() {
-                        payloads.insert(0, crate::types::ReplayablePayload {
-                            payload: data.initial_payload.clone(),
-                            payload_type: data.initial_payload_type,
-                            delay: 0,
-                        });
-                    }
-                    let replay = crate::types::ReplayableSession {
-                        protocol: data.protocol,
-                        payloads,
-                    };
-                    let title = window_data.title.clone();
+                    let replay = data.to_replayable_session();
+                    let title = window_data.export_stem();
                     Task::perform(
                         async move {
-                            let json = serde_json::to_string_pretty(&replay).unwrap();
-                            let file_path = tokio::task::spawn_blocking(move || {
+                            let json = serde_json::to_string_pretty(&replay)
+                                .map_err(|e| format!("Failed to export replay: {}", e))?;
+                            let file_path = dialog(move || {
                                 rfd::FileDialog::new()
-                                    .set_title("Export Replay")
-                                    .add_filter("JSON Files", &["json"])
-                                    .set_file_name(format!(
-                                        "{}.json",
-                                        title
-                                            .replace(" ", "_")
-                                            .replace("(", "")
-                                            .replace(")", "")
-                                            .replace("to_", "")
-                                    ))
+                                    .set_title(tr("Export Replay"))
+                                    .add_filter(tr("JSON Files"), &["json"])
+                                    .set_file_name(format!("{}.json", title))
                                     .save_file()
                             })
-                            .await
-                            .unwrap();
-                            if let Some(path) = file_path {
-                                if let Err(e) = tokio::fs::write(path, json).await {
-                                    eprintln!("Failed to export replay: {}", e);
-                                }
+                            .await?;
+                            match file_path {
+                                Some(path) => tokio::fs::write(&path, json)
+                                    .await
+                                    .map(|_| Some(path))
+                                    .map_err(|e| format!("Failed to export replay: {}", e)),
+                                None => Ok(None),
                             }
                         },
-                        |_| Message::NoOp,
+                        move |result| Message::ReplayExported(id, result),
                     )
                 } else {
                     Task::none()
@@ -549,37 +5297,52 @@ pub fn update_app(state: &mut App, message: Message) -> Task<Message> {
                 Task::none()
             }
         }
-        // Export session logs to text file
-        Message::ExportLogs(id) => {
+        // Mark the recording exported, so closing the window no longer asks
+        Message::ReplayExported(id, result) => match result {
+            Ok(Some(path)) => {
+                if let Some(window_data) = state.windows.get_mut(&id)
+                    && let WindowState::Session(data) = &mut window_data.state
+                {
+                    data.unexported = false;
+                    data.log.push(
+                        crate::log::LogLevel::Info,
+                        &format!("Replay exported to {}", path.display()),
+                    );
+                }
+                Task::none()
+            }
+            Ok(None) => Task::none(),
+            Err(e) => update_app(state, Message::Failed(id, e)),
+        },
+        // Export session payloads to CSV file for spreadsheet workflows
+        Message::ExportCsv(id) => {
             if let Some(window_data) = state.windows.get(&id) {
                 if let WindowState::Session(data) = &window_data.state {
-                    let logs = data.log.clone();
-                    let title = window_data.title.clone();
+                    let replay = data.to_replayable_session();
+                    let title = window_data.export_stem();
                     Task::perform(
                         async move {
-                            let file_path = tokio::task::spawn_blocking(move || {
+                            let csv = match replay.to_csv() {
+                                Ok(csv) => csv,
+                                Err(e) => return Err(format!("Failed to export CSV: {}", e)),
+                            };
+                            let file_path = dialog(move || {
                                 rfd::FileDialog::new()
-                                    .set_title("Export Logs")
-                                    .add_filter("Text Files", &["txt"])
-                                    .set_file_name(format!(
-                                        "{}_logs.txt",
-                                        title
-                                            .replace(" ", "_")
-                                            .replace("(", "")
-                                            .replace(")", "")
-                                            .replace("to_", "")
-                                    ))
+                                    .set_title(tr("Export as CSV"))
+                                    .add_filter(tr("CSV Files"), &["csv"])
+                                    .set_file_name(format!("{}.csv", title))
                                     .save_file()
                             })
-                            .await
-                            .unwrap();
-                            if let Some(path) = file_path {
-                                if let Err(e) = tokio::fs::write(path, logs).await {
-                                    eprintln!("Failed to export logs: {}", e);
-                                }
+                            .await?;
+                            match file_path {
+                                Some(path) => tokio::fs::write(&path, csv)
+                                    .await
+                                    .map(|_| Some(path))
+                                    .map_err(|e| format!("Failed to export CSV: {}", e)),
+                                None => Ok(None),
                             }
                         },
-                        |_| Message::NoOp,
+                        move |result| Message::ExportFinished(id, result),
                     )
                 } else {
                     Task::none()
@@ -588,185 +5351,1813 @@ pub fn update_app(state: &mut App, message: Message) -> Task<Message> {
                 Task::none()
             }
         }
-        // Open file dialog to select replay file and load session
-        Message::ReplayConnect => {
-            let current_protocol = state.config.protocol;
+        // Export every packet of the session as JSON lines
+        Message::ExportTranscript(id) => {
+            if let Some(window_data) = state.windows.get(&id)
+                && let WindowState::Session(data) = &window_data.state
+            {
+                let transcript = match data.transcript() {
+                    Ok(transcript) => transcript,
+                    Err(err) => return update_app(state, Message::ReplayError(err)),
+                };
+                let title = window_data.export_stem();
+                Task::perform(
+                    async move {
+                        let file_path = dialog(move || {
+                            rfd::FileDialog::new()
+                                .set_title(tr("Export Transcript"))
+                                .add_filter("JSON Lines", &["jsonl"])
+                                .set_file_name(format!("{}_transcript.jsonl", title))
+                                .save_file()
+                        })
+                        .await?;
+                        match file_path {
+                            Some(path) => tokio::fs::write(&path, transcript)
+                                .await
+                                .map(|_| Some(path))
+                                .map_err(|e| format!("Failed to export transcript: {}", e)),
+                            None => Ok(None),
+                        }
+                    },
+                    move |result| Message::ExportFinished(id, result),
+                )
+            } else {
+                Task::none()
+            }
+        }
+        // Choose where to put the log bundle
+        Message::ExportAllLogs(id) => {
+            if id != state.main_window_id || state.log_bundle_progress.is_some() {
+                return Task::none();
+            }
             Task::perform(
                 async move {
-                    let file_path = tokio::task::spawn_blocking(|| {
+                    dialog(|| {
                         rfd::FileDialog::new()
-                            .set_title("Select Replay File")
-                            .add_filter("JSON Files", &["json"])
-                            .pick_file()
+                            .set_title(tr("Export all logs into"))
+                            .pick_folder()
                     })
                     .await
-                    .unwrap();
-                    if let Some(path) = file_path {
-                        let content = tokio::fs::read_to_string(&path)
-                            .await
-                            .map_err(|e| format!("Failed to read file: {}", e))?;
-                        let replay: ReplayableSession = serde_json::from_str(&content)
-                            .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-                        if replay.protocol == current_protocol {
-                            let file_name = path
-                                .file_name()
-                                .unwrap_or_default()
-                                .to_string_lossy()
-                                .to_string();
-                            Ok((replay, file_name))
-                        } else {
-                            Err(format!(
-                                "Replay protocol ({:?}) does not match current protocol ({:?})",
-                                replay.protocol, current_protocol
-                            ))
-                        }
-                    } else {
-                        Err("No file selected".to_string())
+                },
+                move |result| match result {
+                    Ok(dir) => Message::LogBundleDirPicked(dir),
+                    Err(e) => Message::Failed(id, e),
+                },
+            )
+        }
+        // Write the main log and the log of every open window into a new directory
+        Message::LogBundleDirPicked(dir) => {
+            let Some(dir) = dir else {
+                return Task::none();
+            };
+            let now = chrono::Local::now();
+            let dir = dir.join(format!("replayr_logs_{}", now.format("%Y%m%d_%H%M%S")));
+            let mut files = vec![(
+                "main_log.txt".to_string(),
+                format!(
+                    "# replayr main log\n# Exported: {}\n# Windows: {}\n\n{}",
+                    now.format("%Y-%m-%d %H:%M:%S"),
+                    state.windows.len(),
+                    state.main_log.text(state.main_log_filter.all_levels())
+                ),
+            )];
+            let mut used = std::collections::HashSet::from(["main_log".to_string()]);
+            for window_data in state.windows.values() {
+                files.push((
+                    format!("{}.txt", bundle_name(&window_data.export_stem(), &mut used)),
+                    window_data.log_export(),
+                ));
+            }
+            let total = files
+                .iter()
+                .map(|(_, contents)| contents.len() as u64)
+                .sum();
+            state.log_bundle_progress = Some((0, total));
+            Task::run(write_log_bundle(dir, files), Message::LogBundle)
+        }
+        // Show the bundle progress, and log failed files and where the bundle went
+        Message::LogBundle(event) => {
+            match event {
+                LogBundleEvent::Progress(written, total) => {
+                    state.log_bundle_progress = Some((written, total));
+                }
+                LogBundleEvent::Failed(path, e) => state.raise(
+                    state.main_window_id,
+                    crate::log::LogLevel::Error,
+                    &format!("Failed to write {}: {}", path.display(), e),
+                ),
+                LogBundleEvent::Finished(dir, written) => {
+                    state.log_bundle_progress = None;
+                    state.main_log.push(
+                        crate::log::LogLevel::Info,
+                        &format!("{} logs exported to {}", written, dir.display()),
+                    );
+                }
+            }
+            Task::none()
+        }
+        // Ask whether to keep runs of repeated lines collapsed, if the log view collapses any
+        Message::ExportLogs(id) => {
+            let Some(repeats) = state
+                .windows
+                .get(&id)
+                .map(|window_data| window_data.log().has_repeats(window_data.log_filter))
+            else {
+                return Task::none();
+            };
+            if !repeats {
+                return update_app(state, Message::ExportLogsCollapsed(id, false));
+            }
+            let (collapsed, expanded) = (tr("Collapsed"), tr("Expanded"));
+            Task::perform(
+                dialog(move || {
+                    rfd::MessageDialog::new()
+                        .set_title(tr("Export Logs"))
+                        .set_description(tr(
+                            "Runs of repeated lines are collapsed in the log. Export them collapsed or in full?",
+                        ))
+                        .set_buttons(rfd::MessageButtons::YesNoCancelCustom(
+                            collapsed.to_string(),
+                            expanded.to_string(),
+                            tr("Cancel").to_string(),
+                        ))
+                        .show()
+                }),
+                move |result| match result {
+                    Ok(rfd::MessageDialogResult::Custom(choice)) if choice == collapsed => {
+                        Message::ExportLogsCollapsed(id, true)
+                    }
+                    Ok(rfd::MessageDialogResult::Custom(choice)) if choice == expanded => {
+                        Message::ExportLogsCollapsed(id, false)
+                    }
+                    Ok(_) => Message::NoOp,
+                    Err(e) => Message::Failed(id, e),
+                },
+            )
+        }
+        // Ask whether to export every level or only those shown, unless all are shown.
+        // Debug entries are only exported while shown
+        Message::ExportLogsCollapsed(id, collapse) => {
+            let Some(filter) = state
+                .windows
+                .get(&id)
+                .map(|window_data| window_data.log_filter)
+            else {
+                return Task::none();
+            };
+            if filter == filter.all_levels() {
+                return update_app(state, Message::ExportLogsFiltered(id, filter, collapse));
+            }
+            let (all_levels, current_filter) = (tr("All levels"), tr("Current filter"));
+            Task::perform(
+                dialog(move || {
+                    rfd::MessageDialog::new()
+                        .set_title(tr("Export Logs"))
+                        .set_description(tr(
+                            "Some log entries are hidden. Which entries should be exported?",
+                        ))
+                        .set_buttons(rfd::MessageButtons::YesNoCancelCustom(
+                            all_levels.to_string(),
+                            current_filter.to_string(),
+                            tr("Cancel").to_string(),
+                        ))
+                        .show()
+                }),
+                move |result| match result {
+                    Ok(rfd::MessageDialogResult::Custom(choice)) if choice == all_levels => {
+                        Message::ExportLogsFiltered(id, filter.all_levels(), collapse)
+                    }
+                    Ok(rfd::MessageDialogResult::Custom(choice)) if choice == current_filter => {
+                        Message::ExportLogsFiltered(id, filter, collapse)
                     }
+                    Ok(_) => Message::NoOp,
+                    Err(e) => Message::Failed(id, e),
                 },
+            )
+        }
+        // Export session logs with the levels in `filter` to a text file, with runs of
+        // repeated lines collapsed as in the view if `collapse` is set
+        Message::ExportLogsFiltered(id, filter, collapse) => {
+            if let Some(window_data) = state.windows.get(&id) {
+                if let WindowState::Session(data) = &window_data.state {
+                    let lines = if collapse {
+                        data.log.collapsed(filter)
+                    } else {
+                        data.log.text(filter)
+                    };
+                    let logs = window_data.log_header() + &lines;
+                    let title = window_data.export_stem();
+                    Task::perform(
+                        async move {
+                            let file_path = dialog(move || {
+                                rfd::FileDialog::new()
+                                    .set_title(tr("Export Logs"))
+                                    .add_filter(tr("Text Files"), &["txt"])
+                                    .set_file_name(format!("{}_logs.txt", title))
+                                    .save_file()
+                            })
+                            .await?;
+                            match file_path {
+                                Some(path) => tokio::fs::write(&path, logs)
+                                    .await
+                                    .map(|_| Some(path))
+                                    .map_err(|e| format!("Failed to export logs: {}", e)),
+                                None => Ok(None),
+                            }
+                        },
+                        move |result| Message::ExportFinished(id, result),
+                    )
+                } else {
+                    Task::none()
+                }
+            } else {
+                Task::none()
+            }
+        }
+        // Open file dialog to select replay file and load session
+        Message::ReplayConnect => {
+            let current_protocol = state.config.protocol;
+            let options = match state.replay_options() {
+                Ok(options) => options,
+                Err(err) => return update_app(state, Message::ReplayError(err)),
+            };
+            Task::perform(
+                pick_replay_file(current_protocol),
                 move |result| match result {
-                    Ok((replay, file_name)) => {
-                        Message::ReplayWindowOpenedWithFile(replay, file_name)
+                    Ok(Some((replay, path))) => {
+                        Message::ReplayWindowOpenedWithFile(replay, path, options.clone(), None)
                     }
+                    Ok(None) => Message::NoOp,
                     Err(err) => Message::ReplayError(err),
                 },
             )
         }
+        // Load a recent replay file without the dialog and run it against its last target
+        Message::ReplayRecent(recent) => {
+            let options = match state.replay_options() {
+                Ok(options) => options,
+                Err(err) => return update_app(state, Message::ReplayError(err)),
+            };
+            Task::perform(
+                async move {
+                    let content = tokio::fs::read_to_string(&recent.path)
+                        .await
+                        .map_err(|e| format!("Failed to read file: {}", e));
+                    let replay = content.and_then(|content| {
+                        parse_replay_file(&recent.path, &content, recent.protocol)
+                    });
+                    (recent, replay)
+                },
+                move |(recent, result)| match result {
+                    Ok(replay) => Message::ReplayWindowOpenedWithFile(
+                        replay,
+                        recent.path,
+                        options.clone(),
+                        Some((recent.address, recent.port)),
+                    ),
+                    Err(err) => Message::RecentReplayFailed(recent.path, err),
+                },
+            )
+        }
+        // Report a recent replay file that failed to load and drop it from the list
+        Message::RecentReplayFailed(path, err) => {
+            if state.config.forget_replay(&path) {
+                crate::config::save_config(&state.config);
+            }
+            update_app(
+                state,
+                Message::ReplayError(format!("{}: {}", path.display(), err)),
+            )
+        }
         // Open replay window and start replay task
-        Message::ReplayWindowOpenedWithFile(replay, file_name) => {
-            let (new_id, task) = window::open(window::Settings::default());
-            let payloads_clone = replay.payloads.clone();
+        Message::ReplayWindowOpenedWithFile(replay, path, options, target) => {
+            let (address, port) =
+                target.unwrap_or_else(|| (state.config.address.clone(), state.config.port));
+            let (new_id, task) = state.open_window(WindowKind::Replay);
+            let file_name = path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
             let protocol = replay.protocol;
+            let validation = crate::validate::validate_replay(
+                protocol,
+                &replay.payloads,
+                state.config.udp_safe_datagram_size,
+            );
+            let mut log = crate::log::Log::default();
+            log.push(
+                crate::log::LogLevel::Info,
+                &format!("Validation: {}", crate::validate::summary(&validation)),
+            );
             state.windows.insert(
                 new_id,
                 Window {
+                    label: None,
+                    label_edit: None,
                     title: "Replay session".to_string(),
-                    state: Replay(ReplayData {
-                        log: String::new(),
+                    state: Replay(Box::new(ReplayData {
+                        log,
                         payloads: replay.payloads,
+                        tokens: replay.tokens,
+                        recorded_target: replay.target,
+                        notes: replay.notes,
                         connected: false,
-                        file_name: file_name.clone(),
+                        file_name,
+                        path: Some(path),
+                        dirty: false,
                         current_index: 0,
-                    }),
+                        loops: options.loops,
+                        iteration: 0,
+                        speed: options.describe(0),
+                        protocol,
+                        received: Vec::new(),
+                        received_bytes: 0,
+                        received_capped: false,
+                        packet_seq: Default::default(),
+                        response_format: ResponseFormat::Binary,
+                        validation,
+                        show_validation: false,
+                        address,
+                        port: port.to_string(),
+                        options,
+                        cancel: None,
+                        stress: false,
+                        stress_duration_secs: "10".to_string(),
+                        stress_iterations: String::new(),
+                        stress_stats: None,
+                        status: ReplayStatus::Running,
+                        failure: None,
+                        started_at: Some(chrono::Local::now()),
+                        ended_at: None,
+                        show_payloads: false,
+                        payload_status: Vec::new(),
+                        payloads_offset: 0.0,
+                        follow_payload: true,
+                    })),
+                    log_file: None,
+                    log_filter: Default::default(),
+                    log_scroll: Default::default(),
+                    log_zoom: Default::default(),
+                    banners: Default::default(),
                 },
             );
-            let addr = state.config.address.clone();
-            let port = state.config.port.clone();
+            if state.config.log_to_file {
+                auto_log_file(state, new_id);
+            }
             Task::batch(vec![
-                task.map(move |_| Message::ReplayStarted(new_id)),
+                task.discard(),
+                update_app(state, Message::StartReplay(new_id)),
+            ])
+        }
+        // Start a normal or stress run in a replay window that is not already running
+        Message::StartReplay(id) => {
+            let mut recent = None;
+            let task = if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Replay(data) = &mut window_data.state
+                && data.cancel.is_none()
+            {
+                let cancel = tokio_util::sync::CancellationToken::new();
+                let task = if data.stress {
+                    let limit = match data.stress_limit() {
+                        Ok(limit) => limit,
+                        Err(err) => {
+                            data.log.push(crate::log::LogLevel::Error, &err);
+                            return Task::none();
+                        }
+                    };
+                    data.stress_stats = Some(Default::default());
+                    Task::perform(
+                        crate::replay::stress_task(
+                            data.protocol,
+                            data.payloads.clone(),
+                            data.address.clone(),
+                            data.port.clone(),
+                            limit,
+                            cancel.clone(),
+                            id,
+                        ),
+                        |_| Message::NoOp,
+                    )
+                } else {
+                    data.current_index = 0;
+                    data.iteration = 0;
+                    data.payload_status.clear();
+                    Task::perform(
+                        crate::replay::replay_task(
+                            data.protocol,
+                            data.payloads.clone(),
+                            data.address.clone(),
+                            data.port.clone(),
+                            data.options.clone(),
+                            data.tokens.clone(),
+                            cancel.clone(),
+                            crate::replay::Reporter::Window(id, data.packet_seq.clone()),
+                        ),
+                        |_| Message::NoOp,
+                    )
+                };
+                data.cancel = Some(cancel);
+                data.status = ReplayStatus::Running;
+                data.connected = false;
+                data.failure = None;
+                data.started_at = Some(chrono::Local::now());
+                data.ended_at = None;
+                window_data.title = data.title();
+                // Remembered with the target actually used, for "Replay last"
+                if let Some(path) = &data.path
+                    && let Ok(port) = data.port.trim().parse()
+                {
+                    recent = Some(RecentReplay {
+                        path: path.clone(),
+                        protocol: data.protocol,
+                        address: data.address.clone(),
+                        port,
+                    });
+                }
+                task
+            } else {
+                Task::none()
+            };
+            if let Some(recent) = recent
+                && state.config.remember_replay(recent)
+            {
+                crate::config::save_config(&state.config);
+            }
+            task
+        }
+        // Ask the running replay or stress task to stop
+        Message::StopReplay(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Replay(data) = &mut window_data.state
+                && let Some(cancel) = &data.cancel
+            {
+                cancel.cancel();
+                data.status = ReplayStatus::Stopped;
+                window_data.title = data.title();
+            }
+            Task::none()
+        }
+        // Switch between normal replay and stress mode for the next run
+        Message::StressToggled(id, stress) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Replay(data) = &mut window_data.state
+            {
+                data.stress = stress;
+            }
+            Task::none()
+        }
+        // Update the stress duration limit
+        Message::StressDurationChanged(id, secs) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Replay(data) = &mut window_data.state
+            {
+                data.stress_duration_secs = secs;
+            }
+            Task::none()
+        }
+        // Update the stress iteration limit
+        Message::StressIterationsChanged(id, count) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Replay(data) = &mut window_data.state
+            {
+                data.stress_iterations = count;
+            }
+            Task::none()
+        }
+        // Update replay progress index or current iteration
+        Message::ReplayProgress(id, event) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Replay(data) = &mut window_data.state
+            {
+                match event {
+                    // A coalesced update is the first of those pending; take them all
+                    event @ (ReplayEvent::Progress(_)
+                    | ReplayEvent::Iteration(..)
+                    | ReplayEvent::Sent(..)
+                    | ReplayEvent::Skipped(_)
+                    | ReplayEvent::SendFailed(_)) => {
+                        let progress = crate::log::take_progress(id).unwrap_or_else(|| {
+                            let mut progress = crate::types::PendingProgress::default();
+                            let _ = progress.fold(event);
+                            progress
+                        });
+                        data.apply_progress(progress);
+                    }
+                    ReplayEvent::Stress(stats) => data.stress_stats = Some(stats),
+                    ReplayEvent::Connected => data.connected = true,
+                    ReplayEvent::Failed(err) => {
+                        window_data.banners.raise(crate::log::LogLevel::Error, &err);
+                        data.failure = Some(err);
+                    }
+                    // Progress still pending is the last the replay made
+                    ReplayEvent::Finished => {
+                        if let Some(progress) = crate::log::take_progress(id) {
+                            data.apply_progress(progress);
+                        }
+                        data.cancel = None;
+                        data.connected = false;
+                        data.ended_at = Some(chrono::Local::now());
+                        if data.status == ReplayStatus::Running {
+                            data.status = ReplayStatus::Finished;
+                        }
+                    }
+                    ReplayEvent::Received(chunk) => {
+                        if !data.push_received(chunk) && !data.received_capped {
+                            data.received_capped = true;
+                            data.log.push(
+                                crate::log::LogLevel::Warn,
+                                "Received data limit reached, further responses are not kept for export",
+                            );
+                        }
+                    }
+                }
+                window_data.title = data.title();
+                let row_height =
+                    LogText::new(&state.config, window_data.log_zoom).payload_row_height();
+                return follow_payload(id, data, row_height);
+            }
+            Task::none()
+        }
+        // Log replay error to main log and show it there
+        Message::ReplayError(err) => {
+            state.raise(state.main_window_id, crate::log::LogLevel::Error, &err);
+            Task::none()
+        }
+        // Log a failure in its window and show it there
+        Message::Failed(id, err) => {
+            state.raise(id, crate::log::LogLevel::Error, &err);
+            Task::none()
+        }
+        // Log where an export went in its window; failures are also shown as a banner
+        Message::ExportFinished(id, result) => match result {
+            Ok(Some(path)) => {
+                let message = format!("Exported to {}", path.display());
+                match state.windows.get_mut(&id) {
+                    Some(window_data) => window_data.push_log(crate::log::LogEntry::new(
+                        crate::log::LogLevel::Info,
+                        &message,
+                    )),
+                    None => state.main_log.push(crate::log::LogLevel::Info, &message),
+                }
+                Task::none()
+            }
+            Ok(None) => Task::none(),
+            Err(e) => update_app(state, Message::Failed(id, e)),
+        },
+        // Hide one banner of a window
+        Message::DismissBanner(id, banner) => {
+            match state.windows.get_mut(&id) {
+                Some(window_data) => window_data.banners.dismiss(banner),
+                None => state.main_banners.dismiss(banner),
+            }
+            Task::none()
+        }
+        // Hide every banner of a window
+        Message::DismissBanners(id) => {
+            match state.windows.get_mut(&id) {
+                Some(window_data) => window_data.banners.dismiss_all(),
+                None => state.main_banners.dismiss_all(),
+            }
+            Task::none()
+        }
+        // Dismiss notices that have shown long enough; errors stay until dismissed
+        Message::ExpireBanners => {
+            let timeout = std::time::Duration::from_secs(state.config.banner_timeout_secs);
+            state.main_banners.expire(timeout);
+            for window_data in state.windows.values_mut() {
+                window_data.banners.expire(timeout);
+            }
+            Task::none()
+        }
+        // Update the export format for received replay data
+        Message::ResponseFormatChanged(id, format) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Replay(data) = &mut window_data.state
+            {
+                data.response_format = format;
+            }
+            Task::none()
+        }
+        // Export data received during the replay to a file
+        Message::ExportResponses(id) => {
+            if let Some(window_data) = state.windows.get(&id) {
+                if let WindowState::Replay(data) = &window_data.state {
+                    let (filter, extension) = match data.response_format {
+                        ResponseFormat::Binary => (tr("Binary Files"), "bin"),
+                        ResponseFormat::Json | ResponseFormat::Replay => (tr("JSON Files"), "json"),
+                    };
+                    let contents = data.export_responses();
+                    let file_name =
+                        format!("{}_responses.{}", window_data.export_stem(), extension);
+                    Task::perform(
+                        async move {
+                            let contents = match contents {
+                                Ok(contents) => contents,
+                                Err(e) => return Err(format!("Failed to export responses: {}", e)),
+                            };
+                            let file_path = dialog(move || {
+                                rfd::FileDialog::new()
+                                    .set_title(tr("Export Responses"))
+                                    .add_filter(filter, &[extension])
+                                    .set_file_name(file_name)
+                                    .save_file()
+                            })
+                            .await?;
+                            match file_path {
+                                Some(path) => tokio::fs::write(&path, contents)
+                                    .await
+                                    .map(|_| Some(path))
+                                    .map_err(|e| format!("Failed to export responses: {}", e)),
+                                None => Ok(None),
+                            }
+                        },
+                        move |result| Message::ExportFinished(id, result),
+                    )
+                } else {
+                    Task::none()
+                }
+            } else {
+                Task::none()
+            }
+        }
+        // Re-run validation on the current payloads and show the report
+        Message::ValidateReplay(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Replay(data) = &mut window_data.state
+            {
+                data.validation = crate::validate::validate_replay(
+                    data.protocol,
+                    &data.payloads,
+                    state.config.udp_safe_datagram_size,
+                );
+                data.show_validation = !data.show_validation;
+            }
+            Task::none()
+        }
+        // Export the validation report to a text file
+        Message::ExportValidation(id) => {
+            if let Some(window_data) = state.windows.get(&id)
+                && let WindowState::Replay(data) = &window_data.state
+            {
+                let reports = crate::validate::validate_replay(
+                    data.protocol,
+                    &data.payloads,
+                    state.config.udp_safe_datagram_size,
+                );
+                let report = crate::validate::render_report(&data.file_name, &reports);
+                let file_name = format!("{}_validation.txt", window_data.export_stem());
+                Task::perform(
+                    async move {
+                        let file_path = dialog(move || {
+                            rfd::FileDialog::new()
+                                .set_title(tr("Export Validation"))
+                                .add_filter(tr("Text Files"), &["txt"])
+                                .set_file_name(file_name)
+                                .save_file()
+                        })
+                        .await?;
+                        match file_path {
+                            Some(path) => tokio::fs::write(&path, report)
+                                .await
+                                .map(|_| Some(path))
+                                .map_err(|e| format!("Failed to export validation: {}", e)),
+                            None => Ok(None),
+                        }
+                    },
+                    move |result| Message::ExportFinished(id, result),
+                )
+            } else {
+                Task::none()
+            }
+        }
+        // Add a token rule, marking the replay as edited
+        Message::AddTokenRule(id) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Replay(data) = &mut window_data.state
+            {
+                data.tokens.push(TokenRule {
+                    name: format!("token{}", data.tokens.len() + 1),
+                    source: TokenSource::Range {
+                        offset: 0,
+                        length: 0,
+                    },
+                });
+                data.dirty = true;
+            }
+            Task::none()
+        }
+        // Remove a token rule, marking the replay as edited
+        Message::RemoveTokenRule(id, index) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Replay(data) = &mut window_data.state
+                && index < data.tokens.len()
+            {
+                data.tokens.remove(index);
+                data.dirty = true;
+            }
+            Task::none()
+        }
+        // Apply an edit to a token rule, marking the replay as edited
+        Message::TokenRuleChanged(id, index, edit) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Replay(data) = &mut window_data.state
+                && let Some(rule) = data.tokens.get_mut(index)
+            {
+                rule.apply(edit);
+                data.dirty = true;
+            }
+            Task::none()
+        }
+        // Save the replay to its original file, or ask for one if it has none
+        Message::SaveReplay(id) => {
+            if let Some(window_data) = state.windows.get(&id)
+                && let WindowState::Replay(data) = &window_data.state
+            {
+                match data.path.clone() {
+                    Some(path) => {
+                        let replay = data.to_replayable_session();
+                        Task::perform(save_replay(path, replay), move |result| {
+                            Message::ReplaySaved(id, result)
+                        })
+                    }
+                    None => update_app(state, Message::SaveReplayAs(id)),
+                }
+            } else {
+                Task::none()
+            }
+        }
+        // Save the replay to a file chosen in a dialog
+        Message::SaveReplayAs(id) => {
+            if let Some(window_data) = state.windows.get(&id)
+                && let WindowState::Replay(data) = &window_data.state
+            {
+                let replay = data.to_replayable_session();
+                let file_name = data.file_name.clone();
                 Task::perform(
                     async move {
-                        crate::replay::replay_task(protocol, payloads_clone, addr, port, new_id)
-                            .await;
+                        let file_path = dialog(move || {
+                            rfd::FileDialog::new()
+                                .set_title(tr("Save Replay As"))
+                                .add_filter(tr("JSON Files"), &["json"])
+                                .add_filter(tr("CSV Files"), &["csv"])
+                                .set_file_name(file_name)
+                                .save_file()
+                        })
+                        .await;
+                        match file_path {
+                            Ok(Some(path)) => Some(save_replay(path, replay).await),
+                            Ok(None) => None,
+                            Err(e) => Some(Err(e)),
+                        }
                     },
-                    |_| Message::NoOp,
-                ),
-            ])
+                    move |result| match result {
+                        Some(result) => Message::ReplaySaved(id, result),
+                        None => Message::NoOp,
+                    },
+                )
+            } else {
+                Task::none()
+            }
         }
-        // Mark replay as connected
-        Message::ReplayStarted(id) => {
-            if let Some(window_data) = state.windows.get_mut(&id) {
-                if let WindowState::Replay(data) = &mut window_data.state {
-                    data.connected = true;
-                }
+        // Remember the saved path and clear the dirty flag, or log the failure
+        Message::ReplaySaved(id, result) => {
+            if let Some(window_data) = state.windows.get_mut(&id)
+                && let WindowState::Replay(data) = &mut window_data.state
+            {
+                let entry = match result {
+                    Ok(path) => {
+                        let entry = crate::log::LogEntry::new(
+                            crate::log::LogLevel::Info,
+                            &format!("Saved replay to {}", path.display()),
+                        );
+                        data.file_name = path
+                            .file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .to_string();
+                        data.path = Some(path);
+                        data.dirty = false;
+                        window_data.title = data.title();
+                        entry
+                    }
+                    Err(err) => {
+                        let text = format!("Failed to save replay: {}", err);
+                        window_data
+                            .banners
+                            .raise(crate::log::LogLevel::Error, &text);
+                        crate::log::LogEntry::new(crate::log::LogLevel::Error, &text)
+                    }
+                };
+                data.log.push_entry(entry);
             }
             Task::none()
         }
-        // Update replay progress index
-        Message::ReplayProgress(id, current) => {
-            if let Some(window_data) = state.windows.get_mut(&id) {
-                if let WindowState::Replay(data) = &mut window_data.state {
-                    data.current_index = current;
+
+        _ => Task::none(),
+    }
+}
+
+/// Shows a message box with an OK button, e.g. the list of keyboard shortcuts.
+fn info_dialog(title: &'static str, description: String) -> Task<Message> {
+    Task::perform(
+        dialog(move || {
+            rfd::MessageDialog::new()
+                .set_title(title)
+                .set_description(description)
+                .set_buttons(rfd::MessageButtons::Ok)
+                .show()
+        }),
+        |_| Message::NoOp,
+    )
+}
+
+/// Asks whether to export a session's recording before its window closes. While exiting,
+/// the answer goes on to the remaining windows.
+fn confirm_unexported(
+    id: window::Id,
+    title: String,
+    count: usize,
+    dir: String,
+    exiting: bool,
+) -> Task<Message> {
+    let (export, close) = (tr("Export and close"), tr("Close without saving"));
+    Task::perform(
+        dialog(move || {
+            rfd::MessageDialog::new()
+                    .set_title(tr("Unexported session"))
+                    .set_description(tr_args(
+                        "{} recorded {} payloads that have not been exported. Export the replay to {} before closing?",
+                        &[&title, &count, &dir],
+                    ))
+                    .set_buttons(rfd::MessageButtons::YesNoCancelCustom(
+                        export.to_string(),
+                        close.to_string(),
+                        tr("Cancel").to_string(),
+                    ))
+                    .show()
+        }),
+        move |result| match result {
+            Ok(rfd::MessageDialogResult::Custom(choice)) if choice == export => {
+                if exiting {
+                    Message::ClosedForExit(id, true)
+                } else {
+                    Message::ExportAndClose(id)
                 }
             }
-            Task::none()
+            Ok(rfd::MessageDialogResult::Custom(choice)) if choice == close => {
+                if exiting {
+                    Message::ClosedForExit(id, false)
+                } else {
+                    Message::Closed(id)
+                }
+            }
+            Ok(_) => Message::NoOp,
+            Err(e) => Message::Failed(id, e),
+        },
+    )
+}
+
+/// Runs a blocking file or message dialog off the async runtime. A dialog that panics is
+/// reported as an error instead of ending the task silently.
+async fn dialog<T: Send + 'static>(show: impl FnOnce() -> T + Send + 'static) -> Result<T, String> {
+    tokio::task::spawn_blocking(show)
+        .await
+        .map_err(|e| format!("The dialog failed: {}", e))
+}
+
+/// Asks the user for a JSON or CSV replay file and loads it, checking it matches `protocol`.
+/// Returns None if the dialog was cancelled.
+async fn pick_replay_file(
+    protocol: crate::types::Protocol,
+) -> Result<Option<(ReplayableSession, std::path::PathBuf)>, String> {
+    let file_path = dialog(|| {
+        rfd::FileDialog::new()
+            .set_title(tr("Select Replay File"))
+            .add_filter(tr("Replay Files"), &["json", "csv"])
+            .add_filter(tr("JSON Files"), &["json"])
+            .add_filter(tr("CSV Files"), &["csv"])
+            .pick_file()
+    })
+    .await?;
+    let Some(path) = file_path else {
+        return Ok(None);
+    };
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let replay = parse_replay_file(&path, &content, protocol)?;
+    Ok(Some((replay, path)))
+}
+
+/// Parses the contents of a replay file, as CSV for `.csv` files and JSON otherwise,
+/// and checks that it was recorded with `protocol`.
+pub fn parse_replay_file(
+    path: &std::path::Path,
+    content: &str,
+    protocol: crate::types::Protocol,
+) -> Result<ReplayableSession, String> {
+    let is_csv = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    let replay: ReplayableSession = if is_csv {
+        ReplayableSession::from_csv(content, protocol)?
+    } else {
+        serde_json::from_str(content).map_err(|e| format!("Failed to parse JSON: {}", e))?
+    };
+    if replay.protocol == protocol {
+        Ok(replay)
+    } else {
+        Err(format!(
+            "Replay protocol ({:?}) does not match current protocol ({:?})",
+            replay.protocol, protocol
+        ))
+    }
+}
+
+/// Picks a settings file, parses it on top of `current`, and asks to apply the changes.
+/// Returns None if no file was picked or the import was declined.
+async fn import_settings(
+    current: Config,
+) -> Result<Option<Box<crate::config::SettingsImport>>, String> {
+    let Some(path) = dialog(|| {
+        rfd::FileDialog::new()
+            .set_title(tr("Import settings"))
+            .add_filter(tr("Settings"), &["toml", "json"])
+            .pick_file()
+    })
+    .await?
+    else {
+        return Ok(None);
+    };
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    let import = crate::config::import_settings(&current, &path, &content)?;
+    let mut description = if import.changes.is_empty() {
+        tr("No settings change.").to_string()
+    } else {
+        format!("{}\n{}", tr("Changes:"), import.changes.join("\n"))
+    };
+    if !import.warnings.is_empty() {
+        description.push_str(&format!(
+            "\n\n{}\n{}",
+            tr("Warnings:"),
+            import.warnings.join("\n")
+        ));
+    }
+    description.push_str(&format!("\n\n{}", tr("Apply these settings?")));
+    let confirmed = dialog(move || {
+        rfd::MessageDialog::new()
+            .set_title(tr("Import settings"))
+            .set_description(description)
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .show()
+    })
+    .await?;
+    Ok((confirmed == rfd::MessageDialogResult::Yes).then(|| Box::new(import)))
+}
+
+/// Writes a replay to `path`, as CSV for `.csv` files and pretty-printed JSON otherwise.
+async fn save_replay(
+    path: std::path::PathBuf,
+    replay: ReplayableSession,
+) -> Result<std::path::PathBuf, String> {
+    let is_csv = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    let contents = if is_csv {
+        replay.to_csv()?
+    } else {
+        serde_json::to_string_pretty(&replay).map_err(|e| e.to_string())?
+    };
+    tokio::fs::write(&path, contents)
+        .await
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Height of a row in a replay window's payload table at the default text size of 16, used
+/// to scroll to a row.
+const PAYLOAD_ROW_HEIGHT: f32 = 24.0;
+
+/// Scrollable id of a replay window's payload table.
+fn payloads_scroll_id(id: window::Id) -> scrollable::Id {
+    scrollable::Id::new(format!("payloads-{:?}", id))
+}
+
+/// Height of the payload table of a replay window.
+const PAYLOAD_TABLE_HEIGHT: f32 = 200.0;
+
+/// Scrolls the payload table of a replay window to the payload being sent, if following
+/// it and the row is out of view.
+fn follow_payload(id: window::Id, data: &ReplayData, row_height: f32) -> Task<Message> {
+    let top = data.current_index as f32 * row_height;
+    if !data.follow_payload
+        || !data.show_payloads
+        || data.status != ReplayStatus::Running
+        || (top >= data.payloads_offset
+            && top + row_height <= data.payloads_offset + PAYLOAD_TABLE_HEIGHT)
+    {
+        return Task::none();
+    }
+    scrollable::scroll_to(
+        payloads_scroll_id(id),
+        scrollable::AbsoluteOffset {
+            x: 0.0,
+            y: (top - PAYLOAD_TABLE_HEIGHT / 2.0).max(0.0),
+        },
+    )
+}
+
+/// Number of bytes a payload sends and the first of them in hex, or "?" and the payload
+/// as typed if it uses tokens or does not decode.
+fn payload_summary(payload: &ReplayablePayload) -> (String, String) {
+    const PREVIEW: usize = 8;
+    match crate::validate::payload_bytes(&payload.payload, payload.payload_type) {
+        Ok(bytes) => {
+            let mut preview = bytes
+                .iter()
+                .take(PREVIEW)
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(" ");
+            if bytes.len() > PREVIEW {
+                preview.push_str(" …");
+            }
+            (format_size(bytes.len()), preview)
         }
-        // Log replay error to main log
-        Message::ReplayError(err) => {
-            state.main_log.push_str(&format!(
-                "[{}] Error: {}\n",
-                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-                err
-            ));
-            Task::none()
+        Err(_) => ("?".to_string(), payload.payload.chars().take(24).collect()),
+    }
+}
+
+/// Renders the collapsible payload table of a replay window, with a label jump list. Each
+/// row shows the status of its payload in the current iteration, and the payload being
+/// sent is highlighted. Only the rows near the scroll position are laid out.
+fn replay_payloads_view(
+    id: window::Id,
+    data: &ReplayData,
+    log_text: LogText,
+) -> Element<'_, Message> {
+    let labels: Vec<String> = data
+        .payloads
+        .iter()
+        .filter_map(|p| p.label.clone())
+        .collect();
+    let header = row![
+        button(if data.show_payloads {
+            tr("▾ Payloads")
+        } else {
+            tr("▸ Payloads")
+        })
+        .on_press(Message::PayloadsToggled(id)),
+        text(format!("{} payloads", data.payloads.len())),
+        iced::widget::pick_list(labels, None::<String>, move |label| {
+            Message::JumpToLabel(id, label)
+        })
+        .placeholder(tr("Jump to label…")),
+        checkbox(tr("Follow"), data.follow_payload)
+            .on_toggle(move |follow| Message::PayloadFollowToggled(id, follow)),
+    ]
+    .spacing(10)
+    .align_y(iced::Alignment::Center);
+    if !data.show_payloads {
+        return header.into();
+    }
+    let row_height = log_text.payload_row_height();
+    let first = ((data.payloads_offset / row_height) as usize)
+        .saturating_sub(LOG_OVERSCAN)
+        .min(data.payloads.len());
+    let last = (first + (PAYLOAD_TABLE_HEIGHT / row_height).ceil() as usize + 2 * LOG_OVERSCAN)
+        .min(data.payloads.len());
+    let rows = data.payloads[first..last]
+        .iter()
+        .enumerate()
+        .map(|(offset, payload)| {
+            let index = first + offset;
+            let status = data.payload_status.get(index).copied().unwrap_or_default();
+            let active = index == data.current_index && data.status == ReplayStatus::Running;
+            let (glyph, tone) = match status {
+                _ if active => ("▶", Tone::Info),
+                crate::types::PayloadStatus::Pending => (status.glyph(), Tone::Muted),
+                crate::types::PayloadStatus::Sent(_) => (status.glyph(), Tone::Success),
+                crate::types::PayloadStatus::Failed => (status.glyph(), Tone::Error),
+                crate::types::PayloadStatus::Skipped => (status.glyph(), Tone::Warning),
+            };
+            let text_tone = if active { Tone::Info } else { Tone::Normal };
+            let (length, preview) = payload_summary(payload);
+            let cells = row![
+                text(glyph)
+                    .size(log_text.size)
+                    .style(tone.style())
+                    .width(Length::Fixed(20f32)),
+                text(format!("{}.", index + 1))
+                    .size(log_text.size)
+                    .style(text_tone.style())
+                    .width(Length::Fixed(50f32)),
+                text(payload.label.as_deref().unwrap_or(""))
+                    .size(log_text.size)
+                    .style(text_tone.style())
+                    .width(Length::Fixed(140f32)),
+                text(length)
+                    .size(log_text.size)
+                    .style(text_tone.style())
+                    .width(Length::Fixed(70f32)),
+                text(format!("{} ms", payload.delay))
+                    .size(log_text.size)
+                    .style(text_tone.style())
+                    .width(Length::Fixed(80f32)),
+                text(preview)
+                    .font(log_text.font)
+                    .size(log_text.size)
+                    .style(text_tone.style())
+                    .width(Length::Fill),
+            ]
+            .spacing(5)
+            .height(Length::Fixed(row_height));
+            // Highlighted with a background so the active row stands out without color
+            let cells = container(cells).style(move |theme: &Theme| {
+                if active {
+                    container::Style {
+                        background: Some(Background::Color(
+                            theme.extended_palette().background.strong.color,
+                        )),
+                        ..Default::default()
+                    }
+                } else {
+                    container::Style::default()
+                }
+            });
+            iced::widget::mouse_area(cells)
+                .on_press(Message::PayloadRowPressed(id, index))
+                .into()
+        });
+    column![
+        header,
+        scrollable(column![
+            Space::with_height(first as f32 * row_height),
+            column(rows),
+            Space::with_height((data.payloads.len() - last) as f32 * row_height),
+        ])
+        .id(payloads_scroll_id(id))
+        .on_scroll(move |viewport| Message::PayloadsScrolled(id, viewport))
+        .height(Length::Fixed(PAYLOAD_TABLE_HEIGHT)),
+    ]
+    .spacing(5)
+    .into()
+}
+
+/// Writes an export into `dir`, which must already exist so a mistyped directory is
+/// noticed instead of created.
+async fn quick_export(
+    dir: std::path::PathBuf,
+    file_name: String,
+    contents: String,
+) -> Result<std::path::PathBuf, String> {
+    if !tokio::fs::metadata(&dir)
+        .await
+        .is_ok_and(|metadata| metadata.is_dir())
+    {
+        return Err(format!("{} is not a directory", dir.display()));
+    }
+    let path = dir.join(file_name);
+    tokio::fs::write(&path, contents)
+        .await
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Returns the timestamped name of a log file for a window exported as `stem`.
+fn log_file_name(stem: &str) -> String {
+    format!(
+        "{}_{}.log",
+        stem,
+        chrono::Local::now().format("%Y%m%d_%H%M%S")
+    )
+}
+
+/// Starts writing the log of window `id` to a timestamped file in the export directory,
+/// creating the directory if needed.
+fn auto_log_file(state: &mut App, id: window::Id) {
+    let dir = std::path::PathBuf::from(&state.config.export_dir);
+    let rotation = state.config.log_rotation();
+    if let Some(window_data) = state.windows.get_mut(&id) {
+        let log_file = std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("{}: {}", dir.display(), e))
+            .and_then(|()| {
+                let path = dir.join(log_file_name(&window_data.export_stem()));
+                crate::logfile::LogFile::open(&path, &window_data.title, rotation)
+            });
+        set_log_file(window_data, log_file);
+    }
+}
+
+/// Sets the file a window's log is written to and logs where it goes, or why it could not
+/// be opened.
+fn set_log_file(window_data: &mut Window, log_file: Result<crate::logfile::LogFile, String>) {
+    let entry = match log_file {
+        Ok(log_file) => {
+            let entry = crate::log::LogEntry::new(
+                crate::log::LogLevel::Info,
+                &format!("Logging to {}", log_file.path.display()),
+            );
+            window_data.log_file = Some(log_file);
+            entry
         }
+        Err(e) => crate::log::LogEntry::new(
+            crate::log::LogLevel::Error,
+            &format!("Failed to open log file {}", e),
+        ),
+    };
+    window_data.push_log(entry);
+}
 
-        _ => Task::none(),
+/// Stops writing a window's log to its file, if any, after writing the remaining lines
+/// and a footer with the `reason`.
+fn close_log_file(window_data: &mut Window, reason: &str) {
+    let Some(mut log_file) = window_data.log_file.take() else {
+        return;
+    };
+    let error = log_file
+        .mirror(window_data.log(), window_data.log_filter.all_levels())
+        .or_else(|| log_file.footer(reason));
+    let entry = error.unwrap_or_else(|| {
+        crate::log::LogEntry::new(
+            crate::log::LogLevel::Info,
+            &format!("Stopped logging to {}", log_file.path.display()),
+        )
+    });
+    window_data.push_log(entry);
+}
+
+/// Size of the pieces log bundle files are written in, so progress shows for large logs.
+const LOG_BUNDLE_CHUNK: usize = 256 * 1024;
+
+/// Turns a window's export name into a file name unique within a log bundle.
+fn bundle_name(stem: &str, used: &mut std::collections::HashSet<String>) -> String {
+    let stem: String = stem
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let mut name = stem.clone();
+    let mut n = 1;
+    while !used.insert(name.clone()) {
+        n += 1;
+        name = format!("{}_{}", stem, n);
     }
+    name
+}
+
+/// Writes `files` into the new directory `dir`, reporting progress as it goes. A file that
+/// fails is reported and the remaining files are still written.
+fn write_log_bundle(
+    dir: std::path::PathBuf,
+    files: Vec<(String, String)>,
+) -> impl iced::futures::Stream<Item = LogBundleEvent> {
+    iced::stream::channel(16, |mut output| async move {
+        use iced::futures::SinkExt;
+        use tokio::io::AsyncWriteExt;
+        if let Err(e) = tokio::fs::create_dir_all(&dir).await {
+            let _ = output
+                .send(LogBundleEvent::Failed(dir.clone(), e.to_string()))
+                .await;
+            let _ = output.send(LogBundleEvent::Finished(dir, 0)).await;
+            return;
+        }
+        let total = files
+            .iter()
+            .map(|(_, contents)| contents.len() as u64)
+            .sum();
+        let mut written = 0;
+        let mut done = 0;
+        for (name, contents) in files {
+            let path = dir.join(name);
+            let result = async {
+                let mut file = tokio::fs::File::create(&path).await?;
+                for chunk in contents.as_bytes().chunks(LOG_BUNDLE_CHUNK) {
+                    file.write_all(chunk).await?;
+                    written += chunk.len() as u64;
+                    let _ = output.send(LogBundleEvent::Progress(written, total)).await;
+                }
+                file.flush().await
+            }
+            .await;
+            match result {
+                Ok(()) => done += 1,
+                Err(e) => {
+                    let _ = output
+                        .send(LogBundleEvent::Failed(path, e.to_string()))
+                        .await;
+                }
+            }
+        }
+        let _ = output.send(LogBundleEvent::Finished(dir, done)).await;
+    })
+}
+
+/// Writes a session's replay into `dir` under a timestamped name, creating `dir` if needed.
+async fn auto_export_replay(
+    dir: std::path::PathBuf,
+    stem: String,
+    replay: ReplayableSession,
+) -> Result<std::path::PathBuf, String> {
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let file_name = format!(
+        "{}_{}.json",
+        stem,
+        chrono::Local::now().format("%Y%m%d_%H%M%S")
+    );
+    save_replay(dir.join(file_name), replay).await
+}
+
+/// Renders the token rule editor of a replay window.
+fn token_rules_view(id: window::Id, rules: &[TokenRule]) -> Element<'_, Message> {
+    let rows = rules.iter().enumerate().map(|(index, rule)| {
+        let edit = move |edit: TokenRuleEdit| Message::TokenRuleChanged(id, index, edit);
+        let source: Element<'_, Message> = match &rule.source {
+            TokenSource::Range { offset, length } => row![
+                text(tr("Offset:")),
+                text_input("0", &offset.to_string())
+                    .on_input(move |s| edit(TokenRuleEdit::Offset(s)))
+                    .width(Length::Fixed(60f32)),
+                text(tr("Length:")),
+                text_input("0", &length.to_string())
+                    .on_input(move |s| edit(TokenRuleEdit::Length(s)))
+                    .width(Length::Fixed(60f32)),
+            ]
+            .spacing(10)
+            .into(),
+            TokenSource::Regex { pattern } => text_input(tr("regex, group 1 is captured"), pattern)
+                .on_input(move |s| edit(TokenRuleEdit::Pattern(s)))
+                .into(),
+        };
+        row![
+            text_input("name", &rule.name)
+                .on_input(move |s| edit(TokenRuleEdit::Name(s)))
+                .width(Length::Fixed(120f32)),
+            radio(tr("Range"), TokenKind::Range, Some(rule.kind()), move |k| {
+                edit(TokenRuleEdit::Kind(k))
+            }),
+            radio(tr("Regex"), TokenKind::Regex, Some(rule.kind()), move |k| {
+                edit(TokenRuleEdit::Kind(k))
+            }),
+            source,
+            button(tr("Remove")).on_press(Message::RemoveTokenRule(id, index)),
+        ]
+        .spacing(10)
+        .into()
+    });
+    column![
+        row![
+            tooltip(
+                text(tr("Tokens:")),
+                tr("Captured from responses, used in payloads as {{name}}; names are letters, digits, and _"),
+                tooltip::Position::Top
+            ),
+            button(tr("Add token")).on_press(Message::AddTokenRule(id)),
+        ]
+        .spacing(10),
+        iced::widget::Column::with_children(rows).spacing(5),
+    ]
+    .spacing(5)
+    .into()
 }
 
 /// Implementation for Window, providing view rendering based on state.
 impl Window {
-    /// Renders the UI for session or replay windows.
-    pub fn view(&self, id: window::Id) -> iced::Element<'_, crate::ui::Message, iced::Theme> {
-        match &self.state {
-            // Render session window with input controls and logs
-            Session(data) => iced::widget::container(
-                iced::widget::column![
-                    iced::widget::row![
-                        iced::widget::text("Payload type:"),
-                        iced::widget::radio(
-                            "Hex",
-                            PayloadType::Hex,
-                            Some(data.payload_type),
-                            move |pt| crate::ui::Message::PayloadTypeChanged(id, pt)
-                        ),
-                        iced::widget::radio(
-                            "ASCII",
-                            PayloadType::Ascii,
-                            Some(data.payload_type),
-                            move |pt| crate::ui::Message::PayloadTypeChanged(id, pt)
-                        ),
+    /// Renders the UI for session or replay windows below the window label.
+    pub fn view(
+        &self,
+        id: window::Id,
+        config: &Config,
+    ) -> iced::Element<'_, crate::ui::Message, iced::Theme> {
+        iced::widget::column![self.label_view(id), self.state_view(id, config)].into()
+    }
+
+    /// Renders the window label with its rename controls.
+    fn label_view(&self, id: window::Id) -> iced::Element<'_, crate::ui::Message, iced::Theme> {
+        let row = match &self.label_edit {
+            Some(edit) => iced::widget::row![
+                iced::widget::text_input(tr("Window name"), edit)
+                    .on_input(move |s| crate::ui::Message::RenameInputChanged(id, s))
+                    .on_submit(crate::ui::Message::RenameConfirmed(id)),
+                iced::widget::button(tr("OK")).on_press(crate::ui::Message::RenameConfirmed(id)),
+                iced::widget::button(tr("Cancel"))
+                    .on_press(crate::ui::Message::RenameCancelled(id)),
+            ],
+            None => iced::widget::row![
+                iced::widget::text(self.label.as_deref().unwrap_or("Unnamed window")).size(18),
+                iced::widget::button(tr("Rename")).on_press(crate::ui::Message::RenameWindow(id)),
+            ],
+        };
+        let row = row
+            .push(iced::widget::Space::with_width(iced::Length::Fill))
+            .push(
+                iced::widget::checkbox(tr("Log to file"), self.log_file.is_some())
+                    .on_toggle(move |on| crate::ui::Message::LogToFileToggled(id, on)),
+            )
+            .push(match &self.log_file {
+                Some(log_file) if log_file.rotations > 0 => iced::widget::text(format!(
+                    "{} ({}, part {})",
+                    log_file.path.display(),
+                    format_size(log_file.size as usize),
+                    log_file.rotations + 1
+                )),
+                Some(log_file) => iced::widget::text(format!(
+                    "{} ({})",
+                    log_file.path.display(),
+                    format_size(log_file.size as usize)
+                )),
+                None => iced::widget::text(""),
+            });
+        iced::widget::container(row.spacing(10).align_y(iced::Alignment::Center))
+            .padding(iced::Padding::new(0.0).top(10).left(20).right(20))
+            .into()
+    }
+
+    /// Renders the status, input and panels of a session window, above its log.
+    fn session_controls_view<'a>(
+        &'a self,
+        id: window::Id,
+        data: &'a SessionData,
+        config: &Config,
+        invalid_input: Option<&String>,
+    ) -> iced::Element<'a, crate::ui::Message, iced::Theme> {
+        iced::widget::column![
+            iced::widget::row![
+                session_connection_view(id, data, config.accessibility.reduced_motion),
+                iced::widget::text(format!(
+                    "Target: {:?} {}:{}",
+                    data.protocol, data.address, data.port
+                )),
+                match (&data.local_addr, &data.remote_addr) {
+                    (Some(local), Some(remote)) =>
+                        iced::widget::text(tr_args("Local: {}  Remote: {}", &[&local, &remote])),
+                    _ => iced::widget::text(""),
+                },
+                iced::widget::Space::with_width(iced::Length::Fill),
+                match data.pending_sends() {
+                    0 => iced::widget::row![],
+                    pending => iced::widget::row![
+                        iced::widget::text(tr_args("{} sends pending", &[&pending]))
+                            .style(Tone::Warning.style()),
+                        iced::widget::button(tr("Cancel"))
+                            .on_press(crate::ui::Message::CancelPending(id)),
                     ]
-                    .spacing(10),
+                    .spacing(10)
+                    .align_y(iced::Alignment::Center),
+                },
+                match data.connection_duration() {
+                    Some(duration) => iced::widget::tooltip(
+                        iced::widget::text(format!(
+                            "{} {}",
+                            if data.connected_since.is_some() {
+                                tr("Connected for")
+                            } else {
+                                tr("Last connection lasted")
+                            },
+                            crate::log::format_duration(duration)
+                        )),
+                        iced::widget::text(format!(
+                            "Total connected: {}",
+                            crate::log::format_duration(data.cumulative_connected())
+                        )),
+                        iced::widget::tooltip::Position::Bottom,
+                    ),
+                    None => iced::widget::tooltip(
+                        iced::widget::text(""),
+                        "",
+                        iced::widget::tooltip::Position::Bottom,
+                    ),
+                },
+                match data.idle_remaining() {
+                    Some(secs) if secs <= 30 =>
+                        iced::widget::text(tr_args("Idle disconnect in {} s", &[&secs]))
+                            .style(Tone::Warning.style()),
+                    _ => iced::widget::text(""),
+                },
+                if data.protocol == crate::types::Protocol::Tcp {
                     iced::widget::row![
-                        iced::widget::text_input(&data.input_placeholder, &data.payload_input)
-                            .on_input(move |s| crate::ui::Message::InputChanged(id, s)),
-                        if data.connected {
-                            iced::widget::tooltip(
-                                iced::widget::button("Send")
-                                    .on_press(crate::ui::Message::SendPacket(id)),
-                                "",
-                                iced::widget::tooltip::Position::FollowCursor,
-                            )
+                        iced::widget::text(tr("Idle timeout (s):")),
+                        iced::widget::text_input("0", &data.idle_timeout_secs)
+                            .on_input(move |s| crate::ui::Message::IdleTimeoutChanged(id, s))
+                            .width(iced::Length::Fixed(60f32)),
+                    ]
+                    .spacing(10)
+                    .align_y(iced::Alignment::Center)
+                } else {
+                    iced::widget::row![]
+                },
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            iced::widget::row![
+                iced::widget::text(tr("Payload type:")),
+                iced::widget::radio(
+                    "Hex",
+                    PayloadType::Hex,
+                    Some(data.payload_type),
+                    move |pt| crate::ui::Message::PayloadTypeChanged(id, pt)
+                ),
+                iced::widget::radio(
+                    "ASCII",
+                    PayloadType::Ascii,
+                    Some(data.payload_type),
+                    move |pt| crate::ui::Message::PayloadTypeChanged(id, pt)
+                ),
+            ]
+            .push_maybe((data.files_hovered > 0).then(|| {
+                iced::widget::text(tr("Drop to load into the payload, Shift appends"))
+                    .style(Tone::Info.style())
+            }))
+            .spacing(10),
+            iced::widget::row![
+                iced::widget::text_input(
+                    match data.payload_type {
+                        PayloadType::Ascii => tr("Hello World"),
+                        PayloadType::Hex => "68656c6c6f20776f726c64",
+                    },
+                    &data.payload_input
+                )
+                .on_input(move |s| crate::ui::Message::InputChanged(id, s))
+                .on_submit(crate::ui::Message::SubmitPayload(id))
+                .style(input_style(invalid_input.is_some())),
+                iced::widget::text_input(tr("label (optional)"), &data.send_label)
+                    .on_input(move |s| crate::ui::Message::SendLabelChanged(id, s))
+                    .on_submit(crate::ui::Message::SubmitPayload(id))
+                    .width(iced::Length::Fixed(140f32)),
+                if data.replay_cancel.is_some() {
+                    iced::widget::tooltip(
+                        iced::widget::button(tr("Send")),
+                        tr("Replay running"),
+                        iced::widget::tooltip::Position::FollowCursor,
+                    )
+                } else if let Some(e) = invalid_input {
+                    iced::widget::tooltip(
+                        iced::widget::button(if data.connected || !data.queue_offline {
+                            tr("Send")
                         } else {
-                            iced::widget::tooltip(
-                                iced::widget::button("Send"),
-                                "Disconnected!",
-                                iced::widget::tooltip::Position::FollowCursor,
-                            )
-                        }
+                            tr("Queue")
+                        }),
+                        iced::widget::text(e.clone()),
+                        iced::widget::tooltip::Position::FollowCursor,
+                    )
+                } else if data.connected {
+                    iced::widget::tooltip(
+                        iced::widget::button(tr("Send"))
+                            .on_press(crate::ui::Message::SendPacket(id)),
+                        send_preview_view(data, config),
+                        iced::widget::tooltip::Position::FollowCursor,
+                    )
+                } else if data.queue_offline {
+                    iced::widget::tooltip(
+                        iced::widget::button(tr("Queue"))
+                            .on_press(crate::ui::Message::SendPacket(id)),
+                        iced::widget::column![
+                            iced::widget::text(tr("Sent once reconnected")),
+                            send_preview_view(data, config),
+                        ],
+                        iced::widget::tooltip::Position::FollowCursor,
+                    )
+                } else {
+                    iced::widget::tooltip(
+                        iced::widget::button(tr("Send")),
+                        iced::widget::text(session_status(data).1),
+                        iced::widget::tooltip::Position::FollowCursor,
+                    )
+                },
+                iced::widget::tooltip(
+                    iced::widget::button(tr("Record only"))
+                        .on_press(crate::ui::Message::RecordOnly(id)),
+                    tr("Add to the replay without sending, using the \"after\" delay if set"),
+                    iced::widget::tooltip::Position::FollowCursor,
+                ),
+                iced::widget::text(tr("after")),
+                iced::widget::text_input("0", &data.send_after_ms)
+                    .on_input(move |s| crate::ui::Message::SendAfterChanged(id, s))
+                    .width(iced::Length::Fixed(70f32)),
+                iced::widget::text(tr("ms")),
+                match data.scheduled_remaining() {
+                    Some(remaining) => iced::widget::row![
+                        iced::widget::text(tr_args(
+                            "Sending in {} s",
+                            &[&format!("{:.1}", remaining.as_secs_f32())],
+                        )),
+                        iced::widget::button(tr("Cancel"))
+                            .on_press(crate::ui::Message::CancelScheduledSend(id)),
+                    ]
+                    .spacing(10)
+                    .align_y(iced::Alignment::Center),
+                    None => iced::widget::row![],
+                },
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            payload_size_view(
+                &data.payload_input,
+                data.payload_type,
+                data.protocol,
+                config.udp_safe_datagram_size,
+            ),
+            iced::widget::row![
+                iced::widget::checkbox(tr("Queue while disconnected"), data.queue_offline)
+                    .on_toggle(move |b| crate::ui::Message::QueueOfflineToggled(id, b)),
+                iced::widget::text(format!(
+                    "{}/{} queued",
+                    data.offline_queue.len(),
+                    crate::types::OFFLINE_QUEUE_CAP
+                )),
+                iced::widget::button(tr("Clear queue")).on_press_maybe(
+                    (!data.offline_queue.is_empty())
+                        .then_some(crate::ui::Message::ClearOfflineQueue(id))
+                ),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            iced::widget::column(data.offline_queue.iter().enumerate().map(|(i, queued)| {
+                iced::widget::text(format!(
+                    "{}. [{:?}] {}",
+                    i + 1,
+                    queued.payload_type,
+                    queued.payload
+                ))
+                .size(12)
+                .into()
+            })),
+            iced::widget::row![
+                iced::widget::text(tr("Repeat:")),
+                iced::widget::text_input("10", &data.repeat_count)
+                    .on_input(move |s| crate::ui::Message::RepeatCountChanged(id, s))
+                    .width(iced::Length::Fixed(60f32)),
+                iced::widget::text(tr("times, every")),
+                iced::widget::text_input("100", &data.repeat_interval_ms)
+                    .on_input(move |s| crate::ui::Message::RepeatIntervalChanged(id, s))
+                    .width(iced::Length::Fixed(60f32)),
+                iced::widget::text(tr("ms")),
+                match data.repeat_progress {
+                    Some((sent, total)) => iced::widget::row![
+                        iced::widget::text(tr_args("Repeat: {}/{}", &[&sent, &total])),
+                        iced::widget::button(tr("Stop"))
+                            .on_press(crate::ui::Message::StopRepeat(id)),
                     ]
                     .spacing(10),
-                    iced::widget::container(
-                        iced::widget::scrollable(iced::widget::text(&data.log))
-                            .height(iced::Length::Fill)
-                            .width(iced::Length::Fill)
-                    )
-                    .style(|_theme| iced::widget::container::Style {
-                        background: Some(iced::Background::Color(iced::Color::from_rgb(
-                            0.3, 0.3, 0.3
-                        ))),
-                        ..Default::default()
+                    None => iced::widget::row![
+                        iced::widget::button(tr("Send repeatedly")).on_press_maybe(
+                            (data.connected && data.replay_cancel.is_none())
+                                .then_some(crate::ui::Message::SendRepeatedly(id))
+                        )
+                    ],
+                },
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            heartbeat_view(id, data),
+            batch_view(id, data),
+            notes_view(id, data),
+            recording_view(id, data, LogText::new(config, self.log_zoom)),
+        ]
+        .spacing(15)
+        .into()
+    }
+
+    /// Renders the session or replay specific part of the window.
+    fn state_view(
+        &self,
+        id: window::Id,
+        config: &Config,
+    ) -> iced::Element<'_, crate::ui::Message, iced::Theme> {
+        // Checked as the user types; sending stays disabled until the payload decodes
+        let invalid_input = match &self.state {
+            Session(data) => crate::validate::payload(&data.payload_input, data.payload_type).err(),
+            _ => None,
+        };
+        match &self.state {
+            // Render session window with input controls and logs
+            Session(data) => iced::widget::container(
+                iced::widget::column![
+                    iced::widget::pane_grid(&data.split.panes, move |_, pane, _| match pane {
+                        SessionPane::Controls => pane_grid::Content::new(
+                            iced::widget::scrollable(self.session_controls_view(
+                                id,
+                                data,
+                                config,
+                                invalid_input.as_ref()
+                            ))
+                            .spacing(5)
+                        ),
+                        SessionPane::Log => pane_grid::Content::new(log_view(
+                            id,
+                            &data.log,
+                            self.log_filter,
+                            &Default::default(),
+                            self.log_scroll,
+                            LogText::new(config, self.log_zoom),
+                        ))
+                        .title_bar(pane_grid::TitleBar::new(split_grip(id))),
                     })
-                    .height(iced::Length::Fill)
-                    .width(iced::Length::Fill),
+                    .spacing(SPLIT_SPACING)
+                    .on_resize(SPLIT_LEEWAY, move |event| {
+                        crate::ui::Message::SplitResized(id, event)
+                    }),
                     iced::widget::row![
+                        iced::widget::checkbox(tr("Auto-export on close"), data.auto_export)
+                            .on_toggle(move |on| crate::ui::Message::AutoExportToggled(id, on)),
+                        iced::widget::Space::with_width(10),
                         iced::widget::tooltip(
-                            iced::widget::button("Export Replay")
+                            iced::widget::button(tr("Export Replay"))
                                 .on_press(crate::ui::Message::ExportSession(id)),
-                            "Save for replay",
+                            tr("Save for replay"),
+                            iced::widget::tooltip::Position::Top
+                        ),
+                        iced::widget::tooltip(
+                            iced::widget::button(tr("Quick")).on_press(
+                                crate::ui::Message::QuickExport(
+                                    id,
+                                    crate::types::ExportKind::Replay
+                                )
+                            ),
+                            tr("Save to the quick export directory without asking"),
+                            iced::widget::tooltip::Position::Top
+                        ),
+                        iced::widget::Space::with_width(10),
+                        iced::widget::tooltip(
+                            iced::widget::button(tr("Export as CSV"))
+                                .on_press(crate::ui::Message::ExportCsv(id)),
+                            tr("Save for replay as a spreadsheet"),
                             iced::widget::tooltip::Position::Top
                         ),
                         iced::widget::Space::with_width(10),
                         iced::widget::tooltip(
-                            iced::widget::button("Export Logs")
+                            iced::widget::button(tr("Export Logs"))
                                 .on_press(crate::ui::Message::ExportLogs(id)),
-                            "Save all logs to file",
+                            tr("Save all logs to file"),
+                            iced::widget::tooltip::Position::Top
+                        ),
+                        iced::widget::tooltip(
+                            iced::widget::button(tr("Quick")).on_press(
+                                crate::ui::Message::QuickExport(id, crate::types::ExportKind::Logs)
+                            ),
+                            tr("Save to the quick export directory without asking"),
+                            iced::widget::tooltip::Position::Top
+                        ),
+                        iced::widget::Space::with_width(10),
+                        iced::widget::tooltip(
+                            iced::widget::button(tr("Export Transcript"))
+                                .on_press(crate::ui::Message::ExportTranscript(id)),
+                            tr("Save every packet as JSON lines"),
                             iced::widget::tooltip::Position::Top
                         ),
+                        iced::widget::Space::with_width(10),
+                        iced::widget::button(tr("Clear log"))
+                            .on_press(crate::ui::Message::ClearLog(id)),
+                        iced::widget::button(tr("Copy log"))
+                            .on_press(crate::ui::Message::CopyLog(id)),
+                        log_filter_view(id, self.log_filter, self.log_zoom),
+                        iced::widget::Space::with_width(10),
+                        match data.replay_progress {
+                            Some((sent, total)) => iced::widget::row![
+                                iced::widget::text(tr_args("Replay: {}/{}", &[&sent, &total])),
+                                iced::widget::button(tr("Stop replay"))
+                                    .on_press(crate::ui::Message::StopSessionReplay(id)),
+                            ]
+                            .spacing(10),
+                            None => iced::widget::row![iced::widget::tooltip(
+                                iced::widget::button(tr("Run replay here…")).on_press_maybe(
+                                    data.connected
+                                        .then_some(crate::ui::Message::RunReplayHere(id))
+                                ),
+                                tr("Send a recorded session through this connection"),
+                                iced::widget::tooltip::Position::Top
+                            )],
+                        },
                         iced::widget::Space::with_width(iced::Length::Fill),
-                        iced::widget::button("Close").on_press(crate::ui::Message::Closed(id))
+                        iced::widget::button(tr("Duplicate"))
+                            .on_press(crate::ui::Message::DuplicateSession(id)),
+                        iced::widget::Space::with_width(10),
+                        if data.connecting {
+                            iced::widget::button(tr("Cancel"))
+                                .on_press(crate::ui::Message::Disconnect(id))
+                        } else {
+                            iced::widget::button(tr("Disconnect")).on_press_maybe(
+                                data.connected.then_some(crate::ui::Message::Disconnect(id)),
+                            )
+                        },
+                        iced::widget::Space::with_width(10),
+                        iced::widget::button(tr("Reconnect")).on_press_maybe(
+                            (!data.connected && !data.connecting)
+                                .then_some(crate::ui::Message::Reconnect(id))
+                        ),
+                        iced::widget::Space::with_width(10),
+                        iced::widget::button(tr("Close"))
+                            .on_press(crate::ui::Message::CloseRequested(id))
                     ]
                 ]
                 .spacing(15)
@@ -777,28 +7168,158 @@ impl Window {
             // Render replay window with progress and logs
             WindowState::Replay(data) => iced::widget::container(
                 iced::widget::column![
-                    iced::widget::text(format!("Replaying session from: {}", data.file_name)),
+                    replay_connection_view(id, data, config.accessibility.reduced_motion),
+                    iced::widget::text(tr_args("Replaying session from: {}", &[&data.file_name])),
+                    match &data.notes {
+                        Some(notes) => iced::widget::text(tr_args("Notes: {}", &[&notes])),
+                        None => iced::widget::text(""),
+                    },
                     iced::widget::text(format!(
                         "Progress: {}/{}",
                         data.current_index,
                         data.payloads.len()
                     )),
-                    iced::widget::container(
-                        iced::widget::scrollable(iced::widget::text(&data.log))
-                            .height(iced::Length::Fill)
-                            .width(iced::Length::Fill)
-                    )
-                    .style(|_theme| iced::widget::container::Style {
-                        background: Some(iced::Background::Color(iced::Color::from_rgb(
-                            0.3, 0.3, 0.3
-                        ))),
-                        ..Default::default()
-                    })
-                    .height(iced::Length::Fill)
-                    .width(iced::Length::Fill),
+                    match data.payloads.iter().filter(|p| p.recorded_only).count() {
+                        0 => iced::widget::text(""),
+                        unsent => iced::widget::text(format!(
+                            "{} payloads were recorded without being sent",
+                            unsent
+                        ))
+                        .style(Tone::Warning.style()),
+                    },
+                    iced::widget::text(format!(
+                        "Iteration: {}/{} ({})",
+                        data.iteration, data.loops, data.speed
+                    )),
+                    iced::widget::text(format!(
+                        "Received: {} bytes in {} reads",
+                        data.received_bytes,
+                        data.received.len()
+                    )),
+                    iced::widget::row![
+                        iced::widget::text(format!(
+                            "Validation: {}",
+                            crate::validate::summary(&data.validation)
+                        )),
+                        iced::widget::Space::with_width(iced::Length::Fill),
+                        iced::widget::tooltip(
+                            iced::widget::button(tr("Validate"))
+                                .on_press(crate::ui::Message::ValidateReplay(id)),
+                            tr("Check payloads without sending anything"),
+                            iced::widget::tooltip::Position::Top
+                        ),
+                        iced::widget::button(tr("Export validation…"))
+                            .on_press(crate::ui::Message::ExportValidation(id)),
+                    ]
+                    .spacing(10),
+                    iced::widget::row![
+                        iced::widget::checkbox(tr("Stress"), data.stress)
+                            .on_toggle(move |on| crate::ui::Message::StressToggled(id, on)),
+                        iced::widget::text(tr("Duration (s):")),
+                        iced::widget::text_input("", &data.stress_duration_secs)
+                            .on_input(move |s| crate::ui::Message::StressDurationChanged(id, s))
+                            .width(iced::Length::Fixed(60f32)),
+                        iced::widget::text(tr("Iterations:")),
+                        iced::widget::text_input("", &data.stress_iterations)
+                            .on_input(move |s| crate::ui::Message::StressIterationsChanged(id, s))
+                            .width(iced::Length::Fixed(80f32)),
+                        iced::widget::Space::with_width(iced::Length::Fill),
+                        if data.cancel.is_some() {
+                            iced::widget::button(iced::widget::text(tr("STOP")).size(20))
+                                .padding([8, 24])
+                                .style(iced::widget::button::danger)
+                                .on_press(crate::ui::Message::StopReplay(id))
+                        } else {
+                            iced::widget::button(if data.stress {
+                                tr("Run stress")
+                            } else {
+                                tr("Run again")
+                            })
+                            .on_press(crate::ui::Message::StartReplay(id))
+                        },
+                    ]
+                    .spacing(10)
+                    .align_y(iced::Alignment::Center),
+                    match &data.stress_stats {
+                        Some(stats) =>
+                            iced::widget::text(tr_args("Stress: {}", &[&stats.describe()])),
+                        None => iced::widget::text(""),
+                    },
+                    replay_payloads_view(id, data, LogText::new(config, self.log_zoom)),
+                    token_rules_view(id, &data.tokens),
+                    if data.show_validation {
+                        iced::widget::container(iced::widget::scrollable(
+                            iced::widget::Column::with_children(data.validation.iter().map(
+                                |report| {
+                                    let tone = if report.error.is_some() {
+                                        Tone::Error
+                                    } else if !report.warnings.is_empty() {
+                                        Tone::Warning
+                                    } else {
+                                        Tone::Normal
+                                    };
+                                    iced::widget::text(report.line()).style(tone.style()).into()
+                                },
+                            ))
+                            .width(iced::Length::Fill),
+                        ))
+                        .style(pane_style)
+                        .max_height(200)
+                        .width(iced::Length::Fill)
+                    } else {
+                        iced::widget::container(iced::widget::Space::with_height(0))
+                    },
+                    log_view(
+                        id,
+                        &data.log,
+                        self.log_filter,
+                        &Default::default(),
+                        self.log_scroll,
+                        LogText::new(config, self.log_zoom),
+                    ),
                     iced::widget::row![
-                        iced::widget::button("Close").on_press(crate::ui::Message::Closed(id))
+                        iced::widget::radio(
+                            tr("Binary"),
+                            ResponseFormat::Binary,
+                            Some(data.response_format),
+                            move |f| crate::ui::Message::ResponseFormatChanged(id, f)
+                        ),
+                        iced::widget::radio(
+                            "JSON",
+                            ResponseFormat::Json,
+                            Some(data.response_format),
+                            move |f| crate::ui::Message::ResponseFormatChanged(id, f)
+                        ),
+                        iced::widget::radio(
+                            tr("Replay"),
+                            ResponseFormat::Replay,
+                            Some(data.response_format),
+                            move |f| crate::ui::Message::ResponseFormatChanged(id, f)
+                        ),
+                        iced::widget::tooltip(
+                            iced::widget::button(tr("Export responses…"))
+                                .on_press(crate::ui::Message::ExportResponses(id)),
+                            tr("Save data received during the replay"),
+                            iced::widget::tooltip::Position::Top
+                        ),
+                        iced::widget::button(tr("Clear log"))
+                            .on_press(crate::ui::Message::ClearLog(id)),
+                        iced::widget::button(tr("Copy log"))
+                            .on_press(crate::ui::Message::CopyLog(id)),
+                        log_filter_view(id, self.log_filter, self.log_zoom),
+                        iced::widget::Space::with_width(iced::Length::Fill),
+                        iced::widget::tooltip(
+                            iced::widget::button(tr("Save"))
+                                .on_press(crate::ui::Message::SaveReplay(id)),
+                            tr("Overwrite the loaded replay file"),
+                            iced::widget::tooltip::Position::Top
+                        ),
+                        iced::widget::button(tr("Save as…"))
+                            .on_press(crate::ui::Message::SaveReplayAs(id)),
+                        iced::widget::button(tr("Close"))
+                            .on_press(crate::ui::Message::CloseRequested(id))
                     ]
+                    .spacing(10)
                 ]
                 .spacing(15)
                 .padding(20),
@@ -808,3 +7329,142 @@ impl Window {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Protocol;
+
+    /// Adds a session window to `app` the way `Message::WindowOpened` does.
+    fn open_session(app: &mut App, data: SessionData, label: Option<&str>) -> window::Id {
+        let id = window::Id::unique();
+        app.windows.insert(
+            id,
+            Window {
+                label: label.map(str::to_string),
+                label_edit: None,
+                title: data.title(),
+                state: Session(Box::new(data)),
+                log_file: None,
+                log_filter: Default::default(),
+                log_scroll: Default::default(),
+                log_zoom: Default::default(),
+                banners: Default::default(),
+            },
+        );
+        id
+    }
+
+    #[test]
+    fn session_titles_use_each_windows_own_target_and_state() {
+        let mut app = App::default();
+        let mut tcp = SessionData::new(
+            Protocol::Tcp,
+            "10.0.0.7".to_string(),
+            "8080".to_string(),
+            &app.config,
+        );
+        tcp.connecting = false;
+        tcp.connected = true;
+        let mut udp = SessionData::new(
+            Protocol::Udp,
+            "192.168.1.20".to_string(),
+            "9000".to_string(),
+            &app.config,
+        );
+        udp.connecting = false;
+        // Editing the main window afterwards must not leak into open sessions
+        app.config.address = "127.0.0.1".to_string();
+        let tcp = open_session(&mut app, tcp, Some("boiler"));
+        let udp = open_session(&mut app, udp, None);
+        assert_eq!(
+            app.title(tcp),
+            "boiler — Tcp session 10.0.0.7:8080 (connected)"
+        );
+        assert_eq!(
+            app.title(udp),
+            "Udp session 192.168.1.20:9000 (disconnected)"
+        );
+        assert_eq!(app.title(app.main_window_id), "replayr");
+    }
+
+    #[test]
+    fn export_stems_are_plain_file_names() {
+        let mut app = App::default();
+        let mut data = SessionData::new(
+            Protocol::Tcp,
+            "fe80::1".to_string(),
+            "8080".to_string(),
+            &app.config,
+        );
+        let session = open_session(&mut app, data.clone(), None);
+        assert_eq!(app.windows[&session].export_stem(), "Tcp_fe80__1_8080");
+        // The stem stays the same whatever the connection state
+        data.connecting = false;
+        data.connected = true;
+        let connected = open_session(&mut app, data, None);
+        assert_eq!(app.windows[&connected].export_stem(), "Tcp_fe80__1_8080");
+        for (label, stem) in [
+            ("a/b", "a_b"),
+            ("x:y", "x_y"),
+            ("boiler room", "boiler_room"),
+            ("..", "export"),
+            ("../up", "_up"),
+        ] {
+            let data = SessionData::new(
+                Protocol::Udp,
+                "10.0.0.7".to_string(),
+                "9000".to_string(),
+                &app.config,
+            );
+            let id = open_session(&mut app, data, Some(label));
+            assert_eq!(app.windows[&id].export_stem(), stem, "label {:?}", label);
+        }
+    }
+
+    #[test]
+    fn dropped_files_load_as_payloads_sending_their_bytes() {
+        let mut app = App::default();
+        let bytes = b"GET /\r\n\x00\\\"\xff".to_vec();
+        for (payload_type, typed) in [
+            (PayloadType::Hex, "47 45 54 20 2f 0d 0a 00 5c 22 ff"),
+            (PayloadType::Ascii, r#"GET /\r\n\x00\\\"\xff"#),
+        ] {
+            let mut data = SessionData::new(
+                Protocol::Tcp,
+                "10.0.0.7".to_string(),
+                "8080".to_string(),
+                &app.config,
+            );
+            data.payload_type = payload_type;
+            let id = open_session(&mut app, data, None);
+            let path = std::path::PathBuf::from("request.bin");
+            let _ = update_app(
+                &mut app,
+                Message::DroppedFileRead(id, path.clone(), false, Ok(Some(bytes.clone()))),
+            );
+            let Session(data) = &app.windows[&id].state else {
+                unreachable!()
+            };
+            assert_eq!(data.payload_input, typed);
+            assert_eq!(
+                crate::validate::payload_bytes(&data.payload_input, payload_type),
+                Ok(bytes.clone())
+            );
+            // Appending keeps what was typed and adds the file's bytes after it
+            let _ = update_app(
+                &mut app,
+                Message::DroppedFileRead(id, path, true, Ok(Some(b"!".to_vec()))),
+            );
+            let Session(data) = &app.windows[&id].state else {
+                unreachable!()
+            };
+            let mut appended = bytes.clone();
+            appended.push(b'!');
+            assert_eq!(
+                crate::validate::payload_bytes(&data.payload_input, payload_type),
+                Ok(appended)
+            );
+        }
+    }
+}
@@ -0,0 +1,446 @@
+use crate::types::{PayloadType, Protocol, ReplayablePayload};
+
+/// Delays longer than this are flagged as suspicious.
+const LONG_DELAY_MS: u64 = 10_000;
+/// Largest UDP payload that fits a typical 1500 byte MTU without fragmentation, the
+/// default of the `udp_safe_datagram_size` setting.
+pub const UDP_SAFE_PAYLOAD: usize = 1472;
+/// Largest payload a UDP datagram can carry over IPv4.
+pub const UDP_MAX_PAYLOAD: usize = 65507;
+
+/// Result of validating a single replay payload without sending it.
+#[derive(Clone, Debug)]
+pub struct PayloadReport {
+    pub index: usize,
+    pub size: Option<usize>,
+    pub delay: u64,
+    pub cumulative_ms: u64,
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+}
+
+impl PayloadReport {
+    /// Renders the report as a single line of text.
+    pub fn line(&self) -> String {
+        let size = match self.size {
+            Some(size) => format!("{} bytes", size),
+            None => "-".to_string(),
+        };
+        let mut line = format!(
+            "#{} {} delay {} ms at {} ms",
+            self.index + 1,
+            size,
+            self.delay,
+            self.cumulative_ms
+        );
+        if let Some(error) = &self.error {
+            line.push_str(&format!(" ERROR: {}", error));
+        }
+        for warning in &self.warnings {
+            line.push_str(&format!(" WARN: {}", warning));
+        }
+        line
+    }
+}
+
+/// Decodes every payload and collects sizes, cumulative timing, errors, and warnings.
+/// UDP payloads larger than `udp_safe` bytes are warned about. No sockets are opened.
+pub fn validate_replay(
+    protocol: Protocol,
+    payloads: &[ReplayablePayload],
+    udp_safe: usize,
+) -> Vec<PayloadReport> {
+    let mut cumulative_ms = 0;
+    payloads
+        .iter()
+        .enumerate()
+        .map(|(index, payload)| {
+            cumulative_ms += payload.delay;
+            let mut warnings = Vec::new();
+            let (size, error) = match payload.get_payload() {
+                Ok(data) if protocol == Protocol::Udp && data.len() > UDP_MAX_PAYLOAD => (
+                    Some(data.len()),
+                    Some(format!(
+                        "larger than a UDP datagram ({} bytes)",
+                        UDP_MAX_PAYLOAD
+                    )),
+                ),
+                Ok(data) => (Some(data.len()), None),
+                Err(_) if crate::tokens::uses_tokens(&payload.payload) => {
+                    warnings.push("uses tokens, size is known at replay time".to_string());
+                    (None, None)
+                }
+                Err(e) => (None, Some(format!("invalid hex: {}", e))),
+            };
+            if size == Some(0) {
+                warnings.push("empty payload is skipped".to_string());
+            }
+            if payload.delay > LONG_DELAY_MS {
+                warnings.push(format!("delay over {} ms", LONG_DELAY_MS));
+            }
+            if protocol == Protocol::Udp
+                && error.is_none()
+                && size.is_some_and(|size| size > udp_safe)
+            {
+                warnings.push(format!("larger than typical MTU ({} bytes)", udp_safe));
+            }
+            PayloadReport {
+                index,
+                size,
+                delay: payload.delay,
+                cumulative_ms,
+                error,
+                warnings,
+            }
+        })
+        .collect()
+}
+
+/// Summarizes a validation run, e.g. "12 payloads, 340 bytes, 5200 ms, 1 errors, 2 warnings".
+pub fn summary(reports: &[PayloadReport]) -> String {
+    let bytes: usize = reports.iter().filter_map(|r| r.size).sum();
+    let duration = reports.last().map(|r| r.cumulative_ms).unwrap_or(0);
+    let errors = reports.iter().filter(|r| r.error.is_some()).count();
+    let warnings: usize = reports.iter().map(|r| r.warnings.len()).sum();
+    format!(
+        "{} payloads, {} bytes, {} ms, {} errors, {} warnings",
+        reports.len(),
+        bytes,
+        duration,
+        errors,
+        warnings
+    )
+}
+
+/// Renders the full validation report as text for export.
+pub fn render_report(file_name: &str, reports: &[PayloadReport]) -> String {
+    let mut report = format!("Validation of {}\n{}\n\n", file_name, summary(reports));
+    for line in reports.iter().map(PayloadReport::line) {
+        report.push_str(&line);
+        report.push('\n');
+    }
+    report
+}
+
+/// Checks a numeric port: digits only and between 1 and 65535.
+pub fn port(input: &str) -> Result<u16, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("Port is empty".to_string());
+    }
+    match input.parse::<u16>() {
+        Ok(port) if port > 0 => Ok(port),
+        _ if input.bytes().all(|b| b.is_ascii_digit()) => {
+            Err(format!("Port {} is not between 1 and 65535", input))
+        }
+        _ => Err(format!("Port {} is not a number", input)),
+    }
+}
+
+/// Parses a number of at least `min`, e.g. a loop count or a timeout.
+pub fn at_least<T>(input: &str, min: T) -> Result<T, String>
+where
+    T: std::str::FromStr + PartialOrd + std::fmt::Display,
+{
+    let input = input.trim();
+    let value: T = input
+        .parse()
+        .map_err(|_| format!("{} is not a valid number", input))?;
+    // NaN compares as None and is rejected along with values below the minimum
+    match value.partial_cmp(&min) {
+        Some(std::cmp::Ordering::Less) | None => Err(format!("{} is less than {}", input, min)),
+        _ => Ok(value),
+    }
+}
+
+/// Checks that an address is an IPv4 literal, a bracketed IPv6 literal such as `[::1]`,
+/// or a plausible hostname.
+pub fn address(input: &str) -> Result<(), String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("Address is empty".to_string());
+    }
+    if let Some(inner) = input.strip_prefix('[').and_then(|a| a.strip_suffix(']')) {
+        return inner
+            .parse::<std::net::Ipv6Addr>()
+            .map(|_| ())
+            .map_err(|_| format!("{} is not a valid IPv6 address", input));
+    }
+    if input.parse::<std::net::Ipv6Addr>().is_ok() {
+        return Err(format!("Wrap IPv6 addresses in brackets, e.g. [{}]", input));
+    }
+    if input.parse::<std::net::Ipv4Addr>().is_ok() {
+        return Ok(());
+    }
+    // Anything else must be a hostname: dot-separated labels of letters, digits, and
+    // inner hyphens, with an optional trailing dot
+    let host = input.strip_suffix('.').unwrap_or(input);
+    let valid_label = |label: &str| {
+        (1..=63).contains(&label.len())
+            && label
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+    };
+    // All-numeric names like 10.0.0.300 are mistyped IPv4 addresses, not hostnames
+    let numeric = host.bytes().all(|b| b.is_ascii_digit() || b == b'.');
+    if host.len() <= 253 && !numeric && host.split('.').all(valid_label) {
+        Ok(())
+    } else {
+        Err(format!("{} is not a valid IP address or hostname", input))
+    }
+}
+
+/// Byte count of a payload as typed, for the readout under payload inputs, with a warning
+/// for UDP payloads larger than `udp_safe` bytes. Payloads that do not decode, and UDP
+/// payloads too large for any datagram, are errors.
+pub fn payload_size(
+    payload: &str,
+    payload_type: PayloadType,
+    protocol: Protocol,
+    udp_safe: usize,
+) -> Result<(usize, Option<String>), String> {
+    let size = match payload_type {
+        PayloadType::Hex => hex_digits(payload).map_err(|e| format!("Invalid hex: {}", e))? / 2,
+        PayloadType::Ascii => ascii_payload(payload)
+            .map_err(|e| format!("Invalid ASCII: {}", e))?
+            .len(),
+    };
+    match protocol {
+        Protocol::Udp if size > UDP_MAX_PAYLOAD => Err(format!(
+            "{} bytes, more than a UDP datagram holds ({} bytes)",
+            size, UDP_MAX_PAYLOAD
+        )),
+        Protocol::Udp if size > udp_safe => Ok((
+            size,
+            Some(format!(
+                "more than {} bytes, may be fragmented or dropped",
+                udp_safe
+            )),
+        )),
+        _ => Ok((size, None)),
+    }
+}
+
+/// Checks that a payload decodes under its type.
+pub fn payload(payload: &str, payload_type: PayloadType) -> Result<(), String> {
+    match payload_type {
+        PayloadType::Hex => hex_digits(payload)
+            .map(|_| ())
+            .map_err(|e| format!("Invalid hex payload: {}", e)),
+        PayloadType::Ascii => ascii_payload(payload)
+            .map(|_| ())
+            .map_err(|e| format!("Invalid ASCII payload: {}", e)),
+    }
+}
+
+/// Decodes a hex payload as typed, ignoring spaces and `0x` prefixes. Errors name the
+/// offending character by its position in the input, spaces included, counting from 1.
+pub fn hex_payload(input: &str) -> Result<Vec<u8>, String> {
+    hex_digits(input)?;
+    let digits: String = hex_chars(input).map(|(_, c)| c).collect();
+    hex::decode(&digits).map_err(|e| e.to_string())
+}
+
+/// Characters of a hex payload as typed that should be digits, with their positions
+/// counting from 1. Spaces are skipped, as is a `0x` or `0X` at the start of a group.
+fn hex_chars(input: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut chars = input.chars().enumerate().peekable();
+    let mut group_start = true;
+    std::iter::from_fn(move || {
+        loop {
+            let (position, c) = chars.next()?;
+            if c == ' ' {
+                group_start = true;
+                continue;
+            }
+            if group_start
+                && c == '0'
+                && chars
+                    .next_if(|(_, next)| matches!(next, 'x' | 'X'))
+                    .is_some()
+            {
+                group_start = false;
+                continue;
+            }
+            group_start = false;
+            return Some((position + 1, c));
+        }
+    })
+}
+
+/// Checks a hex payload as typed without decoding it, returning the number of digits.
+fn hex_digits(input: &str) -> Result<usize, String> {
+    let mut digits: usize = 0;
+    for (position, c) in hex_chars(input) {
+        if !c.is_ascii_hexdigit() {
+            return Err(format!(
+                "invalid character '{}' at position {}",
+                c.escape_debug(),
+                position
+            ));
+        }
+        digits += 1;
+    }
+    if !digits.is_multiple_of(2) {
+        return Err(format!("odd number of hex digits ({})", digits));
+    }
+    Ok(digits)
+}
+
+/// Decodes an ASCII payload as typed. Characters are sent as their UTF-8 bytes, and the
+/// escapes `\\`, `\'`, `\"`, `\0`, `\t`, `\r`, `\n` and `\xNN` as the byte they stand for,
+/// as `escape_ascii` writes them. Errors name the offending escape by its position in the
+/// input, counting from 1.
+pub fn ascii_payload(input: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut chars = input.chars().enumerate().peekable();
+    while let Some((position, c)) = chars.next() {
+        if c != '\\' {
+            bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            continue;
+        }
+        let byte = match chars.next().map(|(_, escaped)| escaped) {
+            Some('\\') => b'\\',
+            Some('\'') => b'\'',
+            Some('"') => b'"',
+            Some('0') => b'\0',
+            Some('t') => b'\t',
+            Some('r') => b'\r',
+            Some('n') => b'\n',
+            Some('x') => {
+                let digits: String = (0..2)
+                    .map_while(|_| chars.next_if(|(_, d)| d.is_ascii_hexdigit()))
+                    .map(|(_, d)| d)
+                    .collect();
+                if digits.len() < 2 {
+                    return Err(format!(
+                        "incomplete escape '\\x{}' at position {}",
+                        digits,
+                        position + 1
+                    ));
+                }
+                u8::from_str_radix(&digits, 16).map_err(|e| e.to_string())?
+            }
+            Some(other) => {
+                return Err(format!(
+                    "invalid escape '\\{}' at position {}",
+                    other.escape_debug(),
+                    position + 1
+                ));
+            }
+            None => return Err(format!("unfinished escape at position {}", position + 1)),
+        };
+        bytes.push(byte);
+    }
+    Ok(bytes)
+}
+
+/// Bytes a payload as typed is sent as.
+pub fn payload_bytes(payload: &str, payload_type: PayloadType) -> Result<Vec<u8>, String> {
+    match payload_type {
+        PayloadType::Hex => hex_payload(payload),
+        PayloadType::Ascii => ascii_payload(payload),
+    }
+}
+
+/// Number of leading bytes shown by `send_preview`.
+pub const PREVIEW_BYTES: usize = 32;
+
+/// Describes the bytes sending a payload transmits: the total length and the first
+/// `PREVIEW_BYTES` bytes in hex. Only those of hex payloads are decoded, so long hex inputs
+/// stay cheap to preview on every redraw.
+pub fn send_preview(payload: &str, payload_type: PayloadType) -> Result<String, String> {
+    let (len, head) = match payload_type {
+        PayloadType::Hex => {
+            let len = hex_digits(payload)? / 2;
+            let head: String = hex_chars(payload)
+                .map(|(_, c)| c)
+                .take(PREVIEW_BYTES * 2)
+                .collect();
+            (len, hex::decode(head).map_err(|e| e.to_string())?)
+        }
+        PayloadType::Ascii => {
+            let mut bytes = ascii_payload(payload)?;
+            let len = bytes.len();
+            bytes.truncate(PREVIEW_BYTES);
+            (len, bytes)
+        }
+    };
+    let hex = head
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ");
+    Ok(match len {
+        0 => "Nothing to send".to_string(),
+        len if len > PREVIEW_BYTES => format!("{} bytes: {} …", len, hex),
+        1 => format!("1 byte: {}", hex),
+        len => format!("{} bytes: {}", len, hex),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_hex_characters_are_reported_by_position() {
+        assert_eq!(
+            payload("de ad gf", PayloadType::Hex),
+            Err("Invalid hex payload: invalid character 'g' at position 7".to_string())
+        );
+        assert_eq!(
+            hex_payload("0xde0xad"),
+            Err("invalid character 'x' at position 6".to_string())
+        );
+    }
+
+    #[test]
+    fn odd_hex_digit_counts_are_reported() {
+        assert_eq!(
+            payload("de a", PayloadType::Hex),
+            Err("Invalid hex payload: odd number of hex digits (3)".to_string())
+        );
+    }
+
+    #[test]
+    fn spaced_hex_passes() {
+        assert_eq!(payload("de ad  be ef", PayloadType::Hex), Ok(()));
+        assert_eq!(
+            hex_payload(" de adbe ef "),
+            Ok(vec![0xde, 0xad, 0xbe, 0xef])
+        );
+    }
+
+    #[test]
+    fn prefixed_hex_passes() {
+        assert_eq!(payload("0xdeadbeef", PayloadType::Hex), Ok(()));
+        assert_eq!(hex_payload("0xde 0Xad be"), Ok(vec![0xde, 0xad, 0xbe]));
+        assert_eq!(
+            send_preview("0x00ff", PayloadType::Hex),
+            Ok("2 bytes: 00 ff".to_string())
+        );
+    }
+
+    #[test]
+    fn ascii_escapes_decode_to_their_bytes() {
+        assert_eq!(
+            ascii_payload(r#"GET /\r\n\t\0\\\'\"\x7F\xff é"#),
+            Ok(b"GET /\r\n\t\0\\'\"\x7f\xff \xc3\xa9".to_vec())
+        );
+        assert_eq!(
+            payload(r"C:\users", PayloadType::Ascii),
+            Err("Invalid ASCII payload: invalid escape '\\u' at position 3".to_string())
+        );
+        assert_eq!(
+            ascii_payload(r"\x4"),
+            Err(r"incomplete escape '\x4' at position 1".to_string())
+        );
+        assert_eq!(
+            ascii_payload("ends in \\"),
+            Err("unfinished escape at position 9".to_string())
+        );
+    }
+}